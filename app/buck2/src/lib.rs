@@ -381,6 +381,7 @@ impl CommandKind {
             stdin: process.stdin,
             restarter: process.restarter,
             restarted_trace_id: process.restarted_trace_id.dupe(),
+            local_command_output: false,
         };
 
         match self {
@@ -24,6 +24,7 @@ use buck2_build_api::actions::IncrementalActionExecutable;
 use buck2_build_api::actions::UnregisteredAction;
 use buck2_build_api::artifact_groups::ArtifactGroup;
 use buck2_common::cas_digest::RawDigest;
+use buck2_common::executor_config::RemoteExecutorUseCase;
 use buck2_common::file_ops::FileDigest;
 use buck2_common::file_ops::FileMetadata;
 use buck2_common::file_ops::TrackedFileDigest;
@@ -31,12 +32,14 @@ use buck2_common::http::HttpClient;
 use buck2_common::io::trace::TracingIoProvider;
 use buck2_core::category::Category;
 use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::digest::CasDigestFromReExt;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::execute::command_executor::ActionExecutionTimingData;
 use buck2_execute::materialize::http::http_download;
 use buck2_execute::materialize::http::http_head;
 use buck2_execute::materialize::http::Checksum;
 use buck2_execute::materialize::http::HttpError;
+use buck2_execute::materialize::materializer::CasDownloadInfo;
 use buck2_execute::materialize::materializer::HttpDownloadInfo;
 use dupe::Dupe;
 use indexmap::IndexSet;
@@ -54,6 +57,14 @@ enum DownloadFileActionError {
     WrongNumberOfOutputs(usize),
     #[error(transparent)]
     Http(#[from] HttpError),
+    #[error(
+        "Remote asset server returned `{actual}` for `{url}`, but the checksum is `{expected}`"
+    )]
+    RemoteAssetMismatch {
+        url: Arc<str>,
+        expected: String,
+        actual: String,
+    },
 }
 
 #[derive(Debug, Allocative)]
@@ -128,17 +139,9 @@ impl DownloadFileAction {
             .expect("a single artifact by construction")
     }
 
-    /// Try to produce a FileMetadata without downloading the file.
-    async fn declared_metadata(
-        &self,
-        client: &dyn HttpClient,
-        digest_config: DigestConfig,
-    ) -> anyhow::Result<Option<FileMetadata>> {
-        if !self.inner.is_deferrable {
-            return Ok(None);
-        }
-
-        let digest = if digest_config.cas_digest_config().allows_sha1() {
+    /// The digest of the file in the CAS, if the checksum uses the same algorithm.
+    fn checksum_digest(&self, digest_config: DigestConfig) -> Option<RawDigest> {
+        if digest_config.cas_digest_config().allows_sha1() {
             self.inner
                 .checksum
                 .sha1()
@@ -150,9 +153,20 @@ impl DownloadFileAction {
                 .and_then(|sha256| RawDigest::parse_sha256(sha256.as_bytes()).ok())
         } else {
             None
-        };
+        }
+    }
 
-        let digest = match digest {
+    /// Try to produce a FileMetadata without downloading the file.
+    async fn declared_metadata(
+        &self,
+        client: &dyn HttpClient,
+        digest_config: DigestConfig,
+    ) -> anyhow::Result<Option<FileMetadata>> {
+        if !self.inner.is_deferrable {
+            return Ok(None);
+        }
+
+        let digest = match self.checksum_digest(digest_config) {
             Some(digest) => digest,
             None => return Ok(None),
         };
@@ -195,6 +209,68 @@ impl DownloadFileAction {
         }
     }
 
+    /// Resolve the file through the Remote Asset API instead of downloading it. The remote asset
+    /// server puts it in the CAS, so from then on this is just like a `cas_artifact`, and it is
+    /// only downloaded if it's needed locally.
+    async fn fetch_via_remote_asset(
+        &self,
+        ctx: &mut dyn ActionExecutionCtx,
+    ) -> anyhow::Result<ArtifactValue> {
+        let use_case = RemoteExecutorUseCase::buck2_default();
+        let digest_config = ctx.digest_config();
+
+        let (re_digest, expiration) = ctx
+            .re_client()
+            .fetch_blob(&self.inner.url, &self.inner.checksum, use_case)
+            .await?;
+
+        let digest = FileDigest::from_re(&re_digest, digest_config)?;
+        if let Some(expected) = self.checksum_digest(digest_config) {
+            if *digest.raw_digest() != expected {
+                return Err(DownloadFileActionError::RemoteAssetMismatch {
+                    url: self.inner.url.dupe(),
+                    expected: expected.to_string(),
+                    actual: digest.raw_digest().to_string(),
+                }
+                .into());
+            }
+        }
+
+        let expiration = match expiration {
+            Some(expiration) => expiration,
+            None => {
+                ctx.re_client()
+                    .get_digest_expirations(vec![re_digest], use_case)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .context("get_digest_expirations did not return anything")?
+                    .1
+            }
+        };
+
+        let metadata = FileMetadata {
+            digest: TrackedFileDigest::new_expires(
+                digest,
+                expiration,
+                digest_config.cas_digest_config(),
+            ),
+            is_executable: self.inner.is_executable,
+        };
+        let value = ArtifactValue::file(metadata);
+
+        let rel_path = ctx.fs().resolve_build(self.output().get_path());
+        ctx.materializer()
+            .declare_cas_many(
+                Arc::new(CasDownloadInfo::new_declared(use_case)),
+                vec![(rel_path, value.dupe())],
+                ctx.cancellation_context(),
+            )
+            .await?;
+
+        Ok(value)
+    }
+
     /// Execute this action for offline builds (e.g. no network).
     async fn execute_for_offline(
         &self,
@@ -260,7 +336,12 @@ impl IncrementalActionExecutable for DownloadFileAction {
             return self.execute_for_offline(ctx).await;
         }
 
-        let (value, execution_kind) = {
+        let (value, execution_kind) = if ctx.run_action_knobs().download_file_via_remote_asset {
+            (
+                self.fetch_via_remote_asset(ctx).await?,
+                ActionExecutionKind::Deferred,
+            )
+        } else {
             match self
                 .declared_metadata(&*ctx.http_client(), ctx.digest_config())
                 .await?
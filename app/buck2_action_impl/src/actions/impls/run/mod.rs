@@ -502,6 +502,15 @@ impl Action for RunAction {
             "no_outputs_cleanup".to_owned() => self.inner.no_outputs_cleanup.to_string(),
         }
     }
+
+    fn expanded_command_line(
+        &self,
+        fs: &ExecutorFs,
+    ) -> anyhow::Result<Option<ExpandedCommandLine>> {
+        let (cmdline, _worker) =
+            self.expand_command_line_and_worker(fs, &mut SimpleCommandLineArtifactVisitor::new())?;
+        Ok(Some(cmdline))
+    }
 }
 
 #[async_trait]
@@ -24,9 +24,27 @@ pub struct AuditConfigurationsCommand {
     #[clap(
         name = "configurations",
         multiple_values = true,
+        conflicts_with = "why",
         help = "configurations to audit (example: `cell//package:target-105fe3389fc7e436`). If none provided, will print information about all known configurations."
     )]
     pub configs: Vec<String>,
+
+    /// Explain why the given target is built in each of its configurations: for every
+    /// configuration the target appears in within the graph of the `--root` targets, print the
+    /// shortest dependency path from a root, the transition or execution platform resolution
+    /// that introduced the configuration, and the difference with the other configurations.
+    #[clap(long, value_name = "TARGET", requires = "roots")]
+    pub why: Option<String>,
+
+    /// Patterns of the root targets to search from with `--why`. May be specified multiple
+    /// times.
+    #[clap(
+        long = "root",
+        value_name = "PATTERN",
+        number_of_values = 1,
+        requires = "why"
+    )]
+    pub roots: Vec<String>,
 }
 
 #[async_trait]
@@ -35,3 +53,65 @@ impl AuditSubcommand for AuditConfigurationsCommand {
         &self.common_opts
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_why_roots() {
+        let command = AuditConfigurationsCommand::try_parse_from([
+            "audit-configuration",
+            "--why",
+            "//foo:bar",
+            "--root",
+            "//app/...",
+            "--root",
+            "//lib:lib",
+        ])
+        .unwrap();
+        assert_eq!(Some("//foo:bar"), command.why.as_deref());
+        assert_eq!(vec!["//app/...", "//lib:lib"], command.roots);
+        assert!(command.configs.is_empty());
+
+        let command = AuditConfigurationsCommand::try_parse_from([
+            "audit-configuration",
+            "cell//package:target-105fe3389fc7e436",
+        ])
+        .unwrap();
+        assert_eq!(
+            vec!["cell//package:target-105fe3389fc7e436"],
+            command.configs
+        );
+        assert!(command.roots.is_empty());
+
+        // `--why` needs roots, which are not positional.
+        assert!(
+            AuditConfigurationsCommand::try_parse_from([
+                "audit-configuration",
+                "--why",
+                "//foo:bar"
+            ])
+            .is_err()
+        );
+        assert!(
+            AuditConfigurationsCommand::try_parse_from([
+                "audit-configuration",
+                "--why",
+                "//foo:bar",
+                "//app/...",
+            ])
+            .is_err()
+        );
+        assert!(
+            AuditConfigurationsCommand::try_parse_from([
+                "audit-configuration",
+                "--root",
+                "//app/..."
+            ])
+            .is_err()
+        );
+    }
+}
@@ -86,7 +86,8 @@ impl AuditSubcommand for AuditAnalysisQueriesCommand {
                                 }
                             }
                         }
-                        buck2_core::pattern::PackageSpec::All => {
+                        buck2_core::pattern::PackageSpec::All
+                        | buck2_core::pattern::PackageSpec::AllExcept(..) => {
                             unimplemented!()
                         }
                     }
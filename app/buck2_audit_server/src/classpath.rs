@@ -20,6 +20,7 @@ use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::ctx::ServerCommandDiceContext;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::pattern::modifiers_from_client_context;
 use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
 use buck2_server_ctx::pattern::target_platform_from_client_context;
 use dupe::Dupe;
@@ -49,11 +50,14 @@ impl AuditSubcommand for AuditClasspathCommand {
                 .await?;
                 let target_platform =
                     target_platform_from_client_context(&client_ctx, server_ctx, &ctx).await?;
+                let modifiers =
+                    modifiers_from_client_context(&client_ctx, server_ctx, &ctx).await?;
                 // Incompatible targets are skipped because this is an audit command
                 let targets = load_compatible_patterns(
                     &ctx,
                     parsed_patterns,
                     target_platform,
+                    &modifiers,
                     MissingTargetBehavior::Fail,
                 )
                 .await?;
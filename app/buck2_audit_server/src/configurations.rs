@@ -7,27 +7,103 @@
  * of this source tree.
  */
 
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::io::Write;
 
+use anyhow::Context;
 use async_trait::async_trait;
 use buck2_audit::configurations::AuditConfigurationsCommand;
+use buck2_build_api::calculation::load_patterns;
+use buck2_build_api::calculation::Calculation;
+use buck2_build_api::calculation::MissingTargetBehavior;
+use buck2_build_api::nodes::calculation::NodeCalculation;
 use buck2_cli_proto::ClientContext;
 use buck2_core::configuration::bound_id::BoundConfigurationId;
+use buck2_core::configuration::cfg_diff::cfg_diff;
 use buck2_core::configuration::data::ConfigurationData;
+use buck2_core::configuration::transition::applied::TransitionApplied;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_core::target::label::TargetLabel;
+use buck2_node::nodes::configured::ConfiguredTargetNode;
+use buck2_query::query::compatibility::MaybeCompatible;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::ctx::ServerCommandDiceContext;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
+use buck2_server_ctx::pattern::target_platform_from_client_context;
+use dice::DiceComputations;
+use dupe::Dupe;
+use gazebo::prelude::*;
+use indent_write::io::IndentWriter;
 use itertools::Itertools;
 
 use crate::AuditSubcommand;
 
+#[derive(Debug, thiserror::Error)]
+enum AuditConfigurationsCommandError {
+    #[error("Target `{0}` is not reachable from the given roots")]
+    WhyTargetNotFound(TargetLabel),
+}
+
 #[async_trait]
 impl AuditSubcommand for AuditConfigurationsCommand {
     async fn server_execute(
         &self,
-        _server_ctx: &dyn ServerCommandContextTrait,
+        server_ctx: &dyn ServerCommandContextTrait,
         mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
-        _client_ctx: ClientContext,
+        client_ctx: ClientContext,
     ) -> anyhow::Result<()> {
+        if let Some(why) = &self.why {
+            return server_ctx
+                .with_dice_ctx(async move |server_ctx, ctx| {
+                    let target = parse_patterns_from_cli_args::<TargetPatternExtra>(
+                        &ctx,
+                        &[buck2_data::TargetPattern { value: why.clone() }],
+                        server_ctx.working_dir(),
+                    )
+                    .await?
+                    .into_iter()
+                    .next()
+                    .context("Parsing patterns returned nothing")?
+                    .as_target_label(why)?;
+
+                    let root_patterns = parse_patterns_from_cli_args::<TargetPatternExtra>(
+                        &ctx,
+                        &self.roots.map(|value| buck2_data::TargetPattern {
+                            value: value.clone(),
+                        }),
+                        server_ctx.working_dir(),
+                    )
+                    .await?;
+                    let target_platform =
+                        target_platform_from_client_context(&client_ctx, server_ctx, &ctx).await?;
+
+                    let mut roots = Vec::new();
+                    for (_, targets) in
+                        load_patterns(&ctx, root_patterns, MissingTargetBehavior::Fail)
+                            .await?
+                            .into_iter()
+                    {
+                        for (_, node) in targets? {
+                            let label = ctx
+                                .get_configured_target(node.label(), target_platform.as_ref())
+                                .await?;
+                            // Incompatible roots are not part of the build graph.
+                            if let MaybeCompatible::Compatible(node) =
+                                ctx.get_configured_target_node(&label).await?
+                            {
+                                roots.push(node);
+                            }
+                        }
+                    }
+
+                    audit_configurations_why(&ctx, &mut stdout.as_writer(), &target, roots).await
+                })
+                .await;
+        }
+
         let mut stdout = stdout.as_writer();
 
         if self.configs.is_empty() {
@@ -68,3 +144,247 @@ fn print_cfg(stdout: &mut impl Write, cfg: &ConfigurationData) -> anyhow::Result
 
     Ok(())
 }
+
+/// How a node was first reached from its parent while searching the configured graph.
+#[derive(Clone, Copy, Dupe, Debug, PartialEq, Eq)]
+enum DepKind {
+    /// Regular or transitioned dependency.
+    Target,
+    /// Dependency configured for the execution platform of the parent.
+    Exec,
+}
+
+/// A node on a path from a root, with the parent it was reached from (`None` for the root).
+type PathStep = (
+    ConfiguredTargetNode,
+    Option<(ConfiguredTargetNode, DepKind)>,
+);
+
+/// Finds the configurations of `target` in the graph of `roots`, and for each one the shortest
+/// path to it from a root.
+fn find_configuration_paths(
+    target: &TargetLabel,
+    roots: Vec<ConfiguredTargetNode>,
+) -> Vec<Vec<PathStep>> {
+    // Breadth-first search, so the recorded parents give the shortest path from a root.
+    let mut parents: HashMap<_, Option<(ConfiguredTargetNode, DepKind)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for root in roots {
+        if let Entry::Vacant(e) = parents.entry(root.label().dupe()) {
+            e.insert(None);
+            queue.push_back(root);
+        }
+    }
+
+    let mut found = Vec::new();
+    while let Some(node) = queue.pop_front() {
+        if node.label().unconfigured() == target {
+            found.push(node.dupe());
+        }
+        let deps = node
+            .target_deps()
+            .map(|dep| (dep, DepKind::Target))
+            .chain(node.exec_deps().map(|dep| (dep, DepKind::Exec)));
+        for (dep, kind) in deps {
+            if let Entry::Vacant(e) = parents.entry(dep.label().dupe()) {
+                e.insert(Some((node.dupe(), kind)));
+                queue.push_back(dep.dupe());
+            }
+        }
+    }
+
+    found
+        .into_iter()
+        .map(|node| {
+            // Path from the root to the node, with the way each step was reached.
+            let mut path = vec![(node, None)];
+            while let Some(Some((parent, kind))) = parents.get(path.last().unwrap().0.label()) {
+                path.last_mut().unwrap().1 = Some((parent.dupe(), *kind));
+                path.push((parent.dupe(), None));
+            }
+            path.reverse();
+            path
+        })
+        .collect()
+}
+
+/// The step of `path` whose configuration is the one of the last node, and the parent it was
+/// reached from: the configuration was introduced by the edge into the first node of the path
+/// suffix which already has that configuration. Returns `None` if it is the configuration of
+/// the root.
+fn configuration_introduced_at(
+    path: &[PathStep],
+) -> Option<(&ConfiguredTargetNode, &(ConfiguredTargetNode, DepKind))> {
+    let cfg = path.last()?.0.label().cfg();
+    path.iter()
+        .rev()
+        .take_while(|(step, _)| step.label().cfg() == cfg)
+        .last()
+        .and_then(|(step, parent)| Some((step, parent.as_ref()?)))
+}
+
+async fn audit_configurations_why(
+    ctx: &DiceComputations,
+    stdout: &mut impl Write,
+    target: &TargetLabel,
+    roots: Vec<ConfiguredTargetNode>,
+) -> anyhow::Result<()> {
+    let paths = find_configuration_paths(target, roots);
+    if paths.is_empty() {
+        return Err(AuditConfigurationsCommandError::WhyTargetNotFound(target.dupe()).into());
+    }
+
+    for path in &paths {
+        let node = &path.last().unwrap().0;
+        writeln!(stdout, "{}:", node.label())?;
+        writeln!(stdout, "  Path:")?;
+        for (step, _) in path {
+            writeln!(stdout, "    {}", step.label())?;
+        }
+
+        let cfg = node.label().cfg();
+        let cause = match configuration_introduced_at(path) {
+            Some((step, (parent, kind))) => describe_cause(ctx, parent, *kind, step).await?,
+            None => format!("target platform of requested root `{}`", path[0].0.label()),
+        };
+        writeln!(stdout, "  Introduced by: {}", cause)?;
+
+        for other in &paths {
+            let other = &other.last().unwrap().0;
+            if let Err(diff) = cfg_diff(cfg, other.label().cfg()) {
+                writeln!(stdout, "  Difference with {}:", other.label().cfg())?;
+                write!(IndentWriter::new("    ", &mut *stdout), "{}", diff)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Describe why `child` has a different configuration than its `parent`.
+async fn describe_cause(
+    ctx: &DiceComputations,
+    parent: &ConfiguredTargetNode,
+    kind: DepKind,
+    child: &ConfiguredTargetNode,
+) -> anyhow::Result<String> {
+    match kind {
+        DepKind::Exec => Ok(format!(
+            "execution platform `{}` resolved for `{}`",
+            parent.execution_platform_resolution().platform()?.id(),
+            parent.label()
+        )),
+        DepKind::Target => {
+            if parent.forward_target().is_some() {
+                let target_node = ctx.get_target_node(child.label().unconfigured()).await?;
+                if let Some(transition_id) = &target_node.0.rule.cfg {
+                    return Ok(format!(
+                        "rule transition `{}` of `{}`",
+                        transition_id,
+                        child.label().unconfigured()
+                    ));
+                }
+            }
+            for (transition_id, applied) in parent.transition_configurations() {
+                let matches = match &**applied {
+                    TransitionApplied::Single(cfg) => cfg == child.label().cfg(),
+                    TransitionApplied::Split(cfgs) => {
+                        cfgs.values().any(|cfg| cfg == child.label().cfg())
+                    }
+                };
+                if matches {
+                    return Ok(format!(
+                        "attribute transition `{}` on `{}`",
+                        transition_id,
+                        parent.label()
+                    ));
+                }
+            }
+            Ok(format!(
+                "transition on a dependency of `{}`",
+                parent.label()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_core::target::label::ConfiguredTargetLabel;
+
+    use super::*;
+
+    fn node(
+        label: &str,
+        cfg: &ConfigurationData,
+        deps: Vec<ConfiguredTargetNode>,
+        exec_deps: Vec<ConfiguredTargetNode>,
+    ) -> ConfiguredTargetNode {
+        ConfiguredTargetNode::testing_new_with_deps(
+            ConfiguredTargetLabel::testing_parse(label, cfg.dupe()),
+            "rule",
+            deps,
+            exec_deps,
+        )
+    }
+
+    fn labels(path: &[PathStep]) -> Vec<String> {
+        path.map(|(step, _)| step.label().unconfigured().to_string())
+    }
+
+    #[test]
+    fn test_find_configuration_paths() {
+        let target_cfg = ConfigurationData::testing_new();
+        let exec_cfg = ConfigurationData::unbound_exec();
+
+        // `app` depends on `tool` directly, and on `compiler` which uses `tool` as an exec dep.
+        let tool_target = node("root//tool:tool", &target_cfg, Vec::new(), Vec::new());
+        let tool_exec = node("root//tool:tool", &exec_cfg, Vec::new(), Vec::new());
+        let compiler = node(
+            "root//compiler:compiler",
+            &target_cfg,
+            Vec::new(),
+            vec![tool_exec],
+        );
+        let app = node(
+            "root//app:app",
+            &target_cfg,
+            vec![compiler, tool_target],
+            Vec::new(),
+        );
+
+        let paths =
+            find_configuration_paths(&TargetLabel::testing_parse("root//tool:tool"), vec![app]);
+        assert_eq!(2, paths.len());
+
+        // Reached first, since it is closer to the root.
+        assert_eq!(vec!["root//app:app", "root//tool:tool"], labels(&paths[0]));
+        assert_eq!(&target_cfg, paths[0][1].0.label().cfg());
+        assert!(configuration_introduced_at(&paths[0]).is_none());
+
+        assert_eq!(
+            vec![
+                "root//app:app",
+                "root//compiler:compiler",
+                "root//tool:tool"
+            ],
+            labels(&paths[1])
+        );
+        assert_eq!(&exec_cfg, paths[1][2].0.label().cfg());
+        let (step, (parent, kind)) = configuration_introduced_at(&paths[1]).unwrap();
+        assert_eq!(paths[1][2].0.label(), step.label());
+        assert_eq!(
+            "root//compiler:compiler",
+            parent.label().unconfigured().to_string()
+        );
+        assert_eq!(DepKind::Exec, *kind);
+
+        assert!(
+            find_configuration_paths(
+                &TargetLabel::testing_parse("root//other:other"),
+                vec![node("root//app:app", &target_cfg, Vec::new(), Vec::new())],
+            )
+            .is_empty()
+        );
+    }
+}
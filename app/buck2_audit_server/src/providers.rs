@@ -84,11 +84,13 @@ async fn server_execute_with_dice(
         let ctx = &ctx;
         let targets = match spec {
             buck2_core::pattern::PackageSpec::Targets(targets) => targets,
-            buck2_core::pattern::PackageSpec::All => {
+            spec @ (buck2_core::pattern::PackageSpec::All
+            | buck2_core::pattern::PackageSpec::AllExcept(..)) => {
                 let interpreter_results = ctx.get_interpreter_results(package.dupe()).await?;
                 interpreter_results
                     .targets()
                     .keys()
+                    .filter(|target| !spec.is_excluded(target))
                     .map(|target| {
                         (
                             target.to_owned(),
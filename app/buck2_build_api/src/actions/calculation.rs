@@ -167,7 +167,14 @@ async fn build_action_no_redirect(
                 // We can then unconditionally print the error message for compute(),
                 // including ones near the beginning of this method, and also not
                 // duplicate any error messages.
-                action_result = Err(anyhow::anyhow!("Failed to build '{}'", action.owner()));
+                let failed = anyhow::anyhow!("Failed to build '{}'", action.owner());
+                action_result = Err(match command_reports.last().map(|r| &r.status) {
+                    // The command ran and failed, as opposed to e.g. RE erroring out.
+                    Some(CommandExecutionStatus::Failure { .. }) => {
+                        failed.context(buck2_data::ErrorCause::ActionCommandFailed)
+                    }
+                    _ => failed,
+                });
                 // TODO (torozco): Remove (see protobuf file)?
                 execution_kind = command_reports
                     .last()
@@ -27,6 +27,10 @@ pub struct RunActionKnobs {
 
     /// Whether to enforce timeouts when running things on RE.
     pub enforce_re_timeouts: bool,
+
+    /// Whether to resolve download_file actions through the Remote Asset API, instead of
+    /// downloading them locally.
+    pub download_file_via_remote_asset: bool,
 }
 
 pub trait HasRunActionKnobs {
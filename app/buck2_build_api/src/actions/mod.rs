@@ -73,6 +73,7 @@ use thiserror::Error;
 use crate::actions::execute::action_execution_target::ActionExecutionTarget;
 use crate::actions::execute::action_executor::ActionExecutionMetadata;
 use crate::actions::execute::action_executor::ActionOutputs;
+use crate::actions::impls::expanded_command_line::ExpandedCommandLine;
 use crate::actions::impls::run_action_knobs::RunActionKnobs;
 use crate::artifact_groups::ArtifactGroup;
 use crate::artifact_groups::ArtifactGroupValues;
@@ -148,6 +149,14 @@ pub trait Action: Allocative + Debug + Send + Sync + 'static {
         indexmap! {}
     }
 
+    /// The command line this action runs, fully expanded, for actions that run a command.
+    fn expanded_command_line(
+        &self,
+        _fs: &ExecutorFs,
+    ) -> anyhow::Result<Option<ExpandedCommandLine>> {
+        Ok(None)
+    }
+
     // TODO this probably wants more data for execution, like printing a short_name and the target
 }
 
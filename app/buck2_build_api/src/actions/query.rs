@@ -15,7 +15,9 @@ use std::io::Write;
 use std::pin::Pin;
 use std::sync::Arc;
 
+use allocative::Allocative;
 use buck2_artifact::actions::key::ActionKey;
+use buck2_artifact::artifact::artifact_type::Artifact;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::CellResolver;
@@ -37,8 +39,10 @@ use ref_cast::RefCast;
 use serde::Serialize;
 use serde::Serializer;
 
+use crate::actions::impls::expanded_command_line::ExpandedCommandLine;
 use crate::actions::RegisteredAction;
 use crate::analysis::AnalysisResult;
+use crate::artifact_groups::ResolvedArtifactGroup;
 use crate::artifact_groups::TransitiveSetProjectionKey;
 
 #[derive(Debug, derive_more::Display, RefCast, Serialize)]
@@ -89,12 +93,15 @@ pub enum ActionInput {
     IndirectInputs(SetProjectionInputs),
 }
 
-#[derive(Derivative, Clone, Dupe)]
-#[derivative(Debug)]
+#[derive(Derivative, Clone, Dupe, Allocative)]
+#[derivative(Debug, PartialEq, Eq)]
 pub struct ActionQueryNode {
     action: Arc<RegisteredAction>,
+    #[derivative(PartialEq = "ignore")]
+    #[allocative(skip)]
     deps: Arc<Vec<ActionInput>>,
-    #[derivative(Debug = "ignore")]
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    #[allocative(skip)]
     fs: Arc<ArtifactFs>,
 }
 
@@ -107,11 +114,15 @@ impl ActionQueryNode {
         }
     }
 
-    pub fn attrs(&self) -> IndexMap<String, String> {
-        let mut attrs = self.action.action().aquery_attributes(&ExecutorFs::new(
+    fn executor_fs(&self) -> ExecutorFs {
+        ExecutorFs::new(
             &self.fs,
             self.action.execution_config().options.path_separator,
-        ));
+        )
+    }
+
+    pub fn attrs(&self) -> IndexMap<String, String> {
+        let mut attrs = self.action.action().aquery_attributes(&self.executor_fs());
         attrs.insert(
             "executor_configuration".to_owned(),
             self.action.execution_config().executor.to_string(),
@@ -122,6 +133,26 @@ impl ActionQueryNode {
     pub fn action(&self) -> Arc<RegisteredAction> {
         self.action.dupe()
     }
+
+    /// The command line run by this action, if it runs one.
+    pub fn expanded_command_line(&self) -> anyhow::Result<Option<ExpandedCommandLine>> {
+        self.action
+            .action()
+            .expanded_command_line(&self.executor_fs())
+    }
+
+    /// The artifacts this action directly consumes. Inputs reached through transitive set
+    /// projections are not included.
+    pub fn direct_inputs(&self) -> anyhow::Result<Vec<Artifact>> {
+        let mut inputs = Vec::new();
+        for input in self.action.inputs()?.iter() {
+            match input.resolved()? {
+                ResolvedArtifactGroup::Artifact(artifact) => inputs.push(artifact.dupe()),
+                ResolvedArtifactGroup::TransitiveSetProjection(..) => {}
+            }
+        }
+        Ok(inputs)
+    }
 }
 
 impl LabeledNode for ActionQueryNode {
@@ -304,3 +335,187 @@ pub static PRINT_ACTION_NODE: LateBinding<
         cell_resolver: &'a CellResolver,
     ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>,
 > = LateBinding::new("PRINT_ACTION_NODE");
+
+pub mod testing {
+    use std::borrow::Cow;
+    use std::sync::Arc;
+
+    use allocative::Allocative;
+    use async_trait::async_trait;
+    use buck2_artifact::artifact::artifact_type::testing::BuildArtifactTestingExt;
+    use buck2_artifact::artifact::artifact_type::Artifact;
+    use buck2_artifact::artifact::build_artifact::BuildArtifact;
+    use buck2_artifact::artifact::source_artifact::SourceArtifact;
+    use buck2_artifact::deferred::id::DeferredId;
+    use buck2_common::executor_config::CommandExecutorConfig;
+    use buck2_core::buck_path::path::BuckPath;
+    use buck2_core::buck_path::resolver::BuckPathResolver;
+    use buck2_core::category::Category;
+    use buck2_core::cells::cell_root_path::CellRootPathBuf;
+    use buck2_core::cells::name::CellName;
+    use buck2_core::cells::CellResolver;
+    use buck2_core::configuration::data::ConfigurationData;
+    use buck2_core::fs::artifact_path_resolver::ArtifactFs;
+    use buck2_core::fs::buck_out_path::BuckOutPathResolver;
+    use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+    use buck2_core::fs::project::ProjectRoot;
+    use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+    use buck2_core::package::package_relative_path::PackageRelativePathBuf;
+    use buck2_core::package::PackageLabel;
+    use buck2_core::target::label::TargetLabel;
+    use buck2_core::target::name::TargetNameRef;
+    use buck2_execute::artifact::fs::ExecutorFs;
+    use buck2_query::query::environment::LabeledNode;
+    use dupe::Dupe;
+    use indexmap::indexset;
+    use sorted_vector_map::SortedVectorMap;
+
+    use crate::actions::box_slice_set::BoxSliceSet;
+    use crate::actions::execute::action_executor::ActionExecutionMetadata;
+    use crate::actions::execute::action_executor::ActionOutputs;
+    use crate::actions::impls::expanded_command_line::ExpandedCommandLine;
+    use crate::actions::query::ActionInput;
+    use crate::actions::query::ActionQueryNode;
+    use crate::actions::Action;
+    use crate::actions::ActionExecutable;
+    use crate::actions::ActionExecutionCtx;
+    use crate::actions::PristineActionExecutable;
+    use crate::actions::RegisteredAction;
+    use crate::artifact_groups::ArtifactGroup;
+
+    /// A `run` action with a fixed command line. It can be queried but not executed.
+    #[derive(Debug, Allocative)]
+    struct TestingRunAction {
+        inputs: BoxSliceSet<ArtifactGroup>,
+        outputs: BoxSliceSet<BuildArtifact>,
+        cmd: Vec<String>,
+        env: SortedVectorMap<String, String>,
+        category: Category,
+        identifier: String,
+    }
+
+    #[async_trait]
+    impl Action for TestingRunAction {
+        fn kind(&self) -> buck2_data::ActionKind {
+            buck2_data::ActionKind::Run
+        }
+
+        fn inputs(&self) -> anyhow::Result<Cow<'_, [ArtifactGroup]>> {
+            Ok(Cow::Borrowed(self.inputs.as_slice()))
+        }
+
+        fn outputs(&self) -> anyhow::Result<Cow<'_, [BuildArtifact]>> {
+            Ok(Cow::Borrowed(self.outputs.as_slice()))
+        }
+
+        fn as_executable(&self) -> ActionExecutable<'_> {
+            ActionExecutable::Pristine(self)
+        }
+
+        fn category(&self) -> &Category {
+            &self.category
+        }
+
+        fn identifier(&self) -> Option<&str> {
+            Some(&self.identifier)
+        }
+
+        fn expanded_command_line(
+            &self,
+            _fs: &ExecutorFs,
+        ) -> anyhow::Result<Option<ExpandedCommandLine>> {
+            Ok(Some(ExpandedCommandLine {
+                exe: self.cmd[..1].to_vec(),
+                args: self.cmd[1..].to_vec(),
+                env: self.env.clone(),
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl PristineActionExecutable for TestingRunAction {
+        async fn execute(
+            &self,
+            _ctx: &mut dyn ActionExecutionCtx,
+        ) -> anyhow::Result<(ActionOutputs, ActionExecutionMetadata)> {
+            Err(anyhow::anyhow!("testing actions cannot be executed"))
+        }
+    }
+
+    /// An `ArtifactFs` for a `cell` cell at `cell-path`, for nodes created by
+    /// `ActionQueryNodeTestingExt`.
+    pub fn testing_artifact_fs(project_root: ProjectRoot) -> Arc<ArtifactFs> {
+        let cells = CellResolver::testing_with_name_and_path(
+            CellName::testing_new("cell"),
+            CellRootPathBuf::new(ProjectRelativePathBuf::unchecked_new("cell-path".into())),
+        );
+        Arc::new(ArtifactFs::new(
+            BuckPathResolver::new(cells),
+            BuckOutPathResolver::new(ProjectRelativePathBuf::unchecked_new("buck-out/v2".into())),
+            project_root,
+        ))
+    }
+
+    pub trait ActionQueryNodeTestingExt {
+        /// Creates a node for a `run` action of `cell//pkg:<name>` with category `testing` and
+        /// identifier `<name>`. The action runs `cmd` with `NAME=<name>` in its environment,
+        /// reads the source `<name>.c` plus the outputs of `deps`, and produces `<name>.out`.
+        fn testing_new(
+            name: &str,
+            cmd: &[&str],
+            deps: &[&ActionQueryNode],
+            fs: Arc<ArtifactFs>,
+        ) -> ActionQueryNode;
+    }
+
+    impl ActionQueryNodeTestingExt for ActionQueryNode {
+        fn testing_new(
+            name: &str,
+            cmd: &[&str],
+            deps: &[&ActionQueryNode],
+            fs: Arc<ArtifactFs>,
+        ) -> ActionQueryNode {
+            let pkg = PackageLabel::testing_new("cell", "pkg");
+            let label = TargetLabel::new(pkg.dupe(), TargetNameRef::unchecked_new(name))
+                .configure(ConfigurationData::testing_new());
+            let output = BuildArtifact::testing_new(
+                label,
+                ForwardRelativePathBuf::unchecked_new(format!("{}.out", name)),
+                DeferredId::testing_new(0),
+            );
+
+            let mut inputs = indexset![ArtifactGroup::Artifact(Artifact::from(
+                SourceArtifact::new(BuckPath::testing_new(
+                    pkg,
+                    PackageRelativePathBuf::unchecked_new(format!("{}.c", name)),
+                ),)
+            ))];
+            for dep in deps {
+                for dep_output in dep.action().outputs().unwrap().iter() {
+                    inputs.insert(ArtifactGroup::Artifact(Artifact::from(dep_output.dupe())));
+                }
+            }
+
+            let action = TestingRunAction {
+                inputs: BoxSliceSet::from(inputs),
+                outputs: BoxSliceSet::from(indexset![output.dupe()]),
+                cmd: cmd.iter().map(|s| (*s).to_owned()).collect(),
+                env: SortedVectorMap::from_iter([("NAME".to_owned(), name.to_owned())]),
+                category: Category::try_from("testing").unwrap(),
+                identifier: name.to_owned(),
+            };
+
+            ActionQueryNode::new(
+                Arc::new(RegisteredAction::new(
+                    output.key().dupe(),
+                    Box::new(action),
+                    CommandExecutorConfig::testing_local(),
+                )),
+                deps.iter()
+                    .map(|dep| ActionInput::ActionKey(dep.node_ref().dupe()))
+                    .collect(),
+                fs,
+            )
+        }
+    }
+}
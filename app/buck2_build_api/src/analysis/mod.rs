@@ -314,7 +314,8 @@ async fn run_analysis_with_env_underlying(
         resolved_attrs.push((
             a.name,
             a.value
-                .resolve_single(node.label().pkg(), &resolution_ctx)?,
+                .resolve_single(node.label().pkg(), &resolution_ctx)
+                .context(buck2_data::ErrorCategory::User)?,
         ));
     }
 
@@ -355,16 +356,24 @@ async fn run_analysis_with_env_underlying(
 
         profiler.initialize(&mut eval)?;
 
-        let list_res = analysis_env.impl_function.invoke(&mut eval, ctx)?;
+        // Errors from the rule implementation are the user's.
+        let list_res = analysis_env
+            .impl_function
+            .invoke(&mut eval, ctx)
+            .context(buck2_data::ErrorCategory::User)?;
 
         profiler
             .evaluation_complete(&mut eval)
             .context("Profiler finalization failed")?;
 
-        ctx.actions.run_promises(dice, &mut eval).await?;
+        ctx.actions
+            .run_promises(dice, &mut eval)
+            .await
+            .context(buck2_data::ErrorCategory::User)?;
 
         // TODO: Convert the ValueError from `try_from_value` better than just printing its Debug
-        let res_typed = ProviderCollection::try_from_value(list_res)?;
+        let res_typed = ProviderCollection::try_from_value(list_res)
+            .context(buck2_data::ErrorCategory::User)?;
         let res = env.heap().alloc(res_typed);
         env.set_extra_value(res);
 
@@ -18,6 +18,7 @@ use buck2_common::dice::cells::HasCellResolver;
 use buck2_common::dice::cycles::CycleAdapterDescriptor;
 use buck2_common::dice::data::HasIoProvider;
 use buck2_common::pattern::package_roots::find_package_roots_stream;
+use buck2_common::pattern::resolve::PatternExclusions;
 use buck2_common::pattern::resolve::ResolvedPattern;
 use buck2_common::result::SharedResult;
 use buck2_common::result::ToSharedResultExt;
@@ -145,6 +146,16 @@ pub trait Calculation<'c> {
         global_target_platform: Option<&TargetLabel>,
     ) -> anyhow::Result<T::Configured>;
 
+    /// Like `get_configured_target`, but applies configuration modifiers (e.g. from
+    /// `--modifier` or `//foo:bar?linux-arm64`) on top of the resolved target platform.
+    /// Configuration rules are not affected by modifiers.
+    async fn get_configured_target_with_modifiers<T: ConfigurableTargetLabel>(
+        &self,
+        target: &T,
+        global_target_platform: Option<&TargetLabel>,
+        modifiers: &[TargetLabel],
+    ) -> anyhow::Result<T::Configured>;
+
     async fn get_default_configured_target<T: ConfigurableTargetLabel>(
         &self,
         target: &T,
@@ -174,11 +185,21 @@ impl<'c> Calculation<'c> for DiceComputations {
         &self,
         target: &T,
         global_target_platform: Option<&TargetLabel>,
+    ) -> anyhow::Result<T::Configured> {
+        self.get_configured_target_with_modifiers(target, global_target_platform, &[])
+            .await
+    }
+
+    async fn get_configured_target_with_modifiers<T: ConfigurableTargetLabel>(
+        &self,
+        target: &T,
+        global_target_platform: Option<&TargetLabel>,
+        modifiers: &[TargetLabel],
     ) -> anyhow::Result<T::Configured> {
         let node = self.get_target_node(target.target()).await?;
 
-        let get_platform_configuration = async || -> SharedResult<ConfigurationData> {
-            Ok(match global_target_platform {
+        let get_platform_configuration = async || -> anyhow::Result<ConfigurationData> {
+            let cfg = match global_target_platform {
                 Some(global_target_platform) => {
                     self.get_platform_configuration(global_target_platform)
                         .await?
@@ -187,7 +208,8 @@ impl<'c> Calculation<'c> for DiceComputations {
                     Some(target) => self.get_platform_configuration(target.target()).await?,
                     None => self.get_default_platform(target.target()).await?,
                 },
-            })
+            };
+            self.apply_configuration_modifiers(&cfg, modifiers).await
         };
 
         match node.rule_kind() {
@@ -226,6 +248,7 @@ impl<'c> Calculation<'c> for DiceComputations {
 async fn resolve_patterns_and_load_buildfiles<'c, T: PatternType>(
     ctx: &'c DiceComputations,
     parsed_patterns: Vec<ParsedPattern<T>>,
+    exclusions: &PatternExclusions,
 ) -> anyhow::Result<(
     ResolvedPattern<T>,
     impl Stream<Item = (PackageLabel, anyhow::Result<Arc<EvaluationResult>>)> + 'c,
@@ -247,7 +270,10 @@ async fn resolve_patterns_and_load_buildfiles<'c, T: PatternType>(
     };
 
     impl<'c> Builder<'c> {
-        fn load_package(&mut self, package: PackageLabel) {
+        fn load_package(&mut self, package: PackageLabel, exclusions: &PatternExclusions) {
+            if exclusions.excludes_package(&package) {
+                return;
+            }
             if !self.already_loading.insert(package.dupe()) {
                 return;
             }
@@ -271,11 +297,11 @@ async fn resolve_patterns_and_load_buildfiles<'c, T: PatternType>(
         match pattern {
             ParsedPattern::Target(package, target_name, extra) => {
                 spec.add_target(package.dupe(), target_name, extra);
-                builder.load_package(package.dupe());
+                builder.load_package(package.dupe(), exclusions);
             }
             ParsedPattern::Package(package) => {
                 spec.add_package(package.dupe());
-                builder.load_package(package.dupe());
+                builder.load_package(package.dupe(), exclusions);
             }
             ParsedPattern::Recursive(package) => {
                 recursive_packages.push(package);
@@ -287,10 +313,10 @@ async fn resolve_patterns_and_load_buildfiles<'c, T: PatternType>(
     while let Some(res) = recursive_pattern_packages.next().await {
         let package = res?;
         spec.add_package(package.dupe());
-        builder.load_package(package);
+        builder.load_package(package, exclusions);
     }
 
-    Ok((spec, builder.load_package_futs))
+    Ok((spec.exclude(exclusions), builder.load_package_futs))
 }
 
 pub struct LoadedPatterns<T: PatternType> {
@@ -405,9 +431,26 @@ pub async fn load_patterns<T: PatternType>(
     ctx: &DiceComputations,
     parsed_patterns: Vec<ParsedPattern<T>>,
     skip_missing_targets: MissingTargetBehavior,
+) -> anyhow::Result<LoadedPatterns<T>> {
+    load_patterns_with_exclusions(
+        ctx,
+        parsed_patterns,
+        &PatternExclusions::default(),
+        skip_missing_targets,
+    )
+    .await
+}
+
+/// Like [`load_patterns`], but skips the packages and targets matched by `exclusions`.
+/// Excluded packages are not loaded.
+pub async fn load_patterns_with_exclusions<T: PatternType>(
+    ctx: &DiceComputations,
+    parsed_patterns: Vec<ParsedPattern<T>>,
+    exclusions: &PatternExclusions,
+    skip_missing_targets: MissingTargetBehavior,
 ) -> anyhow::Result<LoadedPatterns<T>> {
     let (spec, mut load_package_futs) =
-        resolve_patterns_and_load_buildfiles(ctx, parsed_patterns).await?;
+        resolve_patterns_and_load_buildfiles(ctx, parsed_patterns, exclusions).await?;
 
     let mut results: BTreeMap<PackageLabel, SharedResult<Arc<EvaluationResult>>> = BTreeMap::new();
     while let Some((pkg, load_res)) = load_package_futs.next().await {
@@ -22,6 +22,7 @@ use buck2_core::cells::name::CellName;
 use buck2_core::collections::unordered_map::UnorderedMap;
 use buck2_core::configuration::config_setting::ConfigSettingData;
 use buck2_core::configuration::data::ConfigurationData;
+use buck2_core::configuration::data::ConfigurationDataData;
 use buck2_core::configuration::pair::ConfigurationNoExec;
 use buck2_core::target::label::ConfiguredTargetLabel;
 use buck2_core::target::label::TargetLabel;
@@ -40,6 +41,7 @@ use dice::Key;
 use dupe::Dupe;
 use gazebo::prelude::*;
 use indexmap::IndexSet;
+use itertools::Itertools;
 use more_futures::cancellation::CancellationContext;
 use starlark::collections::SmallMap;
 use thiserror::Error;
@@ -68,6 +70,10 @@ pub enum ConfigurationError {
         "Platform target `{0}` evaluation returned `ProviderInfo` label `{1}` which resolved to an unequal configuration"
     )]
     PlatformEvalUnequalConfiguration(TargetLabel, TargetLabel),
+    #[error(
+        "Configuration modifier `{0}` matches on buckconfig values, only constraints can be used as modifiers"
+    )]
+    ModifierWithBuckconfigs(TargetLabel),
 }
 
 async fn get_target_platform_detector(
@@ -363,6 +369,17 @@ struct ResolvedConfigurationKey {
     configuration_deps: Vec<TargetLabel>,
 }
 
+#[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
+#[display(
+    fmt = "ConfigurationModifiers({}, {})",
+    cfg,
+    "modifiers.iter().join(\",\")"
+)]
+struct ConfigurationModifiersKey {
+    cfg: ConfigurationData,
+    modifiers: Vec<TargetLabel>,
+}
+
 #[async_trait]
 pub trait ConfigurationCalculation {
     async fn get_default_platform(&self, target: &TargetLabel) -> SharedResult<ConfigurationData>;
@@ -372,6 +389,15 @@ pub trait ConfigurationCalculation {
         target: &TargetLabel,
     ) -> anyhow::Result<ConfigurationData>;
 
+    /// Applies configuration modifiers (e.g. from `--modifier` or `//foo:bar?linux-arm64`) on top
+    /// of a configuration. Each modifier must provide `ConfigurationInfo`, and its constraints
+    /// override those of the configuration (and of earlier modifiers).
+    async fn apply_configuration_modifiers(
+        &self,
+        cfg: &ConfigurationData,
+        modifiers: &[TargetLabel],
+    ) -> anyhow::Result<ConfigurationData>;
+
     async fn get_resolved_configuration<'a, T: IntoIterator<Item = &'a TargetLabel> + Send>(
         &self,
         target_cfg: &ConfigurationData,
@@ -460,6 +486,27 @@ async fn compute_platform_configuration(
     Ok(configuration_data)
 }
 
+async fn compute_configuration_modifiers(
+    ctx: &DiceComputations,
+    cfg: &ConfigurationData,
+    modifiers: &[TargetLabel],
+) -> anyhow::Result<ConfigurationData> {
+    let mut modifiers_data = Vec::with_capacity(modifiers.len());
+    for modifier in modifiers {
+        let analysis_result = ctx.get_configuration_analysis_result(modifier).await?;
+        let setting = FrozenConfigurationInfo::from_providers(
+            analysis_result.providers().provider_collection(),
+        )
+        .ok_or_else(|| ConfigurationError::MissingConfigurationInfoProvider(modifier.dupe()))?
+        .to_config_setting_data();
+        if !setting.buckconfigs.is_empty() {
+            return Err(ConfigurationError::ModifierWithBuckconfigs(modifier.dupe()).into());
+        }
+        modifiers_data.push(ConfigurationDataData::new(setting.constraints));
+    }
+    cfg.apply_modifiers(&modifiers.iter().join(","), modifiers_data)
+}
+
 #[async_trait]
 impl ConfigurationCalculation for DiceComputations {
     async fn get_platform_configuration(
@@ -496,6 +543,52 @@ impl ConfigurationCalculation for DiceComputations {
             .unshared_error()
     }
 
+    async fn apply_configuration_modifiers(
+        &self,
+        cfg: &ConfigurationData,
+        modifiers: &[TargetLabel],
+    ) -> anyhow::Result<ConfigurationData> {
+        if modifiers.is_empty() {
+            return Ok(cfg.dupe());
+        }
+
+        #[async_trait]
+        impl Key for ConfigurationModifiersKey {
+            type Value = SharedResult<ConfigurationData>;
+
+            async fn compute(
+                &self,
+                ctx: &DiceComputations,
+                _cancellation: &CancellationContext,
+            ) -> Self::Value {
+                compute_configuration_modifiers(ctx, &self.cfg, &self.modifiers)
+                    .await
+                    .shared_error()
+            }
+
+            fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+                match (x, y) {
+                    (Ok(x), Ok(y)) => x == y,
+                    _ => false,
+                }
+            }
+        }
+
+        self.compute(&ConfigurationModifiersKey {
+            cfg: cfg.dupe(),
+            modifiers: modifiers.to_vec(),
+        })
+        .await?
+        .unshared_error()
+        .with_context(|| {
+            format!(
+                "Error applying configuration modifiers `{}` to `{}`",
+                modifiers.iter().join(","),
+                cfg
+            )
+        })
+    }
+
     async fn get_default_platform(&self, target: &TargetLabel) -> SharedResult<ConfigurationData> {
         let detector = get_target_platform_detector(self).await?;
         if let Some(target) = detector.detect(target) {
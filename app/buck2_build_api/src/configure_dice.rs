@@ -11,6 +11,7 @@ use std::sync::Arc;
 
 use buck2_common::dice::cells::SetCellResolver;
 use buck2_common::dice::data::SetIoProvider;
+use buck2_common::dice::file_ops::register_persistent_file_keys;
 use buck2_common::io::IoProvider;
 use buck2_common::legacy_configs::dice::SetLegacyConfigs;
 use buck2_common::legacy_configs::LegacyBuckConfig;
@@ -18,17 +19,41 @@ use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::digest_config::SetDigestConfig;
 use dice::DetectCycles;
 use dice::Dice;
+use dice::DiceEvictionPolicy;
+use dice::DiceSnapshot;
+use dice::DiceSnapshotRegistry;
 use dice::WhichDice;
 use dice::WhichSpawner;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+enum ConfigureDiceError {
+    #[error(
+        "`buck2.{0}` is only supported by the modern DICE implementation, set `buck2.dice = modern` to use it"
+    )]
+    RequiresModernDice(&'static str),
+}
+
+/// The keys that are written to and restored from DICE snapshots when `buck2.dice_snapshot` is
+/// enabled.
+pub fn dice_snapshot_registry() -> DiceSnapshotRegistry {
+    let mut registry = DiceSnapshotRegistry::new();
+    register_persistent_file_keys(&mut registry);
+    registry
+}
 
 /// Utility to configure the dice globals.
 /// One place to not forget to initialize something in all places.
+///
+/// `snapshot` is restored into the new DICE before anything else is computed in it. The caller is
+/// responsible for having validated it.
 pub async fn configure_dice_for_buck(
     io: Arc<dyn IoProvider>,
     digest_config: DigestConfig,
     root_config: Option<&LegacyBuckConfig>,
     detect_cycles: Option<DetectCycles>,
     which_dice: Option<WhichDice>,
+    snapshot: Option<DiceSnapshot>,
 ) -> anyhow::Result<Arc<Dice>> {
     let detect_cycles = detect_cycles.map_or_else(
         || {
@@ -55,6 +80,48 @@ pub async fn configure_dice_for_buck(
         .and_then(|c| c.parse::<WhichSpawner>("buck2", "dice_spawner").transpose())
         .unwrap_or(Ok(WhichSpawner::DropCancel))?;
 
+    let record_invalidations = root_config
+        .and_then(|c| {
+            c.parse::<bool>("buck2", "dice_record_invalidations")
+                .transpose()
+        })
+        .unwrap_or(Ok(false))?;
+    if record_invalidations && matches!(which_dice, WhichDice::Legacy) {
+        return Err(ConfigureDiceError::RequiresModernDice("dice_record_invalidations").into());
+    }
+
+    let dice_snapshot = root_config
+        .and_then(|c| c.parse::<bool>("buck2", "dice_snapshot").transpose())
+        .unwrap_or(Ok(false))?;
+    if dice_snapshot && matches!(which_dice, WhichDice::Legacy) {
+        return Err(ConfigureDiceError::RequiresModernDice("dice_snapshot").into());
+    }
+
+    let eviction_policy = DiceEvictionPolicy {
+        max_unused_versions: root_config
+            .and_then(|c| {
+                c.parse::<usize>("buck2", "dice_evict_unused_after_versions")
+                    .transpose()
+            })
+            .transpose()?,
+        memory_budget_bytes: root_config
+            .and_then(|c| {
+                c.parse::<usize>("buck2", "dice_memory_budget_bytes")
+                    .transpose()
+            })
+            .transpose()?,
+    };
+    if matches!(which_dice, WhichDice::Legacy) {
+        if eviction_policy.max_unused_versions.is_some() {
+            return Err(
+                ConfigureDiceError::RequiresModernDice("dice_evict_unused_after_versions").into(),
+            );
+        }
+        if eviction_policy.memory_budget_bytes.is_some() {
+            return Err(ConfigureDiceError::RequiresModernDice("dice_memory_budget_bytes").into());
+        }
+    }
+
     let mut dice = match which_dice {
         WhichDice::Legacy => Dice::builder(),
         WhichDice::Modern => Dice::modern(),
@@ -63,6 +130,19 @@ pub async fn configure_dice_for_buck(
     dice.set_digest_config(digest_config);
 
     let dice = dice.build_with_which_spawner(detect_cycles, which_spawner);
+    if eviction_policy != DiceEvictionPolicy::default() {
+        dice.set_eviction_policy(eviction_policy)?;
+    }
+    if record_invalidations {
+        dice.record_invalidations(true)?;
+    }
+    if let Some(snapshot) = snapshot {
+        // A snapshot that can't be restored only costs recomputing its nodes.
+        match dice.restore(&dice_snapshot_registry(), snapshot).await {
+            Ok(restored) => tracing::info!("Restored {} DICE nodes from snapshot", restored),
+            Err(e) => tracing::warn!("Error restoring DICE snapshot: {:#}", e),
+        }
+    }
     let mut dice_ctx = dice.updater();
     dice_ctx.set_none_cell_resolver()?;
     dice_ctx.set_none_legacy_configs()?;
@@ -56,20 +56,27 @@ pub async fn get_maybe_compatible_targets(
     ctx: &DiceComputations,
     loaded_targets: impl IntoIterator<Item = (PackageLabel, anyhow::Result<Vec<TargetNode>>)>,
     global_target_platform: Option<TargetLabel>,
+    modifiers: &[TargetLabel],
 ) -> anyhow::Result<impl Iterator<Item = anyhow::Result<MaybeCompatible<ConfiguredTargetNode>>>> {
     let mut by_package_futs: Vec<_> = Vec::new();
     for (_package, result) in loaded_targets {
         let targets = result?;
         let global_target_platform = global_target_platform.dupe();
+        let modifiers = modifiers.to_vec();
 
         by_package_futs.push({
             ctx.temporary_spawn(|ctx, _cancellation| {
                 async move {
                     let ctx = &ctx;
                     let global_target_platform = global_target_platform.as_ref();
+                    let modifiers = &modifiers;
                     let target_futs: Vec<_> = targets.map(|target| async move {
                         let target = ctx
-                            .get_configured_target(target.label(), global_target_platform)
+                            .get_configured_target_with_modifiers(
+                                target.label(),
+                                global_target_platform,
+                                modifiers,
+                            )
                             .await?;
                         anyhow::Ok(ctx.get_configured_target_node(&target).await?)
                     });
@@ -91,9 +98,11 @@ pub async fn get_compatible_targets(
     ctx: &DiceComputations,
     loaded_targets: impl IntoIterator<Item = (PackageLabel, anyhow::Result<Vec<TargetNode>>)>,
     global_target_platform: Option<TargetLabel>,
+    modifiers: &[TargetLabel],
 ) -> anyhow::Result<TargetSet<ConfiguredTargetNode>> {
     let maybe_compatible_targets =
-        get_maybe_compatible_targets(ctx, loaded_targets, global_target_platform).await?;
+        get_maybe_compatible_targets(ctx, loaded_targets, global_target_platform, modifiers)
+            .await?;
 
     let (compatible_targets, incompatible_targets) =
         split_compatible_incompatible(maybe_compatible_targets)?;
@@ -111,6 +120,7 @@ pub async fn load_compatible_patterns(
     ctx: &DiceComputations,
     parsed_patterns: Vec<ParsedPattern<TargetPatternExtra>>,
     global_target_platform: Option<TargetLabel>,
+    modifiers: &[TargetLabel],
     skip_missing_targets: MissingTargetBehavior,
 ) -> anyhow::Result<TargetSet<ConfiguredTargetNode>> {
     let loaded_patterns = load_patterns(ctx, parsed_patterns, skip_missing_targets).await?;
@@ -118,6 +128,7 @@ pub async fn load_compatible_patterns(
         ctx,
         loaded_patterns.iter_loaded_targets_by_package(),
         global_target_platform,
+        modifiers,
     )
     .await
 }
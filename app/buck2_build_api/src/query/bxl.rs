@@ -23,6 +23,8 @@ use buck2_query::query::syntax::simple::functions::helpers::CapturedExpr;
 use buck2_util::late_binding::LateBinding;
 use dice::DiceComputations;
 
+use crate::actions::query::ActionQueryNode;
+
 #[async_trait]
 pub trait BxlCqueryFunctions<'c>: Send + 'c {
     async fn allpaths(
@@ -89,6 +91,35 @@ pub trait BxlUqueryFunctions<'c>: Send + 'c {
     async fn owner(&self, file_set: &FileSet) -> anyhow::Result<TargetSet<TargetNode>>;
 }
 
+#[async_trait]
+pub trait BxlAqueryFunctions<'c>: Send + 'c {
+    async fn allpaths(
+        &self,
+        from: &TargetSet<ActionQueryNode>,
+        to: &TargetSet<ActionQueryNode>,
+    ) -> anyhow::Result<TargetSet<ActionQueryNode>>;
+    async fn somepath(
+        &self,
+        from: &TargetSet<ActionQueryNode>,
+        to: &TargetSet<ActionQueryNode>,
+    ) -> anyhow::Result<TargetSet<ActionQueryNode>>;
+    async fn deps(
+        &self,
+        targets: &TargetSet<ActionQueryNode>,
+        deps: Option<i32>,
+        captured_expr: Option<&CapturedExpr>,
+    ) -> anyhow::Result<TargetSet<ActionQueryNode>>;
+    async fn rdeps(
+        &self,
+        universe: &TargetSet<ActionQueryNode>,
+        targets: &TargetSet<ActionQueryNode>,
+        depth: Option<i32>,
+    ) -> anyhow::Result<TargetSet<ActionQueryNode>>;
+    /// Resolves target patterns to the actions producing their default outputs, as aquery does
+    /// for literals.
+    async fn eval_literals(&self, literals: &[&str]) -> anyhow::Result<TargetSet<ActionQueryNode>>;
+}
+
 pub static NEW_BXL_CQUERY_FUNCTIONS: LateBinding<
     for<'c> fn(
         &'c DiceComputations,
@@ -110,3 +141,14 @@ pub static NEW_BXL_UQUERY_FUNCTIONS: LateBinding<
         Box<dyn Future<Output = anyhow::Result<Box<dyn BxlUqueryFunctions<'c> + 'c>>> + 'c>,
     >,
 > = LateBinding::new("NEW_BXL_UQUERY_FUNCTIONS");
+
+pub static NEW_BXL_AQUERY_FUNCTIONS: LateBinding<
+    for<'c> fn(
+        &'c DiceComputations,
+        // Target platform
+        Option<TargetLabel>,
+        CellName,
+    ) -> Pin<
+        Box<dyn Future<Output = anyhow::Result<Box<dyn BxlAqueryFunctions<'c> + 'c>>> + 'c>,
+    >,
+> = LateBinding::new("NEW_BXL_AQUERY_FUNCTIONS");
@@ -46,6 +46,7 @@ pub trait QueryFrontend: Send + Sync + 'static {
         query: &str,
         query_args: &[String],
         global_target_platform: Option<TargetLabel>,
+        modifiers: Vec<TargetLabel>,
         target_universe: Option<&[String]>,
     ) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>>;
 
@@ -64,6 +65,7 @@ pub trait QueryFrontend: Send + Sync + 'static {
         cwd: &ProjectRelativePath,
         literals: &[String],
         global_target_platform: Option<TargetLabel>,
+        modifiers: Vec<TargetLabel>,
     ) -> anyhow::Result<CqueryUniverse>;
 }
 
@@ -0,0 +1,548 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use allocative::Allocative;
+use buck2_build_api::actions::query::ActionQueryNode;
+use buck2_build_api::query::bxl::BxlAqueryFunctions;
+use buck2_build_api::query::bxl::NEW_BXL_AQUERY_FUNCTIONS;
+use buck2_build_api::query::oneshot::QUERY_FRONTEND;
+use buck2_core::target::label::TargetLabel;
+use buck2_query::query::syntax::simple::eval::set::TargetSet;
+use buck2_query::query::syntax::simple::eval::set::TargetSetExt;
+use buck2_query::query::syntax::simple::functions::helpers::CapturedExpr;
+use derivative::Derivative;
+use derive_more::Display;
+use dupe::Dupe;
+use gazebo::prelude::*;
+use starlark::any::ProvidesStaticType;
+use starlark::environment::Methods;
+use starlark::environment::MethodsBuilder;
+use starlark::environment::MethodsStatic;
+use starlark::eval::Evaluator;
+use starlark::starlark_module;
+use starlark::starlark_type;
+use starlark::values::list::ListRef;
+use starlark::values::none::NoneOr;
+use starlark::values::type_repr::StarlarkTypeRepr;
+use starlark::values::AllocValue;
+use starlark::values::Heap;
+use starlark::values::NoSerialize;
+use starlark::values::StarlarkValue;
+use starlark::values::Trace;
+use starlark::values::UnpackValue;
+use starlark::values::Value;
+use starlark::values::ValueError;
+use starlark::values::ValueLike;
+use starlark::StarlarkDocs;
+
+use crate::bxl::starlark_defs::context::BxlContext;
+use crate::bxl::starlark_defs::nodes::action::StarlarkActionQueryNode;
+use crate::bxl::starlark_defs::query_util::parse_query_evaluation_result;
+use crate::bxl::starlark_defs::targetset::StarlarkTargetSet;
+use crate::bxl::value_as_starlark_target_label::ValueAsStarlarkTargetLabel;
+
+#[derive(
+    ProvidesStaticType,
+    Derivative,
+    Display,
+    Trace,
+    NoSerialize,
+    StarlarkDocs,
+    Allocative
+)]
+#[starlark_docs(directory = "bxl")]
+#[derivative(Debug)]
+#[display(fmt = "{:?}", self)]
+#[allocative(skip)]
+pub struct StarlarkAQueryCtx<'v> {
+    #[trace(unsafe_ignore)]
+    #[derivative(Debug = "ignore")]
+    ctx: &'v BxlContext<'v>,
+    #[derivative(Debug = "ignore")]
+    target_platform: Option<TargetLabel>,
+}
+
+impl<'v> StarlarkValue<'v> for StarlarkAQueryCtx<'v> {
+    starlark_type!("aqueryctx");
+
+    fn get_methods() -> Option<&'static Methods> {
+        static RES: MethodsStatic = MethodsStatic::new();
+        RES.methods(register_aquery)
+    }
+}
+
+impl<'v> AllocValue<'v> for StarlarkAQueryCtx<'v> {
+    fn alloc_value(self, heap: &'v Heap) -> Value<'v> {
+        heap.alloc_complex_no_freeze(self)
+    }
+}
+
+impl<'v> StarlarkTypeRepr for &'v StarlarkAQueryCtx<'v> {
+    fn starlark_type_repr() -> String {
+        StarlarkAQueryCtx::get_type_starlark_repr()
+    }
+}
+
+impl<'v> UnpackValue<'v> for &'v StarlarkAQueryCtx<'v> {
+    fn unpack_value(x: Value<'v>) -> Option<&'v StarlarkAQueryCtx<'v>> {
+        x.downcast_ref()
+    }
+}
+
+pub(crate) async fn get_aquery_env<'v>(
+    ctx: &'v BxlContext<'v>,
+    target_platform: Option<TargetLabel>,
+) -> anyhow::Result<Box<dyn BxlAqueryFunctions<'v> + 'v>> {
+    (NEW_BXL_AQUERY_FUNCTIONS.get()?)(ctx.async_ctx.0, target_platform, ctx.cell_name).await
+}
+
+impl<'v> StarlarkAQueryCtx<'v> {
+    pub async fn new(
+        ctx: &'v BxlContext<'v>,
+        global_target_platform: Value<'v>,
+        default_target_platform: &Option<TargetLabel>,
+    ) -> anyhow::Result<StarlarkAQueryCtx<'v>> {
+        let target_platform = global_target_platform.parse_target_platforms(
+            &ctx.target_alias_resolver,
+            &ctx.cell_resolver,
+            ctx.cell_name,
+            default_target_platform,
+        )?;
+
+        Ok(Self {
+            ctx,
+            target_platform,
+        })
+    }
+}
+
+/// Resolves the actions given to an aquery function, which can be a target pattern string, a list
+/// of target pattern strings, a single action query node, or a target set of action query nodes.
+/// Target patterns resolve to the actions producing the default outputs of the targets, as with
+/// literals in `buck2 aquery`.
+async fn unpack_actions<'v>(
+    aquery_env: &dyn BxlAqueryFunctions<'v>,
+    value: Value<'v>,
+) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+    if let Some(set) = <&StarlarkTargetSet<ActionQueryNode>>::unpack_value(value) {
+        return Ok(set.0.clone());
+    }
+    if let Some(node) = value.downcast_ref::<StarlarkActionQueryNode>() {
+        let mut set = TargetSet::new();
+        set.insert(node.0.dupe());
+        return Ok(set);
+    }
+    if let Some(literal) = value.unpack_str() {
+        return aquery_env.eval_literals(&[literal]).await;
+    }
+    if let Some(list) = <&ListRef>::unpack_value(value) {
+        let literals = list.content().try_map(|e| {
+            e.unpack_str().ok_or_else(|| {
+                ValueError::IncorrectParameterTypeWithExpected(
+                    "list of strings".to_owned(),
+                    e.get_type().to_owned(),
+                )
+            })
+        })?;
+        return aquery_env.eval_literals(&literals).await;
+    }
+    Err(ValueError::IncorrectParameterTypeWithExpected(
+        "str, list of str, action_query_node, or target_set of action query nodes".to_owned(),
+        value.get_type().to_owned(),
+    )
+    .into())
+}
+
+/// The context for performing `aquery` operations in bxl. The functions offered on this ctx are
+/// the same behaviour as the query functions available within aquery command.
+///
+/// Query results are `[StarlarkTargetSet]`s of `[StarlarkActionQueryNode]`s, which supports
+/// iteration, indexing, `len()`, set addition/subtraction, and `equals()`. Functions accepting
+/// actions also accept target patterns, which resolve to the actions producing the targets'
+/// default outputs.
+#[starlark_module]
+fn register_aquery(builder: &mut MethodsBuilder) {
+    /// The `allpaths` query for computing all dependency paths.
+    fn allpaths<'v>(
+        this: &StarlarkAQueryCtx<'v>,
+        from: Value<'v>,
+        to: Value<'v>,
+    ) -> anyhow::Result<StarlarkTargetSet<ActionQueryNode>> {
+        this.ctx
+            .async_ctx
+            .via(|| async {
+                let aquery_env = get_aquery_env(this.ctx, this.target_platform.dupe()).await?;
+                let from = unpack_actions(&*aquery_env, from).await?;
+                let to = unpack_actions(&*aquery_env, to).await?;
+                aquery_env.allpaths(&from, &to).await
+            })
+            .map(StarlarkTargetSet::from)
+    }
+
+    /// The `somepath` query.
+    fn somepath<'v>(
+        this: &StarlarkAQueryCtx<'v>,
+        from: Value<'v>,
+        to: Value<'v>,
+    ) -> anyhow::Result<StarlarkTargetSet<ActionQueryNode>> {
+        this.ctx
+            .async_ctx
+            .via(|| async {
+                let aquery_env = get_aquery_env(this.ctx, this.target_platform.dupe()).await?;
+                let from = unpack_actions(&*aquery_env, from).await?;
+                let to = unpack_actions(&*aquery_env, to).await?;
+                aquery_env.somepath(&from, &to).await
+            })
+            .map(StarlarkTargetSet::from)
+    }
+
+    /// The deps query for finding the transitive closure of dependencies.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_deps(ctx):
+    ///     result = ctx.aquery().deps("root//bin:the_binary", 1)
+    ///     ctx.output.print(result)
+    /// ```
+    fn deps<'v>(
+        this: &StarlarkAQueryCtx<'v>,
+        universe: Value<'v>,
+        #[starlark(default = NoneOr::None)] depth: NoneOr<i32>,
+        #[starlark(default = NoneOr::None)] filter: NoneOr<&'v str>,
+    ) -> anyhow::Result<StarlarkTargetSet<ActionQueryNode>> {
+        this.ctx
+            .async_ctx
+            .via(|| async {
+                let filter = filter
+                    .into_option()
+                    .try_map(buck2_query_parser::parse_expr)?;
+
+                let aquery_env = get_aquery_env(this.ctx, this.target_platform.dupe()).await?;
+                let universe = unpack_actions(&*aquery_env, universe).await?;
+                aquery_env
+                    .deps(
+                        &universe,
+                        depth.into_option(),
+                        filter
+                            .as_ref()
+                            .map(|span| CapturedExpr { expr: span })
+                            .as_ref(),
+                    )
+                    .await
+            })
+            .map(StarlarkTargetSet::from)
+    }
+
+    /// The rdeps query for finding the transitive closure of reverse dependencies.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_rdeps(ctx):
+    ///     result = ctx.aquery().rdeps("root//bin:the_binary", "//lib:file1", 100)
+    ///     ctx.output.print(result)
+    /// ```
+    fn rdeps<'v>(
+        this: &StarlarkAQueryCtx<'v>,
+        universe: Value<'v>,
+        from: Value<'v>,
+        depth: Option<i32>,
+    ) -> anyhow::Result<StarlarkTargetSet<ActionQueryNode>> {
+        this.ctx
+            .async_ctx
+            .via(|| async {
+                let aquery_env = get_aquery_env(this.ctx, this.target_platform.dupe()).await?;
+                let universe = unpack_actions(&*aquery_env, universe).await?;
+                let from = unpack_actions(&*aquery_env, from).await?;
+                aquery_env.rdeps(&universe, &from, depth).await
+            })
+            .map(StarlarkTargetSet::from)
+    }
+
+    /// The filter query for filtering actions by name.
+    fn filter<'v>(
+        this: &StarlarkAQueryCtx<'v>,
+        regex: &str,
+        targets: Value<'v>,
+    ) -> anyhow::Result<StarlarkTargetSet<ActionQueryNode>> {
+        this.ctx
+            .async_ctx
+            .via(|| async {
+                let aquery_env = get_aquery_env(this.ctx, this.target_platform.dupe()).await?;
+                unpack_actions(&*aquery_env, targets)
+                    .await?
+                    .filter_name(regex)
+            })
+            .map(StarlarkTargetSet::from)
+    }
+
+    /// The kind query for filtering actions by kind, e.g. `run` or `write`.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_kind(ctx):
+    ///     runs = ctx.aquery().kind("run", "deps(root//bin:the_binary)")
+    ///     ctx.output.print(runs)
+    /// ```
+    fn kind<'v>(
+        this: &StarlarkAQueryCtx<'v>,
+        regex: &str,
+        targets: Value<'v>,
+    ) -> anyhow::Result<StarlarkTargetSet<ActionQueryNode>> {
+        this.ctx
+            .async_ctx
+            .via(|| async {
+                let aquery_env = get_aquery_env(this.ctx, this.target_platform.dupe()).await?;
+                unpack_actions(&*aquery_env, targets).await?.kind(regex)
+            })
+            .map(StarlarkTargetSet::from)
+    }
+
+    /// The attrfilter query for action attribute filtering.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_attrfilter(ctx):
+    ///     compiles = ctx.aquery().attrfilter("category", "cxx_compile", "root//bin:the_binary")
+    ///     ctx.output.print(compiles)
+    /// ```
+    fn attrfilter<'v>(
+        this: &StarlarkAQueryCtx<'v>,
+        attr: &str,
+        value: &str,
+        targets: Value<'v>,
+    ) -> anyhow::Result<StarlarkTargetSet<ActionQueryNode>> {
+        this.ctx
+            .async_ctx
+            .via(|| async {
+                let aquery_env = get_aquery_env(this.ctx, this.target_platform.dupe()).await?;
+                unpack_actions(&*aquery_env, targets)
+                    .await?
+                    .attrfilter(attr, &|v| Ok(v == value))
+            })
+            .map(StarlarkTargetSet::from)
+    }
+
+    /// The attrregexfilter query for action attribute filtering with regex.
+    fn attrregexfilter<'v>(
+        this: &StarlarkAQueryCtx<'v>,
+        attribute: &str,
+        value: &str,
+        targets: Value<'v>,
+    ) -> anyhow::Result<StarlarkTargetSet<ActionQueryNode>> {
+        this.ctx
+            .async_ctx
+            .via(|| async {
+                let aquery_env = get_aquery_env(this.ctx, this.target_platform.dupe()).await?;
+                unpack_actions(&*aquery_env, targets)
+                    .await?
+                    .attrregexfilter(attribute, value)
+            })
+            .map(StarlarkTargetSet::from)
+    }
+
+    /// Evaluates some general query string. `query_args` can be a list of strings.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_eval(ctx):
+    ///     result1 = ctx.aquery().eval("deps(root//bin:the_binary)")
+    ///     ctx.output.print(result1)
+    ///
+    ///     result2 = ctx.aquery().eval("deps(%s)", query_args = ["cell//path/to/file:target"])
+    ///     ctx.output.print(result2)
+    /// ```
+    fn eval<'v>(
+        this: &StarlarkAQueryCtx<'v>,
+        query: &'v str,
+        #[starlark(default = NoneOr::None)] query_args: NoneOr<Vec<String>>,
+        eval: &mut Evaluator<'v, '_>,
+    ) -> anyhow::Result<Value<'v>> {
+        let query_args = query_args.into_option().unwrap_or_default();
+
+        this.ctx.async_ctx.via_dice(|ctx| async {
+            parse_query_evaluation_result(
+                QUERY_FRONTEND
+                    .get()?
+                    .eval_aquery(
+                        ctx,
+                        &this.ctx.working_dir()?,
+                        query,
+                        &query_args,
+                        this.target_platform.dupe(),
+                    )
+                    .await?,
+                eval,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use buck2_build_api::actions::query::testing::testing_artifact_fs;
+    use buck2_build_api::actions::query::testing::ActionQueryNodeTestingExt;
+    use buck2_build_api::actions::query::ActionQueryNode;
+    use buck2_build_api::query::bxl::BxlAqueryFunctions;
+    use buck2_core::fs::project::ProjectRootTemp;
+    use buck2_query::query::syntax::simple::eval::set::TargetSet;
+    use buck2_query::query::syntax::simple::eval::set::TargetSetExt;
+    use buck2_query::query::syntax::simple::functions::helpers::CapturedExpr;
+    use dupe::Dupe;
+    use starlark::values::Heap;
+
+    use crate::bxl::starlark_defs::aquery::unpack_actions;
+    use crate::bxl::starlark_defs::nodes::action::StarlarkActionQueryNode;
+    use crate::bxl::starlark_defs::targetset::StarlarkTargetSet;
+
+    /// Resolves `//pkg:<name>` literals to the nodes with that identifier. Graph queries are not
+    /// needed by these tests.
+    struct TestAqueryFunctions {
+        nodes: Vec<ActionQueryNode>,
+    }
+
+    #[async_trait]
+    impl<'c> BxlAqueryFunctions<'c> for TestAqueryFunctions {
+        async fn allpaths(
+            &self,
+            _from: &TargetSet<ActionQueryNode>,
+            _to: &TargetSet<ActionQueryNode>,
+        ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+            unimplemented!("allpaths is not used by these tests")
+        }
+
+        async fn somepath(
+            &self,
+            _from: &TargetSet<ActionQueryNode>,
+            _to: &TargetSet<ActionQueryNode>,
+        ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+            unimplemented!("somepath is not used by these tests")
+        }
+
+        async fn deps(
+            &self,
+            _targets: &TargetSet<ActionQueryNode>,
+            _deps: Option<i32>,
+            _captured_expr: Option<&CapturedExpr>,
+        ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+            unimplemented!("deps is not used by these tests")
+        }
+
+        async fn rdeps(
+            &self,
+            _universe: &TargetSet<ActionQueryNode>,
+            _targets: &TargetSet<ActionQueryNode>,
+            _depth: Option<i32>,
+        ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+            unimplemented!("rdeps is not used by these tests")
+        }
+
+        async fn eval_literals(
+            &self,
+            literals: &[&str],
+        ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+            let mut set = TargetSet::new();
+            for literal in literals {
+                let name = literal
+                    .strip_prefix("//pkg:")
+                    .ok_or_else(|| anyhow::anyhow!("unexpected literal `{}`", literal))?;
+                let node = self
+                    .nodes
+                    .iter()
+                    .find(|node| node.action().identifier() == Some(name))
+                    .ok_or_else(|| anyhow::anyhow!("no action for `{}`", literal))?;
+                set.insert(node.dupe());
+            }
+            Ok(set)
+        }
+    }
+
+    fn names(set: &TargetSet<ActionQueryNode>) -> Vec<String> {
+        set.iter()
+            .map(|node| node.action().identifier().unwrap().to_owned())
+            .collect()
+    }
+
+    fn test_functions(project_root: &ProjectRootTemp) -> TestAqueryFunctions {
+        let fs = testing_artifact_fs(project_root.path().dupe());
+        let lib = ActionQueryNode::testing_new("lib", &["cc", "-c", "lib.c"], &[], fs.dupe());
+        let bin = ActionQueryNode::testing_new("bin", &["ld", "-o", "bin.out"], &[&lib], fs);
+        TestAqueryFunctions {
+            nodes: vec![lib, bin],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unpack_actions_resolves_literal() -> anyhow::Result<()> {
+        let project_root = ProjectRootTemp::new()?;
+        let functions = test_functions(&project_root);
+        let heap = Heap::new();
+
+        let set = unpack_actions(&functions, heap.alloc("//pkg:bin")).await?;
+        assert_eq!(names(&set), vec!["bin"]);
+
+        let node = set.iter().next().unwrap();
+        let cmd = node.expanded_command_line()?.unwrap();
+        assert_eq!(cmd.exe, vec!["ld"]);
+        assert_eq!(cmd.args, vec!["-o", "bin.out"]);
+        let outputs = node.action().outputs()?;
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].get_path().path().as_str(), "bin.out");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unpack_actions_accepts_lists_nodes_and_sets() -> anyhow::Result<()> {
+        let project_root = ProjectRootTemp::new()?;
+        let functions = test_functions(&project_root);
+        let heap = Heap::new();
+
+        let list = heap.alloc(vec!["//pkg:lib", "//pkg:bin"]);
+        let set = unpack_actions(&functions, list).await?;
+        assert_eq!(names(&set), vec!["lib", "bin"]);
+
+        let node = heap.alloc(StarlarkActionQueryNode(functions.nodes[1].dupe()));
+        assert_eq!(names(&unpack_actions(&functions, node).await?), vec!["bin"]);
+
+        let target_set = heap.alloc(StarlarkTargetSet::from(set));
+        assert_eq!(
+            names(&unpack_actions(&functions, target_set).await?),
+            vec!["lib", "bin"]
+        );
+
+        assert!(
+            unpack_actions(&functions, heap.alloc(vec![1, 2]))
+                .await
+                .is_err()
+        );
+        assert!(unpack_actions(&functions, heap.alloc(1)).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filters_on_resolved_actions() -> anyhow::Result<()> {
+        let project_root = ProjectRootTemp::new()?;
+        let functions = test_functions(&project_root);
+        let heap = Heap::new();
+
+        let set = unpack_actions(&functions, heap.alloc(vec!["//pkg:lib", "//pkg:bin"])).await?;
+        assert_eq!(names(&set.filter_name(":bin ")?), vec!["bin"]);
+        assert_eq!(names(&set.kind("^run$")?), vec!["lib", "bin"]);
+        assert!(set.kind("^write$")?.is_empty());
+        assert_eq!(
+            names(&set.attrfilter("identifier", &|v| Ok(v == "lib"))?),
+            vec!["lib"]
+        );
+        assert_eq!(
+            names(&set.attrregexfilter("category", "^test")?),
+            vec!["lib", "bin"]
+        );
+        Ok(())
+    }
+}
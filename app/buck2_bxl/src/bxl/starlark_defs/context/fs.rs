@@ -10,11 +10,20 @@
 use allocative::Allocative;
 use buck2_common::dice::file_ops::HasFileOps;
 use buck2_common::file_ops::FileOps;
+use buck2_core::cells::cell_path::CellPath;
+use buck2_core::cells::cell_path::CellPathRef;
 use buck2_core::cells::instance::CellInstance;
+use buck2_core::cells::paths::CellRelativePath;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_interpreter::globspec::GlobSpec;
+use buck2_query::query::syntax::simple::eval::file_set::FileNode;
+use buck2_query::query::syntax::simple::eval::file_set::FileSet;
 use derivative::Derivative;
 use derive_more::Display;
 use starlark::any::ProvidesStaticType;
@@ -36,6 +45,7 @@ use starlark::StarlarkDocs;
 
 use crate::bxl::starlark_defs::context::starlark_async::BxlSafeDiceComputations;
 use crate::bxl::starlark_defs::file_expr::FileExpr;
+use crate::bxl::starlark_defs::file_set::StarlarkFileSet;
 use crate::bxl::starlark_defs::file_set::StarlarkReadDirSet;
 
 #[derive(
@@ -122,6 +132,93 @@ impl<'v> BxlFilesystem<'v> {
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+enum ReadFileError {
+    #[error("File `{0}` is not valid UTF-8, use `ctx.fs.read_bytes` to read binary files")]
+    NotUtf8(CellPath),
+}
+
+/// Reads the bytes of the file at `path` (which is at `abs_path` on disk), tracking its contents
+/// with `file_ops`.
+async fn read_file_bytes(
+    file_ops: &dyn FileOps,
+    path: CellPathRef<'_>,
+    abs_path: &AbsNormPath,
+) -> anyhow::Result<Vec<u8>> {
+    // `FileOps` only reads UTF-8, so depend on the metadata of the file (which includes its
+    // digest) and read its bytes directly.
+    file_ops.read_path_metadata(path).await?;
+    Ok(fs_util::read(abs_path)?)
+}
+
+/// Reads the file at `path` as a string, erroring if it is not valid UTF-8.
+async fn read_file(
+    file_ops: &dyn FileOps,
+    path: CellPathRef<'_>,
+    abs_path: &AbsNormPath,
+) -> anyhow::Result<String> {
+    let bytes = read_file_bytes(file_ops, path, abs_path).await?;
+    String::from_utf8(bytes).map_err(|_| ReadFileError::NotUtf8(path.to_owned()).into())
+}
+
+/// Returns the directory, relative to the glob root, that all files matching `patterns` are under,
+/// so that globbing doesn't need to walk the whole tree.
+fn glob_walk_prefix<P: AsRef<str>>(patterns: &[P]) -> String {
+    let mut common: Option<Vec<&str>> = None;
+    for pattern in patterns {
+        let pattern = pattern.as_ref();
+        let literal = match pattern.find(|c: char| "*[]?".contains(c)) {
+            Some(idx) => &pattern[..idx],
+            None => pattern,
+        };
+        let dir: Vec<&str> = match literal.rfind('/') {
+            Some(idx) => literal[..idx].split('/').collect(),
+            None => Vec::new(),
+        };
+        common = Some(match common {
+            None => dir,
+            Some(common) => common
+                .into_iter()
+                .zip(dir)
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    common.unwrap_or_default().join("/")
+}
+
+/// Walks the source tree under `root`, returning the files whose root-relative paths match `spec`.
+/// Only directories on the way to or under `prefix` are listed. Directories are listed through
+/// DICE, so ignored paths are skipped and the result is invalidated when the tree changes.
+async fn glob_files(
+    file_ops: &dyn FileOps,
+    root: &CellPath,
+    prefix: &str,
+    spec: &GlobSpec,
+) -> anyhow::Result<FileSet> {
+    let prefix = ForwardRelativePath::new(prefix)?;
+
+    let mut found = Vec::new();
+    let mut queue = vec![root.clone()];
+    while let Some(dir) = queue.pop() {
+        for entry in file_ops.read_dir(dir.as_ref()).await?.included.iter() {
+            let path = dir.join(&entry.file_name);
+            let relative = path.as_ref().strip_prefix(root.as_ref())?;
+            if entry.file_type.is_dir() {
+                if relative.starts_with(prefix) || prefix.starts_with(relative) {
+                    queue.push(path);
+                }
+            } else if spec.matches(relative.as_str()) {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+
+    Ok(found.into_iter().map(FileNode).collect())
+}
+
 /// Provides some basic tracked filesystem access for bxl functions so that they can meaningfully
 /// detect simple properties of artifacts, and source directories.
 #[starlark_module]
@@ -177,6 +274,83 @@ fn fs_operations(builder: &mut MethodsBuilder) {
         }
     }
 
+    /// Reads the contents of a source file as a string. Errors if the file does not exist or is
+    /// not valid UTF-8; use `read_bytes` for binary files. Reads are tracked, so the bxl function
+    /// is rerun when the file changes.
+    ///
+    /// The input is a either a literal, a source artifact (via `[StarlarkArtifact]`), or a `[StarlarkFileNode]`.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_read(ctx):
+    ///     package = json.decode(ctx.fs.read("web/package.json"))
+    ///     ctx.output.print(package["name"])
+    /// ```
+    fn read<'v>(this: &BxlFilesystem<'v>, expr: FileExpr<'v>) -> anyhow::Result<String> {
+        let path = expr.get(this.dice, this.cell)?;
+        let abs_path = this
+            .project_fs
+            .resolve(&this.artifact_fs.resolve_cell_path(path.as_ref())?);
+
+        this.dice
+            .via_dice(async move |ctx| read_file(&ctx.file_ops(), path.as_ref(), &abs_path).await)
+    }
+
+    /// Reads the contents of a source file, which may be binary, as a list of its bytes (ints
+    /// from 0 to 255). Errors if the file does not exist. Reads are tracked, so the bxl function
+    /// is rerun when the file changes.
+    ///
+    /// The input is a either a literal, a source artifact (via `[StarlarkArtifact]`), or a `[StarlarkFileNode]`.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_read_bytes(ctx):
+    ///     magic = ctx.fs.read_bytes("web/logo.png")[:8]
+    ///     ctx.output.print(magic == [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a])
+    /// ```
+    fn read_bytes<'v>(this: &BxlFilesystem<'v>, expr: FileExpr<'v>) -> anyhow::Result<Vec<i32>> {
+        let path = expr.get(this.dice, this.cell)?;
+        let abs_path = this
+            .project_fs
+            .resolve(&this.artifact_fs.resolve_cell_path(path.as_ref())?);
+
+        let bytes = this.dice.via_dice(async move |ctx| {
+            read_file_bytes(&ctx.file_ops(), path.as_ref(), &abs_path).await
+        })?;
+        Ok(bytes.into_iter().map(i32::from).collect())
+    }
+
+    /// Returns the files matching any of the `include` glob patterns and none of the `exclude`
+    /// patterns, as a `[StarlarkFileSet]`. Patterns follow the same syntax as the `glob()` build file
+    /// function, and are relative to `root`, which defaults to the root of the current cell.
+    /// Paths ignored by `project.ignore` are never returned. Listings are tracked, so the bxl
+    /// function is rerun when matching files are added or removed.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_glob(ctx):
+    ///     for proto in ctx.fs.glob(["**/*.proto"], exclude = ["third-party/**"], root = "idl"):
+    ///         ctx.output.print(proto)
+    /// ```
+    fn glob<'v>(
+        this: &BxlFilesystem<'v>,
+        include: Vec<String>,
+        #[starlark(require = named, default = Vec::new())] exclude: Vec<String>,
+        #[starlark(require = named)] root: Option<FileExpr<'v>>,
+    ) -> anyhow::Result<StarlarkFileSet> {
+        let root = match root {
+            Some(root) => root.get(this.dice, this.cell)?,
+            None => CellPath::new(this.cell.name(), CellRelativePath::empty().to_buf()),
+        };
+        let spec = GlobSpec::new(&include, &exclude)?;
+
+        this.dice
+            .via_dice(async move |ctx| {
+                glob_files(&ctx.file_ops(), &root, &glob_walk_prefix(&include), &spec).await
+            })
+            .map(StarlarkFileSet::from)
+    }
+
     /// Returns whether the provided path is a dir. Returns false is the dir does not exist.
     /// The input is a either a literal, a source artifact (via `[StarlarkArtifact]`), or a `[StarlarkFileNode]`.
     ///
@@ -201,3 +375,109 @@ fn fs_operations(builder: &mut MethodsBuilder) {
         Ok(std::path::Path::is_file(this.resolve(expr)?.as_ref()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use buck2_common::file_ops::testing::TestFileOps;
+    use buck2_core::fs::project::ProjectRootTemp;
+    use buck2_core::fs::project_rel_path::ProjectRelativePath;
+
+    use super::*;
+
+    #[test]
+    fn test_glob_walk_prefix() {
+        assert_eq!("", glob_walk_prefix(&["**/*.proto"]));
+        assert_eq!("idl/foo", glob_walk_prefix(&["idl/foo/*.proto"]));
+        assert_eq!(
+            "idl",
+            glob_walk_prefix(&["idl/foo/*.proto", "idl/bar.proto"])
+        );
+        assert_eq!("", glob_walk_prefix(&["idl/*.proto", "src/*.rs"]));
+        assert_eq!("a", glob_walk_prefix(&["a/b*/c.txt"]));
+    }
+
+    #[tokio::test]
+    async fn test_glob_files() -> anyhow::Result<()> {
+        let file_ops = TestFileOps::new_with_files(BTreeMap::from_iter(
+            [
+                "root//idl/a.proto",
+                "root//idl/nested/b.proto",
+                "root//idl/third-party/c.proto",
+                "root//idl/README",
+                "root//src/d.proto",
+            ]
+            .into_iter()
+            .map(|p| (CellPath::testing_new(p), String::new())),
+        ));
+        let root = CellPath::testing_new("root//");
+
+        let include = ["idl/**/*.proto"];
+        let spec = GlobSpec::new(&include, &["idl/third-party/**"])?;
+        let found = glob_files(&file_ops, &root, &glob_walk_prefix(&include), &spec).await?;
+        assert_eq!(
+            vec![
+                CellPath::testing_new("root//idl/a.proto"),
+                CellPath::testing_new("root//idl/nested/b.proto"),
+            ],
+            found.iter().cloned().collect::<Vec<_>>()
+        );
+
+        let include = ["missing/*.proto"];
+        let spec = GlobSpec::new(&include, &[] as &[&str])?;
+        let found = glob_files(&file_ops, &root, &glob_walk_prefix(&include), &spec).await?;
+        assert_eq!(0, found.len());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_file() -> anyhow::Result<()> {
+        let fs = ProjectRootTemp::new()?;
+        fs.write_file("text.txt", "hello");
+        fs.path().write_file(
+            ProjectRelativePath::new("binary.bin")?,
+            [0x89, b'P', 0x00, 0xff],
+            false,
+        )?;
+        let file_ops = TestFileOps::new_with_files(BTreeMap::from_iter(
+            ["root//text.txt", "root//binary.bin"]
+                .into_iter()
+                .map(|p| (CellPath::testing_new(p), "hello".to_owned())),
+        ));
+        let read = |path: &'static str| {
+            let cell_path = CellPath::testing_new(&format!("root//{}", path));
+            let abs_path = fs
+                .path()
+                .root()
+                .join(ForwardRelativePath::new(path).unwrap());
+            let file_ops = &file_ops;
+            async move {
+                let bytes = read_file_bytes(file_ops, cell_path.as_ref(), &abs_path).await?;
+                let text = read_file(file_ops, cell_path.as_ref(), &abs_path).await;
+                anyhow::Ok((bytes, text))
+            }
+        };
+
+        let (bytes, text) = read("text.txt").await?;
+        assert_eq!(b"hello".to_vec(), bytes);
+        assert_eq!("hello", text?);
+
+        let (bytes, text) = read("binary.bin").await?;
+        assert_eq!(vec![0x89, b'P', 0x00, 0xff], bytes);
+        let err = text.unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<ReadFileError>(),
+                Some(ReadFileError::NotUtf8(_))
+            ),
+            "{:#}",
+            err
+        );
+
+        assert!(read("missing.txt").await.is_err());
+
+        Ok(())
+    }
+}
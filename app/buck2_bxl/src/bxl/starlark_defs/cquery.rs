@@ -629,6 +629,7 @@ fn register_cquery(builder: &mut MethodsBuilder) {
                         query,
                         &query_args,
                         this.target_platform.dupe(),
+                        Vec::new(),
                         target_universe.into_option().as_ref().map(|v| &v[..]),
                     )
                     .await?,
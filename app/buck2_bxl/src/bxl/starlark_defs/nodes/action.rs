@@ -10,18 +10,23 @@
 use std::sync::Arc;
 
 use allocative::Allocative;
+use buck2_build_api::actions::query::ActionQueryNode;
 use buck2_build_api::actions::RegisteredAction;
+use buck2_build_api::interpreter::rule_defs::artifact::StarlarkArtifact;
 use buck2_core::base_deferred_key::BaseDeferredKey;
 use buck2_interpreter::types::target_label::StarlarkConfiguredTargetLabel;
+use buck2_query::query::environment::QueryTarget;
 use derive_more::Display;
 use dupe::Dupe;
 use starlark::any::ProvidesStaticType;
+use starlark::collections::SmallMap;
 use starlark::environment::Methods;
 use starlark::environment::MethodsBuilder;
 use starlark::environment::MethodsStatic;
 use starlark::starlark_module;
 use starlark::starlark_simple_value;
 use starlark::starlark_type;
+use starlark::values::none::NoneOr;
 use starlark::values::NoSerialize;
 use starlark::values::StarlarkValue;
 use starlark::values::UnpackValue;
@@ -77,3 +82,170 @@ fn action_methods(builder: &mut MethodsBuilder) {
         }
     }
 }
+
+#[derive(Debug, Display, ProvidesStaticType, Allocative, StarlarkDocs)]
+#[derive(NoSerialize)]
+#[display(fmt = "{}", "self.0.action()")]
+#[starlark_docs(directory = "bxl")]
+pub struct StarlarkActionQueryNode(pub ActionQueryNode);
+
+starlark_simple_value!(StarlarkActionQueryNode);
+
+impl<'v> StarlarkValue<'v> for StarlarkActionQueryNode {
+    starlark_type!("action_query_node");
+
+    fn get_methods() -> Option<&'static Methods> {
+        static RES: MethodsStatic = MethodsStatic::new();
+        RES.methods(action_query_node_value_methods)
+    }
+}
+
+impl<'a> UnpackValue<'a> for StarlarkActionQueryNode {
+    fn expected() -> String {
+        "action query node".to_owned()
+    }
+
+    fn unpack_value(value: starlark::values::Value<'a>) -> Option<Self> {
+        value
+            .downcast_ref::<Self>()
+            .map(|value| Self(value.0.dupe()))
+    }
+}
+
+/// Methods for an action query node, as returned by `ctx.aquery()`.
+#[starlark_module]
+fn action_query_node_value_methods(builder: &mut MethodsBuilder) {
+    /// Gets the action this node represents.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_action(ctx):
+    ///     node = ctx.aquery().eval("//bin:the_binary")[0]
+    ///     ctx.output.print(node.action().owner())
+    /// ```
+    fn action(this: &StarlarkActionQueryNode) -> anyhow::Result<StarlarkAction> {
+        Ok(StarlarkAction(this.0.action()))
+    }
+
+    /// Returns the attributes of this action as a dict of strings, the same attributes that
+    /// `buck2 aquery --output-attribute` prints.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_attrs(ctx):
+    ///     node = ctx.aquery().eval("//bin:the_binary")[0]
+    ///     ctx.output.print(node.attrs()["executor_configuration"])
+    /// ```
+    fn attrs(this: &StarlarkActionQueryNode) -> anyhow::Result<SmallMap<String, String>> {
+        Ok(this.0.attrs().into_iter().collect())
+    }
+
+    /// The kind of the action, e.g. `run` or `write`.
+    #[starlark(attribute)]
+    fn kind(this: &StarlarkActionQueryNode) -> anyhow::Result<String> {
+        Ok(this.0.rule_type().into_owned())
+    }
+
+    /// The category of the action.
+    #[starlark(attribute)]
+    fn category(this: &StarlarkActionQueryNode) -> anyhow::Result<String> {
+        Ok(this.0.action().category().as_str().to_owned())
+    }
+
+    /// The identifier of the action, if it has one.
+    #[starlark(attribute)]
+    fn identifier(this: &StarlarkActionQueryNode) -> anyhow::Result<NoneOr<String>> {
+        Ok(match this.0.action().identifier() {
+            Some(identifier) => NoneOr::Other(identifier.to_owned()),
+            None => NoneOr::None,
+        })
+    }
+
+    /// Returns the fully expanded command line of the action, executable first, or `None` if the
+    /// action does not run a command.
+    ///
+    /// Sample usage:
+    /// ```text
+    /// def _impl_cmd(ctx):
+    ///     for node in ctx.aquery().eval("deps(//bin:the_binary)"):
+    ///         ctx.output.print(node.cmd())
+    /// ```
+    fn cmd(this: &StarlarkActionQueryNode) -> anyhow::Result<NoneOr<Vec<String>>> {
+        Ok(match this.0.expanded_command_line()? {
+            Some(cmd) => NoneOr::Other(cmd.exe.into_iter().chain(cmd.args).collect()),
+            None => NoneOr::None,
+        })
+    }
+
+    /// Returns the environment the action's command runs with, or `None` if the action does not
+    /// run a command.
+    fn env(this: &StarlarkActionQueryNode) -> anyhow::Result<NoneOr<SmallMap<String, String>>> {
+        Ok(match this.0.expanded_command_line()? {
+            Some(cmd) => NoneOr::Other(cmd.env.into_iter().collect()),
+            None => NoneOr::None,
+        })
+    }
+
+    /// Returns the artifacts the action directly consumes. Artifacts consumed through transitive
+    /// sets are not included; use `deps()` on the aquery ctx to find the actions producing them.
+    fn inputs(this: &StarlarkActionQueryNode) -> anyhow::Result<Vec<StarlarkArtifact>> {
+        Ok(this
+            .0
+            .direct_inputs()?
+            .into_iter()
+            .map(StarlarkArtifact::new)
+            .collect())
+    }
+
+    /// Returns the artifacts the action produces.
+    fn outputs(this: &StarlarkActionQueryNode) -> anyhow::Result<Vec<StarlarkArtifact>> {
+        Ok(this
+            .0
+            .action()
+            .outputs()?
+            .iter()
+            .map(|output| StarlarkArtifact::new(output.dupe().into()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buck2_build_api::actions::query::testing::testing_artifact_fs;
+    use buck2_build_api::actions::query::testing::ActionQueryNodeTestingExt;
+    use buck2_build_api::actions::query::ActionQueryNode;
+    use buck2_core::fs::project::ProjectRootTemp;
+    use dupe::Dupe;
+    use starlark::assert::Assert;
+    use starlark::environment::Module;
+
+    use crate::bxl::starlark_defs::nodes::action::StarlarkActionQueryNode;
+
+    #[test]
+    fn test_action_query_node_accessors() -> anyhow::Result<()> {
+        let project_root = ProjectRootTemp::new()?;
+        let fs = testing_artifact_fs(project_root.path().dupe());
+        let lib = ActionQueryNode::testing_new("lib", &["cc", "-c", "lib.c"], &[], fs.dupe());
+        let bin = ActionQueryNode::testing_new("bin", &["ld", "-o", "bin.out"], &[&lib], fs);
+
+        let module = Module::new();
+        module.set("bin", module.heap().alloc(StarlarkActionQueryNode(bin)));
+        let mut a = Assert::new();
+        a.module_add("nodes", module.freeze()?);
+        a.pass(
+            r#"
+load("nodes", "bin")
+assert_eq(bin.cmd(), ["ld", "-o", "bin.out"])
+assert_eq(bin.env(), {"NAME": "bin"})
+assert_eq([o.short_path for o in bin.outputs()], ["bin.out"])
+assert_eq([i.short_path for i in bin.inputs()], ["bin.c", "lib.out"])
+assert_eq(bin.kind, "run")
+assert_eq(bin.category, "testing")
+assert_eq(bin.identifier, "bin")
+assert_eq(bin.attrs()["executor_configuration"], "Local + use persistent workers false")
+assert_true(str(bin.action().owner()).startswith("cell//pkg:bin ("))
+"#,
+        );
+        Ok(())
+    }
+}
@@ -243,6 +243,7 @@ impl<'v> TargetExpr<'v, ConfiguredTargetNode> {
                         ctx.async_ctx.0,
                         loaded_patterns.iter_loaded_targets_by_package(),
                         target_platform.dupe(),
+                        &[],
                     )
                     .await?
                     .collect::<Result<Vec<_>, _>>()?;
@@ -126,6 +126,9 @@ async fn bxl(
     let project_root = server_ctx.project_root().to_string();
 
     let client_ctx = request.client_context()?;
+    if !client_ctx.modifiers.is_empty() {
+        return Err(BxlCommandError::Modifiers.into());
+    }
     let global_target_platform =
         target_platform_from_client_context(client_ctx, server_ctx, &ctx).await?;
 
@@ -313,6 +316,12 @@ async fn ensure_artifacts(
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+enum BxlCommandError {
+    #[error("`--modifier` is not supported by bxl, use `--target-platforms` instead")]
+    Modifiers,
+}
+
 #[derive(Debug, thiserror::Error)]
 enum BxlLabelError {
     #[error(
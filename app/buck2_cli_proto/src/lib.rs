@@ -294,5 +294,7 @@ define_request!(AllocativeRequest, has(context));
 define_request!(CleanStaleRequest, has(context));
 define_request!(FileStatusRequest, has(context));
 define_request!(TraceIoRequest, has(context));
+define_request!(MaterializerStatsRequest, has(context));
+define_request!(ExplainRecomputeRequest, has(context));
 
 define_request!(InstallRequest, has(context, build_options));
@@ -47,7 +47,14 @@ use multimap::MultiMap;
 use serde::Serialize;
 
 #[derive(Debug, clap::Parser)]
-#[clap(name = "build", about = "Build the specified targets")]
+#[clap(
+    name = "build",
+    about = "Build the specified targets",
+    // Accept negative patterns such as `-//third-party/...` without `--`. This is set on the
+    // command rather than on the patterns: clap would otherwise take every flag after a
+    // pattern as another pattern.
+    allow_hyphen_values = true
+)]
 pub struct BuildCommand {
     #[clap(flatten)]
     common_opts: CommonCommandOptions,
@@ -155,7 +162,11 @@ pub struct BuildCommand {
     )]
     output_path: Option<OutputDestinationArg>,
 
-    #[clap(name = "TARGET_PATTERNS", help = "Patterns to build")]
+    /// Patterns to build.
+    ///
+    /// Patterns prefixed with `-` exclude matching targets, e.g.
+    /// `buck2 build //... -//third-party/...`.
+    #[clap(name = "TARGET_PATTERNS")]
     patterns: Vec<String>,
 }
 
@@ -673,6 +684,29 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn negative_patterns() -> anyhow::Result<()> {
+        let opts = parse(&[
+            "//...",
+            "-//third-party/...",
+            "--show-output",
+            "-cell//foo:bar",
+            "-:baz",
+        ])?;
+        assert_eq!(
+            opts.patterns,
+            vec!["//...", "-//third-party/...", "-cell//foo:bar", "-:baz"]
+        );
+        assert!(opts.show_output);
+
+        // Known short flags are still flags.
+        let opts = parse(&["-//foo/...", "-j", "4", "//..."])?;
+        assert_eq!(opts.patterns, vec!["-//foo/...", "//..."]);
+        assert_eq!(opts.build_opts.num_threads, Some(4));
+
+        Ok(())
+    }
+
     #[test]
     fn infos_validation() -> anyhow::Result<()> {
         // Test duplicate args
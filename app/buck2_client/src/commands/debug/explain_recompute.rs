@@ -0,0 +1,83 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_cli_proto::ExplainRecomputeRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonDaemonCommandOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::daemon::client::StdoutPartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+#[derive(Debug, clap::Parser)]
+pub struct ExplainRecomputeCommand {
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+
+    /// The target to explain, configured with `--target-platforms` like for `buck2 build`.
+    #[clap(value_name = "TARGET")]
+    target: String,
+
+    /// Only explain the action of the target with this action key.
+    #[clap(long)]
+    action_key: Option<String>,
+}
+
+#[async_trait]
+impl StreamingCommand for ExplainRecomputeCommand {
+    const COMMAND_NAME: &'static str = "explain-recompute";
+
+    fn existing_only() -> bool {
+        true
+    }
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(
+            &self.common_opts.config_opts,
+            matches,
+            self.sanitized_argv(),
+        )?;
+        buckd
+            .with_flushing()
+            .explain_recompute(
+                ExplainRecomputeRequest {
+                    context: Some(context),
+                    target: self.target,
+                    action_key: self.action_key,
+                },
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+                &mut StdoutPartialResultHandler,
+            )
+            .await??;
+
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonDaemonCommandOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn common_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+}
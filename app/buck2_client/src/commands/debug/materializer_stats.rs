@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use async_trait::async_trait;
+use buck2_cli_proto::MaterializerStatsRequest;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::CommonBuildConfigurationOptions;
+use buck2_client_ctx::common::CommonCommandOptions;
+use buck2_client_ctx::common::CommonConsoleOptions;
+use buck2_client_ctx::common::CommonDaemonCommandOptions;
+use buck2_client_ctx::daemon::client::BuckdClientConnector;
+use buck2_client_ctx::daemon::client::StdoutPartialResultHandler;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_client_ctx::streaming::StreamingCommand;
+
+#[derive(Debug, clap::Parser)]
+pub struct MaterializerStatsCommand {
+    #[clap(flatten)]
+    common_opts: CommonCommandOptions,
+
+    /// Number of largest subtrees of buck-out to report.
+    #[clap(long, default_value = "10")]
+    largest_subtrees: u64,
+
+    /// Hash materialized artifacts and report the ones whose contents on disk no longer match
+    /// their recorded digest. This reads all of buck-out and may be slow.
+    #[clap(long)]
+    verify_digests: bool,
+
+    /// Evict artifacts that don't match their recorded digest, so that they are materialized again
+    /// the next time they are needed. Implies `--verify-digests`.
+    #[clap(long)]
+    repair: bool,
+}
+
+#[async_trait]
+impl StreamingCommand for MaterializerStatsCommand {
+    const COMMAND_NAME: &'static str = "materializer-stats";
+
+    fn existing_only() -> bool {
+        true
+    }
+
+    async fn exec_impl(
+        self,
+        buckd: &mut BuckdClientConnector,
+        matches: &clap::ArgMatches,
+        ctx: &mut ClientCommandContext<'_>,
+    ) -> ExitResult {
+        let context = ctx.client_context(
+            &self.common_opts.config_opts,
+            matches,
+            self.sanitized_argv(),
+        )?;
+        buckd
+            .with_flushing()
+            .materializer_stats(
+                MaterializerStatsRequest {
+                    context: Some(context),
+                    largest_subtrees: self.largest_subtrees,
+                    verify_digests: self.verify_digests,
+                    repair: self.repair,
+                },
+                ctx.stdin()
+                    .console_interaction_stream(&self.common_opts.console_opts),
+                &mut StdoutPartialResultHandler,
+            )
+            .await??;
+
+        ExitResult::success()
+    }
+
+    fn console_opts(&self) -> &CommonConsoleOptions {
+        &self.common_opts.console_opts
+    }
+
+    fn event_log_opts(&self) -> &CommonDaemonCommandOptions {
+        &self.common_opts.event_log_opts
+    }
+
+    fn common_opts(&self) -> &CommonBuildConfigurationOptions {
+        &self.common_opts.config_opts
+    }
+}
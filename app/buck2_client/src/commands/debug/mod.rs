@@ -14,11 +14,13 @@ use buck2_client_ctx::streaming::BuckSubcommand;
 use chrome_trace::ChromeTraceCommand;
 use crash::CrashCommand;
 use dice_dump::DiceDumpCommand;
+use explain_recompute::ExplainRecomputeCommand;
 use file_status::FileStatusCommand;
 use flush_dep_files::FlushDepFilesCommand;
 use heap_dump::HeapDumpCommand;
 use internal_version::InternalVersionCommand;
 use materialize::MaterializeCommand;
+use materializer_stats::MaterializerStatsCommand;
 use replay::ReplayCommand;
 
 use crate::commands::debug::allocative::AllocativeCommand;
@@ -40,12 +42,14 @@ mod crash;
 mod daemon_dir;
 mod dice_dump;
 mod exe;
+mod explain_recompute;
 mod file_status;
 mod flush_dep_files;
 mod heap_dump;
 mod internal_version;
 mod log_perf;
 mod materialize;
+mod materializer_stats;
 mod persist_event_logs;
 pub mod replay;
 mod segfault;
@@ -68,6 +72,11 @@ pub enum DebugCommand {
     AllocatorStats(AllocatorStatsCommand),
     /// Dump the DICE graph to a file and saves it to disk.
     DiceDump(DiceDumpCommand),
+    /// Explains why a target's configured node, analysis and actions were last recomputed, as the
+    /// chain of invalidations from the changed file or config value, for the most recent versions
+    /// that invalidated anything. Requires `buck2.dice = modern` and
+    /// `buck2.dice_record_invalidations = true`.
+    ExplainRecompute(ExplainRecomputeCommand),
     /// Replay a previous command by reading off from an event log.
     ///
     /// This does not interact (or even launch) a daemon.
@@ -81,6 +90,9 @@ pub enum DebugCommand {
     FlushDepFiles(FlushDepFilesCommand),
     /// Forces materialization of a path, even on the deferred materializer
     Materialize(MaterializeCommand),
+    /// Reports on the artifacts tracked by the deferred materializer, and optionally checks
+    /// their contents on disk.
+    MaterializerStats(MaterializerStatsCommand),
     // Upload RE logs given an RE session ID
     UploadReLogs(UploadReLogsCommand),
     /// Validates that Buck2 and disk agree on the state of files.
@@ -110,6 +122,7 @@ impl DebugCommand {
         let matches = matches.subcommand().expect("subcommand not found").1;
         match self {
             DebugCommand::DiceDump(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::ExplainRecompute(cmd) => cmd.exec(matches, ctx),
             DebugCommand::Crash(cmd) => cmd.exec(matches, ctx),
             DebugCommand::HeapDump(cmd) => cmd.exec(matches, ctx),
             DebugCommand::AllocatorStats(cmd) => cmd.exec(matches, ctx),
@@ -121,6 +134,7 @@ impl DebugCommand {
             DebugCommand::WhatRan(cmd) => cmd.exec(matches, ctx),
             DebugCommand::LastLog(cmd) => cmd.exec(matches, ctx),
             DebugCommand::Materialize(cmd) => cmd.exec(matches, ctx),
+            DebugCommand::MaterializerStats(cmd) => cmd.exec(matches, ctx),
             DebugCommand::UploadReLogs(cmd) => cmd.exec(matches, ctx),
             DebugCommand::DaemonDir(cmd) => cmd.exec(matches, ctx),
             DebugCommand::Exe(cmd) => cmd.exec(matches, ctx),
@@ -23,7 +23,11 @@ use buck2_client_ctx::streaming::StreamingCommand;
 use gazebo::prelude::*;
 
 #[derive(Debug, clap::Parser)]
-#[clap(name = "install", about = "Build and install an application")]
+#[clap(
+    name = "install",
+    about = "Build and install an application",
+    allow_hyphen_values = true
+)]
 pub struct InstallCommand {
     #[clap(flatten)]
     common_opts: CommonCommandOptions,
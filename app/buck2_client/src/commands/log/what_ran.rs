@@ -147,18 +147,42 @@ trait WhatRanCommandImplementation: Default {
     }
 }
 
+/// Maps spans nested within actions to the action they belong to. Most commands are reported by
+/// spans whose parent is the action, but some events, such as RE retries, happen deeper.
+#[derive(Default)]
+struct NestedSpans {
+    actions: HashMap<u64, u64>,
+}
+
+impl NestedSpans {
+    /// The span of the action that `span_id` belongs to, or `span_id` itself.
+    fn resolve(&self, span_id: u64) -> u64 {
+        self.actions.get(&span_id).copied().unwrap_or(span_id)
+    }
+
+    fn record(&mut self, event: &buck2_data::BuckEvent, is_action: impl Fn(u64) -> bool) {
+        if let Some(buck2_data::buck_event::Data::SpanStart(..)) = &event.data {
+            let parent = self.resolve(event.parent_id);
+            if is_action(parent) {
+                self.actions.insert(event.span_id, parent);
+            }
+        }
+    }
+}
+
 /// The state for a WhatRan command. This is all the events we have seen that are
 /// WhatRanRelevantActions. This emits the actions immediately.
 #[derive(Default)]
 pub struct WhatRanImpl {
     /// Maps action spans to their details.
     known_actions: HashMap<u64, Box<buck2_data::BuckEvent>>,
+    nested_spans: NestedSpans,
 }
 
 impl WhatRanState<u64> for WhatRanImpl {
     fn get(&self, span_id: u64) -> Option<WhatRanRelevantAction<'_>> {
         self.known_actions
-            .get(&span_id)
+            .get(&self.nested_spans.resolve(span_id))
             .and_then(|e| e.data.as_ref())
             .and_then(WhatRanRelevantAction::from_buck_data)
     }
@@ -180,6 +204,10 @@ impl WhatRanCommandImplementation for WhatRanImpl {
 
             if WhatRanRelevantAction::from_buck_data(data).is_some() {
                 self.known_actions.insert(event.span_id, event);
+            } else {
+                let known_actions = &self.known_actions;
+                self.nested_spans
+                    .record(&event, |span_id| known_actions.contains_key(&span_id));
             }
         }
 
@@ -193,6 +221,7 @@ impl WhatRanCommandImplementation for WhatRanImpl {
 pub struct WhatFailedImpl {
     /// Maps action spans to their details.
     known_actions: HashMap<u64, WhatFailedEntry>,
+    nested_spans: NestedSpans,
 }
 
 #[allow(clippy::vec_box)]
@@ -207,7 +236,7 @@ struct WhatFailedEntry {
 impl WhatRanState<u64> for WhatFailedImpl {
     fn get(&self, span_id: u64) -> Option<WhatRanRelevantAction<'_>> {
         self.known_actions
-            .get(&span_id)
+            .get(&self.nested_spans.resolve(span_id))
             .and_then(|e| e.event.data.as_ref())
             .and_then(WhatRanRelevantAction::from_buck_data)
     }
@@ -236,8 +265,13 @@ impl WhatRanCommandImplementation for WhatFailedImpl {
                 return Ok(());
             }
 
+            let known_actions = &self.known_actions;
+            self.nested_spans
+                .record(&event, |span_id| known_actions.contains_key(&span_id));
+
             if CommandReproducer::from_buck_data(data, options).is_some() {
-                if let Some(entry) = self.known_actions.get_mut(&event.parent_id) {
+                let action = self.nested_spans.resolve(event.parent_id);
+                if let Some(entry) = self.known_actions.get_mut(&action) {
                     entry.reproducers.push(event);
                 }
                 return Ok(());
@@ -249,6 +283,9 @@ impl WhatRanCommandImplementation for WhatFailedImpl {
                         if action.failed =>
                     {
                         if let Some(entry) = self.known_actions.remove(&event.span_id) {
+                            self.nested_spans
+                                .actions
+                                .retain(|_, action| *action != event.span_id);
                             let action = WhatRanRelevantAction::from_buck_data(
                                 entry.event.data.as_ref().expect("Checked above"),
                             );
@@ -315,6 +352,12 @@ impl WhatRanOutputWriter for LogCommandOutputFormat {
                             .map(|entry| (entry.key.as_ref(), entry.value.as_ref()))
                             .collect(),
                     },
+                    CommandReproducer::ReRetry(retry) => JsonReproducer::ReRetry {
+                        rpc: &retry.rpc,
+                        digest: retry.action_digest.as_deref(),
+                        attempt: retry.attempt,
+                        error: &retry.error,
+                    },
                 };
 
                 let command = JsonCommand {
@@ -394,6 +437,13 @@ mod json_reproducer {
             command: Cow<'a, [String]>,
             env: IndexMap<&'a str, &'a str>,
         },
+        ReRetry {
+            rpc: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            digest: Option<&'a str>,
+            attempt: u64,
+            error: &'a str,
+        },
     }
 }
 
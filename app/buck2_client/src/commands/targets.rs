@@ -66,7 +66,11 @@ enum TargetHashFunction {
 }
 
 #[derive(Debug, clap::Parser)]
-#[clap(name = "targets", about = "Show details about the specified targets")]
+#[clap(
+    name = "targets",
+    about = "Show details about the specified targets",
+    allow_hyphen_values = true
+)]
 pub struct TargetsCommand {
     #[clap(flatten)]
     common_opts: CommonCommandOptions,
@@ -169,7 +173,10 @@ pub struct TargetsCommand {
     #[clap(long, short = 'o', value_name = "PATH")]
     output: Option<PathArg>,
 
-    /// Patterns to interpret
+    /// Patterns to interpret.
+    ///
+    /// Patterns prefixed with `-` exclude matching targets, e.g.
+    /// `buck2 targets //... -//third-party/...`.
     #[clap(name = "TARGET_PATTERNS")]
     patterns: Vec<String>,
 
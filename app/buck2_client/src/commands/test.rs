@@ -66,7 +66,11 @@ fn print_error_counter(
     Ok(())
 }
 #[derive(Debug, clap::Parser)]
-#[clap(name = "test", about = "Build and test the specified targets")]
+#[clap(
+    name = "test",
+    about = "Build and test the specified targets",
+    allow_hyphen_values = true
+)]
 pub struct TestCommand {
     #[clap(flatten)]
     common_opts: CommonCommandOptions,
@@ -49,6 +49,9 @@ pub struct ClientCommandContext<'a> {
     pub stdin: &'a mut Stdin,
     pub restarter: &'a mut Restarter,
     pub restarted_trace_id: Option<TraceId>,
+    /// Whether a subscriber shows the output of local commands while they run, see
+    /// `EventSubscriber::shows_local_command_output`.
+    pub local_command_output: bool,
 }
 
 impl<'a> ClientCommandContext<'a> {
@@ -95,6 +98,7 @@ impl<'a> ClientCommandContext<'a> {
         Ok(ClientContext {
             config_overrides: config_opts.config_overrides(arg_matches)?,
             target_platform: config_opts.target_platforms.clone().unwrap_or_default(),
+            modifiers: config_opts.modifiers.clone(),
             host_platform: match config_opts.host_platform_override() {
                 HostPlatformOverride::Default => GrpcHostPlatformOverride::DefaultPlatform,
                 HostPlatformOverride::Linux => GrpcHostPlatformOverride::Linux,
@@ -145,6 +149,7 @@ impl<'a> ClientCommandContext<'a> {
                 .to_owned(),
             config_overrides: Default::default(),
             target_platform: Default::default(),
+            modifiers: Vec::new(),
             host_platform: Default::default(),
             host_arch: Default::default(),
             host_xcode_version: Default::default(),
@@ -159,6 +164,7 @@ impl<'a> ClientCommandContext<'a> {
             argfiles: Vec::new(),
             buck2_hard_error: BUCK2_HARD_ERROR_ENV_VAR.get()?.cloned().unwrap_or_default(),
             exit_when_different_state: false,
+            local_command_output: self.local_command_output,
         })
     }
 
@@ -55,6 +55,8 @@ pub enum ConsoleType {
     Super,
     Auto,
     None,
+    /// One JSON object per line on stderr, for tools that wrap buck2.
+    Jsonl,
 }
 
 #[derive(
@@ -128,6 +130,15 @@ pub struct CommonDaemonCommandOptions {
     /// regarding the stability of the format.
     #[clap(long, value_name = "PATH")]
     pub(crate) unstable_write_invocation_record: Option<PathArg>,
+
+    /// Write a summary of the command as JSON to this path: actions by how they executed, the
+    /// slowest actions, the critical path, bytes uploaded and downloaded, and peak memory.
+    #[clap(long, value_name = "PATH")]
+    pub(crate) build_report_summary: Option<PathArg>,
+
+    /// Write the same summary as `--build-report-summary`, as markdown, to this path.
+    #[clap(long, value_name = "PATH")]
+    pub(crate) build_report_summary_markdown: Option<PathArg>,
 }
 
 impl CommonDaemonCommandOptions {
@@ -137,6 +148,8 @@ impl CommonDaemonCommandOptions {
             no_event_log: false,
             write_build_id: None,
             unstable_write_invocation_record: None,
+            build_report_summary: None,
+            build_report_summary_markdown: None,
         };
         &DEFAULT
     }
@@ -172,6 +185,12 @@ pub struct CommonBuildConfigurationOptions {
     )]
     pub target_platforms: Option<String>,
 
+    /// Configuration modifier (a constraint value, config setting or alias) applied on top of
+    /// the target platform. May be specified multiple times, later modifiers take precedence.
+    /// Modifiers can also be attached to individual patterns: `//foo:bar?linux-arm64`.
+    #[clap(long = "modifier", value_name = "MODIFIER", number_of_values = 1)]
+    pub modifiers: Vec<String>,
+
     #[clap(long, ignore_case = true, value_name = "HOST", arg_enum)]
     fake_host: Option<HostPlatformOverride>,
 
@@ -311,6 +330,7 @@ impl CommonBuildConfigurationOptions {
             config_values: vec![],
             config_files: vec![],
             target_platforms: None,
+            modifiers: vec![],
             fake_host: None,
             fake_arch: None,
             fake_xcode_version: None,
@@ -409,6 +429,22 @@ pub struct CommonBuildOptions {
     /// which are skipped unconditionally.
     #[clap(long)]
     skip_incompatible_targets: bool,
+
+    /// Run every action twice, locally and without caching, and fail actions whose outputs differ
+    /// between the two runs. Only actions that are executed by this command are checked, so
+    /// outputs that are already up to date are not checked.
+    #[clap(long)]
+    check_determinism: bool,
+
+    /// Only run actions of this category twice when checking determinism (e.g. `cxx_compile`).
+    /// Can be passed multiple times.
+    #[clap(
+        long,
+        value_name = "CATEGORY",
+        requires("check-determinism"),
+        number_of_values = 1
+    )]
+    check_determinism_category: Vec<String>,
 }
 
 impl CommonBuildOptions {
@@ -453,6 +489,8 @@ impl CommonBuildOptions {
             keep_going: self.keep_going,
             skip_missing_targets: self.skip_missing_targets,
             skip_incompatible_targets: self.skip_incompatible_targets,
+            check_determinism: self.check_determinism,
+            check_determinism_categories: self.check_determinism_category.clone(),
         }
     }
 }
@@ -541,7 +579,7 @@ impl CommonConsoleOptions {
             ConsoleType::Super => true,
             ConsoleType::SimpleNoTty => false,
             ConsoleType::SimpleTty => true,
-            ConsoleType::None => false,
+            ConsoleType::None | ConsoleType::Jsonl => false,
         };
         if is_tty {
             FinalConsole::new_with_tty()
@@ -77,6 +77,14 @@ impl BuckdClientConnector {
             .filter_map(|s| s.as_error_observer())
     }
 
+    pub fn shows_local_command_output(&self) -> bool {
+        self.client
+            .events_ctx
+            .subscribers
+            .iter()
+            .any(|s| s.shows_local_command_output())
+    }
+
     pub fn collect_error_cause(&self) -> ErrorCause {
         if let Some(obs) = self.error_observers().next() {
             return obs.error_cause();
@@ -571,6 +579,18 @@ impl<'a> FlushingBuckdClient<'a> {
     wrap_method!(status(snapshot: bool), StatusResponse);
     wrap_method!(set_log_filter(log_filter: SetLogFilterRequest), ());
     stream_method!(trace_io, TraceIoRequest, TraceIoResponse, NoPartialResult);
+    stream_method!(
+        materializer_stats,
+        MaterializerStatsRequest,
+        GenericResponse,
+        buck2_cli_proto::StdoutBytes
+    );
+    stream_method!(
+        explain_recompute,
+        ExplainRecomputeRequest,
+        GenericResponse,
+        buck2_cli_proto::StdoutBytes
+    );
 }
 
 /// Create a stream that is sent over as a parameter via GRPC to the daemon.
@@ -31,6 +31,7 @@ use crate::exit_result::ExitResult;
 use crate::exit_result::FailureExitCode;
 use crate::subscribers::get::get_console_with_root;
 use crate::subscribers::get::try_get_build_id_writer;
+use crate::subscribers::get::try_get_build_summary_writer;
 use crate::subscribers::get::try_get_event_log_subscriber;
 use crate::subscribers::get::try_get_re_log_subscriber;
 use crate::subscribers::recorder::try_get_invocation_recorder;
@@ -78,6 +79,9 @@ fn default_subscribers<T: StreamingCommand>(
     if let Some(build_id_writer) = try_get_build_id_writer(cmd.event_log_opts(), ctx)? {
         subscribers.push(build_id_writer)
     }
+    if let Some(build_summary_writer) = try_get_build_summary_writer(cmd.event_log_opts(), ctx)? {
+        subscribers.push(build_summary_writer)
+    }
     if let Some(recorder) = try_get_invocation_recorder(
         ctx,
         cmd.event_log_opts(),
@@ -189,6 +193,7 @@ impl<T: StreamingCommand> BuckSubcommand for T {
                     }
                 };
 
+                ctx.local_command_output = buckd.shows_local_command_output();
                 let command_result = self.exec_impl(&mut buckd, matches, &mut ctx).await;
                 let command_result = command_result
                     .categorized_or_else(|| gen_error_exit_code(buckd.collect_error_cause()));
@@ -0,0 +1,61 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_core::fs::paths::abs_path::AbsPathBuf;
+use buck2_event_observer::build_summary::BuildSummaryState;
+use buck2_events::BuckEvent;
+
+use crate::subscribers::subscriber::EventSubscriber;
+
+/// Writes a summary of the command, as JSON and/or markdown, once it's over.
+pub struct BuildSummaryWriter {
+    json_path: Option<AbsPathBuf>,
+    markdown_path: Option<AbsPathBuf>,
+    state: BuildSummaryState,
+}
+
+impl BuildSummaryWriter {
+    pub fn new(json_path: Option<AbsPathBuf>, markdown_path: Option<AbsPathBuf>) -> Self {
+        Self {
+            json_path,
+            markdown_path,
+            state: BuildSummaryState::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for BuildSummaryWriter {
+    async fn handle_events(&mut self, events: &[Arc<BuckEvent>]) -> anyhow::Result<()> {
+        for event in events {
+            self.state.update(event)?;
+        }
+        Ok(())
+    }
+
+    async fn exit(&mut self) -> anyhow::Result<()> {
+        let summary = self.state.summary();
+        if let Some(path) = &self.json_path {
+            let json = serde_json::to_string_pretty(&summary)?;
+            tokio::fs::write(path, json)
+                .await
+                .with_context(|| format!("Error writing build summary to `{}`", path))?;
+        }
+        if let Some(path) = &self.markdown_path {
+            tokio::fs::write(path, summary.to_markdown())
+                .await
+                .with_context(|| format!("Error writing build summary to `{}`", path))?;
+        }
+        Ok(())
+    }
+}
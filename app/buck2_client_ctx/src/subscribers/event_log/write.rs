@@ -448,6 +448,15 @@ impl WriteEventLog {
         let mut event_refs = Vec::new();
         let mut first = true;
         for event in events {
+            // The output of running commands is only sent for consoles to show it. It's in the
+            // event ending the command's span once it finishes.
+            if let buck2_data::buck_event::Data::Instant(buck2_data::InstantEvent {
+                data: Some(buck2_data::instant_event::Data::LocalCommandOutput(..)),
+            }) = event.data()
+            {
+                continue;
+            }
+
             if first {
                 self.ensure_log_writers_opened(event).await?;
                 first = false;
@@ -20,7 +20,9 @@ use crate::client_ctx::ClientCommandContext;
 use crate::common::CommonDaemonCommandOptions;
 use crate::common::ConsoleType;
 use crate::subscribers::build_id_writer::BuildIdWriter;
+use crate::subscribers::build_summary_writer::BuildSummaryWriter;
 use crate::subscribers::event_log::subscriber::EventLog;
+use crate::subscribers::jsonl_console::JsonlConsole;
 use crate::subscribers::re_log::ReLog;
 use crate::subscribers::simpleconsole::SimpleConsole;
 use crate::subscribers::subscriber::EventSubscriber;
@@ -99,6 +101,9 @@ pub fn get_console_with_root(
                 )))),
             }
         }
+        ConsoleType::Jsonl => Ok(Some(Box::new(UnpackingEventSubscriberAsEventSubscriber(
+            JsonlConsole::new(trace_id),
+        )))),
         ConsoleType::None => Ok(None),
     }
 }
@@ -153,3 +158,24 @@ pub(crate) fn try_get_build_id_writer(
         Ok(None)
     }
 }
+
+pub(crate) fn try_get_build_summary_writer(
+    opts: &CommonDaemonCommandOptions,
+    ctx: &ClientCommandContext,
+) -> anyhow::Result<Option<Box<dyn EventSubscriber>>> {
+    let json_path = opts
+        .build_report_summary
+        .as_ref()
+        .map(|p| p.resolve(&ctx.working_dir));
+    let markdown_path = opts
+        .build_report_summary_markdown
+        .as_ref()
+        .map(|p| p.resolve(&ctx.working_dir));
+    if json_path.is_none() && markdown_path.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Box::new(BuildSummaryWriter::new(
+        json_path,
+        markdown_path,
+    ))))
+}
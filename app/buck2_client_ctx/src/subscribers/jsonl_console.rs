@@ -0,0 +1,521 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! `--console=jsonl`: progress as one JSON object per line on stderr, for tools that wrap buck2.
+//!
+//! Unlike the `BuckEvent` protobuf, this schema is stable, and documented in
+//! `docs/build_observability/jsonl_console.md`. Keep the two in sync.
+
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_common::convert::ProstDurationExt;
+use buck2_event_observer::display;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_event_observer::event_observer::EventObserver;
+use buck2_event_observer::event_observer::NoopEventObserverExtra;
+use buck2_event_observer::what_ran::local_command_to_string;
+use buck2_events::BuckEvent;
+use buck2_wrapper_common::invocation_id::TraceId;
+use dupe::Dupe;
+
+use crate::subscribers::simpleconsole::sanitize_output_colors;
+use crate::subscribers::subscriber::Tick;
+use crate::subscribers::subscriber_unpack::UnpackingEventSubscriber;
+
+/// Bumped when a field changes name or meaning. Adding fields doesn't bump it.
+const SCHEMA_VERSION: u32 = 1;
+
+/// How often to emit a `progress` record.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, serde::Serialize)]
+struct ActionIdentity {
+    id: Option<String>,
+    target: String,
+    category: String,
+    identifier: String,
+}
+
+impl ActionIdentity {
+    fn new(
+        event: &BuckEvent,
+        key: Option<&buck2_data::ActionKey>,
+        name: Option<&buck2_data::ActionName>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: event.span_id().map(|id| id.to_string()),
+            target: match key {
+                Some(key) => display::display_action_key(key, TargetDisplayOptions::for_log())?,
+                None => String::new(),
+            },
+            category: name.map(|n| n.category.clone()).unwrap_or_default(),
+            identifier: name.map(|n| n.identifier.clone()).unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+struct ActionProgress {
+    running: usize,
+    finished: usize,
+    local: u64,
+    remote: u64,
+    cache_hits: u64,
+    fallback: u64,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct TestProgress {
+    discovered: u64,
+    pass: u64,
+    fail: u64,
+    skipped: u64,
+    fatal: u64,
+    timeout: u64,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record {
+    CommandStart {
+        schema_version: u32,
+        build_id: String,
+    },
+    ActionStart {
+        #[serde(flatten)]
+        action: ActionIdentity,
+    },
+    ActionEnd {
+        #[serde(flatten)]
+        action: ActionIdentity,
+        status: &'static str,
+        execution_kind: &'static str,
+        duration_ms: Option<u64>,
+    },
+    ActionError {
+        #[serde(flatten)]
+        action: ActionIdentity,
+        reason: String,
+        command: Option<String>,
+        action_digest: Option<String>,
+        exit_code: Option<i32>,
+        stdout: Option<String>,
+        stderr: Option<String>,
+    },
+    TestResult {
+        target: Option<String>,
+        name: String,
+        status: &'static str,
+        duration_ms: Option<u64>,
+        message: Option<String>,
+        details: String,
+    },
+    Error {
+        message: String,
+        category: Option<&'static str>,
+        cause: Option<&'static str>,
+    },
+    Message {
+        message: String,
+    },
+    Progress {
+        actions: ActionProgress,
+        cache_hit_percentage: u8,
+        tests: TestProgress,
+        rss_bytes: Option<u64>,
+        cpu_percent: Option<u32>,
+    },
+    CommandEnd {
+        success: bool,
+    },
+}
+
+#[derive(serde::Serialize)]
+struct Line<'a> {
+    timestamp: String,
+    #[serde(flatten)]
+    record: &'a Record,
+}
+
+fn emit(record: &Record) -> anyhow::Result<()> {
+    let line = serde_json::to_string(&Line {
+        timestamp: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+        record,
+    })
+    .context("Error serializing console record")?;
+    crate::eprintln!("{}", line)
+}
+
+fn execution_kind(kind: i32) -> &'static str {
+    use buck2_data::ActionExecutionKind;
+
+    match ActionExecutionKind::from_i32(kind) {
+        Some(ActionExecutionKind::Local) => "local",
+        Some(ActionExecutionKind::Remote) => "remote",
+        Some(ActionExecutionKind::ActionCache) => "cache",
+        Some(ActionExecutionKind::Skipped) => "dep_file",
+        Some(ActionExecutionKind::Simple) => "simple",
+        Some(ActionExecutionKind::Deferred) => "deferred",
+        Some(ActionExecutionKind::NotSet) | None => "unknown",
+    }
+}
+
+fn error_category(category: i32) -> &'static str {
+    use buck2_data::ErrorCategory;
+
+    match ErrorCategory::from_i32(category) {
+        Some(ErrorCategory::User) => "user",
+        Some(ErrorCategory::Infra) => "infra",
+        Some(ErrorCategory::Environment) => "environment",
+        None => "unknown",
+    }
+}
+
+fn error_cause(cause: i32) -> &'static str {
+    use buck2_data::ErrorCause;
+
+    match ErrorCause::from_i32(cause) {
+        Some(ErrorCause::InvalidPackage) => "invalid_package",
+        Some(ErrorCause::DaemonIsBusy) => "daemon_is_busy",
+        Some(ErrorCause::ReUnavailable) => "re_unavailable",
+        Some(ErrorCause::MaterializationFailed) => "materialization_failed",
+        Some(ErrorCause::ActionCommandFailed) => "action_command_failed",
+        Some(ErrorCause::FileWatcherUnavailable) => "file_watcher_unavailable",
+        None => "unknown",
+    }
+}
+
+fn test_status(status: i32) -> &'static str {
+    use buck2_data::TestStatus;
+
+    match TestStatus::from_i32(status) {
+        Some(TestStatus::Pass) => "pass",
+        Some(TestStatus::Fail) => "fail",
+        Some(TestStatus::Skip) => "skip",
+        Some(TestStatus::Omitted) => "omitted",
+        Some(TestStatus::Fatal) => "fatal",
+        Some(TestStatus::Timeout) => "timeout",
+        Some(TestStatus::Rerun) => "rerun",
+        Some(TestStatus::ListingSuccess) => "listing_success",
+        Some(TestStatus::ListingFailed) => "listing_failed",
+        Some(TestStatus::Unknown) | Some(TestStatus::NotSetTestStatus) | None => "unknown",
+    }
+}
+
+fn duration_ms(duration: Option<&prost_types::Duration>) -> anyhow::Result<Option<u64>> {
+    duration
+        .map(|d| Ok(d.try_into_duration()?.as_millis() as u64))
+        .transpose()
+}
+
+/// Emits a stable, machine-readable stream of what the command is doing.
+pub(crate) struct JsonlConsole {
+    observer: EventObserver<NoopEventObserverExtra>,
+    /// When the last `progress` record was emitted. `None` until the command starts.
+    last_progress: Option<Instant>,
+}
+
+impl JsonlConsole {
+    pub(crate) fn new(trace_id: TraceId) -> Self {
+        Self {
+            observer: EventObserver::new(trace_id),
+            last_progress: None,
+        }
+    }
+
+    fn progress(&self) -> Record {
+        let spans = self.observer.spans();
+        let action_stats = self.observer.action_stats();
+        let test_state = self.observer.test_state();
+        let snapshots = self.observer.two_snapshots();
+        Record::Progress {
+            actions: ActionProgress {
+                running: spans.roots_ongoing(),
+                finished: spans.roots_completed(),
+                local: action_stats.local_actions,
+                remote: action_stats.remote_actions,
+                cache_hits: action_stats.cached_actions,
+                fallback: action_stats.fallback_actions,
+            },
+            cache_hit_percentage: action_stats.action_cache_hit_percentage(),
+            tests: TestProgress {
+                discovered: test_state.discovered,
+                pass: test_state.pass,
+                fail: test_state.fail,
+                skipped: test_state.skipped,
+                fatal: test_state.fatal,
+                timeout: test_state.timeout,
+            },
+            rss_bytes: snapshots
+                .last
+                .as_ref()
+                .and_then(|(_, snapshot)| snapshot.buck2_rss),
+            cpu_percent: snapshots.cpu_percents(),
+        }
+    }
+
+    fn emit_progress(&mut self) -> anyhow::Result<()> {
+        self.last_progress = Some(Instant::now());
+        emit(&self.progress())
+    }
+}
+
+#[async_trait]
+impl UnpackingEventSubscriber for JsonlConsole {
+    async fn handle_output(&mut self, raw_output: &[u8]) -> anyhow::Result<()> {
+        // Command output goes to stdout as usual, and the records to stderr.
+        crate::stdio::print_bytes(raw_output)?;
+        crate::stdio::flush()?;
+        Ok(())
+    }
+
+    async fn handle_stderr(&mut self, stderr: &str) -> anyhow::Result<()> {
+        emit(&Record::Message {
+            message: stderr.to_owned(),
+        })
+    }
+
+    async fn handle_event(&mut self, event: &Arc<BuckEvent>) -> anyhow::Result<()> {
+        self.observer
+            .observe(Instant::now(), event)
+            .context("Error tracking event")?;
+        self.handle_inner_event(event)
+            .await
+            .with_context(|| display::InvalidBuckEvent(event.dupe()))
+    }
+
+    async fn handle_command_start(
+        &mut self,
+        _command: &buck2_data::CommandStart,
+        event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        self.last_progress = Some(Instant::now());
+        emit(&Record::CommandStart {
+            schema_version: SCHEMA_VERSION,
+            build_id: event.trace_id()?.to_string(),
+        })
+    }
+
+    async fn handle_command_end(
+        &mut self,
+        _command: &buck2_data::CommandEnd,
+        _event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        self.emit_progress()
+    }
+
+    async fn handle_command_result(
+        &mut self,
+        result: &buck2_cli_proto::CommandResult,
+    ) -> anyhow::Result<()> {
+        let success = match &result.result {
+            Some(buck2_cli_proto::command_result::Result::Error(e)) => {
+                let report = e.error_reports.first();
+                for message in &e.messages {
+                    emit(&Record::Error {
+                        message: message.clone(),
+                        category: report.and_then(|r| r.category).map(error_category),
+                        cause: report.and_then(|r| r.cause).map(error_cause),
+                    })?;
+                }
+                false
+            }
+            Some(buck2_cli_proto::command_result::Result::BuildResponse(
+                buck2_cli_proto::BuildResponse { error_reports, .. },
+            ))
+            | Some(buck2_cli_proto::command_result::Result::TestResponse(
+                buck2_cli_proto::TestResponse { error_reports, .. },
+            )) => {
+                for report in error_reports {
+                    emit(&Record::Error {
+                        message: report.error_message.clone(),
+                        category: report.category.map(error_category),
+                        cause: report.cause.map(error_cause),
+                    })?;
+                }
+                error_reports.is_empty()
+            }
+            _ => true,
+        };
+        emit(&Record::CommandEnd { success })
+    }
+
+    async fn handle_action_execution_start(
+        &mut self,
+        action: &buck2_data::ActionExecutionStart,
+        event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        emit(&Record::ActionStart {
+            action: ActionIdentity::new(event, action.key.as_ref(), action.name.as_ref())?,
+        })
+    }
+
+    async fn handle_action_execution_end(
+        &mut self,
+        action: &buck2_data::ActionExecutionEnd,
+        event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        emit(&Record::ActionEnd {
+            action: ActionIdentity::new(event, action.key.as_ref(), action.name.as_ref())?,
+            status: if action.failed { "failure" } else { "success" },
+            execution_kind: execution_kind(action.execution_kind),
+            duration_ms: duration_ms(action.wall_time.as_ref())?,
+        })?;
+
+        if let Some(error) = &action.error {
+            use buck2_data::command_execution_details::Command;
+
+            let error =
+                display::display_action_error(action, error, TargetDisplayOptions::for_log())?;
+            let details = error.command.as_deref();
+            emit(&Record::ActionError {
+                action: ActionIdentity::new(event, action.key.as_ref(), action.name.as_ref())?,
+                reason: error.reason,
+                command: match details.and_then(|d| d.command.as_ref()) {
+                    Some(Command::LocalCommand(command)) => Some(local_command_to_string(command)),
+                    _ => None,
+                },
+                action_digest: match details.and_then(|d| d.command.as_ref()) {
+                    Some(Command::LocalCommand(command)) => Some(command.action_digest.clone()),
+                    Some(Command::RemoteCommand(command)) => Some(command.action_digest.clone()),
+                    Some(Command::OmittedLocalCommand(command)) => {
+                        Some(command.action_digest.clone())
+                    }
+                    None => None,
+                },
+                exit_code: details.and_then(|d| {
+                    d.signed_exit_code
+                        .or_else(|| d.exit_code.and_then(|c| c.try_into().ok()))
+                }),
+                stdout: details.map(|d| sanitize_output_colors(d.stdout.as_bytes())),
+                stderr: details.map(|d| sanitize_output_colors(d.stderr.as_bytes())),
+            })?;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_file_watcher_end(
+        &mut self,
+        _file_watcher: &buck2_data::FileWatcherEnd,
+        _event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn handle_console_message(
+        &mut self,
+        message: &buck2_data::ConsoleMessage,
+        _event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        self.handle_stderr(&message.message).await
+    }
+
+    async fn handle_structured_error(
+        &mut self,
+        err: &buck2_data::StructuredError,
+        _event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        if err.quiet {
+            return Ok(());
+        }
+        emit(&Record::Error {
+            message: err.payload.clone(),
+            category: None,
+            cause: None,
+        })
+    }
+
+    async fn handle_test_discovery(
+        &mut self,
+        _test_info: &buck2_data::TestDiscovery,
+        _event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn handle_test_result(
+        &mut self,
+        result: &buck2_data::TestResult,
+        _event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        emit(&Record::TestResult {
+            target: result
+                .target_label
+                .as_ref()
+                .map(|t| {
+                    display::display_configured_target_label(t, TargetDisplayOptions::for_log())
+                })
+                .transpose()?,
+            name: result.name.clone(),
+            status: test_status(result.status),
+            duration_ms: duration_ms(result.duration.as_ref())?,
+            message: result.msg.as_ref().map(|m| m.msg.clone()),
+            details: result.details.clone(),
+        })
+    }
+
+    async fn handle_console_preferences(
+        &mut self,
+        _prefs: &buck2_data::ConsolePreferences,
+        _event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn handle_error(&mut self, _error: &anyhow::Error) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn tick(&mut self, _: &Tick) -> anyhow::Result<()> {
+        match self.last_progress {
+            Some(last) if last.elapsed() >= PROGRESS_INTERVAL => self.emit_progress(),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema() -> anyhow::Result<()> {
+        let record = Record::ActionEnd {
+            action: ActionIdentity {
+                id: Some("7".to_owned()),
+                target: "root//:foo".to_owned(),
+                category: "cxx_compile".to_owned(),
+                identifier: "foo.cpp".to_owned(),
+            },
+            status: "success",
+            execution_kind: execution_kind(buck2_data::ActionExecutionKind::Skipped as i32),
+            duration_ms: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&record)?,
+            r#"{"type":"action_end","id":"7","target":"root//:foo","category":"cxx_compile","identifier":"foo.cpp","status":"success","execution_kind":"dep_file","duration_ms":null}"#
+        );
+
+        let record = Record::CommandEnd { success: false };
+        let line = serde_json::to_value(Line {
+            timestamp: "now".to_owned(),
+            record: &record,
+        })?;
+        assert_eq!(
+            line,
+            serde_json::json!({"timestamp": "now", "type": "command_end", "success": false})
+        );
+        Ok(())
+    }
+}
@@ -10,8 +10,10 @@
 use buck2_core::env_helper::EnvHelper;
 
 pub(crate) mod build_id_writer;
+pub(crate) mod build_summary_writer;
 pub mod event_log;
 pub mod get;
+pub(crate) mod jsonl_console;
 pub(crate) mod observer;
 pub mod re_log;
 pub mod recorder;
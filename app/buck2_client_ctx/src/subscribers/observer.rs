@@ -30,9 +30,11 @@ pub trait ErrorObserver {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
 pub enum ErrorCause {
     Unknown,
     Infra,
     User,
+    Environment,
     DaemonIsBusy,
 }
@@ -15,6 +15,7 @@ use dupe::Dupe;
 use crate::build_count::BuildCountManager;
 use crate::client_ctx::ClientCommandContext;
 use crate::common::CommonDaemonCommandOptions;
+use crate::subscribers::observer::ErrorCause;
 use crate::subscribers::subscriber::EventSubscriber;
 
 mod imp {
@@ -51,7 +52,9 @@ mod imp {
     use crate::cleanup_ctx::AsyncCleanupContext;
     use crate::subscribers::observer::ErrorCause;
     use crate::subscribers::observer::ErrorObserver;
+    use crate::subscribers::recorder::error_cause;
     use crate::subscribers::recorder::system_memory_stats;
+    use crate::subscribers::recorder::worst_error_report;
     use crate::subscribers::subscriber::EventSubscriber;
 
     pub struct InvocationRecorder {
@@ -126,6 +129,8 @@ mod imp {
         compressed_event_log_size_bytes: Option<Arc<AtomicU64>>,
         use_streaming_upload: bool,
         critical_path_backend: Option<String>,
+        /// What the daemon said about the error the command failed with, if it did.
+        error_report: Option<buck2_data::ErrorReport>,
     }
 
     impl InvocationRecorder {
@@ -219,6 +224,7 @@ mod imp {
                 compressed_event_log_size_bytes: log_size_counter_bytes,
                 use_streaming_upload,
                 critical_path_backend: None,
+                error_report: None,
             }
         }
 
@@ -355,6 +361,8 @@ mod imp {
                 ),
                 use_streaming_upload: self.use_streaming_upload,
                 critical_path_backend: self.critical_path_backend.take(),
+                error_category: self.error_report.as_ref().and_then(|r| r.category),
+                error_cause: self.error_report.as_ref().and_then(|r| r.cause),
             };
 
             let event = BuckEvent::new(
@@ -935,9 +943,16 @@ mod imp {
 
         async fn handle_command_result(
             &mut self,
-            _result: &buck2_cli_proto::CommandResult,
+            result: &buck2_cli_proto::CommandResult,
         ) -> anyhow::Result<()> {
             self.has_command_result = true;
+            let error_reports = match &result.result {
+                Some(buck2_cli_proto::command_result::Result::Error(e)) => &e.error_reports,
+                Some(buck2_cli_proto::command_result::Result::BuildResponse(r)) => &r.error_reports,
+                Some(buck2_cli_proto::command_result::Result::TestResponse(r)) => &r.error_reports,
+                _ => return Ok(()),
+            };
+            self.error_report = worst_error_report(error_reports).cloned();
             Ok(())
         }
 
@@ -956,12 +971,9 @@ mod imp {
             if self.exit_when_different_state {
                 // User wants to immediately exit concurrent commands with different states
                 return ErrorCause::DaemonIsBusy;
-            } else if self.run_command_failure_count > 0 {
-                // Action fails likely because of user-defined commands in the action
-                return ErrorCause::User;
             }
 
-            ErrorCause::Unknown
+            error_cause(self.error_report.as_ref(), self.run_command_failure_count)
         }
 
         fn daemon_in_memory_state_is_corrupted(&self) -> bool {
@@ -1035,6 +1047,49 @@ pub fn try_get_invocation_recorder(
     Ok(Some(Box::new(recorder) as _))
 }
 
+/// The report that decides how a command that failed with several errors (e.g. a build of several
+/// targets) is categorized. A user error means retrying won't help, so it wins over environment
+/// errors, which win over infra errors. Tagged errors win over untagged ones in the same category.
+fn worst_error_report(reports: &[buck2_data::ErrorReport]) -> Option<&buck2_data::ErrorReport> {
+    reports.iter().max_by_key(|r| {
+        let category = match r.category.and_then(buck2_data::ErrorCategory::from_i32) {
+            Some(buck2_data::ErrorCategory::User) => 3,
+            Some(buck2_data::ErrorCategory::Environment) => 2,
+            Some(buck2_data::ErrorCategory::Infra) => 1,
+            None => 0,
+        };
+        (category, r.cause.is_some())
+    })
+}
+
+fn error_cause(
+    report: Option<&buck2_data::ErrorReport>,
+    run_command_failure_count: u64,
+) -> ErrorCause {
+    let cause = report
+        .and_then(|r| r.cause)
+        .and_then(buck2_data::ErrorCause::from_i32);
+    let category = report
+        .and_then(|r| r.category)
+        .and_then(buck2_data::ErrorCategory::from_i32);
+    if cause == Some(buck2_data::ErrorCause::DaemonIsBusy) {
+        return ErrorCause::DaemonIsBusy;
+    }
+    // Errors without a tag are reported as infra errors, so only trust the category over
+    // failed commands when the error was tagged.
+    if cause.is_none() && run_command_failure_count > 0 {
+        // Action fails likely because of user-defined commands in the action
+        return ErrorCause::User;
+    }
+
+    match category {
+        Some(buck2_data::ErrorCategory::User) => ErrorCause::User,
+        Some(buck2_data::ErrorCategory::Infra) => ErrorCause::Infra,
+        Some(buck2_data::ErrorCategory::Environment) => ErrorCause::Environment,
+        None => ErrorCause::Unknown,
+    }
+}
+
 fn system_memory_stats() -> u64 {
     use sysinfo::RefreshKind;
     use sysinfo::System;
@@ -1047,6 +1102,7 @@ fn system_memory_stats() -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::exit_result::gen_error_exit_code;
 
     #[test]
     fn get_system_memory_stats() {
@@ -1054,4 +1110,69 @@ mod tests {
         // sysinfo returns zero when fails to retrieve data
         assert!(total_mem > 0);
     }
+
+    fn report(
+        category: buck2_data::ErrorCategory,
+        cause: Option<buck2_data::ErrorCause>,
+    ) -> buck2_data::ErrorReport {
+        buck2_data::ErrorReport {
+            category: Some(category as i32),
+            cause: cause.map(|c| c as i32),
+            error_message: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_error_cause() {
+        use buck2_data::ErrorCategory;
+        use buck2_data::ErrorCause as Tag;
+
+        let untagged = report(ErrorCategory::Infra, None);
+        let re = report(ErrorCategory::Infra, Some(Tag::ReUnavailable));
+        let materialization = report(ErrorCategory::Infra, Some(Tag::MaterializationFailed));
+        let command = report(ErrorCategory::User, Some(Tag::ActionCommandFailed));
+
+        assert_eq!(error_cause(None, 0), ErrorCause::Unknown);
+        assert_eq!(error_cause(Some(&untagged), 0), ErrorCause::Infra);
+        assert_eq!(error_cause(Some(&re), 0), ErrorCause::Infra);
+        assert_eq!(error_cause(Some(&command), 0), ErrorCause::User);
+        // Errors categorized at their source without a cause, e.g. a syntax error in a BUCK file.
+        let syntax_error = report(ErrorCategory::User, None);
+        assert_eq!(error_cause(Some(&syntax_error), 0), ErrorCause::User);
+        assert_eq!(gen_error_exit_code(error_cause(Some(&syntax_error), 0)), 3);
+        assert_eq!(
+            error_cause(
+                Some(&report(ErrorCategory::Environment, Some(Tag::DaemonIsBusy))),
+                0
+            ),
+            ErrorCause::DaemonIsBusy
+        );
+
+        // Untagged errors of builds where commands failed are blamed on those commands, but
+        // tagged errors keep their category.
+        assert_eq!(error_cause(None, 1), ErrorCause::User);
+        assert_eq!(error_cause(Some(&untagged), 1), ErrorCause::User);
+        assert_eq!(error_cause(Some(&materialization), 1), ErrorCause::Infra);
+        assert_eq!(error_cause(Some(&command), 1), ErrorCause::User);
+    }
+
+    #[test]
+    fn test_worst_error_report() {
+        use buck2_data::ErrorCategory;
+        use buck2_data::ErrorCause as Tag;
+
+        let untagged = report(ErrorCategory::Infra, None);
+        let re = report(ErrorCategory::Infra, Some(Tag::ReUnavailable));
+        let command = report(ErrorCategory::User, Some(Tag::ActionCommandFailed));
+
+        assert_eq!(worst_error_report(&[]), None);
+        assert_eq!(
+            worst_error_report(&[untagged.clone(), re.clone()]),
+            Some(&re)
+        );
+        assert_eq!(
+            worst_error_report(&[re.clone(), command.clone(), untagged]),
+            Some(&command)
+        );
+    }
 }
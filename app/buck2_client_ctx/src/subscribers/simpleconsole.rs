@@ -641,7 +641,7 @@ impl WhatRanOutputWriter for PrintDebugCommandToStderr {
     }
 }
 
-fn sanitize_output_colors(stderr: &[u8]) -> String {
+pub(crate) fn sanitize_output_colors(stderr: &[u8]) -> String {
     let mut sanitized = String::with_capacity(stderr.len());
     let mut parser = termwiz::escape::parser::Parser::new();
     parser.parse(stderr, |a| match a {
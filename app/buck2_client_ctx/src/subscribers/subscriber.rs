@@ -80,4 +80,10 @@ pub trait EventSubscriber: Send {
     fn as_error_observer(&self) -> Option<&dyn ErrorObserver> {
         None
     }
+
+    /// Whether this subscriber shows the output of local commands while they run. The daemon
+    /// only sends it if a subscriber does.
+    fn shows_local_command_output(&self) -> bool {
+        false
+    }
 }
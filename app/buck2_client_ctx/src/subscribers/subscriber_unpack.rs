@@ -72,6 +72,9 @@ pub trait UnpackingEventSubscriber: Send {
             buck2_data::span_start_event::Data::Command(command) => {
                 self.handle_command_start(command, event).await
             }
+            buck2_data::span_start_event::Data::ActionExecution(action) => {
+                self.handle_action_execution_start(action, event).await
+            }
             _ => Ok(()),
         }
     }
@@ -146,6 +149,14 @@ pub trait UnpackingEventSubscriber: Send {
         _event: &BuckEvent,
     ) -> anyhow::Result<()>;
 
+    async fn handle_action_execution_start(
+        &mut self,
+        _action: &buck2_data::ActionExecutionStart,
+        _event: &BuckEvent,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
     async fn handle_action_execution_end(
         &mut self,
         _action: &buck2_data::ActionExecutionEnd,
@@ -212,6 +223,10 @@ pub trait UnpackingEventSubscriber: Send {
     fn as_error_observer(&self) -> Option<&dyn ErrorObserver> {
         None
     }
+
+    fn shows_local_command_output(&self) -> bool {
+        false
+    }
 }
 
 #[async_trait]
@@ -257,4 +272,8 @@ impl<U: UnpackingEventSubscriber> EventSubscriber for UnpackingEventSubscriberAs
     fn as_error_observer(&self) -> Option<&dyn ErrorObserver> {
         self.0.as_error_observer()
     }
+
+    fn shows_local_command_output(&self) -> bool {
+        self.0.shows_local_command_output()
+    }
 }
@@ -48,6 +48,7 @@ pub(crate) use superconsole::SuperConsole;
 use crate::subscribers::simpleconsole::SimpleConsole;
 use crate::subscribers::subscriber::Tick;
 use crate::subscribers::subscriber_unpack::UnpackingEventSubscriber;
+use crate::subscribers::superconsole::action_details::ActionCursor;
 use crate::subscribers::superconsole::commands::CommandsComponent;
 use crate::subscribers::superconsole::debug_events::DebugEventsComponent;
 use crate::subscribers::superconsole::debugger::StarlarkDebuggerComponent;
@@ -58,6 +59,7 @@ use crate::subscribers::superconsole::test::TestHeader;
 use crate::subscribers::superconsole::timed_list::Cutoffs;
 use crate::subscribers::superconsole::timed_list::TimedList;
 
+mod action_details;
 mod commands;
 mod common;
 pub(crate) mod debug_events;
@@ -81,6 +83,17 @@ pub struct StatefulSuperConsole {
     state: SuperConsoleState,
     super_console: Option<SuperConsole>,
     verbosity: Verbosity,
+    escape: EscapeSequence,
+}
+
+/// How far into an arrow key escape sequence (e.g. `ESC [ A` for up) we are. The console delivers
+/// input one byte at a time.
+#[derive(Copy, Clone, Dupe, Debug, Default)]
+enum EscapeSequence {
+    #[default]
+    None,
+    Escape,
+    Bracket,
 }
 
 #[derive(Copy, Clone, Dupe, Debug)]
@@ -111,6 +124,7 @@ pub struct SuperConsoleState {
     /// This contains the SpanTracker, which is why it's part of the SuperConsoleState.
     simple_console: SimpleConsole<DebugEventObserverExtra>,
     config: SuperConsoleConfig,
+    cursor: ActionCursor,
 }
 
 #[derive(Clone)]
@@ -298,6 +312,7 @@ impl StatefulSuperConsole {
             )?,
             super_console: Some(super_console),
             verbosity,
+            escape: EscapeSequence::None,
         })
     }
 
@@ -355,10 +370,13 @@ impl SuperConsoleState {
                 show_waiting_message,
             ),
             config,
+            cursor: ActionCursor::default(),
         })
     }
 
     pub fn update_event_observer(&mut self, event: &Arc<BuckEvent>) -> anyhow::Result<()> {
+        self.cursor
+            .observe(self.simple_console.observer().spans(), event);
         self.simple_console.update_event_observer(event)
     }
 
@@ -494,6 +512,21 @@ impl UnpackingEventSubscriber for StatefulSuperConsole {
     }
 
     async fn handle_console_interaction(&mut self, c: char) -> anyhow::Result<()> {
+        let c = match (self.escape, c) {
+            (EscapeSequence::None, '\x1b') => {
+                self.escape = EscapeSequence::Escape;
+                return Ok(());
+            }
+            (EscapeSequence::Escape, '[') => {
+                self.escape = EscapeSequence::Bracket;
+                return Ok(());
+            }
+            (EscapeSequence::Bracket, 'A') => 'k',
+            (EscapeSequence::Bracket, 'B') => 'j',
+            _ => c,
+        };
+        self.escape = EscapeSequence::None;
+
         if c == 'd' {
             self.toggle("DICE component", 'd', |s| &mut s.state.config.enable_dice)
                 .await?;
@@ -525,6 +558,12 @@ impl UnpackingEventSubscriber for StatefulSuperConsole {
             self.state.config.max_lines = self.state.config.max_lines.saturating_add(1);
         } else if c == '-' {
             self.state.config.max_lines = self.state.config.max_lines.saturating_sub(1);
+        } else if c == 'j' || c == 'k' {
+            self.state
+                .cursor
+                .move_cursor(self.state.simple_console.observer().spans(), c == 'j');
+        } else if c == '\n' || c == '\r' {
+            self.state.cursor.expanded = !self.state.cursor.expanded;
         } else if c == '?' || c == 'h' {
             self.handle_stderr(
                 "Help:\n\
@@ -536,6 +575,8 @@ impl UnpackingEventSubscriber for StatefulSuperConsole {
                 `p` = display target configurations\n\
                 `+` = show more lines\n\
                 `-` = show fewer lines\n\
+                `j`, `k` or arrows = move the cursor over running actions\n\
+                `Enter` = show details of the action under the cursor\n\
                 `h` = show this help",
             )
             .await?;
@@ -740,6 +781,10 @@ impl UnpackingEventSubscriber for StatefulSuperConsole {
     ) -> anyhow::Result<()> {
         Ok(())
     }
+    fn shows_local_command_output(&self) -> bool {
+        // The simple console doesn't show it.
+        self.super_console.is_some()
+    }
 }
 
 fn lines_for_command_details(
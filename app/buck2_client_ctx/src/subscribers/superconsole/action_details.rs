@@ -0,0 +1,359 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A cursor over the running actions in the timed list, and details of the action under it.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use buck2_event_observer::display;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_event_observer::fmt_duration;
+use buck2_event_observer::span_tracker::BuckEventSpanHandle;
+use buck2_event_observer::span_tracker::BuckEventSpanTracker;
+use buck2_event_observer::what_ran::local_command_to_string;
+use buck2_events::span::SpanId;
+use buck2_events::BuckEvent;
+use superconsole::style::Stylize;
+use superconsole::Component;
+use superconsole::Dimensions;
+use superconsole::DrawMode;
+use superconsole::Line;
+use superconsole::Lines;
+use superconsole::Span;
+
+use crate::subscribers::superconsole::SuperConsoleState;
+
+/// How many lines of each output stream to show.
+const OUTPUT_LINES: usize = 5;
+
+/// How much of each output stream to keep, which is plenty for `OUTPUT_LINES`.
+const MAX_OUTPUT_BYTES: usize = 4096;
+
+/// The end of the output of a running local command.
+#[derive(Default)]
+struct OutputTail {
+    stdout: String,
+    stderr: String,
+}
+
+fn append_to_tail(tail: &mut String, data: &str) {
+    tail.push_str(data);
+    if tail.len() > MAX_OUTPUT_BYTES {
+        let mut start = tail.len() - MAX_OUTPUT_BYTES;
+        while !tail.is_char_boundary(start) {
+            start += 1;
+        }
+        tail.drain(..start);
+    }
+}
+
+fn last_lines(tail: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = tail.lines().collect();
+    lines.drain(..lines.len().saturating_sub(OUTPUT_LINES));
+    lines
+}
+
+/// Which running action is selected in the timed list, if any, and whether to show its details.
+#[derive(Default)]
+pub(crate) struct ActionCursor {
+    /// The root span under the cursor, and where it was in the list. When it finishes, the cursor
+    /// stays where it was, on the root that took its place.
+    focus: Option<(SpanId, usize)>,
+    pub(crate) expanded: bool,
+    /// Output of running local commands, by the root they run under.
+    output: HashMap<SpanId, OutputTail>,
+}
+
+impl ActionCursor {
+    /// Where the cursor is in `spans.iter_roots()`.
+    pub(crate) fn position(&self, spans: &BuckEventSpanTracker) -> Option<usize> {
+        let (span_id, index) = self.focus?;
+        let roots = spans.roots();
+        if roots.is_empty() {
+            return None;
+        }
+        Some(
+            roots
+                .iter()
+                .position(|root| *root == span_id)
+                .unwrap_or_else(|| index.min(roots.len() - 1)),
+        )
+    }
+
+    /// Move the cursor to the next or previous root. Moving up from the first root hides it.
+    pub(crate) fn move_cursor(&mut self, spans: &BuckEventSpanTracker, down: bool) {
+        let position = match (self.position(spans), down) {
+            (None, true) => Some(0),
+            (None | Some(0), false) => None,
+            (Some(position), true) => Some(position + 1),
+            (Some(position), false) => Some(position - 1),
+        };
+
+        let roots = spans.roots();
+        self.focus = position.and_then(|position| {
+            let position = position.min(roots.len().checked_sub(1)?);
+            let span_id = *roots.iter().nth(position)?;
+            Some((span_id, position))
+        });
+    }
+
+    /// Collect the output of running commands, and drop it when the action they belong to ends.
+    pub(crate) fn observe(&mut self, spans: &BuckEventSpanTracker, event: &BuckEvent) {
+        if event.span_end_event().is_some() {
+            if let Some(span_id) = event.span_id() {
+                self.output.remove(&span_id);
+            }
+            return;
+        }
+
+        if let buck2_data::buck_event::Data::Instant(buck2_data::InstantEvent {
+            data: Some(buck2_data::instant_event::Data::LocalCommandOutput(output)),
+        }) = event.data()
+        {
+            if let Some(root) = event.parent_id().and_then(|id| spans.root_of(id)) {
+                let tail = self.output.entry(root).or_default();
+                append_to_tail(&mut tail.stdout, &output.stdout);
+                append_to_tail(&mut tail.stderr, &output.stderr);
+            }
+        }
+    }
+}
+
+/// What the executor stages under a root tell us about the action.
+#[derive(Default)]
+struct ExecutionInfo {
+    executor: Option<&'static str>,
+    /// The innermost stage that is running, and when it started.
+    stage: Option<(&'static str, Instant)>,
+    command: Option<String>,
+    action_digest: Option<String>,
+}
+
+impl ExecutionInfo {
+    fn collect(&mut self, span: &BuckEventSpanHandle) {
+        use buck2_data::executor_stage_start::Stage;
+
+        let info = span.info();
+        if let Some(buck2_data::span_start_event::Data::ExecutorStage(
+            buck2_data::ExecutorStageStart { stage: Some(stage) },
+        )) = info
+            .event
+            .span_start_event()
+            .and_then(|span| span.data.as_ref())
+        {
+            if let Some(label) = display::display_executor_stage(stage) {
+                self.stage = Some((label, info.start));
+            }
+            match stage {
+                Stage::CacheQuery(query) => {
+                    self.executor = Some("cache");
+                    self.action_digest = Some(query.action_digest.clone());
+                }
+                Stage::Re(re) => {
+                    self.executor = Some("remote");
+                    if let Some(buck2_data::re_stage::Stage::Execute(execute)) = &re.stage {
+                        self.action_digest = Some(execute.action_digest.clone());
+                    }
+                }
+                Stage::Local(local) => {
+                    self.executor = Some("local");
+                    if let Some(buck2_data::local_stage::Stage::Execute(execute)) = &local.stage {
+                        if let Some(command) = &execute.command {
+                            self.command = Some(local_command_to_string(command));
+                            self.action_digest = Some(command.action_digest.clone());
+                        }
+                    }
+                }
+                Stage::Prepare(..) | Stage::CacheHit(..) => {}
+            }
+        }
+
+        for child in span.children() {
+            self.collect(&child);
+        }
+    }
+}
+
+/// Shows the action under the cursor: what runs it, what it runs, and the end of its output.
+pub(crate) struct ActionDetails<'s> {
+    pub(crate) state: &'s SuperConsoleState,
+}
+
+impl<'s> Component for ActionDetails<'s> {
+    fn draw_unchecked(&self, _dimensions: Dimensions, mode: DrawMode) -> anyhow::Result<Lines> {
+        let cursor = &self.state.cursor;
+        let spans = self.state.simple_console.observer().spans();
+        let root = match cursor.position(spans) {
+            Some(position) if cursor.expanded && mode == DrawMode::Normal => {
+                spans.iter_roots().nth(position)
+            }
+            _ => None,
+        };
+        let root = match root {
+            Some(root) => root,
+            None => return Ok(Lines::new()),
+        };
+
+        let mut info = ExecutionInfo::default();
+        info.collect(&root);
+
+        let mut lines = vec![Line::from_iter([Span::new_styled(
+            display::display_event(
+                &root.info().event,
+                TargetDisplayOptions::for_console(self.state.config.display_platform),
+            )?
+            .bold(),
+        )?])];
+
+        if let Some(executor) = info.executor {
+            lines.push(Line::sanitized(&format!("  Executor: {}", executor)));
+        }
+        if let Some((stage, start)) = info.stage {
+            lines.push(Line::sanitized(&format!(
+                "  Stage: {} ({})",
+                stage,
+                fmt_duration::fmt_duration(start.elapsed(), self.state.time_speed.speed())
+            )));
+        }
+        if let Some(command) = info.command {
+            lines.push(Line::sanitized(&format!("  Command: {}", command)));
+        }
+        if let Some(action_digest) = info.action_digest.filter(|d| !d.is_empty()) {
+            lines.push(Line::sanitized(&format!(
+                "  Action digest: {}",
+                action_digest
+            )));
+        }
+
+        if let Some(output) = cursor.output.get(&root.span_id()) {
+            for (name, tail) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+                if tail.is_empty() {
+                    continue;
+                }
+                lines.push(Line::sanitized(&format!("  {}:", name)));
+                for line in last_lines(tail) {
+                    lines.push(Line::sanitized(&format!("    {}", line)));
+                }
+            }
+        }
+
+        Ok(Lines(lines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::UNIX_EPOCH;
+
+    use buck2_data::FakeStart;
+    use buck2_data::SpanStartEvent;
+    use buck2_wrapper_common::invocation_id::TraceId;
+
+    use super::*;
+
+    fn fake_span(parent: Option<SpanId>) -> Arc<BuckEvent> {
+        Arc::new(BuckEvent::new(
+            UNIX_EPOCH,
+            TraceId::new(),
+            Some(SpanId::new()),
+            parent,
+            buck2_data::buck_event::Data::SpanStart(SpanStartEvent {
+                data: Some(buck2_data::span_start_event::Data::Fake(FakeStart {
+                    caramba: "test".to_owned(),
+                })),
+            }),
+        ))
+    }
+
+    #[test]
+    fn test_move_cursor() -> anyhow::Result<()> {
+        let roots = [fake_span(None), fake_span(None), fake_span(None)];
+        let mut spans = BuckEventSpanTracker::new();
+        for root in &roots {
+            spans.start_at(root, Instant::now())?;
+        }
+
+        let mut cursor = ActionCursor::default();
+        assert_eq!(cursor.position(&spans), None);
+        cursor.move_cursor(&spans, true);
+        assert_eq!(cursor.position(&spans), Some(0));
+        for _ in 0..5 {
+            cursor.move_cursor(&spans, true);
+        }
+        assert_eq!(cursor.position(&spans), Some(2));
+        cursor.move_cursor(&spans, false);
+        assert_eq!(cursor.position(&spans), Some(1));
+
+        // The cursor stays in place when the root under it finishes.
+        spans.handle_event(
+            Instant::now(),
+            &Arc::new(BuckEvent::new(
+                UNIX_EPOCH,
+                TraceId::new(),
+                roots[1].span_id(),
+                None,
+                buck2_data::buck_event::Data::SpanEnd(Default::default()),
+            )),
+        )?;
+        assert_eq!(cursor.position(&spans), Some(1));
+        assert_eq!(
+            spans.iter_roots().nth(1).unwrap().span_id(),
+            roots[2].span_id().unwrap()
+        );
+
+        cursor.move_cursor(&spans, false);
+        cursor.move_cursor(&spans, false);
+        assert_eq!(cursor.position(&spans), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_observe_output() -> anyhow::Result<()> {
+        let root = fake_span(None);
+        let child = fake_span(root.span_id());
+        let mut spans = BuckEventSpanTracker::new();
+        spans.start_at(&root, Instant::now())?;
+        spans.start_at(&child, Instant::now())?;
+
+        let mut cursor = ActionCursor::default();
+        for i in 0..10 {
+            cursor.observe(
+                &spans,
+                &BuckEvent::new(
+                    UNIX_EPOCH,
+                    TraceId::new(),
+                    None,
+                    child.span_id(),
+                    buck2_data::InstantEvent {
+                        data: Some(
+                            buck2_data::LocalCommandOutput {
+                                stdout: format!("line {}\n", i),
+                                stderr: String::new(),
+                            }
+                            .into(),
+                        ),
+                    }
+                    .into(),
+                ),
+            );
+        }
+
+        let output = &cursor.output[&root.span_id().unwrap()];
+        assert_eq!(
+            last_lines(&output.stdout),
+            ["line 5", "line 6", "line 7", "line 8", "line 9"]
+        );
+        assert!(output.stderr.is_empty());
+
+        Ok(())
+    }
+}
@@ -22,10 +22,13 @@ impl<'a> Component for ReHeader<'a> {
     fn draw_unchecked(
         &self,
 
-        _dimensions: superconsole::Dimensions,
+        dimensions: superconsole::Dimensions,
         mode: superconsole::DrawMode,
     ) -> anyhow::Result<superconsole::Lines> {
-        self.re_state
-            .render(self.super_console_config.enable_detailed_re, mode)
+        self.re_state.render(
+            self.super_console_config.enable_detailed_re,
+            mode,
+            dimensions,
+        )
     }
 }
@@ -11,6 +11,8 @@ use anyhow::Context;
 use buck2_event_observer::test_state::TestState;
 use crossterm::style::Color;
 use crossterm::style::ContentStyle;
+use superconsole::components::progress_bar::ProgressBarCharset;
+use superconsole::components::ProgressBar;
 use superconsole::Component;
 use superconsole::Dimensions;
 use superconsole::DrawMode;
@@ -20,6 +22,9 @@ use superconsole::Span;
 
 use crate::subscribers::superconsole::SessionInfo;
 
+/// Width of the bar that shows how many of the discovered tests finished.
+const PROGRESS_BAR_WIDTH: usize = 20;
+
 struct TestCounterComponent;
 
 pub struct TestCounterColumn {
@@ -114,6 +119,24 @@ impl TestCounterComponent {
         // TODO(brasselsprouts): use the outer try_into conversion on Lines.
 
         let mut spans = Vec::new();
+        if test_state.discovered > 0 {
+            let finished = test_state.pass
+                + test_state.fail
+                + test_state.fatal
+                + test_state.timeout
+                + test_state.unknown
+                + test_state.not_executed();
+            let bar = ProgressBar::new(finished as f64 / test_state.discovered as f64)
+                // Fonts in Windows consoles may not have block elements.
+                .charset(if cfg!(windows) {
+                    ProgressBarCharset::Ascii
+                } else {
+                    ProgressBarCharset::Unicode
+                })
+                .draw(Dimensions::new(PROGRESS_BAR_WIDTH, 1), mode)?;
+            spans.extend(bar.0.into_iter().flatten());
+            spans.push(" ".try_into()?);
+        }
         if test_state.listing_failed > 0 {
             spans.push(TestCounterColumn::LISTING_FAIL.to_span_from_test_state(test_state)?);
             spans.push(". ".try_into()?);
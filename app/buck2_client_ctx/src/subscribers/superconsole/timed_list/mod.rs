@@ -21,6 +21,7 @@ use buck2_event_observer::span_tracker::BuckEventSpanTracker;
 use superconsole::components::bordering::BorderedSpec;
 use superconsole::components::Bordered;
 use superconsole::components::DrawVertical;
+use superconsole::components::Focused;
 use superconsole::style::Stylize;
 use superconsole::Component;
 use superconsole::Dimensions;
@@ -30,6 +31,7 @@ use superconsole::Lines;
 use superconsole::Span;
 
 use self::table_builder::Table;
+use crate::subscribers::superconsole::action_details::ActionDetails;
 use crate::subscribers::superconsole::common::HeaderLineComponent;
 use crate::subscribers::superconsole::common::StaticStringComponent;
 use crate::subscribers::superconsole::timed_list::table_builder::Row;
@@ -173,30 +175,47 @@ impl<'c> Component for TimedListBody<'c> {
 
         let pending = pending_estimate(spans.roots(), observer.extra().dice_state());
 
-        for root in &mut roots {
+        // With a cursor, all roots are rendered and we scroll to the one under it.
+        let cursor = self.state.cursor.position(spans);
+        let mut focus = None;
+
+        for (i, root) in roots.by_ref().enumerate() {
             let rows = self.draw_root(&root)?;
 
-            if builder.len() + rows.len() >= max_lines {
+            if cursor.is_none() && builder.len() + rows.len() >= max_lines {
                 first_not_rendered = Some(root);
                 break;
             }
 
+            if cursor == Some(i) {
+                focus = Some(builder.len()..builder.len() + rows.len());
+            }
+
             builder.rows.extend(rows.into_iter().map(Row::from));
         }
 
+        let mut lines = match focus {
+            Some(focus) => Focused::new(builder, Some(focus)).draw(
+                Dimensions {
+                    width: dimensions.width,
+                    height: max_lines.saturating_sub(1),
+                },
+                mode,
+            )?,
+            None => builder.draw(dimensions, mode)?,
+        };
+
         // Add remaining unshown tasks, if any.
         let more = roots.len() as u64 + first_not_rendered.map_or(0, |_| 1) + pending;
 
         if more > 0 {
             let remaining = format!("... and {} more", more);
-            builder.rows.push(
-                std::iter::once(Span::new_styled(remaining.italic())?)
-                    .collect::<Line>()
-                    .into(),
-            );
+            lines
+                .0
+                .push(std::iter::once(Span::new_styled(remaining.italic())?).collect::<Line>());
         }
 
-        builder.draw(dimensions, mode)
+        Ok(lines)
     }
 }
 
@@ -327,6 +346,7 @@ impl<'a> Component for TimedList<'a> {
                 let mut draw = DrawVertical::new(dimensions);
                 draw.draw(&header, mode)?;
                 draw.draw(&body, mode)?;
+                draw.draw(&ActionDetails { state: self.state }, mode)?;
                 Ok(draw.finish())
             }
             // show a summary at the end
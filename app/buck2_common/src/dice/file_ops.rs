@@ -19,6 +19,7 @@ use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::cell_path::CellPathRef;
 use buck2_core::cells::cell_root_path::CellRootPathBuf;
 use buck2_core::cells::name::CellName;
+use buck2_core::cells::paths::CellRelativePathBuf;
 use buck2_core::cells::unchecked_cell_rel_path::UncheckedCellRelativePath;
 use buck2_core::cells::CellResolver;
 use buck2_core::fs::paths::file_name::FileNameBuf;
@@ -28,11 +29,18 @@ use buck2_events::dispatch::console_message;
 use derivative::Derivative;
 use derive_more::Display;
 use dice::DiceComputations;
+use dice::DiceSnapshotRegistry;
 use dice::DiceTransactionUpdater;
 use dice::Key;
+use dice::PersistentKey;
 use dupe::Dupe;
 use gazebo::cmp::PartialEqAny;
 use more_futures::cancellation::CancellationContext;
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
 
 use crate::dice::cells::HasCellResolver;
 use crate::dice::data::HasIoProvider;
@@ -369,6 +377,41 @@ impl Key for ReadFileKey {
     }
 }
 
+/// Stored in DICE snapshots as the cell name and the cell relative path of the file.
+impl Serialize for ReadFileKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.0.cell().as_str(), self.0.path().as_str()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReadFileKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (cell, path) = <(String, String)>::deserialize(deserializer)?;
+        let cell = CellName::unchecked_new(&cell).map_err(D::Error::custom)?;
+        let path = CellRelativePathBuf::try_from(path).map_err(D::Error::custom)?;
+        Ok(ReadFileKey(Arc::new(CellPath::new(cell, path))))
+    }
+}
+
+impl PersistentKey for ReadFileKey {
+    const PERSISTENT_ID: &'static str = "buck2_common::dice::file_ops::ReadFileKey";
+
+    fn serialize_value(value: &FileToken) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(&ReadFileKey(value.0.dupe()))?)
+    }
+
+    fn deserialize_value(bytes: &[u8]) -> anyhow::Result<FileToken> {
+        Ok(FileToken(bincode::deserialize::<ReadFileKey>(bytes)?.0))
+    }
+}
+
+/// Registers the file keys that can be written to DICE snapshots. These are only the keys of file
+/// contents: listings and metadata depend on `FileOpsKey`, whose value can't be serialized, so
+/// they're never part of a snapshot.
+pub fn register_persistent_file_keys(registry: &mut DiceSnapshotRegistry) {
+    registry.register::<ReadFileKey>();
+}
+
 #[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative)]
 struct ReadDirKey(CellPath);
 
@@ -21,18 +21,58 @@ where
     fn create_error_report(&self) -> Option<buck2_data::ErrorReport> {
         let err = self.as_anyhow()?;
 
-        // Infra error by default if no category tag is set
-        let category = Some(
-            recursive_shared_downcast_ref::<buck2_data::ErrorCategory>(err)
-                .map_or(buck2_data::ErrorCategory::Infra as i32, |c| *c as i32),
-        );
-        let cause = recursive_shared_downcast_ref::<buck2_data::ErrorCause>(err).map(|c| *c as i32);
+        let cause = recursive_shared_downcast_ref::<buck2_data::ErrorCause>(err).copied();
+        // An explicit category wins over the one implied by the cause, and errors with neither
+        // are infra errors.
+        let category = recursive_shared_downcast_ref::<buck2_data::ErrorCategory>(err)
+            .copied()
+            .or_else(|| cause.map(|c| c.category()))
+            .unwrap_or(buck2_data::ErrorCategory::Infra);
         let error_message = format!("{:#}", err);
 
         Some(buck2_data::ErrorReport {
-            category,
-            cause,
+            category: Some(category as i32),
+            cause: cause.map(|c| c as i32),
             error_message,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(err: anyhow::Error) -> (Option<i32>, Option<i32>) {
+        let report = err.create_error_report().unwrap();
+        (report.category, report.cause)
+    }
+
+    #[test]
+    fn test_category() {
+        use buck2_data::ErrorCategory;
+        use buck2_data::ErrorCause;
+
+        assert_eq!(
+            report(anyhow::anyhow!("oops")),
+            (Some(ErrorCategory::Infra as i32), None)
+        );
+        assert_eq!(
+            report(anyhow::anyhow!("oops").context(ErrorCause::ActionCommandFailed)),
+            (
+                Some(ErrorCategory::User as i32),
+                Some(ErrorCause::ActionCommandFailed as i32)
+            )
+        );
+        assert_eq!(
+            report(
+                anyhow::anyhow!("oops")
+                    .context(ErrorCause::ActionCommandFailed)
+                    .context(ErrorCategory::Environment)
+            ),
+            (
+                Some(ErrorCategory::Environment as i32),
+                Some(ErrorCause::ActionCommandFailed as i32)
+            )
+        );
+    }
+}
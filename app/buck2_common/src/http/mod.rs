@@ -15,9 +15,12 @@ use std::sync::Arc;
 use allocative::Allocative;
 use anyhow::Context;
 use buck2_core::is_open_source;
+use buck2_credential_helper::CredentialHelper;
 use dice::UserComputationData;
 use dupe::Dupe;
 use gazebo::prelude::VecExt;
+use http::HeaderName;
+use http::HeaderValue;
 use http::Method;
 use hyper::body;
 use hyper::client::connect::Connect;
@@ -53,18 +56,23 @@ const DEFAULT_MAX_REDIRECTS: usize = 10;
 /// General-purpose function to get a regular HTTP client for use throughout the
 /// buck2 codebase.
 ///
-/// This should work for internal and OSS use cases.
-pub fn http_client() -> anyhow::Result<Arc<dyn HttpClient>> {
+/// This should work for internal and OSS use cases. If a credential helper is given, the headers
+/// it returns are sent with every request.
+pub fn http_client(
+    credential_helper: Option<Arc<CredentialHelper>>,
+) -> anyhow::Result<Arc<dyn HttpClient>> {
     if is_open_source() {
-        http_client_for_oss()
+        http_client_for_oss(credential_helper)
     } else {
-        http_client_for_internal()
+        http_client_for_internal(credential_helper)
     }
 }
 
 /// Returns a client suitable for OSS usecases. Supports standard Curl-like
 /// proxy environment variables: $HTTP_PROXY, $HTTPS_PROXY.
-pub fn http_client_for_oss() -> anyhow::Result<Arc<dyn HttpClient>> {
+pub fn http_client_for_oss(
+    credential_helper: Option<Arc<CredentialHelper>>,
+) -> anyhow::Result<Arc<dyn HttpClient>> {
     // Add standard proxy variables if defined.
     // Ignores values that cannot be turned into valid URIs.
     let mut proxies = Vec::new();
@@ -76,19 +84,24 @@ pub fn http_client_for_oss() -> anyhow::Result<Arc<dyn HttpClient>> {
     }
 
     if !proxies.is_empty() {
-        Ok(Arc::new(SecureProxiedClient::with_proxies(proxies)?))
+        Ok(Arc::new(SecureProxiedClient::with_proxies(
+            proxies,
+            credential_helper,
+        )?))
     } else {
         let config = tls_config_with_system_roots()?;
-        Ok(Arc::new(SecureHttpClient::new(
-            config,
-            DEFAULT_MAX_REDIRECTS,
-        )))
+        Ok(Arc::new(
+            SecureHttpClient::new(config, DEFAULT_MAX_REDIRECTS)
+                .with_credential_helper(credential_helper),
+        ))
     }
 }
 
 /// Returns a client suitable for Meta-internal usecases. Supports standard
 /// $THRIFT_TLS_CL_* environment variables.
-fn http_client_for_internal() -> anyhow::Result<Arc<dyn HttpClient>> {
+fn http_client_for_internal(
+    credential_helper: Option<Arc<CredentialHelper>>,
+) -> anyhow::Result<Arc<dyn HttpClient>> {
     let tls_config = if let (Some(cert_path), Some(key_path)) = (
         std::env::var_os("THRIFT_TLS_CL_CERT_PATH"),
         std::env::var_os("THRIFT_TLS_CL_KEY_PATH"),
@@ -106,10 +119,10 @@ fn http_client_for_internal() -> anyhow::Result<Arc<dyn HttpClient>> {
         )?));
     }
 
-    Ok(Arc::new(SecureHttpClient::new(
-        tls_config,
-        DEFAULT_MAX_REDIRECTS,
-    )))
+    Ok(Arc::new(
+        SecureHttpClient::new(tls_config, DEFAULT_MAX_REDIRECTS)
+            .with_credential_helper(credential_helper),
+    ))
 }
 
 /// Dice implementations so we can pass along the HttpClient to various subsystems
@@ -297,6 +310,7 @@ pub struct SecureHttpClient {
     #[allocative(skip)]
     inner: Arc<dyn RequestClient>,
     max_redirects: usize,
+    credential_helper: Option<Arc<CredentialHelper>>,
 }
 
 impl SecureHttpClient {
@@ -318,10 +332,69 @@ impl SecureHttpClient {
         Self {
             inner: Arc::new(hyper::Client::builder().build::<_, Body>(connector)),
             max_redirects,
+            credential_helper: None,
         }
     }
 
-    async fn send_request_impl(&self, request: Request<Body>) -> Result<Response<Body>, HttpError> {
+    fn with_credential_helper(mut self, credential_helper: Option<Arc<CredentialHelper>>) -> Self {
+        self.credential_helper = credential_helper;
+        self
+    }
+
+    /// Add the headers returned by the credential helper for the host of this request. This is
+    /// done for every request sent, rather than once, so that redirects to other hosts get their
+    /// own credentials.
+    ///
+    /// If the helper fails, the request is sent without credentials: the server may not need
+    /// them, and if it does, its error is more useful than the helper's.
+    async fn add_credentials(&self, request: &mut Request<Body>) {
+        let helper = match &self.credential_helper {
+            Some(helper) => helper,
+            None => return,
+        };
+
+        match Self::credential_headers(helper, request.uri()).await {
+            Ok(headers) => {
+                for (key, value) in headers {
+                    request.headers_mut().append(key, value);
+                }
+            }
+            Err(e) => tracing::warn!(
+                "Sending request to `{}` without credentials: {:#}",
+                request.uri(),
+                e
+            ),
+        }
+    }
+
+    async fn credential_headers(
+        helper: &CredentialHelper,
+        uri: &http::Uri,
+    ) -> anyhow::Result<Vec<(HeaderName, HeaderValue)>> {
+        let credentials = match helper.get(uri).await? {
+            Some(credentials) => credentials,
+            None => return Ok(Vec::new()),
+        };
+
+        credentials
+            .headers
+            .iter()
+            .map(|(key, value)| {
+                let key = HeaderName::from_bytes(key.as_bytes()).with_context(|| {
+                    format!("Invalid header name from credential helper: `{}`", key)
+                })?;
+                let value = HeaderValue::from_str(value)
+                    .with_context(|| format!("Invalid value for header `{}`", key))?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+
+    async fn send_request_impl(
+        &self,
+        mut request: Request<Body>,
+    ) -> Result<Response<Body>, HttpError> {
+        self.add_credentials(&mut request).await;
         self.inner
             .request(request)
             .await
@@ -369,7 +442,10 @@ struct SecureProxiedClient {
 }
 
 impl SecureProxiedClient {
-    fn with_proxies<I: IntoIterator<Item = Proxy>>(proxies: I) -> anyhow::Result<Self> {
+    fn with_proxies<I: IntoIterator<Item = Proxy>>(
+        proxies: I,
+        credential_helper: Option<Arc<CredentialHelper>>,
+    ) -> anyhow::Result<Self> {
         let config = tls_config_with_system_roots()?;
 
         // This connector establishes a secure connection from client -> dest
@@ -391,7 +467,8 @@ impl SecureProxiedClient {
         proxy_connector.extend_proxies(proxies);
 
         Ok(Self {
-            inner: SecureHttpClient::with_connector(proxy_connector, DEFAULT_MAX_REDIRECTS),
+            inner: SecureHttpClient::with_connector(proxy_connector, DEFAULT_MAX_REDIRECTS)
+                .with_credential_helper(credential_helper),
         })
     }
 }
@@ -648,10 +725,13 @@ mod tests {
         let proxy_server = ProxyServer::new().await?;
         println!("proxy_server uri: {}", proxy_server.uri()?);
 
-        let client = SecureProxiedClient::with_proxies([Proxy::new(
-            hyper_proxy::Intercept::Http,
-            proxy_server.uri()?,
-        )])?;
+        let client = SecureProxiedClient::with_proxies(
+            [Proxy::new(
+                hyper_proxy::Intercept::Http,
+                proxy_server.uri()?,
+            )],
+            None,
+        )?;
         let resp = client.get(&test_server.url_str("/foo")).await?;
         assert_eq!(200, resp.status().as_u16());
 
@@ -676,10 +756,13 @@ mod tests {
         let authority = proxy_server.uri()?.authority().unwrap().clone();
         let proxy_uri = format!("{}:{}", authority.host(), authority.port().unwrap());
         println!("proxy_uri: {}", proxy_uri);
-        let client = SecureProxiedClient::with_proxies([Proxy::new(
-            hyper_proxy::Intercept::Http,
-            crate::http::proxy::DefaultSchemeUri(proxy_uri.try_into()?).into(),
-        )])?;
+        let client = SecureProxiedClient::with_proxies(
+            [Proxy::new(
+                hyper_proxy::Intercept::Http,
+                crate::http::proxy::DefaultSchemeUri(proxy_uri.try_into()?).into(),
+            )],
+            None,
+        )?;
         let resp = client.get(&test_server.url_str("/foo")).await?;
         assert_eq!(200, resp.status().as_u16());
 
@@ -709,10 +792,13 @@ mod tests {
         let no_proxy = crate::http::proxy::NoProxy::new(http::uri::Scheme::HTTP, test_server_host);
 
         // Don't proxy connections to test_server.
-        let client = SecureProxiedClient::with_proxies([Proxy::new(
-            no_proxy.into_proxy_intercept(),
-            proxy_server.uri()?,
-        )])?;
+        let client = SecureProxiedClient::with_proxies(
+            [Proxy::new(
+                no_proxy.into_proxy_intercept(),
+                proxy_server.uri()?,
+            )],
+            None,
+        )?;
         let resp = client.get(&test_server.url_str("/foo")).await?;
         assert_eq!(200, resp.status().as_u16());
 
@@ -738,10 +824,13 @@ mod tests {
         // Don't proxy HTTPS connections to *.foobar.com
         let no_proxy = crate::http::proxy::NoProxy::new(http::uri::Scheme::HTTP, ".foobar.com");
 
-        let client = SecureProxiedClient::with_proxies([Proxy::new(
-            no_proxy.into_proxy_intercept(),
-            proxy_server.uri()?,
-        )])?;
+        let client = SecureProxiedClient::with_proxies(
+            [Proxy::new(
+                no_proxy.into_proxy_intercept(),
+                proxy_server.uri()?,
+            )],
+            None,
+        )?;
         let resp = client.get(&test_server.url_str("/foo")).await?;
         assert_eq!(200, resp.status().as_u16());
 
@@ -200,8 +200,21 @@ impl InvocationPaths {
         FileName::unchecked_new("materializer_state")
     }
 
+    /// Subdirectory of `cache_dir` where the DICE snapshot is written on shutdown when
+    /// `buck2.dice_snapshot` is enabled.
+    pub fn dice_snapshot_path(&self) -> AbsNormPathBuf {
+        self.cache_dir_path().join(self.dice_snapshot_dir_name())
+    }
+
+    pub fn dice_snapshot_dir_name(&self) -> &FileName {
+        FileName::unchecked_new("dice_snapshot")
+    }
+
     pub fn valid_cache_dirs(&self) -> Vec<&FileName> {
-        vec![self.materializer_state_dir_name()]
+        vec![
+            self.materializer_state_dir_name(),
+            self.dice_snapshot_dir_name(),
+        ]
     }
 
     /// When client and server versions mismatch, we restart the daemon. This file allows doing the
@@ -78,6 +78,10 @@ impl PackageListing {
         }
     }
 
+    pub fn directories(&self) -> impl ExactSizeIterator<Item = &ArcS<PackageRelativePath>> {
+        self.listing.directories.iter()
+    }
+
     pub fn files_within<'a>(
         &'a self,
         dir: &PackageRelativePath,
@@ -13,6 +13,7 @@ use buck2_core::package::PackageLabel;
 use buck2_core::pattern::display_precise_pattern;
 use buck2_core::pattern::pattern_type::ConfiguredProvidersPatternExtra;
 use buck2_core::pattern::pattern_type::PatternType;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_core::pattern::PackageSpec;
 use buck2_core::pattern::ParsedPattern;
 use buck2_core::target::name::TargetName;
@@ -54,13 +55,101 @@ where
         if let Some(s) = self.specs.get_mut(&package) {
             match s {
                 PackageSpec::Targets(ref mut t) => t.push((target_name, extra)),
-                PackageSpec::All => {}
+                PackageSpec::All | PackageSpec::AllExcept(..) => {}
             }
         } else {
             self.specs
                 .insert(package, PackageSpec::Targets(vec![(target_name, extra)]));
         }
     }
+
+    /// Removes the excluded packages and targets.
+    pub fn exclude(self, exclusions: &PatternExclusions) -> Self {
+        if exclusions.is_empty() {
+            return self;
+        }
+        let specs = self
+            .specs
+            .into_iter()
+            .filter_map(|(package, spec)| {
+                let spec = exclusions.apply_to_spec(&package, spec)?;
+                Some((package, spec))
+            })
+            .collect();
+        ResolvedPattern { specs }
+    }
+}
+
+/// Patterns subtracted from other patterns, e.g. `-//third-party/...` on the command line.
+#[derive(Debug, Default)]
+pub struct PatternExclusions {
+    patterns: Vec<ParsedPattern<TargetPatternExtra>>,
+}
+
+impl PatternExclusions {
+    pub fn new(patterns: Vec<ParsedPattern<TargetPatternExtra>>) -> Self {
+        Self { patterns }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether all the targets of the package are excluded, in which case the package
+    /// does not need to be loaded.
+    pub fn excludes_package(&self, package: &PackageLabel) -> bool {
+        self.patterns.iter().any(|pattern| match pattern {
+            ParsedPattern::Target(..) => false,
+            ParsedPattern::Package(excluded) => excluded == package,
+            ParsedPattern::Recursive(excluded) => {
+                package.as_cell_path().starts_with(excluded.as_ref())
+            }
+        })
+    }
+
+    /// Removes the excluded targets from the spec of a package.
+    /// Returns `None` if nothing is left to match in the package.
+    pub fn apply_to_spec<T: PatternType>(
+        &self,
+        package: &PackageLabel,
+        spec: PackageSpec<T>,
+    ) -> Option<PackageSpec<T>> {
+        if self.excludes_package(package) {
+            return None;
+        }
+        let excluded: Vec<&TargetName> = self
+            .patterns
+            .iter()
+            .filter_map(|pattern| match pattern {
+                ParsedPattern::Target(excluded, target_name, TargetPatternExtra)
+                    if excluded == package =>
+                {
+                    Some(target_name)
+                }
+                _ => None,
+            })
+            .collect();
+        if excluded.is_empty() {
+            return Some(spec);
+        }
+        match spec {
+            PackageSpec::Targets(mut targets) => {
+                targets.retain(|(target_name, _)| !excluded.contains(&target_name));
+                if targets.is_empty() {
+                    None
+                } else {
+                    Some(PackageSpec::Targets(targets))
+                }
+            }
+            PackageSpec::All => Some(PackageSpec::AllExcept(
+                excluded.into_iter().cloned().collect(),
+            )),
+            PackageSpec::AllExcept(mut targets) => {
+                targets.extend(excluded.into_iter().cloned());
+                Some(PackageSpec::AllExcept(targets))
+            }
+        }
+    }
 }
 
 impl ResolvedPattern<ConfiguredProvidersPatternExtra> {
@@ -81,6 +170,7 @@ impl ResolvedPattern<ConfiguredProvidersPatternExtra> {
                     })?)
                 }
                 PackageSpec::All => PackageSpec::All,
+                PackageSpec::AllExcept(targets) => PackageSpec::AllExcept(targets),
             };
             specs.insert(package, spec);
         }
@@ -147,6 +237,7 @@ mod tests {
     use crate::file_ops::testing::TestFileOps;
     use crate::file_ops::FileOps;
     use crate::pattern::resolve::resolve_target_patterns;
+    use crate::pattern::resolve::PatternExclusions;
     use crate::pattern::resolve::ResolvedPattern;
 
     #[derive(Clone)]
@@ -381,4 +472,62 @@ mod tests {
                 ]);
         })
     }
+
+    #[tokio::test]
+    async fn test_exclusions() -> anyhow::Result<()> {
+        let tester = TestPatternResolver::new(
+            &[("root", "")],
+            &[
+                "foo/BUCK",
+                "foo/bar/BUCK",
+                "foo/bar/baz/BUCK",
+                "foo/qux/BUCK",
+                "third-party/BUCK",
+                "third-party/lib/BUCK",
+            ],
+        )?;
+        let exclusions = PatternExclusions::new(
+            [
+                "//third-party/...",
+                "//foo/bar/...",
+                "//foo:excluded",
+                "//foo/qux:a",
+            ]
+            .map(|p| {
+                ParsedPattern::parse_precise(p, CellName::testing_new("root"), &tester.resolver)
+                    .unwrap()
+            })
+            .into_iter()
+            .collect(),
+        );
+
+        assert!(exclusions.excludes_package(&PackageLabel::testing_parse("root//third-party")));
+        assert!(exclusions.excludes_package(&PackageLabel::testing_parse("root//foo/bar/baz")));
+        assert!(!exclusions.excludes_package(&PackageLabel::testing_parse("root//foo")));
+
+        tester
+            .resolve::<TargetPatternExtra>(&["//...", "//foo/qux:a"])
+            .await?
+            .exclude(&exclusions)
+            .assert_eq(&[
+                (
+                    PackageLabel::testing_parse("root//foo"),
+                    PackageSpec::AllExcept(vec![TargetName::unchecked_new("excluded")]),
+                ),
+                (
+                    PackageLabel::testing_parse("root//foo/qux"),
+                    PackageSpec::AllExcept(vec![TargetName::unchecked_new("a")]),
+                ),
+            ]);
+
+        tester
+            .resolve::<TargetPatternExtra>(&["//foo/qux:a", "//foo/qux:b", "//foo/bar:c"])
+            .await?
+            .exclude(&exclusions)
+            .assert_eq(&[(
+                PackageLabel::testing_parse("root//foo/qux"),
+                PackageSpec::Targets(vec![(TargetName::unchecked_new("b"), TargetPatternExtra)]),
+            )]);
+        Ok(())
+    }
 }
@@ -265,6 +265,27 @@ impl ConfigurationData {
     pub fn full_name(&self) -> &str {
         &self.0.full_name
     }
+
+    /// Produces the configuration obtained by applying command-line modifiers on top of this one.
+    /// Constraints from later modifiers take precedence over earlier ones, and all of them take
+    /// precedence over the constraints of this configuration. The result is labelled
+    /// `<this label>?<modifiers_label>`.
+    pub fn apply_modifiers(
+        &self,
+        modifiers_label: &str,
+        modifiers: impl IntoIterator<Item = ConfigurationDataData>,
+    ) -> anyhow::Result<ConfigurationData> {
+        let mut data = match &self.0.configuration_platform {
+            ConfigurationPlatform::Bound(_, data) => {
+                ConfigurationDataData::new(data.constraints.clone())
+            }
+            ConfigurationPlatform::Builtin(_) => ConfigurationDataData::empty(),
+        };
+        for modifier in modifiers {
+            data = data.merge(modifier);
+        }
+        ConfigurationData::from_platform(format!("{}?{}", self.short_name(), modifiers_label), data)
+    }
 }
 
 impl Serialize for ConfigurationData {
@@ -475,4 +496,48 @@ mod tests {
         .unwrap();
         assert_eq!(configuration, looked_up);
     }
+
+    #[test]
+    fn test_apply_modifiers() -> anyhow::Result<()> {
+        let constraint = |key: &str, value: &str| {
+            (
+                ConstraintKey(TargetLabel::testing_parse(key)),
+                ConstraintValue(TargetLabel::testing_parse(value)),
+            )
+        };
+        let base = ConfigurationData::from_platform(
+            "root//:linux".to_owned(),
+            ConfigurationDataData::new(BTreeMap::from_iter([
+                constraint("root//os:os", "root//os:linux"),
+                constraint("root//cpu:cpu", "root//cpu:x86_64"),
+            ])),
+        )?;
+
+        let modified = base.apply_modifiers(
+            "root//cpu:arm64,root//cpu:riscv64",
+            [
+                ConfigurationDataData::new(BTreeMap::from_iter([constraint(
+                    "root//cpu:cpu",
+                    "root//cpu:arm64",
+                )])),
+                ConfigurationDataData::new(BTreeMap::from_iter([constraint(
+                    "root//cpu:cpu",
+                    "root//cpu:riscv64",
+                )])),
+            ],
+        )?;
+
+        assert_eq!(
+            "root//:linux?root//cpu:arm64,root//cpu:riscv64",
+            modified.short_name()
+        );
+        assert_eq!(
+            &BTreeMap::from_iter([
+                constraint("root//os:os", "root//os:linux"),
+                constraint("root//cpu:cpu", "root//cpu:riscv64"),
+            ]),
+            &modified.data()?.constraints
+        );
+        Ok(())
+    }
 }
@@ -271,6 +271,152 @@ pub fn copy<P: AsRef<AbsPath>, Q: AsRef<AbsPath>>(from: P, to: Q) -> anyhow::Res
     })
 }
 
+/// Copy a file, cloning it instead of copying its contents when the filesystem supports
+/// copy-on-write (e.g. btrfs or XFS). Returns `true` if the file was cloned.
+pub fn clone_or_copy<P: AsRef<AbsPath>, Q: AsRef<AbsPath>>(from: P, to: Q) -> anyhow::Result<bool> {
+    let _guard = IoCounterKey::Copy.guard();
+    let from = from.as_ref();
+    let to = to.as_ref();
+    let cloned = clone_file_impl(from.as_maybe_relativized(), to.as_maybe_relativized()).and_then(
+        |cloned| {
+            if !cloned {
+                fs::copy(from.as_maybe_relativized(), to.as_maybe_relativized())?;
+            }
+            Ok(cloned)
+        },
+    );
+    cloned.with_context(|| {
+        format!(
+            "clone_or_copy(from={}, to={})",
+            from.display(),
+            to.display()
+        )
+    })
+}
+
+/// Clone a file with `FICLONE`. Returns `false` if the filesystem does not support it.
+#[cfg(target_os = "linux")]
+fn clone_file_impl(from: &Path, to: &Path) -> io::Result<bool> {
+    use std::collections::HashSet;
+    use std::os::unix::fs::MetadataExt;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::io::AsRawFd;
+    use std::sync::Mutex;
+
+    use once_cell::sync::Lazy;
+
+    // `_IOW(0x94, 9, int)`. The `libc` crate doesn't expose it in the versions we support, and
+    // the direction bits of the encoding differ between architectures.
+    const IOC_WRITE: u32 = if cfg!(any(
+        target_arch = "powerpc",
+        target_arch = "powerpc64",
+        target_arch = "mips",
+        target_arch = "mips64",
+        target_arch = "sparc",
+        target_arch = "sparc64",
+    )) {
+        0x8000_0000
+    } else {
+        0x4000_0000
+    };
+    const FICLONE: u32 =
+        IOC_WRITE | ((std::mem::size_of::<libc::c_int>() as u32) << 16) | (0x94 << 8) | 9;
+
+    // Devices whose filesystem rejected a clone, so we don't try again for every file. Other
+    // filesystems (e.g. a buck-out on a different mount) may still support it.
+    static UNSUPPORTED_DEVICES: Lazy<Mutex<HashSet<u64>>> = Lazy::new(Default::default);
+
+    let dest_dir = match to.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let device = fs::metadata(dest_dir)?.dev();
+    if UNSUPPORTED_DEVICES.lock().unwrap().contains(&device) {
+        return Ok(false);
+    }
+
+    let src = File::open(from)?;
+    let mode = src.metadata()?.permissions().mode();
+    let dest = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(mode)
+        .open(to)?;
+
+    let cloned = if unsafe { libc::ioctl(dest.as_raw_fd(), FICLONE as _, src.as_raw_fd()) } == 0 {
+        // The mode passed to `open` is subject to umask, `fs::copy` does not apply it.
+        dest.set_permissions(fs::Permissions::from_mode(mode))
+            .map(|()| true)
+    } else {
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            // Files on different filesystems, another pair of files may still be cloned.
+            Some(libc::EXDEV) => Ok(false),
+            Some(libc::EOPNOTSUPP | libc::ENOTTY | libc::EINVAL | libc::ENOSYS) => {
+                UNSUPPORTED_DEVICES.lock().unwrap().insert(device);
+                Ok(false)
+            }
+            _ => Err(err),
+        }
+    };
+
+    // `open` truncated the destination, don't leave an empty file behind if we didn't clone.
+    drop(dest);
+    if !matches!(cloned, Ok(true)) {
+        let _ignored = fs::remove_file(to);
+    }
+    cloned
+}
+
+#[cfg(not(target_os = "linux"))]
+fn clone_file_impl(_from: &Path, _to: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+pub fn hard_link<P: AsRef<AbsPath>, Q: AsRef<AbsPath>>(original: P, link: Q) -> anyhow::Result<()> {
+    let _guard = IoCounterKey::Hardlink.guard();
+    fs::hard_link(
+        original.as_ref().as_maybe_relativized(),
+        link.as_ref().as_maybe_relativized(),
+    )
+    .with_context(|| {
+        format!(
+            "hard_link(original={}, link={})",
+            P::as_ref(&original).display(),
+            Q::as_ref(&link).display()
+        )
+    })
+}
+
+/// Set the modification time of a file to the current time.
+pub fn touch<P: AsRef<AbsPath>>(path: P) -> anyhow::Result<()> {
+    let _guard = IoCounterKey::Write.guard();
+    touch_impl(path.as_ref().as_maybe_relativized())
+        .with_context(|| format!("touch({})", P::as_ref(&path).display()))
+}
+
+#[cfg(unix)]
+fn touch_impl(path: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    // Null `times` sets both access and modification time to now.
+    if unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), std::ptr::null(), 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn touch_impl(path: &Path) -> io::Result<()> {
+    // TODO: use `File::set_modified` once it is available on our toolchain.
+    fs::metadata(path)?;
+    Ok(())
+}
+
 pub fn read_link<P: AsRef<AbsPath>>(path: P) -> anyhow::Result<PathBuf> {
     let _guard = IoCounterKey::ReadLink.guard();
     fs::read_link(path.as_ref().as_maybe_relativized())
@@ -980,4 +1126,26 @@ mod tests {
             assert_eq!(0o111, mode & 0o111);
         }
     }
+
+    #[test]
+    fn test_clone_or_copy() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = AbsPath::new(tempdir.path()).unwrap();
+        let src = root.join("src");
+        let dst = root.join("dst");
+        fs_util::write(&src, b"contents").unwrap();
+        fs_util::set_executable(&src).unwrap();
+
+        // Whether a clone happens depends on the filesystem, but the result must be the same.
+        fs_util::clone_or_copy(&src, &dst).unwrap();
+        assert_eq!("contents", fs_util::read_to_string(&dst).unwrap());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs_util::metadata(&dst).unwrap().permissions().mode();
+            assert_eq!(0o111, mode & 0o111);
+        }
+    }
 }
@@ -530,7 +530,7 @@ impl ProjectRoot {
     }
 
     fn copy_file(src: &AbsNormPathBuf, dst: &AbsNormPathBuf) -> anyhow::Result<()> {
-        fs_util::copy(src, dst).map(|_| ())
+        fs_util::clone_or_copy(src, dst).map(|_| ())
     }
 
     fn copy_dir(src_dir: &AbsNormPathBuf, dest_dir: &AbsNormPathBuf) -> anyhow::Result<()> {
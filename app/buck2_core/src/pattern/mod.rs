@@ -12,6 +12,7 @@
 #![doc = include_str!("target_pattern.md")]
 
 mod ascii_pattern;
+pub mod modifiers;
 pub mod parse_package;
 pub mod pattern_type;
 pub mod query_file_literal;
@@ -990,6 +991,19 @@ pub enum PackageSpec<T: PatternType> {
     /// All targets in a package, without subpackages.
     /// Syntax for this variant is `foo:`.
     All,
+    /// All targets in a package except the given ones, without subpackages.
+    /// Produced when targets are excluded on the command line (`foo: -foo:bar`).
+    AllExcept(Vec<TargetName>),
+}
+
+impl<T: PatternType> PackageSpec<T> {
+    /// Whether the target is excluded by a [`PackageSpec::AllExcept`].
+    pub fn is_excluded(&self, target: &TargetNameRef) -> bool {
+        match self {
+            PackageSpec::AllExcept(excluded) => excluded.iter().any(|t| t.as_ref() == target),
+            PackageSpec::Targets(..) | PackageSpec::All => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,62 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Configuration modifiers attached to command-line patterns, e.g. `//foo:bar?linux-arm64`.
+
+#[derive(Debug, thiserror::Error)]
+enum PatternModifiersError {
+    #[error("Pattern `{0}` has a `?` but no modifiers after it")]
+    EmptyModifiers(String),
+    #[error("Pattern `{0}` has an empty modifier")]
+    EmptyModifier(String),
+}
+
+/// Splits the modifiers off a command-line pattern. Modifiers follow the pattern after a `?`,
+/// and are separated by `,`. Returns the pattern without modifiers and the modifiers in the order
+/// they were given (later modifiers take precedence when applied).
+pub fn split_modifiers(pattern: &str) -> anyhow::Result<(&str, Vec<&str>)> {
+    match pattern.split_once('?') {
+        None => Ok((pattern, Vec::new())),
+        Some((_, "")) => Err(PatternModifiersError::EmptyModifiers(pattern.to_owned()).into()),
+        Some((target, modifiers)) => {
+            let modifiers: Vec<&str> = modifiers.split(',').collect();
+            if modifiers.iter().any(|m| m.is_empty()) {
+                return Err(PatternModifiersError::EmptyModifier(pattern.to_owned()).into());
+            }
+            Ok((target, modifiers))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pattern::modifiers::split_modifiers;
+
+    #[test]
+    fn test_split_modifiers() {
+        assert_eq!(
+            ("//foo:bar", Vec::<&str>::new()),
+            split_modifiers("//foo:bar").unwrap()
+        );
+        assert_eq!(
+            ("//foo:bar", vec!["linux-arm64"]),
+            split_modifiers("//foo:bar?linux-arm64").unwrap()
+        );
+        assert_eq!(
+            ("//foo/...", vec!["//os:linux", "//cpu:arm64"]),
+            split_modifiers("//foo/...?//os:linux,//cpu:arm64").unwrap()
+        );
+        assert_eq!(
+            ("//foo:bar[sub]", vec!["release"]),
+            split_modifiers("//foo:bar[sub]?release").unwrap()
+        );
+        assert!(split_modifiers("//foo:bar?").is_err());
+        assert!(split_modifiers("//foo:bar?a,,b").is_err());
+    }
+}
@@ -0,0 +1,588 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Support for credential helpers implementing the
+//! [Bazel credential helper protocol](https://github.com/EngFlow/credential-helper-spec).
+//!
+//! Like Bazel's `--credential_helper=<host-pattern>=<helper>`, each helper is scoped to the hosts
+//! matching a pattern, so that credentials are never sent to hosts they weren't meant for.
+//!
+//! The helper is invoked as `<helper> get` with `{"uri": "..."}` on stdin, and prints the headers
+//! to send with requests to that URI, e.g.
+//! `{"headers": {"Authorization": ["Bearer ..."]}, "expires": "2023-01-01T00:00:00Z"}`.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Weak;
+use std::time::Duration;
+
+use allocative::Allocative;
+use anyhow::Context;
+use chrono::DateTime;
+use chrono::Utc;
+use dupe::Dupe;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// How long to reuse headers for when the helper doesn't say when they expire.
+const DEFAULT_CACHE_DURATION_SECS: i64 = 30 * 60;
+
+/// How long before credentials expire the background refresh fetches new ones.
+const REFRESH_MARGIN_SECS: i64 = 5 * 60;
+
+/// The shortest wait between two background refreshes, which is also how long to wait before
+/// retrying a refresh that failed.
+const MIN_REFRESH_DELAY: Duration = Duration::from_secs(10);
+
+/// How long a helper may run before it is killed, the same default as Bazel.
+const DEFAULT_HELPER_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+enum CredentialHelperError {
+    #[error("Credential helper `{helper}` failed with {status}: {stderr}")]
+    Failed {
+        helper: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+    #[error("Credential helper `{0}` timed out after {1:?}")]
+    TimedOut(String, Duration),
+    #[error("URI `{0}` has no host")]
+    NoHost(String),
+    #[error(
+        "Credential helper `{0}` must be scoped to hosts as `<host-pattern>=<helper>`, e.g. `*.example.com={0}`"
+    )]
+    Unscoped(String),
+    #[error("Invalid host pattern `{0}`, expected a host name or `*.<domain>`")]
+    InvalidHostPattern(String),
+}
+
+/// The hosts a credential helper is used for: either a host name, or `*.example.com` for
+/// `example.com` and all of its subdomains.
+#[derive(Debug, Clone, PartialEq, Eq, Allocative)]
+pub enum HostPattern {
+    Host(String),
+    Domain(String),
+}
+
+impl FromStr for HostPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (pattern, domain) = match s.strip_prefix("*.") {
+            Some(domain) => (HostPattern::Domain(domain.to_ascii_lowercase()), domain),
+            None => (HostPattern::Host(s.to_ascii_lowercase()), s),
+        };
+        if domain.is_empty() || domain.contains(['*', '/', ':']) {
+            return Err(CredentialHelperError::InvalidHostPattern(s.to_owned()).into());
+        }
+        Ok(pattern)
+    }
+}
+
+impl HostPattern {
+    /// `host` must be lowercase.
+    fn matches(&self, host: &str) -> bool {
+        match self {
+            HostPattern::Host(pattern) => host == pattern,
+            HostPattern::Domain(domain) => {
+                host == domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .and_then(|subdomain| subdomain.strip_suffix('.'))
+                        .is_some()
+            }
+        }
+    }
+
+    /// When several patterns match a host, the most specific one wins: a host name, then the
+    /// longest domain.
+    fn specificity(&self) -> (bool, usize) {
+        match self {
+            HostPattern::Host(host) => (true, host.len()),
+            HostPattern::Domain(domain) => (false, domain.len()),
+        }
+    }
+}
+
+/// A helper and the hosts it is used for, parsed from `<host-pattern>=<helper>`.
+#[derive(Debug, Clone, PartialEq, Eq, Allocative)]
+pub struct ScopedCredentialHelper {
+    pattern: HostPattern,
+    helper: String,
+}
+
+impl FromStr for ScopedCredentialHelper {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        match s.split_once('=') {
+            Some((pattern, helper)) if !helper.trim().is_empty() => Ok(Self {
+                pattern: pattern.trim().parse()?,
+                helper: helper.trim().to_owned(),
+            }),
+            _ => Err(CredentialHelperError::Unscoped(s.to_owned()).into()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GetCredentialsResponse {
+    #[serde(default)]
+    headers: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    expires: Option<String>,
+}
+
+/// Headers returned by a credential helper for a host.
+#[derive(Debug)]
+pub struct Credentials {
+    pub headers: Vec<(String, String)>,
+    expires: DateTime<Utc>,
+}
+
+struct HostCredentials {
+    /// The helper to run for this host.
+    helper: String,
+    latest: Mutex<Option<Arc<Credentials>>>,
+    /// Held while the helper runs, so that concurrent requests don't all invoke it.
+    fetching: tokio::sync::Mutex<()>,
+    /// Whether a background refresh was started for this host.
+    refreshing: AtomicBool,
+}
+
+impl HostCredentials {
+    fn new(helper: String) -> Self {
+        Self {
+            helper,
+            latest: Mutex::new(None),
+            fetching: tokio::sync::Mutex::new(()),
+            refreshing: AtomicBool::new(false),
+        }
+    }
+
+    fn latest(&self) -> Option<Arc<Credentials>> {
+        self.latest.lock().as_ref().map(Dupe::dupe)
+    }
+
+    fn valid(&self, now: DateTime<Utc>) -> Option<Arc<Credentials>> {
+        self.latest()
+            .filter(|credentials| credentials.expires > now)
+    }
+}
+
+/// Runs credential helpers and caches their responses per host until they expire.
+///
+/// The helpers are shared by everything that talks to remote hosts (HTTP downloads and RE), so
+/// that they only run once per host.
+#[derive(Allocative)]
+pub struct CredentialHelper {
+    helpers: Vec<ScopedCredentialHelper>,
+    /// The helpers run in this directory, which is the project root.
+    cwd: PathBuf,
+    timeout: Duration,
+    /// Credentials for hosts that a helper matches.
+    #[allocative(skip)]
+    hosts: Mutex<HashMap<String, Arc<HostCredentials>>>,
+}
+
+impl CredentialHelper {
+    /// A helper given as a relative path (as opposed to a program name found in `$PATH`) is
+    /// relative to `cwd`.
+    pub fn new(helpers: Vec<ScopedCredentialHelper>, cwd: PathBuf) -> Self {
+        Self {
+            helpers,
+            cwd,
+            timeout: DEFAULT_HELPER_TIMEOUT,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How long a helper may run before it is killed and the request fails.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The helper for `host`, which must be lowercase.
+    fn helper_for(&self, host: &str) -> Option<&str> {
+        self.helpers
+            .iter()
+            .filter(|helper| helper.pattern.matches(host))
+            .max_by_key(|helper| helper.pattern.specificity())
+            .map(|helper| helper.helper.as_str())
+    }
+
+    /// The credentials for the host of `uri`, or `None` if no helper is configured for it.
+    fn host(&self, uri: &http::Uri) -> anyhow::Result<Option<Arc<HostCredentials>>> {
+        let host = uri
+            .host()
+            .ok_or_else(|| CredentialHelperError::NoHost(uri.to_string()))?
+            .to_ascii_lowercase();
+        let helper = match self.helper_for(&host) {
+            Some(helper) => helper,
+            None => return Ok(None),
+        };
+        Ok(Some(
+            self.hosts
+                .lock()
+                .entry(host)
+                .or_insert_with(|| Arc::new(HostCredentials::new(helper.to_owned())))
+                .dupe(),
+        ))
+    }
+
+    /// Headers to send with a request to `uri`, or `None` if no helper is configured for its host.
+    /// This waits for the helper to run the first time a host is seen and when its credentials
+    /// expire.
+    pub async fn get(&self, uri: &http::Uri) -> anyhow::Result<Option<Arc<Credentials>>> {
+        let host = match self.host(uri)? {
+            Some(host) => host,
+            None => return Ok(None),
+        };
+        if let Some(credentials) = host.valid(Utc::now()) {
+            return Ok(Some(credentials));
+        }
+
+        let _guard = host.fetching.lock().await;
+        // Someone else may have fetched them while we were waiting.
+        if let Some(credentials) = host.valid(Utc::now()) {
+            return Ok(Some(credentials));
+        }
+        Ok(Some(self.fetch(uri, &host).await?))
+    }
+
+    /// The last headers fetched for the host of `uri`, if any, without running the helper. They
+    /// are kept up to date for hosts passed to `prefetch`.
+    pub fn cached(&self, uri: &http::Uri) -> Option<Arc<Credentials>> {
+        let host = uri.host()?.to_ascii_lowercase();
+        self.hosts.lock().get(&host)?.latest()
+    }
+
+    /// Fetches the headers for the host of `uri`, and keeps refreshing them in the background
+    /// before they expire, for as long as this helper is alive. Use this for clients that can only
+    /// read `cached` credentials when sending requests. Does nothing for hosts that no helper is
+    /// configured for.
+    pub async fn prefetch(self: &Arc<Self>, uri: http::Uri) -> anyhow::Result<()> {
+        let host = match self.host(&uri)? {
+            Some(host) => host,
+            None => return Ok(()),
+        };
+        self.get(&uri).await?;
+
+        if !host.refreshing.swap(true, Ordering::Relaxed) {
+            tokio::spawn(Self::refresh(Arc::downgrade(self), uri, host));
+        }
+        Ok(())
+    }
+
+    async fn refresh(this: Weak<Self>, uri: http::Uri, host: Arc<HostCredentials>) {
+        loop {
+            let delay = host
+                .latest()
+                .and_then(|credentials| {
+                    (credentials.expires
+                        - chrono::Duration::seconds(REFRESH_MARGIN_SECS)
+                        - Utc::now())
+                    .to_std()
+                    .ok()
+                })
+                .unwrap_or_default()
+                .max(MIN_REFRESH_DELAY);
+            tokio::time::sleep(delay).await;
+
+            let this = match this.upgrade() {
+                Some(this) => this,
+                None => return,
+            };
+            let _guard = host.fetching.lock().await;
+            if let Err(e) = this.fetch(&uri, &host).await {
+                tracing::warn!("Error refreshing credentials: {:#}", e);
+            }
+        }
+    }
+
+    async fn fetch(
+        &self,
+        uri: &http::Uri,
+        host: &HostCredentials,
+    ) -> anyhow::Result<Arc<Credentials>> {
+        let credentials = Arc::new(
+            self.invoke(&host.helper, uri, Utc::now())
+                .await
+                .with_context(|| format!("Error getting credentials for `{}`", uri))?,
+        );
+        *host.latest.lock() = Some(credentials.dupe());
+        Ok(credentials)
+    }
+
+    async fn invoke(
+        &self,
+        helper: &str,
+        uri: &http::Uri,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Credentials> {
+        let mut child = Command::new(resolve_program(helper, &self.cwd))
+            .arg("get")
+            .current_dir(&self.cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Error spawning credential helper `{}`", helper))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+
+        let request = serde_json::json!({ "uri": uri.to_string() }).to_string();
+        let run = async {
+            // Dropping stdin closes it once written, so the helper sees EOF.
+            stdin
+                .write_all(request.as_bytes())
+                .await
+                .context("Error writing request to credential helper")?;
+            drop(stdin);
+
+            let mut out = Vec::new();
+            let mut err = Vec::new();
+            tokio::try_join!(stdout.read_to_end(&mut out), stderr.read_to_end(&mut err))
+                .context("Error reading output of credential helper")?;
+            let status = child
+                .wait()
+                .await
+                .context("Error waiting for credential helper")?;
+            anyhow::Ok((status, out, err))
+        };
+
+        let (status, stdout, stderr) = match tokio::time::timeout(self.timeout, run).await {
+            Ok(res) => res?,
+            Err(_) => {
+                // A helper that has already exited can't be killed, which is fine.
+                let _ignored = child.kill().await;
+                return Err(
+                    CredentialHelperError::TimedOut(helper.to_owned(), self.timeout).into(),
+                );
+            }
+        };
+        if !status.success() {
+            return Err(CredentialHelperError::Failed {
+                helper: helper.to_owned(),
+                status,
+                stderr: String::from_utf8_lossy(&stderr).trim().to_owned(),
+            }
+            .into());
+        }
+
+        parse_response(&stdout, now)
+    }
+}
+
+/// Whether a relative program path is resolved against the parent's or the child's working
+/// directory is platform dependent, so resolve it ourselves. Bare names are left for `$PATH`.
+fn resolve_program(helper: &str, cwd: &Path) -> PathBuf {
+    let path = Path::new(helper);
+    if path.is_relative() && path.components().count() > 1 {
+        cwd.join(path)
+    } else {
+        path.to_owned()
+    }
+}
+
+fn parse_response(stdout: &[u8], now: DateTime<Utc>) -> anyhow::Result<Credentials> {
+    let response: GetCredentialsResponse =
+        serde_json::from_slice(stdout).context("Invalid response from credential helper")?;
+
+    let expires = match response.expires {
+        Some(expires) => DateTime::parse_from_rfc3339(&expires)
+            .with_context(|| format!("Invalid expiry from credential helper: `{}`", expires))?
+            .with_timezone(&Utc),
+        None => now + chrono::Duration::seconds(DEFAULT_CACHE_DURATION_SECS),
+    };
+
+    let headers = response
+        .headers
+        .into_iter()
+        .flat_map(|(key, values)| values.into_iter().map(move |value| (key.clone(), value)))
+        .collect();
+
+    Ok(Credentials { headers, expires })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() -> anyhow::Result<()> {
+        let now = Utc::now();
+        let credentials = parse_response(
+            br#"{"headers": {"Authorization": ["Bearer token"], "X-Extra": ["a", "b"]}, "expires": "2030-01-02T03:04:05Z"}"#,
+            now,
+        )?;
+        assert_eq!(
+            credentials.headers,
+            vec![
+                ("Authorization".to_owned(), "Bearer token".to_owned()),
+                ("X-Extra".to_owned(), "a".to_owned()),
+                ("X-Extra".to_owned(), "b".to_owned()),
+            ]
+        );
+        assert_eq!(
+            credentials.expires.to_rfc3339(),
+            "2030-01-02T03:04:05+00:00"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_response_defaults() -> anyhow::Result<()> {
+        let now = Utc::now();
+        let credentials = parse_response(b"{}", now)?;
+        assert!(credentials.headers.is_empty());
+        assert_eq!(
+            credentials.expires,
+            now + chrono::Duration::seconds(DEFAULT_CACHE_DURATION_SECS)
+        );
+
+        assert!(parse_response(br#"{"expires": "tomorrow"}"#, now).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_program() {
+        let cwd = Path::new("/project");
+        assert_eq!(
+            resolve_program("tools/helper", cwd),
+            Path::new("/project/tools/helper")
+        );
+        assert_eq!(
+            resolve_program("/usr/bin/helper", cwd),
+            Path::new("/usr/bin/helper")
+        );
+        assert_eq!(resolve_program("helper", cwd), Path::new("helper"));
+    }
+
+    #[test]
+    fn test_parse_scoped_helper() -> anyhow::Result<()> {
+        assert_eq!(
+            " *.Example.com = tools/helper ".parse::<ScopedCredentialHelper>()?,
+            ScopedCredentialHelper {
+                pattern: HostPattern::Domain("example.com".to_owned()),
+                helper: "tools/helper".to_owned(),
+            }
+        );
+        assert!("tools/helper".parse::<ScopedCredentialHelper>().is_err());
+        assert!("=tools/helper".parse::<ScopedCredentialHelper>().is_err());
+        assert!("example.com=".parse::<ScopedCredentialHelper>().is_err());
+        assert!("*=tools/helper".parse::<ScopedCredentialHelper>().is_err());
+        assert!(
+            "a.*.com=tools/helper"
+                .parse::<ScopedCredentialHelper>()
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_helper_for() -> anyhow::Result<()> {
+        let helper = CredentialHelper::new(
+            vec![
+                "*.example.com=domain".parse()?,
+                "*.cache.example.com=cache".parse()?,
+                "special.cache.example.com=special".parse()?,
+            ],
+            PathBuf::new(),
+        );
+        assert_eq!(helper.helper_for("example.com"), Some("domain"));
+        assert_eq!(helper.helper_for("www.example.com"), Some("domain"));
+        assert_eq!(helper.helper_for("a.cache.example.com"), Some("cache"));
+        assert_eq!(
+            helper.helper_for("special.cache.example.com"),
+            Some("special")
+        );
+        assert_eq!(helper.helper_for("notexample.com"), None);
+        assert_eq!(helper.helper_for("example.org"), None);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn write_helper(dir: &Path, script: &str) -> anyhow::Result<String> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("helper");
+        std::fs::write(&path, script)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        Ok(path.to_str().unwrap().to_owned())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_get() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let script = write_helper(
+            dir.path(),
+            "#!/bin/sh\ncat > /dev/null\necho '{\"headers\": {\"Authorization\": [\"Bearer token\"]}}'\n",
+        )?;
+        let helper = CredentialHelper::new(
+            vec![format!("example.com={}", script).parse()?],
+            dir.path().to_owned(),
+        );
+
+        let credentials = helper
+            .get(&"https://EXAMPLE.com/file".parse()?)
+            .await?
+            .unwrap();
+        assert_eq!(
+            credentials.headers,
+            vec![("Authorization".to_owned(), "Bearer token".to_owned())]
+        );
+        assert!(
+            helper
+                .get(&"https://other.com/file".parse()?)
+                .await?
+                .is_none()
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timeout() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let script = write_helper(dir.path(), "#!/bin/sh\nsleep 60\n")?;
+        let helper = CredentialHelper::new(
+            vec![format!("example.com={}", script).parse()?],
+            dir.path().to_owned(),
+        )
+        .with_timeout(Duration::from_millis(200));
+
+        let start = std::time::Instant::now();
+        let err = helper
+            .get(&"https://example.com/file".parse()?)
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("timed out"), "{:#}", err);
+        assert!(start.elapsed() < Duration::from_secs(30));
+        Ok(())
+    }
+}
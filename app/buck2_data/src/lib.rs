@@ -177,6 +177,9 @@ impl fmt::Display for ErrorCategory {
         let msg = match &self {
             ErrorCategory::Infra => "This error is an internal Buck2 error",
             ErrorCategory::User => "This error was caused by the end user",
+            ErrorCategory::Environment => {
+                "This error was caused by the environment Buck2 is running in"
+            }
         };
 
         write!(f, "{}", msg)
@@ -188,12 +191,29 @@ impl fmt::Display for ErrorCause {
         let msg = match &self {
             ErrorCause::InvalidPackage => "The package is invalid",
             ErrorCause::DaemonIsBusy => "Buck daemon is busy processing another command",
+            ErrorCause::ReUnavailable => "Remote execution is unavailable",
+            ErrorCause::MaterializationFailed => "Materializing outputs failed",
+            ErrorCause::ActionCommandFailed => "An action's command failed",
+            ErrorCause::FileWatcherUnavailable => "The file watcher is unavailable",
         };
 
         write!(f, "{}", msg)
     }
 }
 
+impl ErrorCause {
+    /// The category of errors with this cause, unless they carry one explicitly.
+    pub fn category(self) -> ErrorCategory {
+        match self {
+            ErrorCause::InvalidPackage | ErrorCause::ActionCommandFailed => ErrorCategory::User,
+            ErrorCause::ReUnavailable | ErrorCause::MaterializationFailed => ErrorCategory::Infra,
+            ErrorCause::DaemonIsBusy | ErrorCause::FileWatcherUnavailable => {
+                ErrorCategory::Environment
+            }
+        }
+    }
+}
+
 impl fmt::Display for DaemonShutdown {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}, caller:", self.reason)?;
@@ -0,0 +1,505 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A summary of a command for CI to archive: how its actions ran, which were slowest, its
+//! critical path, and how much data and memory it used.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use buck2_common::convert::ProstDurationExt;
+use buck2_events::BuckEvent;
+
+use crate::action_stats::ActionStats;
+use crate::display;
+use crate::display::TargetDisplayOptions;
+use crate::fmt_duration::fmt_duration;
+use crate::humanized::HumanizedBytes;
+
+/// How many of the slowest actions to keep.
+const SLOWEST_ACTIONS: usize = 10;
+
+/// Counts of actions by how they were executed.
+#[derive(Debug, Default, Clone, serde::Serialize)]
+pub struct ActionCounts {
+    pub local: u64,
+    pub remote: u64,
+    pub cache_hits: u64,
+    /// Actions that didn't need to run because their dep files hadn't changed.
+    pub dep_file_hits: u64,
+    /// Actions whose command ran more than once, e.g. locally after failing remotely.
+    pub fallback: u64,
+}
+
+/// An action or critical path node, and how long it took.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+pub struct TimedEntry {
+    pub duration_us: u64,
+    pub kind: &'static str,
+    pub name: String,
+}
+
+/// The summary, as written to `--build-report-summary`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BuildSummary {
+    pub actions: ActionCounts,
+    pub cache_hit_percentage: u8,
+    /// Slowest first.
+    pub slowest_actions: Vec<TimedEntry>,
+    pub critical_path: Vec<TimedEntry>,
+    pub critical_path_duration_us: u64,
+    pub re_upload_bytes: u64,
+    pub re_download_bytes: u64,
+    /// The peak resident set size of the daemon, if it reported one.
+    pub peak_memory_bytes: Option<u64>,
+}
+
+/// Aggregates a [`BuildSummary`](BuildSummary) from the events of a command.
+#[derive(Default)]
+pub struct BuildSummaryState {
+    action_stats: ActionStats,
+    dep_file_hits: u64,
+    /// The slowest actions so far, fastest on top so it's cheap to evict.
+    slowest_actions: BinaryHeap<Reverse<TimedEntry>>,
+    critical_path: Vec<TimedEntry>,
+    /// RE byte counters are cumulative for the daemon, so keep the first and last snapshots of
+    /// this command to tell how much it transferred.
+    first_snapshot: Option<buck2_data::Snapshot>,
+    last_snapshot: Option<buck2_data::Snapshot>,
+    peak_memory_bytes: Option<u64>,
+}
+
+impl BuildSummaryState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, event: &BuckEvent) -> anyhow::Result<()> {
+        match event.data() {
+            buck2_data::buck_event::Data::SpanEnd(buck2_data::SpanEndEvent {
+                data: Some(buck2_data::span_end_event::Data::ActionExecution(action)),
+                ..
+            }) => self.handle_action_execution_end(action)?,
+            buck2_data::buck_event::Data::Instant(buck2_data::InstantEvent {
+                data: Some(instant),
+            }) => match instant {
+                buck2_data::instant_event::Data::Snapshot(snapshot) => {
+                    self.handle_snapshot(snapshot)
+                }
+                buck2_data::instant_event::Data::BuildGraphInfo(info) => {
+                    self.handle_build_graph_info(info)?
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_action_execution_end(
+        &mut self,
+        action: &buck2_data::ActionExecutionEnd,
+    ) -> anyhow::Result<()> {
+        self.action_stats.update(action);
+        if action.execution_kind == buck2_data::ActionExecutionKind::Skipped as i32 {
+            self.dep_file_hits += 1;
+        }
+
+        let duration_us = match &action.wall_time {
+            Some(wall_time) => wall_time.try_into_duration()?.as_micros() as u64,
+            None => return Ok(()),
+        };
+        if self.slowest_actions.len() >= SLOWEST_ACTIONS {
+            match self.slowest_actions.peek() {
+                Some(Reverse(fastest)) if fastest.duration_us < duration_us => {
+                    self.slowest_actions.pop();
+                }
+                _ => return Ok(()),
+            }
+        }
+        self.slowest_actions.push(Reverse(TimedEntry {
+            duration_us,
+            kind: "action",
+            name: display::display_action_identity(
+                action.key.as_ref(),
+                action.name.as_ref(),
+                TargetDisplayOptions::for_log(),
+            )?,
+        }));
+        Ok(())
+    }
+
+    fn handle_snapshot(&mut self, snapshot: &buck2_data::Snapshot) {
+        if snapshot.buck2_max_rss > 0 {
+            self.peak_memory_bytes = Some(
+                self.peak_memory_bytes
+                    .unwrap_or_default()
+                    .max(snapshot.buck2_max_rss),
+            );
+        }
+        if self.first_snapshot.is_none() {
+            self.first_snapshot = Some(snapshot.clone());
+        }
+        self.last_snapshot = Some(snapshot.clone());
+    }
+
+    fn handle_build_graph_info(
+        &mut self,
+        info: &buck2_data::BuildGraphExecutionInfo,
+    ) -> anyhow::Result<()> {
+        let mut critical_path = Vec::with_capacity(info.critical_path2.len());
+        for entry in &info.critical_path2 {
+            let (kind, name) = match critical_path_entry_name(entry)? {
+                Some(name) => name,
+                None => continue,
+            };
+            let duration_us = match &entry.duration {
+                Some(duration) => duration.try_into_duration()?.as_micros() as u64,
+                None => 0,
+            };
+            critical_path.push(TimedEntry {
+                duration_us,
+                kind,
+                name,
+            });
+        }
+        self.critical_path = critical_path;
+        Ok(())
+    }
+
+    pub fn summary(&self) -> BuildSummary {
+        let (re_upload_bytes, re_download_bytes) = match (&self.first_snapshot, &self.last_snapshot)
+        {
+            (Some(first), Some(last)) => (
+                last.re_upload_bytes.saturating_sub(first.re_upload_bytes),
+                last.re_download_bytes
+                    .saturating_sub(first.re_download_bytes),
+            ),
+            _ => (0, 0),
+        };
+
+        let mut slowest_actions: Vec<TimedEntry> = self
+            .slowest_actions
+            .iter()
+            .map(|Reverse(entry)| entry.clone())
+            .collect();
+        slowest_actions.sort_by(|a, b| b.cmp(a));
+
+        BuildSummary {
+            actions: ActionCounts {
+                local: self.action_stats.local_actions,
+                remote: self.action_stats.remote_actions,
+                cache_hits: self.action_stats.cached_actions,
+                dep_file_hits: self.dep_file_hits,
+                fallback: self.action_stats.fallback_actions,
+            },
+            cache_hit_percentage: self.action_stats.action_cache_hit_percentage(),
+            slowest_actions,
+            critical_path_duration_us: self.critical_path.iter().map(|e| e.duration_us).sum(),
+            critical_path: self.critical_path.clone(),
+            re_upload_bytes,
+            re_download_bytes,
+            peak_memory_bytes: self.peak_memory_bytes,
+        }
+    }
+}
+
+/// What kind of node this is on the critical path, and what to call it.
+fn critical_path_entry_name(
+    entry: &buck2_data::CriticalPathEntry2,
+) -> anyhow::Result<Option<(&'static str, String)>> {
+    use buck2_data::critical_path_entry2::Entry;
+
+    let opts = TargetDisplayOptions::for_log();
+    Ok(Some(match &entry.entry {
+        Some(Entry::Analysis(analysis)) => {
+            use buck2_data::critical_path_entry2::analysis::Target;
+
+            match &analysis.target {
+                Some(Target::StandardTarget(t)) => (
+                    "analysis",
+                    display::display_configured_target_label(t, opts)?,
+                ),
+                None => return Ok(None),
+            }
+        }
+        Some(Entry::ActionExecution(action_execution)) => {
+            use buck2_data::critical_path_entry2::action_execution::Owner;
+
+            let owner = match &action_execution.owner {
+                Some(Owner::TargetLabel(t)) => display::display_configured_target_label(t, opts)?,
+                Some(Owner::BxlKey(t)) => display::display_bxl_key(t)?,
+                Some(Owner::AnonTarget(t)) => display::display_anon_target(t)?,
+                None => return Ok(None),
+            };
+            let name = match &action_execution.name {
+                Some(buck2_data::ActionName {
+                    category,
+                    identifier,
+                }) if !identifier.is_empty() => {
+                    format!("{} ({} {})", owner, category, identifier)
+                }
+                Some(buck2_data::ActionName { category, .. }) => {
+                    format!("{} ({})", owner, category)
+                }
+                None => owner,
+            };
+            ("action", name)
+        }
+        Some(Entry::Materialization(materialization)) => {
+            use buck2_data::critical_path_entry2::materialization::Owner;
+
+            let owner = match &materialization.owner {
+                Some(Owner::TargetLabel(t)) => display::display_configured_target_label(t, opts)?,
+                Some(Owner::BxlKey(t)) => display::display_bxl_key(t)?,
+                Some(Owner::AnonTarget(t)) => display::display_anon_target(t)?,
+                None => return Ok(None),
+            };
+            (
+                "materialization",
+                format!("{} ({})", owner, materialization.path),
+            )
+        }
+        Some(Entry::ComputeCriticalPath(..)) => ("compute-critical-path", String::new()),
+        Some(Entry::Load(load)) => ("load", load.package.clone()),
+        None => return Ok(None),
+    }))
+}
+
+/// Keep table cells on one row and in one column.
+fn markdown_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+fn fmt_us(duration_us: u64) -> String {
+    fmt_duration(Duration::from_micros(duration_us), 1.0)
+}
+
+impl BuildSummary {
+    /// The summary as markdown, e.g. to post on a pull request.
+    pub fn to_markdown(&self) -> String {
+        // Writing to a String can't fail.
+        let mut out = String::new();
+        let actions = &self.actions;
+
+        writeln!(out, "## Build summary\n").unwrap();
+        writeln!(out, "| Actions | Count |").unwrap();
+        writeln!(out, "| --- | ---: |").unwrap();
+        for (name, count) in [
+            ("Local", actions.local),
+            ("Remote", actions.remote),
+            ("Cache hits", actions.cache_hits),
+            ("Dep file hits", actions.dep_file_hits),
+            ("Fallback", actions.fallback),
+        ] {
+            writeln!(out, "| {} | {} |", name, count).unwrap();
+        }
+        writeln!(out, "\nCache hit rate: {}%", self.cache_hit_percentage).unwrap();
+
+        if !self.slowest_actions.is_empty() {
+            writeln!(out, "\n### Slowest actions\n").unwrap();
+            writeln!(out, "| Action | Duration |").unwrap();
+            writeln!(out, "| --- | ---: |").unwrap();
+            for entry in &self.slowest_actions {
+                writeln!(
+                    out,
+                    "| {} | {} |",
+                    markdown_cell(&entry.name),
+                    fmt_us(entry.duration_us)
+                )
+                .unwrap();
+            }
+        }
+
+        if !self.critical_path.is_empty() {
+            writeln!(
+                out,
+                "\n### Critical path ({})\n",
+                fmt_us(self.critical_path_duration_us)
+            )
+            .unwrap();
+            writeln!(out, "| Kind | Name | Duration |").unwrap();
+            writeln!(out, "| --- | --- | ---: |").unwrap();
+            for entry in &self.critical_path {
+                writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    entry.kind,
+                    markdown_cell(&entry.name),
+                    fmt_us(entry.duration_us)
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(out, "\n### Resources\n").unwrap();
+        writeln!(
+            out,
+            "- RE uploads: {}",
+            HumanizedBytes::new(self.re_upload_bytes)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "- RE downloads: {}",
+            HumanizedBytes::new(self.re_download_bytes)
+        )
+        .unwrap();
+        if let Some(peak_memory_bytes) = self.peak_memory_bytes {
+            writeln!(
+                out,
+                "- Peak daemon memory: {}",
+                HumanizedBytes::new(peak_memory_bytes)
+            )
+            .unwrap();
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use buck2_wrapper_common::invocation_id::TraceId;
+
+    use super::*;
+
+    fn event(data: buck2_data::buck_event::Data) -> BuckEvent {
+        BuckEvent::new(UNIX_EPOCH, TraceId::new(), None, None, data)
+    }
+
+    fn action(
+        identifier: &str,
+        kind: buck2_data::ActionExecutionKind,
+        secs: u64,
+    ) -> anyhow::Result<BuckEvent> {
+        let command = buck2_data::CommandExecution {
+            details: Some(buck2_data::CommandExecutionDetails {
+                command: Some(match kind {
+                    buck2_data::ActionExecutionKind::Remote => {
+                        buck2_data::command_execution_details::Command::RemoteCommand(
+                            Default::default(),
+                        )
+                    }
+                    _ => buck2_data::command_execution_details::Command::LocalCommand(
+                        Default::default(),
+                    ),
+                }),
+                ..Default::default()
+            }),
+            status: Some(buck2_data::command_execution::Status::Success(
+                Default::default(),
+            )),
+        };
+        Ok(event(
+            buck2_data::SpanEndEvent {
+                data: Some(
+                    Box::new(buck2_data::ActionExecutionEnd {
+                        key: Some(buck2_data::ActionKey {
+                            owner: Some(buck2_data::action_key::Owner::BxlKey(
+                                buck2_data::BxlFunctionKey {
+                                    label: Some(buck2_data::BxlFunctionLabel {
+                                        bxl_path: "//x.bxl".to_owned(),
+                                        name: "f".to_owned(),
+                                    }),
+                                },
+                            )),
+                            ..Default::default()
+                        }),
+                        name: Some(buck2_data::ActionName {
+                            category: "cxx_compile".to_owned(),
+                            identifier: identifier.to_owned(),
+                        }),
+                        execution_kind: kind as i32,
+                        wall_time: Some(Duration::from_secs(secs).try_into()?),
+                        commands: match kind {
+                            buck2_data::ActionExecutionKind::Skipped => Vec::new(),
+                            _ => vec![command],
+                        },
+                        ..Default::default()
+                    })
+                    .into(),
+                ),
+                ..Default::default()
+            }
+            .into(),
+        ))
+    }
+
+    fn snapshot(max_rss: u64, upload: u64, download: u64) -> BuckEvent {
+        event(
+            buck2_data::InstantEvent {
+                data: Some(
+                    Box::new(buck2_data::Snapshot {
+                        buck2_max_rss: max_rss,
+                        re_upload_bytes: upload,
+                        re_download_bytes: download,
+                        ..Default::default()
+                    })
+                    .into(),
+                ),
+            }
+            .into(),
+        )
+    }
+
+    #[test]
+    fn test_summary() -> anyhow::Result<()> {
+        let mut state = BuildSummaryState::new();
+        for i in 0..(SLOWEST_ACTIONS as u64 + 2) {
+            state.update(&action(
+                &format!("a{}.c", i),
+                buck2_data::ActionExecutionKind::Local,
+                i,
+            )?)?;
+        }
+        state.update(&action(
+            "remote.c",
+            buck2_data::ActionExecutionKind::Remote,
+            0,
+        )?)?;
+        state.update(&action(
+            "skipped.c",
+            buck2_data::ActionExecutionKind::Skipped,
+            0,
+        )?)?;
+        state.update(&snapshot(100, 1000, 5000))?;
+        state.update(&snapshot(300, 1500, 5000))?;
+        state.update(&snapshot(200, 3000, 8000))?;
+
+        let summary = state.summary();
+        assert_eq!(summary.actions.local, SLOWEST_ACTIONS as u64 + 2);
+        assert_eq!(summary.actions.remote, 1);
+        assert_eq!(summary.actions.cache_hits, 0);
+        assert_eq!(summary.actions.dep_file_hits, 1);
+        assert_eq!(summary.cache_hit_percentage, 0);
+        assert_eq!(summary.slowest_actions.len(), SLOWEST_ACTIONS);
+        assert_eq!(summary.slowest_actions[0].duration_us, 11_000_000);
+        assert_eq!(
+            summary.slowest_actions[0].name,
+            "//x.bxl:f (cxx_compile a11.c)"
+        );
+        assert_eq!(summary.slowest_actions[9].duration_us, 2_000_000);
+        assert_eq!(summary.re_upload_bytes, 2000);
+        assert_eq!(summary.re_download_bytes, 3000);
+        assert_eq!(summary.peak_memory_bytes, Some(300));
+
+        let markdown = summary.to_markdown();
+        assert!(markdown.contains("| Dep file hits | 1 |"));
+        assert!(markdown.contains("| //x.bxl:f (cxx_compile a11.c) | 11.0s |"));
+        assert!(markdown.contains("- RE downloads: 2.9 KiB"));
+        assert!(!markdown.contains("Critical path"));
+
+        Ok(())
+    }
+}
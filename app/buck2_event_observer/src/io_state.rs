@@ -11,6 +11,11 @@ use std::time::SystemTime;
 
 use buck2_core::io_counters::IoCounterKey;
 use gazebo::prelude::VecExt;
+use superconsole::components::table::Column;
+use superconsole::components::table::ColumnWidth;
+use superconsole::components::Table;
+use superconsole::Component;
+use superconsole::Dimensions;
 use superconsole::DrawMode;
 use superconsole::Line;
 use superconsole::Lines;
@@ -23,28 +28,23 @@ pub struct IoState {
     two_snapshots: TwoSnapshots,
 }
 
-/// Place space-separated words on lines.
-fn words_to_lines(words: Vec<String>, width: usize) -> Vec<String> {
-    let mut lines = Vec::new();
-    let mut current_line = String::new();
-    for word in words {
-        if current_line.is_empty() {
-            current_line = word;
-            continue;
-        }
-        // This works correctly only for ASCII strings.
-        if current_line.len() + 1 + word.len() > width {
-            lines.push(current_line);
-            current_line = word;
-        } else {
-            current_line.push(' ');
-            current_line.push_str(&word);
-        }
-    }
-    if !current_line.is_empty() {
-        lines.push(current_line);
+/// Spaces between columns of counters.
+const COUNTER_SEPARATOR: usize = 2;
+
+/// Lay out counters in as many equally wide columns as fit in `width`.
+fn counters_table(counters: Vec<String>, width: usize) -> anyhow::Result<Table> {
+    let cell_width = counters.iter().map(|c| c.len()).max().unwrap_or(0);
+    let columns = ((width + COUNTER_SEPARATOR) / (cell_width + COUNTER_SEPARATOR))
+        .min(counters.len())
+        .max(1);
+
+    let mut table = Table::new(vec![Column::new(ColumnWidth::Fixed(cell_width)); columns])
+        .separator(COUNTER_SEPARATOR);
+    let cells = counters.into_try_map(|c| Line::unstyled(&c))?;
+    for row in cells.chunks(columns) {
+        table.push_row(row.iter().cloned());
     }
-    lines
+    Ok(table)
 }
 
 pub fn io_in_flight_non_zero_counters(
@@ -114,7 +114,11 @@ impl IoState {
         for (key, value) in io_in_flight_non_zero_counters(snapshot) {
             counters.push(format!("{:?} = {}", key, value));
         }
-        lines.extend(words_to_lines(counters, width).into_try_map(|s| Line::unstyled(&s))?);
+        lines.extend(
+            counters_table(counters, width)?
+                .draw(Dimensions::new(width, usize::MAX), DrawMode::Normal)?
+                .0,
+        );
 
         Ok(Lines(lines))
     }
@@ -141,26 +145,27 @@ impl IoState {
 
 #[cfg(test)]
 mod tests {
-    use super::words_to_lines;
+    use super::*;
+
+    fn draw(counters: &[&str], width: usize) -> Vec<String> {
+        counters_table(counters.iter().map(|c| (*c).to_owned()).collect(), width)
+            .unwrap()
+            .draw(Dimensions::new(width, usize::MAX), DrawMode::Normal)
+            .unwrap()
+            .iter()
+            .map(|line| line.to_unstyled())
+            .collect()
+    }
 
     #[test]
-    fn test_words_to_lines() {
-        assert_eq!(vec![String::new(); 0], words_to_lines(vec![], 5));
-        assert_eq!(
-            vec!["ab".to_owned()],
-            words_to_lines(vec!["ab".to_owned()], 5)
-        );
-        assert_eq!(
-            vec!["ab cd".to_owned()],
-            words_to_lines(vec!["ab".to_owned(), "cd".to_owned()], 5)
-        );
-        assert_eq!(
-            vec!["ab".to_owned(), "cd".to_owned()],
-            words_to_lines(vec!["ab".to_owned(), "cd".to_owned()], 4)
-        );
+    fn test_counters_table() {
+        assert_eq!(draw(&[], 20), Vec::<String>::new());
+        assert_eq!(draw(&["Stat = 1"], 20), ["Stat = 1"]);
         assert_eq!(
-            vec!["abcd".to_owned()],
-            words_to_lines(vec!["abcd".to_owned()], 3)
+            draw(&["Stat = 1", "Copy = 22", "Read = 3"], 20),
+            ["Stat = 1   Copy = 22", "Read = 3            "]
         );
+        // At least one column, even if it doesn't fit.
+        assert_eq!(draw(&["Stat = 1", "Copy = 22"], 5), ["Stat ", "Copy "]);
     }
 }
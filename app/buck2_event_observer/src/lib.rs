@@ -10,6 +10,7 @@
 #![feature(try_blocks)]
 
 pub mod action_stats;
+pub mod build_summary;
 pub mod debug_events;
 pub mod dice_state;
 pub mod display;
@@ -9,6 +9,12 @@
 
 use std::time::SystemTime;
 
+use superconsole::components::alignment::HorizontalAlignmentKind;
+use superconsole::components::table::Column;
+use superconsole::components::table::ColumnWidth;
+use superconsole::components::Table;
+use superconsole::Component;
+use superconsole::Dimensions;
 use superconsole::DrawMode;
 use superconsole::Line;
 use superconsole::Lines;
@@ -92,85 +98,117 @@ impl ReState {
         Some(format!("RE: {}", parts.join("  ")))
     }
 
-    fn render_detailed_items(
+    fn push_detailed_row(
         &self,
+        table: &mut Table,
         name: &str,
         started: u32,
         finished_successfully: u32,
         finished_with_error: u32,
-    ) -> anyhow::Result<Option<Line>> {
+    ) -> anyhow::Result<()> {
         let in_progress = started
             .saturating_sub(finished_successfully)
             .saturating_sub(finished_with_error);
         if in_progress == 0 && finished_successfully == 0 && finished_with_error == 0 {
-            return Ok(None);
+            return Ok(());
         }
-        let line = format!(
-            "{name:<20}: \
-            {in_progress:>5} in progress, \
-            {finished_successfully:>5} success, \
-            {finished_with_error:>5} error"
-        );
-        Ok(Some(Line::unstyled(&line)?))
+        table.push_row([
+            Line::unstyled(name)?,
+            Line::unstyled(&in_progress.to_string())?,
+            Line::unstyled(&finished_successfully.to_string())?,
+            Line::unstyled(&finished_with_error.to_string())?,
+        ]);
+        Ok(())
     }
 
-    fn render_detailed(&self) -> anyhow::Result<Vec<Line>> {
-        let mut r = Vec::new();
+    fn render_detailed(&self, dimensions: Dimensions) -> anyhow::Result<Lines> {
+        let number = || Column::new(ColumnWidth::Content).align(HorizontalAlignmentKind::Right);
+        let mut table = Table::new(vec![
+            Column::new(ColumnWidth::Content),
+            number(),
+            number(),
+            number(),
+        ])
+        .separator(2);
+        table.push_row([
+            Line::default(),
+            Line::unstyled("in progress")?,
+            Line::unstyled("success")?,
+            Line::unstyled("error")?,
+        ]);
+
         if let Some((_, last)) = &self.two_snapshots.last {
-            r.extend(self.render_detailed_items(
+            self.push_detailed_row(
+                &mut table,
                 "uploads",
                 last.re_uploads_started,
                 last.re_uploads_finished_successfully,
                 last.re_uploads_finished_with_error,
-            )?);
-            r.extend(self.render_detailed_items(
+            )?;
+            self.push_detailed_row(
+                &mut table,
                 "downloads",
                 last.re_downloads_started,
                 last.re_downloads_finished_successfully,
                 last.re_downloads_finished_with_error,
-            )?);
-            r.extend(self.render_detailed_items(
+            )?;
+            self.push_detailed_row(
+                &mut table,
                 "action_cache",
                 last.re_action_cache_started,
                 last.re_action_cache_finished_successfully,
                 last.re_action_cache_finished_with_error,
-            )?);
-            r.extend(self.render_detailed_items(
+            )?;
+            self.push_detailed_row(
+                &mut table,
                 "executes",
                 last.re_executes_started,
                 last.re_executes_finished_successfully,
                 last.re_executes_finished_with_error,
-            )?);
-            r.extend(self.render_detailed_items(
+            )?;
+            self.push_detailed_row(
+                &mut table,
                 "materializes",
                 last.re_materializes_started,
                 last.re_materializes_finished_successfully,
                 last.re_materializes_finished_with_error,
-            )?);
-            r.extend(self.render_detailed_items(
+            )?;
+            self.push_detailed_row(
+                &mut table,
                 "write_action_results",
                 last.re_write_action_results_started,
                 last.re_write_action_results_finished_successfully,
                 last.re_write_action_results_finished_with_error,
-            )?);
-            r.extend(self.render_detailed_items(
+            )?;
+            self.push_detailed_row(
+                &mut table,
                 "get_digest_expirations",
                 last.re_get_digest_expirations_started,
                 last.re_get_digest_expirations_finished_successfully,
                 last.re_get_digest_expirations_finished_with_error,
-            )?);
+            )?;
+        }
+
+        // Only the header row: nothing to show.
+        if table.len() == 1 {
+            return Ok(Lines::new());
         }
-        Ok(r)
+        table.draw(dimensions, DrawMode::Normal)
     }
 
-    pub fn render(&self, detailed: bool, draw_mode: DrawMode) -> anyhow::Result<Lines> {
+    pub fn render(
+        &self,
+        detailed: bool,
+        draw_mode: DrawMode,
+        dimensions: Dimensions,
+    ) -> anyhow::Result<Lines> {
         let header = match self.render_header(draw_mode) {
             Some(header) => header,
             None => return Ok(Lines::new()),
         };
         let mut lines = vec![Line::unstyled(&header)?];
         if detailed {
-            lines.extend(self.render_detailed()?);
+            lines.extend(self.render_detailed(dimensions)?.0);
         }
         Ok(Lines(lines))
     }
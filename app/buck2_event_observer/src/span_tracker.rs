@@ -95,6 +95,10 @@ pub struct SpanHandle<'a, T: SpanTrackable> {
 }
 
 impl<'a, T: SpanTrackable> SpanHandle<'a, T> {
+    pub fn span_id(&self) -> <T as SpanTrackable>::Id {
+        self.span.span_id
+    }
+
     pub fn info(&self) -> &SpanInfo<T> {
         &self.span.info
     }
@@ -380,6 +384,19 @@ impl<T: SpanTrackable + Dupe> SpanTracker<T> {
         })
     }
 
+    /// The root that an ongoing span is displayed under.
+    pub fn root_of(
+        &self,
+        mut span_id: <T as SpanTrackable>::Id,
+    ) -> Option<<T as SpanTrackable>::Id> {
+        loop {
+            if self.roots.contains(span_id) {
+                return Some(span_id);
+            }
+            span_id = self.all.get(&span_id)?.info.event.parent_id()?;
+        }
+    }
+
     pub fn roots_completed(&self) -> usize {
         self.roots_completed
     }
@@ -565,12 +582,17 @@ impl BuckEventSpanTracker {
 
 impl WhatRanState<OptionalSpanId> for SpanTracker<Arc<BuckEvent>> {
     fn get(&self, span_id: OptionalSpanId) -> Option<WhatRanRelevantAction<'_>> {
-        let span_id = span_id.0?;
-
-        self.all
-            .get(&span_id)
-            .map(|e| e.info.event.data())
-            .and_then(WhatRanRelevantAction::from_buck_data)
+        let mut span_id = span_id.0?;
+
+        // Events such as RE retries are emitted within a span nested in the action, so walk up
+        // until we find it.
+        loop {
+            let event = &self.all.get(&span_id)?.info.event;
+            if let Some(action) = WhatRanRelevantAction::from_buck_data(event.data()) {
+                return Some(action);
+            }
+            span_id = event.parent_id()?;
+        }
     }
 }
 
@@ -800,4 +822,28 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_root_of() -> anyhow::Result<()> {
+        let t0 = Instant::now();
+
+        let root = TestSpan::new();
+        let child = TestSpan::new().parent(root);
+        let grandchild = TestSpan::new().parent(child);
+        let unknown = TestSpan::new();
+
+        let mut tracker = SpanTracker::new();
+        tracker.start_at(&root, t0)?;
+        tracker.start_at(&child, t0)?;
+        tracker.start_at(&grandchild, t0)?;
+
+        assert_eq!(tracker.root_of(root.span_id), Some(root.span_id));
+        assert_eq!(tracker.root_of(grandchild.span_id), Some(root.span_id));
+        assert_eq!(tracker.root_of(unknown.span_id), None);
+
+        tracker.end(&grandchild)?;
+        assert_eq!(tracker.root_of(grandchild.span_id), None);
+
+        Ok(())
+    }
 }
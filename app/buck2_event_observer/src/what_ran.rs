@@ -174,6 +174,7 @@ pub enum CommandReproducer<'a> {
     CacheHit(&'a buck2_data::CacheHit),
     ReExecute(&'a buck2_data::ReExecute),
     LocalExecute(&'a buck2_data::LocalExecute),
+    ReRetry(&'a buck2_data::ReRetry),
 }
 
 impl<'a> CommandReproducer<'a> {
@@ -183,6 +184,7 @@ impl<'a> CommandReproducer<'a> {
             Self::CacheHit(..) => "cache".to_owned(),
             Self::ReExecute(execute) => executor_with_platform(execute),
             Self::LocalExecute(..) => "local".to_owned(),
+            Self::ReRetry(..) => "re_retry".to_owned(),
         }
     }
 
@@ -238,6 +240,12 @@ impl<'a> CommandReproducer<'a> {
                 }
                 _ => {}
             },
+            buck2_data::buck_event::Data::Instant(instant) => match &instant.data {
+                Some(buck2_data::instant_event::Data::ReRetry(retry)) => {
+                    return Some(CommandReproducer::ReRetry(retry));
+                }
+                _ => {}
+            },
             _ => {}
         };
 
@@ -269,6 +277,16 @@ impl<'a> fmt::Display for HumanReadableCommandReproducer<'a> {
                     Ok(())
                 }
             }
+            CommandReproducer::ReRetry(retry) => {
+                if let Some(action_digest) = &retry.action_digest {
+                    write!(formatter, "{} ", action_digest)?;
+                }
+                write!(
+                    formatter,
+                    "({}, attempt {}): {}",
+                    retry.rpc, retry.attempt, retry.error
+                )
+            }
         }
     }
 }
@@ -378,4 +396,33 @@ mod tests {
         let result = executor_with_platform(&execute);
         assert_eq!(result, "re".to_owned());
     }
+
+    #[test]
+    fn test_re_retry_human_readable() {
+        let retry = buck2_data::ReRetry {
+            rpc: "Execute".to_owned(),
+            action_digest: Some("abc:10".to_owned()),
+            attempt: 2,
+            backoff: None,
+            error: "unavailable".to_owned(),
+        };
+        assert_eq!(
+            CommandReproducer::ReRetry(&retry)
+                .as_human_readable()
+                .to_string(),
+            "abc:10 (Execute, attempt 2): unavailable"
+        );
+
+        let retry = buck2_data::ReRetry {
+            rpc: "BatchUpdateBlobs".to_owned(),
+            action_digest: None,
+            ..retry
+        };
+        assert_eq!(
+            CommandReproducer::ReRetry(&retry)
+                .as_human_readable()
+                .to_string(),
+            "(BatchUpdateBlobs, attempt 2): unavailable"
+        );
+    }
 }
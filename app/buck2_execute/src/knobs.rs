@@ -13,4 +13,6 @@ use dupe::Dupe;
 #[derive(Clone, Dupe, Default)]
 pub struct ExecutorGlobalKnobs {
     pub enable_miniperf: bool,
+    /// Send the output of local commands while they run, for consoles that show it.
+    pub forward_local_command_output: bool,
 }
@@ -60,6 +60,18 @@ impl Checksum {
             Self::Both { sha256, .. } => Some(sha256),
         }
     }
+
+    /// This checksum as a [Subresource Integrity](https://www.w3.org/TR/SRI/) string, which is
+    /// how the Remote Asset API expects it. SHA256 is used if both are known.
+    pub fn to_sri(&self) -> anyhow::Result<String> {
+        let (algorithm, hex_digest) = match self {
+            Self::Sha256(sha256) | Self::Both { sha256, .. } => ("sha256", sha256),
+            Self::Sha1(sha1) => ("sha1", sha1),
+        };
+        let digest = hex::decode(&**hex_digest)
+            .with_context(|| format!("Invalid {} checksum: `{}`", algorithm, hex_digest))?;
+        Ok(format!("{}-{}", algorithm, base64::encode(digest)))
+    }
 }
 
 #[derive(Debug, Error)]
@@ -393,4 +405,28 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_checksum_to_sri() -> anyhow::Result<()> {
+        let sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let sha1 = "da39a3ee5e6b4b0d3255bfef95601890afd80709";
+        assert_eq!(
+            Checksum::Sha256(Arc::from(sha256)).to_sri()?,
+            "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+        assert_eq!(
+            Checksum::Both {
+                sha1: Arc::from(sha1),
+                sha256: Arc::from(sha256),
+            }
+            .to_sri()?,
+            "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+        assert_eq!(
+            Checksum::Sha1(Arc::from(sha1)).to_sri()?,
+            "sha1-2jmj7l5rSw0yVb/vlWAYkK/YBwk="
+        );
+        assert!(Checksum::Sha1(Arc::from("xyz")).to_sri().is_err());
+        Ok(())
+    }
 }
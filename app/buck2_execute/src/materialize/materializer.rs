@@ -7,10 +7,12 @@
  * of this source tree.
  */
 
+use std::collections::BTreeMap;
 use std::fmt;
 use std::sync::Arc;
 
 use allocative::Allocative;
+use anyhow::Context as _;
 use async_trait::async_trait;
 use buck2_common::executor_config::RemoteExecutorUseCase;
 use buck2_common::file_ops::FileMetadata;
@@ -39,6 +41,7 @@ use crate::execute::action_digest::TrackedActionDigest;
 #[cfg(any(fbcode_build, cargo_internal_build))]
 use crate::materialize::eden_api::EdenBuckOut;
 use crate::materialize::http::Checksum;
+use crate::output_size::OutputCountAndBytes;
 
 // Add a stub EdenBuckOut for when we don't have Eden output enabled
 #[cfg(not(any(fbcode_build, cargo_internal_build)))]
@@ -210,11 +213,11 @@ pub trait Materializer: Allocative + Send + Sync + 'static {
         &self,
         artifact_paths: Vec<ProjectRelativePathBuf>,
     ) -> anyhow::Result<()> {
-        Ok(self
-            .materialize_many(artifact_paths)
+        self.materialize_many(artifact_paths)
             .await?
             .try_collect()
-            .await?)
+            .await
+            .context(buck2_data::ErrorCause::MaterializationFailed)
     }
 
     /// Similar to `ensure_materialized`, but it relaxes its most important
@@ -649,6 +652,28 @@ pub trait DeferredMaterializerSubscription: Send + Sync {
     async fn next_materialization(&mut self) -> Option<ProjectRelativePathBuf>;
 }
 
+/// Summary of the state tracked by the deferred materializer.
+#[derive(Default, Debug)]
+pub struct DeferredMaterializerStats {
+    /// Materialized artifacts, by how they were materialized. The method is `None` for artifacts
+    /// which were already on disk. Counts are numbers of artifacts, not files.
+    pub materialized: BTreeMap<Option<buck2_data::MaterializationMethod>, OutputCountAndBytes>,
+    /// Artifacts which were declared but not materialized yet, by how they will be materialized.
+    pub declared: BTreeMap<buck2_data::MaterializationMethod, OutputCountAndBytes>,
+    /// The largest subtrees of materialized artifacts, largest first. Directories are only
+    /// included if they contain more than one entry.
+    pub largest_subtrees: Vec<(ProjectRelativePathBuf, u64)>,
+}
+
+/// An artifact whose contents on disk do not match what the deferred materializer recorded.
+#[derive(Debug)]
+pub struct CorruptedArtifact {
+    pub path: ProjectRelativePathBuf,
+    pub error: anyhow::Error,
+    /// Whether the artifact was evicted, so that it gets materialized again when next used.
+    pub repaired: bool,
+}
+
 /// Extensions to the Materializer trait that are only available in the Deferred materializer.
 #[async_trait]
 pub trait DeferredMaterializerExtensions: Send + Sync {
@@ -675,6 +700,12 @@ pub trait DeferredMaterializerExtensions: Send + Sync {
 
     async fn test_iter(&self, count: usize) -> anyhow::Result<String>;
 
+    async fn stats(&self, largest_subtrees: usize) -> anyhow::Result<DeferredMaterializerStats>;
+
+    /// Hash materialized artifacts and compare them to their recorded digests. When `repair` is
+    /// set, artifacts that don't match are evicted if they aren't in use by the daemon.
+    async fn verify_digests(&self, repair: bool) -> anyhow::Result<Vec<CorruptedArtifact>>;
+
     fn queue_size(&self) -> usize;
 
     /// Create a new DeferredMaterializerSubscription.
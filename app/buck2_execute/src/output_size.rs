@@ -9,11 +9,13 @@
 
 use buck2_core::directory::unordered_entry_walk;
 use buck2_core::directory::DirectoryEntry;
+use dupe::Dupe;
 
 use crate::artifact_value::ArtifactValue;
 use crate::directory::ActionDirectory;
 use crate::directory::ActionDirectoryMember;
 
+#[derive(Default, Debug, Copy, Clone, Dupe)]
 pub struct OutputCountAndBytes {
     pub count: u64,
     pub bytes: u64,
@@ -20,6 +20,7 @@ use buck2_core::env_helper::EnvHelper;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_credential_helper::CredentialHelper;
 use buck2_re_configuration::RemoteExecutionStaticMetadata;
 use buck2_re_configuration::RemoteExecutionStaticMetadataImpl;
 use chrono::DateTime;
@@ -70,6 +71,7 @@ use crate::execute::action_digest::ActionDigest;
 use crate::execute::blobs::ActionBlobs;
 use crate::execute::executor_stage_async;
 use crate::execute::manager::CommandExecutionManager;
+use crate::materialize::http::Checksum;
 use crate::materialize::materializer::Materializer;
 use crate::re::action_identity::ReActionIdentity;
 use crate::re::metadata::RemoteExecutionMetadataExt;
@@ -169,6 +171,7 @@ impl RemoteExecutionClient {
         static_metadata: Arc<RemoteExecutionStaticMetadata>,
         logs_dir_path: Option<&AbsNormPath>,
         buck_out_path: &AbsNormPath,
+        credential_helper: Option<Arc<CredentialHelper>>,
     ) -> anyhow::Result<Self> {
         let client = RemoteExecutionClientImpl::new(
             fb,
@@ -176,6 +179,7 @@ impl RemoteExecutionClient {
             static_metadata,
             logs_dir_path,
             buck_out_path,
+            credential_helper,
         )
         .await?;
 
@@ -206,6 +210,7 @@ impl RemoteExecutionClient {
         static_metadata: Arc<RemoteExecutionStaticMetadata>,
         logs_dir_path: Option<&AbsNormPath>,
         buck_out_path: &AbsNormPath,
+        credential_helper: Option<Arc<CredentialHelper>>,
     ) -> anyhow::Result<Self> {
         // Loop happens times-1 times at most
         for i in 1..times {
@@ -215,6 +220,7 @@ impl RemoteExecutionClient {
                 static_metadata.dupe(),
                 logs_dir_path,
                 buck_out_path,
+                credential_helper.dupe(),
             )
             .await
             {
@@ -235,6 +241,7 @@ impl RemoteExecutionClient {
             static_metadata,
             logs_dir_path,
             buck_out_path,
+            credential_helper,
         )
         .await
     }
@@ -408,6 +415,24 @@ impl RemoteExecutionClient {
             .await
     }
 
+    /// Resolve a file through the Remote Asset API. Returns its digest in the CAS, and when it
+    /// expires if the remote asset server said.
+    pub async fn fetch_blob(
+        &self,
+        url: &str,
+        checksum: &Checksum,
+        use_case: RemoteExecutorUseCase,
+    ) -> anyhow::Result<(TDigest, Option<DateTime<Utc>>)> {
+        self.data
+            .downloads
+            .op(self
+                .data
+                .client
+                .fetch_blob(url, checksum, use_case)
+                .map_err(|e| self.decorate_error(e)))
+            .await
+    }
+
     pub async fn write_action_result(
         &self,
         digest: TDigest,
@@ -502,6 +527,7 @@ impl RemoteExecutionClientImpl {
         static_metadata: Arc<RemoteExecutionStaticMetadata>,
         maybe_logs_dir_path: Option<&AbsNormPath>,
         buck_out_path: &AbsNormPath,
+        credential_helper: Option<Arc<CredentialHelper>>,
     ) -> anyhow::Result<Self> {
         let res: anyhow::Result<Self> = try {
             static DOWNLOAD_CONCURRENCY: EnvHelper<usize> =
@@ -521,6 +547,9 @@ impl RemoteExecutionClientImpl {
                 use remote_execution::EmbeddedCASDaemonClientCfg;
                 use remote_execution::RichClientMode;
 
+                // The internal client authenticates by itself.
+                let _unused = credential_helper;
+
                 let mut re_client_config = create_default_config();
                 re_client_config.action_cache_client_config.connection_count =
                     static_metadata.action_cache_connection_count;
@@ -636,9 +665,25 @@ impl RemoteExecutionClientImpl {
 
             #[cfg(not(fbcode_build))]
             let client = {
+                use buck2_events::dispatch::get_dispatcher_opt;
+                use remote_execution::RetryInfo;
+
                 let _unused = (fb, maybe_logs_dir_path, buck_out_path);
 
-                REClientBuilder::build_and_connect(&static_metadata.0).await?
+                REClientBuilder::build_and_connect(&static_metadata.0, credential_helper)
+                    .await?
+                    .with_retry_observer(Arc::new(|info: &RetryInfo<'_>| {
+                        // Retries happen within the command that made the request, if any.
+                        if let Some(dispatcher) = get_dispatcher_opt() {
+                            dispatcher.instant_event(buck2_data::ReRetry {
+                                rpc: info.rpc.to_owned(),
+                                action_digest: info.action_digest.map(|d| d.to_string()),
+                                attempt: info.attempt as u64,
+                                backoff: info.backoff.try_into().ok(),
+                                error: format!("{:#}", info.error),
+                            });
+                        }
+                    }))
             };
 
             Self {
@@ -1105,6 +1150,53 @@ impl RemoteExecutionClientImpl {
             .collect())
     }
 
+    #[cfg(fbcode_build)]
+    async fn fetch_blob(
+        &self,
+        url: &str,
+        checksum: &Checksum,
+        use_case: RemoteExecutorUseCase,
+    ) -> anyhow::Result<(TDigest, Option<DateTime<Utc>>)> {
+        let _unused = (url, checksum, use_case);
+        Err(anyhow::anyhow!(
+            "The Remote Asset API is not supported by this RE client"
+        ))
+    }
+
+    #[cfg(not(fbcode_build))]
+    async fn fetch_blob(
+        &self,
+        url: &str,
+        checksum: &Checksum,
+        use_case: RemoteExecutorUseCase,
+    ) -> anyhow::Result<(TDigest, Option<DateTime<Utc>>)> {
+        use chrono::TimeZone;
+        use remote_execution::FetchBlobRequest;
+        use remote_execution::Qualifier;
+
+        let response = self
+            .client()
+            .fetch_blob(
+                use_case.metadata(),
+                FetchBlobRequest {
+                    uris: vec![url.to_owned()],
+                    qualifiers: vec![Qualifier {
+                        name: "checksum.sri".to_owned(),
+                        value: checksum.to_sri()?,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let expires_at = response
+            .expires_at
+            .and_then(|ts| Utc.timestamp_opt(ts.seconds, ts.nanos as u32).single());
+
+        Ok((response.digest, expires_at))
+    }
+
     async fn write_action_result(
         &self,
         digest: TDigest,
@@ -25,7 +25,9 @@ use buck2_core::async_once_cell::AsyncOnceCell;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_credential_helper::CredentialHelper;
 use buck2_re_configuration::RemoteExecutionStaticMetadata;
+use buck2_re_configuration::RemoteExecutionStaticMetadataImpl;
 use chrono::DateTime;
 use chrono::Utc;
 use dupe::Dupe;
@@ -45,6 +47,7 @@ use crate::directory::ActionImmutableDirectory;
 use crate::execute::action_digest::ActionDigest;
 use crate::execute::blobs::ActionBlobs;
 use crate::execute::manager::CommandExecutionManager;
+use crate::materialize::http::Checksum;
 use crate::materialize::materializer::Materializer;
 use crate::re::action_identity::ReActionIdentity;
 use crate::re::client::ExecuteResponseOrCancelled;
@@ -88,6 +91,8 @@ struct RemoteExecutionConfig {
     static_metadata: Arc<RemoteExecutionStaticMetadata>,
     logs_dir_path: Option<AbsNormPathBuf>,
     buck_out_path: AbsNormPathBuf,
+    /// Shared with the HTTP client, so the helper only runs once per host.
+    credential_helper: Option<Arc<CredentialHelper>>,
 }
 
 impl RemoteExecutionConfig {
@@ -99,8 +104,10 @@ impl RemoteExecutionConfig {
             self.static_metadata.dupe(),
             self.logs_dir_path.as_deref(),
             &self.buck_out_path,
+            self.credential_helper.dupe(),
         )
         .await
+        .context(buck2_data::ErrorCause::ReUnavailable)
     }
 }
 
@@ -200,6 +207,7 @@ impl ReConnectionManager {
         static_metadata: Arc<RemoteExecutionStaticMetadata>,
         logs_dir_path: Option<AbsNormPathBuf>,
         buck_out_path: AbsNormPathBuf,
+        credential_helper: Option<Arc<CredentialHelper>>,
     ) -> Self {
         Self {
             data: RwLock::new(Weak::new()),
@@ -210,6 +218,7 @@ impl ReConnectionManager {
                 static_metadata,
                 logs_dir_path,
                 buck_out_path,
+                credential_helper,
             },
         }
     }
@@ -282,6 +291,12 @@ impl ReConnectionHandle {
         self.observer = Some(observer);
     }
 
+    /// Whether actions can be executed remotely. If not, the remote is only used as a cache. This
+    /// doesn't require connecting.
+    pub fn execution_enabled(&self) -> bool {
+        self.connection.config.static_metadata.execution_enabled()
+    }
+
     /// gets a client that is tied to the scope of this guard
     pub fn get_client(&self) -> ManagedRemoteExecutionClient {
         ManagedRemoteExecutionClient {
@@ -445,6 +460,19 @@ impl ManagedRemoteExecutionClient {
             .await
     }
 
+    pub async fn fetch_blob(
+        &self,
+        url: &str,
+        checksum: &Checksum,
+        use_case: RemoteExecutorUseCase,
+    ) -> anyhow::Result<(TDigest, Option<DateTime<Utc>>)> {
+        self.lock()?
+            .get()
+            .await?
+            .fetch_blob(url, checksum, use_case)
+            .await
+    }
+
     pub async fn write_action_result(
         &self,
         digest: TDigest,
@@ -0,0 +1,228 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Support for `--check-determinism`: actions are run locally twice and the digests of their
+//! outputs are compared. The outputs of the first run are moved aside while the second one runs so
+//! that files which differ can be diffed.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use buck2_core::directory::unordered_entry_walk;
+use buck2_core::directory::DirectoryEntry;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::directory::ActionDirectoryMember;
+
+/// Which actions to run twice.
+#[derive(Default)]
+pub struct DeterminismCheckOptions {
+    /// Action categories to check. All actions are checked if this is empty.
+    pub categories: Vec<String>,
+}
+
+impl DeterminismCheckOptions {
+    pub(crate) fn applies_to(&self, name: &buck2_data::ActionName) -> bool {
+        self.categories.is_empty() || self.categories.iter().any(|c| *c == name.category)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Action is not deterministic, outputs differ between two runs:\n{}", format_files(.0))]
+pub(crate) struct NondeterministicActionError(
+    pub(crate) Vec<buck2_data::determinism_check::NondeterministicFile>,
+);
+
+fn format_files(files: &[buck2_data::determinism_check::NondeterministicFile]) -> String {
+    let mut s = String::new();
+    for file in files {
+        writeln!(s, "  {}: {}", file.path, file.difference).unwrap();
+    }
+    s
+}
+
+/// Files larger than this are never diffed as text.
+const MAX_TEXT_DIFF_BYTES: usize = 1024 * 1024;
+
+/// Lines of a text diff shown on each side before eliding the rest.
+const MAX_TEXT_DIFF_LINES: usize = 10;
+
+/// Describe how the contents of a file differ between the two runs: a diff of the differing lines
+/// if both versions are text, the offset of the first differing byte otherwise.
+pub(crate) fn describe_difference(first: &[u8], second: &[u8]) -> String {
+    if first.len() <= MAX_TEXT_DIFF_BYTES && second.len() <= MAX_TEXT_DIFF_BYTES {
+        if let (Ok(first), Ok(second)) = (std::str::from_utf8(first), std::str::from_utf8(second)) {
+            if let Some(diff) = text_diff(first, second) {
+                return diff;
+            }
+        }
+    }
+
+    let offset = first
+        .iter()
+        .zip(second)
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| first.len().min(second.len()));
+    format!(
+        "contents first differ at byte offset {} (sizes {} and {})",
+        offset,
+        first.len(),
+        second.len()
+    )
+}
+
+/// Show the lines between the common prefix and the common suffix of both versions. Returns `None`
+/// if only the line endings differ, in which case a byte offset is more helpful.
+fn text_diff(first: &str, second: &str) -> Option<String> {
+    let first: Vec<&str> = first.lines().collect();
+    let second: Vec<&str> = second.lines().collect();
+
+    let prefix = first
+        .iter()
+        .zip(&second)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = first[prefix..]
+        .iter()
+        .rev()
+        .zip(second[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let removed = &first[prefix..first.len() - suffix];
+    let added = &second[prefix..second.len() - suffix];
+    if removed.is_empty() && added.is_empty() {
+        return None;
+    }
+
+    let mut diff = format!("contents differ starting at line {}:", prefix + 1);
+    for (sign, lines) in [('-', removed), ('+', added)] {
+        for line in lines.iter().take(MAX_TEXT_DIFF_LINES) {
+            write!(diff, "\n    {}{}", sign, line).unwrap();
+        }
+        if lines.len() > MAX_TEXT_DIFF_LINES {
+            write!(
+                diff,
+                "\n    {}... {} more lines",
+                sign,
+                lines.len() - MAX_TEXT_DIFF_LINES
+            )
+            .unwrap();
+        }
+    }
+    Some(diff)
+}
+
+/// All the entries of an output, keyed by path relative to the output. Directories map to `None`.
+fn output_members(
+    value: Option<&ArtifactValue>,
+) -> BTreeMap<ForwardRelativePathBuf, Option<&ActionDirectoryMember>> {
+    let mut members = BTreeMap::new();
+    if let Some(value) = value {
+        let mut walk = unordered_entry_walk(value.entry().as_ref());
+        while let Some((path, entry)) = walk.next() {
+            let member = match entry {
+                DirectoryEntry::Leaf(m) => Some(m),
+                DirectoryEntry::Dir(_) => None,
+            };
+            members.insert(path.get(), member);
+        }
+    }
+    members
+}
+
+/// Compare one output of both runs. `first_path` is where the output of the first run was moved
+/// to and `second_path` where the output of the second run is.
+pub(crate) fn compare_output(
+    path: &ProjectRelativePathBuf,
+    first: Option<&ArtifactValue>,
+    second: Option<&ArtifactValue>,
+    first_path: &AbsNormPath,
+    second_path: &AbsNormPath,
+) -> anyhow::Result<Vec<buck2_data::determinism_check::NondeterministicFile>> {
+    if first.map(|v| v.entry()) == second.map(|v| v.entry()) {
+        return Ok(Vec::new());
+    }
+
+    let first_members = output_members(first);
+    let second_members = output_members(second);
+
+    let mut files = Vec::new();
+    let mut keys: Vec<_> = first_members.keys().chain(second_members.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for rel in keys {
+        let a = first_members.get(rel);
+        let b = second_members.get(rel);
+        let difference = match (a, b) {
+            (Some(a), Some(b)) if a == b => continue,
+            (None, _) => "only produced by the second run".to_owned(),
+            (_, None) => "only produced by the first run".to_owned(),
+            (
+                Some(Some(ActionDirectoryMember::File(a))),
+                Some(Some(ActionDirectoryMember::File(b))),
+            ) if a.digest == b.digest => "executable bit differs".to_owned(),
+            (
+                Some(Some(ActionDirectoryMember::File(_))),
+                Some(Some(ActionDirectoryMember::File(_))),
+            ) => describe_difference(
+                &fs_util::read(first_path.join(rel))?,
+                &fs_util::read(second_path.join(rel))?,
+            ),
+            (Some(a), Some(b)) => format!(
+                "`{}` in the first run, `{}` in the second run",
+                a.map_or_else(|| "directory".to_owned(), |m| m.to_string()),
+                b.map_or_else(|| "directory".to_owned(), |m| m.to_string()),
+            ),
+        };
+        files.push(buck2_data::determinism_check::NondeterministicFile {
+            path: path.join(rel).to_string(),
+            difference,
+        });
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_difference_text() {
+        assert_eq!(
+            "contents differ starting at line 2:\n    -b\n    +c\n    +d",
+            describe_difference(b"a\nb\ne\n", b"a\nc\nd\ne\n")
+        );
+    }
+
+    #[test]
+    fn test_describe_difference_binary() {
+        assert_eq!(
+            "contents first differ at byte offset 2 (sizes 4 and 4)",
+            describe_difference(b"\xff\x00\x01\x02", b"\xff\x00\x03\x02")
+        );
+        assert_eq!(
+            "contents first differ at byte offset 3 (sizes 3 and 5)",
+            describe_difference(b"\xffab", b"\xffabcd")
+        );
+    }
+
+    #[test]
+    fn test_describe_difference_line_endings() {
+        assert_eq!(
+            "contents first differ at byte offset 1 (sizes 2 and 3)",
+            describe_difference(b"a\n", b"a\r\n")
+        );
+    }
+}
@@ -25,8 +25,11 @@ use buck2_common::liveliness_observer::LivelinessObserverExt;
 use buck2_common::local_resource_state::LocalResourceHolder;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::tag_error;
 use buck2_core::tag_result;
 use buck2_events::dispatch::get_dispatcher;
@@ -55,12 +58,14 @@ use buck2_execute::execute::request::CommandExecutionRequest;
 use buck2_execute::execute::request::ExecutorPreference;
 use buck2_execute::execute::result::CommandExecutionMetadata;
 use buck2_execute::execute::result::CommandExecutionResult;
+use buck2_execute::execute::target::CommandExecutionTarget;
 use buck2_execute::knobs::ExecutorGlobalKnobs;
 use buck2_execute::materialize::materializer::MaterializationError;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_forkserver::client::ForkserverClient;
-use buck2_forkserver::run::gather_output;
+use buck2_forkserver::run::gather_output_observed;
 use buck2_forkserver::run::timeout_into_cancellation;
+use buck2_forkserver::run::CommandEvent;
 use buck2_forkserver::run::GatherOutputStatus;
 use buck2_util::process::background_command;
 use derive_more::From;
@@ -74,9 +79,14 @@ use host_sharing::HostSharingBroker;
 use indexmap::IndexMap;
 use more_futures::cancellable_future::CancellationObserver;
 use more_futures::cancellation::CancellationContext;
+use parking_lot::Mutex;
 use thiserror::Error;
 use tracing::info;
 
+use crate::executors::determinism::compare_output;
+use crate::executors::determinism::DeterminismCheckOptions;
+use crate::executors::determinism::NondeterministicActionError;
+
 #[derive(Debug, Error)]
 enum LocalExecutionError {
     #[error("Args list was empty")]
@@ -97,6 +107,8 @@ pub struct LocalExecutor {
     forkserver: Option<ForkserverClient>,
     #[allow(unused)]
     knobs: ExecutorGlobalKnobs,
+    /// When set, actions are run twice to check that they are deterministic.
+    check_determinism: Option<Arc<DeterminismCheckOptions>>,
 }
 
 impl LocalExecutor {
@@ -108,6 +120,7 @@ impl LocalExecutor {
         root: AbsNormPathBuf,
         forkserver: Option<ForkserverClient>,
         knobs: ExecutorGlobalKnobs,
+        check_determinism: Option<Arc<DeterminismCheckOptions>>,
     ) -> Self {
         Self {
             artifact_fs,
@@ -117,6 +130,7 @@ impl LocalExecutor {
             root,
             forkserver,
             knobs,
+            check_determinism,
         }
     }
 
@@ -132,6 +146,7 @@ impl LocalExecutor {
         env_inheritance: Option<&'a EnvironmentInheritance>,
         liveliness_observer: impl LivelinessObserver + 'static,
         disable_miniperf: bool,
+        on_event: impl FnMut(&CommandEvent) + Send + 'a,
     ) -> impl futures::future::Future<
         Output = anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>,
     > + Send
@@ -158,13 +173,14 @@ impl LocalExecutor {
                             env_inheritance,
                             liveliness_observer,
                             self.knobs.enable_miniperf && !disable_miniperf,
+                            on_event,
                         )
                         .await
                     }
 
                     #[cfg(not(unix))]
                     {
-                        let _unused = (forkserver, disable_miniperf);
+                        let _unused = (forkserver, disable_miniperf, on_event);
                         Err(anyhow::anyhow!("Forkserver is not supported off-UNIX"))
                     }
                 }
@@ -188,7 +204,7 @@ impl LocalExecutor {
                     let cancellation =
                         select(timeout.boxed(), alive.boxed()).map(|r| r.factor_first().0);
 
-                    gather_output(cmd, cancellation).await
+                    gather_output_observed(cmd, cancellation, on_event).await
                 }
                 .with_context(|| format!("Failed to gather output from command: {}", exe)),
             }
@@ -204,6 +220,7 @@ impl LocalExecutor {
         cancellations: &CancellationContext,
         digest_config: DigestConfig,
         local_resource_holders: &[LocalResourceHolder],
+        determinism_target: Option<&dyn CommandExecutionTarget>,
     ) -> CommandExecutionResult {
         let args = &request.all_args_vec();
         if args.is_empty() {
@@ -254,33 +271,36 @@ impl LocalExecutor {
 
         let scratch_dir = &scratch_dir; // So it doesn't move in the block below.
 
-        if let Err(e) = executor_stage_async(
-            buck2_data::LocalStage {
-                stage: Some(buck2_data::LocalPrepareOutputDirs {}.into()),
-            },
-            async move {
-                // TODO(cjhopman): This should be getting the action exec context so it get use io_blocking_section
-                if let Some(scratch_dir) = scratch_dir {
-                    let project_fs = self.artifact_fs.fs();
-                    project_fs.remove_path_recursive(scratch_dir)?;
-                    fs_util::create_dir_all(&*project_fs.resolve(scratch_dir))?;
-                }
+        // This is a closure because the command runs twice when checking determinism.
+        let prepare_output_dirs = || {
+            executor_stage_async(
+                buck2_data::LocalStage {
+                    stage: Some(buck2_data::LocalPrepareOutputDirs {}.into()),
+                },
+                async move {
+                    // TODO(cjhopman): This should be getting the action exec context so it get use io_blocking_section
+                    if let Some(scratch_dir) = scratch_dir {
+                        let project_fs = self.artifact_fs.fs();
+                        project_fs.remove_path_recursive(scratch_dir)?;
+                        fs_util::create_dir_all(&*project_fs.resolve(scratch_dir))?;
+                    }
 
-                create_output_dirs(
-                    &self.artifact_fs,
-                    request,
-                    self.materializer.dupe(),
-                    self.blocking_executor.dupe(),
-                    cancellations,
-                )
-                .await
-                .context("Error creating output directories")?;
+                    create_output_dirs(
+                        &self.artifact_fs,
+                        request,
+                        self.materializer.dupe(),
+                        self.blocking_executor.dupe(),
+                        cancellations,
+                    )
+                    .await
+                    .context("Error creating output directories")?;
 
-                anyhow::Ok(())
-            },
-        )
-        .await
-        {
+                    anyhow::Ok(())
+                },
+            )
+        };
+
+        if let Err(e) = prepare_output_dirs().await {
             return manager.error("prepare_output_dirs_failed", e);
         };
 
@@ -353,59 +373,74 @@ impl LocalExecutor {
                 )))
         };
 
-        let liveliness_observer = manager.liveliness_observer.dupe().and(cancellation);
+        let execute = || {
+            let liveliness_observer = manager.liveliness_observer.dupe().and(cancellation.dupe());
 
-        let (mut timing, res) = executor_stage_async(
-            {
-                let env = iter_env()
-                    .map(|(k, v)| buck2_data::local_command::EnvironmentEntry {
-                        key: k.to_owned(),
-                        value: v.into_string_lossy(),
-                    })
-                    .collect();
-                let stage = buck2_data::LocalExecute {
-                    command: Some(buck2_data::LocalCommand {
-                        action_digest: action_digest.to_string(),
-                        argv: args.to_vec(),
-                        env,
-                    }),
-                };
-                buck2_data::LocalStage {
-                    stage: Some(stage.into()),
-                }
-            },
-            async move {
-                let execution_start = Instant::now();
-                let start_time = SystemTime::now();
-
-                let env = iter_env().map(|(k, v)| (k, v.into_os_str()));
-                let r = self
-                    .exec(
-                        &args[0],
-                        &args[1..],
-                        env,
-                        request.working_directory(),
-                        request.timeout(),
-                        request.local_environment_inheritance(),
-                        liveliness_observer,
-                        request.disable_miniperf(),
+            executor_stage_async(
+                {
+                    let env = iter_env()
+                        .map(|(k, v)| buck2_data::local_command::EnvironmentEntry {
+                            key: k.to_owned(),
+                            value: v.into_string_lossy(),
+                        })
+                        .collect();
+                    let stage = buck2_data::LocalExecute {
+                        command: Some(buck2_data::LocalCommand {
+                            action_digest: action_digest.to_string(),
+                            argv: args.to_vec(),
+                            env,
+                        }),
+                    };
+                    buck2_data::LocalStage {
+                        stage: Some(stage.into()),
+                    }
+                },
+                async move {
+                    let execution_start = Instant::now();
+                    let start_time = SystemTime::now();
+
+                    let env = iter_env().map(|(k, v)| (k, v.into_os_str()));
+                    // Only forward the output if a console asked to show it.
+                    let output = self
+                        .knobs
+                        .forward_local_command_output
+                        .then(|| Mutex::new(OutputForwarder::default()));
+                    let r = forward_output(
+                        self.exec(
+                            &args[0],
+                            &args[1..],
+                            env,
+                            request.working_directory(),
+                            request.timeout(),
+                            request.local_environment_inheritance(),
+                            liveliness_observer,
+                            request.disable_miniperf(),
+                            |event| {
+                                if let Some(output) = &output {
+                                    output.lock().observe(event);
+                                }
+                            },
+                        ),
+                        output.as_ref(),
                     )
                     .await;
 
-                let execution_time = execution_start.elapsed();
+                    let execution_time = execution_start.elapsed();
 
-                let timing = CommandExecutionMetadata {
-                    wall_time: execution_time,
-                    re_queue_time: None,
-                    execution_time,
-                    start_time,
-                    execution_stats: None, // We fill this in later if available.
-                };
+                    let timing = CommandExecutionMetadata {
+                        wall_time: execution_time,
+                        re_queue_time: None,
+                        execution_time,
+                        start_time,
+                        execution_stats: None, // We fill this in later if available.
+                    };
 
-                (timing, r)
-            },
-        )
-        .await;
+                    (timing, r)
+                },
+            )
+        };
+
+        let (mut timing, res) = execute().await;
 
         let execution_kind = CommandExecutionKind::Local {
             digest: action_digest.dupe(),
@@ -425,6 +460,47 @@ impl LocalExecutor {
                 exit_code,
                 execution_stats,
             } => {
+                if let (0, Some(target)) = (exit_code, determinism_target) {
+                    let res: anyhow::Result<_> = try {
+                        let (first_outputs, _) =
+                            self.calculate_output_values(request, digest_config)?;
+                        let stash = self.stash_outputs(action_digest, request)?;
+
+                        let res: anyhow::Result<_> = try {
+                            prepare_output_dirs().await?;
+                            match execute().await.1? {
+                                (GatherOutputStatus::Finished { exit_code: 0, .. }, _, _) => {}
+                                (status, _, stderr) => Err(anyhow::anyhow!(
+                                    "Action failed when run a second time ({:?}):\n{}",
+                                    status,
+                                    String::from_utf8_lossy(&stderr)
+                                ))?,
+                            }
+                            let (second_outputs, _) =
+                                self.calculate_output_values(request, digest_config)?;
+                            self.compare_outputs(request, &stash, &first_outputs, &second_outputs)?
+                        };
+                        // The outputs of the first run are only kept to describe differences.
+                        let _ignored = fs_util::remove_all(&stash);
+                        let nondeterministic_files = res?;
+
+                        get_dispatcher().instant_event(buck2_data::DeterminismCheck {
+                            key: Some(target.as_proto_action_key()),
+                            name: Some(target.as_proto_action_name()),
+                            nondeterministic_files: nondeterministic_files.clone(),
+                        });
+                        nondeterministic_files
+                    };
+                    match res {
+                        Ok(files) if files.is_empty() => {}
+                        Ok(files) => {
+                            return manager
+                                .error("check_determinism", NondeterministicActionError(files));
+                        }
+                        Err(e) => return manager.error("check_determinism", e),
+                    }
+                }
+
                 let outputs = match self
                     .calculate_and_declare_output_values(request, digest_config)
                     .await
@@ -491,6 +567,23 @@ impl LocalExecutor {
         request: &CommandExecutionRequest,
         digest_config: DigestConfig,
     ) -> anyhow::Result<IndexMap<CommandExecutionOutput, ArtifactValue>> {
+        let (mapped_outputs, to_declare) = self.calculate_output_values(request, digest_config)?;
+
+        self.materializer.declare_existing(to_declare).await?;
+
+        Ok(mapped_outputs)
+    }
+
+    /// Read the outputs from disk. Returns the outputs and those among them which need to be
+    /// declared to the materializer.
+    fn calculate_output_values(
+        &self,
+        request: &CommandExecutionRequest,
+        digest_config: DigestConfig,
+    ) -> anyhow::Result<(
+        IndexMap<CommandExecutionOutput, ArtifactValue>,
+        Vec<(ProjectRelativePathBuf, ArtifactValue)>,
+    )> {
         let mut builder = inputs_directory(request.inputs(), &self.artifact_fs)?;
 
         // Read outputs from disk and add them to the builder
@@ -531,9 +624,62 @@ impl LocalExecutor {
             }
         }
 
-        self.materializer.declare_existing(to_declare).await?;
+        Ok((mapped_outputs, to_declare))
+    }
 
-        Ok(mapped_outputs)
+    /// Move the outputs of the first run of an action out of the way before running it again.
+    fn stash_outputs(
+        &self,
+        action_digest: &ActionDigest,
+        request: &CommandExecutionRequest,
+    ) -> anyhow::Result<AbsNormPathBuf> {
+        let stash = self
+            .root
+            .join(self.artifact_fs.buck_out_path_resolver().root().join(
+                ForwardRelativePathBuf::unchecked_new(format!(
+                    "determinism/{}",
+                    action_digest.raw_digest()
+                )),
+            ));
+        fs_util::remove_all(&stash)?;
+        fs_util::create_dir_all(&stash)?;
+
+        for (i, output) in request.outputs().enumerate() {
+            let path = self
+                .root
+                .join(output.resolve(&self.artifact_fs).into_path());
+            if fs_util::symlink_metadata_if_exists(&path)?.is_some() {
+                fs_util::rename(
+                    &path,
+                    stash.join(ForwardRelativePathBuf::unchecked_new(i.to_string())),
+                )
+                .context("Error moving outputs of the first run")?;
+            }
+        }
+
+        Ok(stash)
+    }
+
+    fn compare_outputs(
+        &self,
+        request: &CommandExecutionRequest,
+        stash: &AbsNormPath,
+        first: &IndexMap<CommandExecutionOutput, ArtifactValue>,
+        second: &IndexMap<CommandExecutionOutput, ArtifactValue>,
+    ) -> anyhow::Result<Vec<buck2_data::determinism_check::NondeterministicFile>> {
+        let mut files = Vec::new();
+        for (i, output) in request.outputs().enumerate() {
+            let path = output.resolve(&self.artifact_fs).into_path();
+            let output = output.cloned();
+            files.extend(compare_output(
+                &path,
+                first.get(&output),
+                second.get(&output),
+                &stash.join(ForwardRelativePathBuf::unchecked_new(i.to_string())),
+                &self.root.join(&path),
+            )?);
+        }
+        Ok(files)
     }
 }
 
@@ -551,11 +697,16 @@ impl PreparedCommandExecutor for LocalExecutor {
 
         let PreparedCommand {
             request,
-            target: _,
+            target,
             prepared_action,
             digest_config,
         } = command;
 
+        let determinism_target = match &self.check_determinism {
+            Some(options) if options.applies_to(&target.as_proto_action_name()) => Some(*target),
+            _ => None,
+        };
+
         let local_resource_holders = executor_stage_async(
             {
                 let a = buck2_data::AcquireLocalResource {};
@@ -600,6 +751,7 @@ impl PreparedCommandExecutor for LocalExecutor {
                     cancellations,
                     *digest_config,
                     &local_resource_holders,
+                    determinism_target,
                 )
             })
             .await
@@ -610,6 +762,68 @@ impl PreparedCommandExecutor for LocalExecutor {
     }
 }
 
+/// How often to send the output of running commands to the client.
+const OUTPUT_FORWARD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How much of each output stream to send at most every `OUTPUT_FORWARD_INTERVAL`. Consoles only
+/// show the last few lines.
+const MAX_FORWARDED_OUTPUT_BYTES: usize = 4096;
+
+/// Buffers the output of a running command until it's sent to the client, so that the console can
+/// show it while the command runs.
+#[derive(Default)]
+struct OutputForwarder {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl OutputForwarder {
+    fn observe(&mut self, event: &CommandEvent) {
+        let (buffer, data) = match event {
+            CommandEvent::Stdout(data) => (&mut self.stdout, data),
+            CommandEvent::Stderr(data) => (&mut self.stderr, data),
+            CommandEvent::Exit(..) => return,
+        };
+        buffer.extend_from_slice(data);
+        if buffer.len() > MAX_FORWARDED_OUTPUT_BYTES {
+            buffer.drain(..buffer.len() - MAX_FORWARDED_OUTPUT_BYTES);
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.stdout.is_empty() && self.stderr.is_empty() {
+            return;
+        }
+        get_dispatcher().instant_event(buck2_data::LocalCommandOutput {
+            stdout: String::from_utf8_lossy(&std::mem::take(&mut self.stdout)).into_owned(),
+            stderr: String::from_utf8_lossy(&std::mem::take(&mut self.stderr)).into_owned(),
+        });
+    }
+}
+
+/// Drive `fut`, periodically sending the output it collected in `output`, and what's left once it
+/// finishes. This runs within the span of the command, so that the client knows which action the
+/// output belongs to.
+async fn forward_output<F: future::Future>(
+    fut: F,
+    output: Option<&Mutex<OutputForwarder>>,
+) -> F::Output {
+    let Some(output) = output else {
+        return fut.await;
+    };
+    let mut interval = tokio::time::interval(OUTPUT_FORWARD_INTERVAL);
+    futures::pin_mut!(fut);
+    loop {
+        tokio::select! {
+            res = &mut fut => {
+                output.lock().flush();
+                return res;
+            }
+            _ = interval.tick() => output.lock().flush(),
+        }
+    }
+}
+
 /// Either a str or a OsStr, so that we can turn it back into a String without having to check for
 /// valid utf-8, while using the same struct.
 #[derive(Copy, Clone, Dupe, From)]
@@ -888,6 +1102,7 @@ mod unix {
         env_inheritance: Option<&EnvironmentInheritance>,
         liveliness_observer: impl LivelinessObserver + 'static,
         enable_miniperf: bool,
+        on_event: impl FnMut(&CommandEvent),
     ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)> {
         let exe = exe.as_ref();
 
@@ -906,7 +1121,11 @@ mod unix {
         };
         apply_local_execution_environment(&mut req, working_directory, env, env_inheritance);
         forkserver
-            .execute(req, async move { liveliness_observer.while_alive().await })
+            .execute(
+                req,
+                async move { liveliness_observer.while_alive().await },
+                on_event,
+            )
             .await
     }
 
@@ -973,6 +1192,7 @@ mod tests {
     use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
     use buck2_execute::execute::blocking::testing::DummyBlockingExecutor;
     use buck2_execute::materialize::nodisk::NoDiskMaterializer;
+    use buck2_forkserver::run::gather_output;
     use host_sharing::HostSharingStrategy;
 
     use super::*;
@@ -1087,6 +1307,7 @@ mod tests {
             temp.path().root().to_buf(),
             None,
             ExecutorGlobalKnobs::default(),
+            None,
         );
 
         Ok((executor, temp.path().root().to_buf(), temp))
@@ -1107,6 +1328,7 @@ mod tests {
                 None,
                 NoopLivelinessObserver::create(),
                 false,
+                |_| {},
             )
             .await?;
         assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
@@ -1143,6 +1365,7 @@ mod tests {
                 Some(&EnvironmentInheritance::empty()),
                 NoopLivelinessObserver::create(),
                 false,
+                |_| {},
             )
             .await?;
         assert!(matches!(status, GatherOutputStatus::Finished { exit_code, .. } if exit_code == 0));
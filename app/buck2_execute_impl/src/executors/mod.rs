@@ -9,6 +9,7 @@
 
 pub mod action_cache;
 pub mod caching;
+pub mod determinism;
 pub mod hybrid;
 pub mod local;
 pub mod re;
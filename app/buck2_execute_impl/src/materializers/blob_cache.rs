@@ -0,0 +1,379 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A content-addressed store of blobs downloaded from CAS, shared by all the daemons on a
+//! machine (typically one per checkout).
+//!
+//! Blobs are written to a temporary file and renamed into place, so a blob is never observed
+//! partially written. Using a blob bumps the mtime of an empty marker file mirroring it under
+//! `access/`, which eviction uses to find the least recently used blobs. Blobs themselves are
+//! never touched, since hardlinked blobs share their inode (and so their mtime) with buck-out.
+//! Several daemons may insert and evict concurrently: a blob that disappears while it is being
+//! linked is simply treated as a miss.
+
+use std::io;
+use std::str::FromStr;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use buck2_common::file_ops::FileDigest;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::abs_path::AbsPath;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use dupe::Dupe;
+
+#[derive(Debug, thiserror::Error)]
+enum BlobCacheError {
+    #[error("Invalid blob cache link mode: `{0}`, expected `reflink` or `hardlink`")]
+    InvalidLinkMode(String),
+}
+
+/// How blobs are placed into buck-out on a cache hit.
+#[derive(Debug, Copy, Clone, Dupe, Eq, PartialEq)]
+pub enum BlobCacheLinkMode {
+    /// Clone the blob if the filesystem supports it, copy it otherwise.
+    Reflink,
+    /// Hardlink the blob. This is the cheapest option, but the materialized file shares its inode
+    /// with the cache, so it must never be modified in place.
+    Hardlink,
+}
+
+impl FromStr for BlobCacheLinkMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "reflink" => Ok(Self::Reflink),
+            "hardlink" => Ok(Self::Hardlink),
+            _ => Err(BlobCacheError::InvalidLinkMode(s.to_owned()).into()),
+        }
+    }
+}
+
+pub struct BlobCacheConfiguration {
+    pub root: AbsNormPathBuf,
+    /// When set, least recently used blobs are evicted to keep the cache under this size.
+    pub max_bytes: Option<u64>,
+    pub link_mode: BlobCacheLinkMode,
+}
+
+pub(crate) struct BlobCache {
+    blobs: AbsNormPathBuf,
+    /// Markers recording when each blob was last used, at the same relative path as the blob.
+    access: AbsNormPathBuf,
+    tmp: AbsNormPathBuf,
+    max_bytes: Option<u64>,
+    link_mode: BlobCacheLinkMode,
+    /// Bytes inserted since the last eviction.
+    inserted_bytes: AtomicU64,
+}
+
+fn is_not_found(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<io::Error>()
+        .map_or(false, |e| e.kind() == io::ErrorKind::NotFound)
+}
+
+/// Temporary files left behind by a daemon which died mid-insert are deleted after this long.
+const STALE_TMP_FILE_AGE: Duration = Duration::from_secs(3600);
+
+impl BlobCache {
+    pub(crate) fn new(config: BlobCacheConfiguration) -> anyhow::Result<Self> {
+        let blobs = config
+            .root
+            .join(ForwardRelativePath::unchecked_new("blobs"));
+        let access = config
+            .root
+            .join(ForwardRelativePath::unchecked_new("access"));
+        let tmp = config.root.join(ForwardRelativePath::unchecked_new("tmp"));
+        fs_util::create_dir_all(&blobs)?;
+        fs_util::create_dir_all(&access)?;
+        fs_util::create_dir_all(&tmp)?;
+        Ok(Self {
+            blobs,
+            access,
+            tmp,
+            max_bytes: config.max_bytes,
+            link_mode: config.link_mode,
+            inserted_bytes: AtomicU64::new(0),
+        })
+    }
+
+    /// Blobs are keyed by permissions as well as digest, since hardlinks share permissions.
+    fn blob_rel_path(digest: &FileDigest, is_executable: bool) -> ForwardRelativePathBuf {
+        let hash = digest.raw_digest().to_string();
+        ForwardRelativePathBuf::unchecked_new(format!(
+            "{}/{}/{}_{}{}",
+            digest.raw_digest().algorithm(),
+            &hash[..2],
+            hash,
+            digest.size(),
+            if is_executable { "_x" } else { "" },
+        ))
+    }
+
+    /// Record that the blob at `rel_path` was just used.
+    fn record_use(&self, rel_path: &ForwardRelativePath) -> anyhow::Result<()> {
+        let marker = self.access.join(rel_path);
+        if fs_util::touch(&marker).is_err() {
+            if let Some(parent) = marker.parent() {
+                fs_util::create_dir_all(parent)?;
+            }
+            fs_util::write(&marker, b"")?;
+        }
+        Ok(())
+    }
+
+    /// Materialize the blob at `dest`. Returns `false` if the blob is not in the cache.
+    pub(crate) fn link(
+        &self,
+        digest: &FileDigest,
+        is_executable: bool,
+        dest: &AbsPath,
+    ) -> anyhow::Result<bool> {
+        let rel_path = Self::blob_rel_path(digest, is_executable);
+        let blob = self.blobs.join(&rel_path);
+
+        // Not checking whether the blob exists first: another daemon could evict it right after.
+        let res = match self.link_mode {
+            BlobCacheLinkMode::Reflink => fs_util::clone_or_copy(&blob, dest).map(|_| ()),
+            BlobCacheLinkMode::Hardlink => match fs_util::hard_link(&blob, dest) {
+                Err(e) if !is_not_found(&e) => {
+                    // Most likely the cache is on another filesystem.
+                    tracing::debug!("Falling back to copying blob: {:#}", e);
+                    fs_util::clone_or_copy(&blob, dest).map(|_| ())
+                }
+                res => res,
+            },
+        };
+        match res {
+            Ok(()) => {}
+            Err(e) if is_not_found(&e) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+
+        // This is only used to order evictions, so a blob evicted in the meantime doesn't matter.
+        if let Err(e) = self.record_use(&rel_path) {
+            tracing::debug!("Error recording blob use: {:#}", e);
+        }
+        Ok(true)
+    }
+
+    /// Store a copy of `src`, which must have the given digest.
+    pub(crate) fn insert(
+        &self,
+        digest: &FileDigest,
+        is_executable: bool,
+        src: &AbsPath,
+    ) -> anyhow::Result<()> {
+        static TMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let rel_path = Self::blob_rel_path(digest, is_executable);
+        let blob = self.blobs.join(&rel_path);
+        if fs_util::try_exists(&blob)? {
+            return Ok(());
+        }
+        if let Some(parent) = blob.parent() {
+            fs_util::create_dir_all(parent)?;
+        }
+
+        // The name must be unique across all the daemons sharing the cache.
+        let tmp = self.tmp.join(ForwardRelativePathBuf::unchecked_new(format!(
+            "{}-{}",
+            std::process::id(),
+            TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        )));
+        let res = fs_util::clone_or_copy(src, &tmp).and_then(|_| fs_util::rename(&tmp, &blob));
+        if res.is_err() {
+            let _ignored = fs_util::remove_file(&tmp);
+        }
+        res?;
+
+        // A marker may be left over from an evicted copy of this blob.
+        if let Err(e) = self.record_use(&rel_path) {
+            tracing::debug!("Error recording blob use: {:#}", e);
+        }
+
+        self.inserted_bytes
+            .fetch_add(digest.size(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Evict least recently used blobs if the cache may have grown over its size limit.
+    pub(crate) fn maybe_evict(&self) -> anyhow::Result<()> {
+        let max_bytes = match self.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return Ok(()),
+        };
+
+        // Scanning the whole cache is expensive, so only do it once a meaningful amount of data
+        // was inserted. The exchange also ensures that only one thread evicts at a time.
+        let inserted = self.inserted_bytes.load(Ordering::Relaxed);
+        if inserted < max_bytes / 10
+            || self
+                .inserted_bytes
+                .compare_exchange(inserted, 0, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return Ok(());
+        }
+
+        let evicted = self.evict(max_bytes)?;
+        tracing::debug!("Evicted {} bytes from blob cache", evicted);
+        Ok(())
+    }
+
+    /// Delete least recently used blobs until the cache fits in `max_bytes`. Returns the number of
+    /// bytes deleted.
+    fn evict(&self, max_bytes: u64) -> anyhow::Result<u64> {
+        let mut blobs = Vec::new();
+        let mut total_bytes = 0;
+        for algorithm in fs_util::read_dir(&self.blobs)? {
+            for prefix in fs_util::read_dir(algorithm?.path())? {
+                for blob in fs_util::read_dir(prefix?.path())? {
+                    let blob = blob?;
+                    // The blob may have been evicted by another daemon.
+                    let metadata = match blob.metadata() {
+                        Ok(metadata) => metadata,
+                        Err(_) => continue,
+                    };
+                    let blob_path = blob.path();
+                    let marker = self.access.join(blob_path.strip_prefix(&self.blobs)?);
+                    // Blobs which were never used since insertion may not have a marker yet.
+                    let last_used = match fs_util::metadata(&marker) {
+                        Ok(marker_metadata) => marker_metadata.modified()?,
+                        Err(_) => metadata.modified()?,
+                    };
+                    total_bytes += metadata.len();
+                    blobs.push((last_used, metadata.len(), blob_path, marker));
+                }
+            }
+        }
+
+        let now = SystemTime::now();
+        for tmp in fs_util::read_dir(&self.tmp)? {
+            let tmp = tmp?;
+            let is_stale = tmp
+                .metadata()
+                .and_then(|m| m.modified())
+                .map_or(false, |modified| {
+                    now.duration_since(modified).unwrap_or_default() > STALE_TMP_FILE_AGE
+                });
+            if is_stale {
+                let _ignored = fs_util::remove_file(tmp.path());
+            }
+        }
+
+        if total_bytes <= max_bytes {
+            return Ok(0);
+        }
+
+        blobs.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut evicted = 0;
+        for (_, size, path, marker) in blobs {
+            if total_bytes - evicted <= max_bytes {
+                break;
+            }
+            // Failures mean another daemon deleted the blob first, which is just as good.
+            let _ignored = fs_util::remove_file(&path);
+            let _ignored = fs_util::remove_file(&marker);
+            evicted += size;
+        }
+        Ok(evicted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestCache {
+        _tempdir: tempfile::TempDir,
+        root: AbsNormPathBuf,
+        cache: BlobCache,
+    }
+
+    impl TestCache {
+        fn new(max_bytes: Option<u64>, link_mode: BlobCacheLinkMode) -> anyhow::Result<Self> {
+            let tempdir = tempfile::tempdir()?;
+            let root = AbsNormPathBuf::new(tempdir.path().to_path_buf())?;
+            let cache = BlobCache::new(BlobCacheConfiguration {
+                root: root.join(ForwardRelativePath::unchecked_new("cache")),
+                max_bytes,
+                link_mode,
+            })?;
+            Ok(Self {
+                _tempdir: tempdir,
+                root,
+                cache,
+            })
+        }
+
+        fn path(&self, name: &str) -> AbsNormPathBuf {
+            self.root.join(ForwardRelativePath::unchecked_new(name))
+        }
+    }
+
+    #[test]
+    fn test_insert_and_link() -> anyhow::Result<()> {
+        let t = TestCache::new(None, BlobCacheLinkMode::Reflink)?;
+        let digest = FileDigest::new_sha1([1; 20], 3);
+        fs_util::write(t.path("src"), b"foo")?;
+
+        assert!(!t.cache.link(&digest, false, &t.path("dest"))?);
+        t.cache.insert(&digest, false, &t.path("src"))?;
+        assert!(t.cache.link(&digest, false, &t.path("dest"))?);
+        assert_eq!("foo", fs_util::read_to_string(t.path("dest"))?);
+
+        // Permissions are part of the key.
+        assert!(!t.cache.link(&digest, true, &t.path("dest_x"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hardlink_does_not_touch_linked_files() -> anyhow::Result<()> {
+        let t = TestCache::new(None, BlobCacheLinkMode::Hardlink)?;
+        let digest = FileDigest::new_sha1([1; 20], 3);
+        fs_util::write(t.path("src"), b"foo")?;
+        // A missing blob is a miss rather than an error.
+        assert!(!t.cache.link(&digest, false, &t.path("dest1"))?);
+        t.cache.insert(&digest, false, &t.path("src"))?;
+
+        assert!(t.cache.link(&digest, false, &t.path("dest1"))?);
+        let mtime = fs_util::metadata(t.path("dest1"))?.modified()?;
+        std::thread::sleep(Duration::from_millis(10));
+        // Using the blob again must not bump the mtime of files already linked to it.
+        assert!(t.cache.link(&digest, false, &t.path("dest2"))?);
+        assert_eq!(mtime, fs_util::metadata(t.path("dest1"))?.modified()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_evict_least_recently_used() -> anyhow::Result<()> {
+        let t = TestCache::new(Some(6), BlobCacheLinkMode::Reflink)?;
+        let a = FileDigest::new_sha1([1; 20], 4);
+        let b = FileDigest::new_sha1([2; 20], 4);
+        fs_util::write(t.path("src"), b"1234")?;
+
+        t.cache.insert(&a, false, &t.path("src"))?;
+        std::thread::sleep(Duration::from_millis(10));
+        t.cache.insert(&b, false, &t.path("src"))?;
+        std::thread::sleep(Duration::from_millis(10));
+        // Using `a` makes `b` the least recently used blob.
+        assert!(t.cache.link(&a, false, &t.path("dest"))?);
+
+        assert_eq!(4, t.cache.evict(6)?);
+        assert!(t.cache.link(&a, false, &t.path("dest_a"))?);
+        assert!(!t.cache.link(&b, false, &t.path("dest_b"))?);
+        Ok(())
+    }
+}
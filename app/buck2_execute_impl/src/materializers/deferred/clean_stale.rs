@@ -327,6 +327,7 @@ impl<'a> StaleFinder<'a> {
                             active: false,
                             last_access_time,
                             metadata,
+                            ..
                         },
                     ..
                 }) if *last_access_time < self.keep_since_time => {
@@ -7,6 +7,7 @@
  * of this source tree.
  */
 
+use std::collections::HashSet;
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -15,13 +16,21 @@ use std::sync::Arc;
 
 use anyhow::Context as _;
 use async_trait::async_trait;
+use buck2_common::file_ops::FileDigestConfig;
 use buck2_core::directory::DirectoryEntry;
 use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
+use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_events::dispatch::get_dispatcher;
+use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::directory::ActionDirectoryMember;
+use buck2_execute::directory::INTERNER;
+use buck2_execute::entry::build_entry_from_disk;
+use buck2_execute::materialize::materializer::CorruptedArtifact;
 use buck2_execute::materialize::materializer::DeferredMaterializerEntry;
 use buck2_execute::materialize::materializer::DeferredMaterializerExtensions;
+use buck2_execute::materialize::materializer::DeferredMaterializerStats;
 use buck2_execute::materialize::materializer::DeferredMaterializerSubscription;
 use chrono::DateTime;
 use chrono::Duration;
@@ -43,10 +52,14 @@ use crate::materializers::deferred::io_handler::create_ttl_refresh;
 use crate::materializers::deferred::subscriptions::MaterializerSubscriptionOperation;
 use crate::materializers::deferred::ArtifactMaterializationMethod;
 use crate::materializers::deferred::ArtifactMaterializationStage;
+use crate::materializers::deferred::ArtifactMetadata;
+use crate::materializers::deferred::ArtifactTree;
 use crate::materializers::deferred::DefaultIoHandler;
 use crate::materializers::deferred::DeferredMaterializer;
 use crate::materializers::deferred::DeferredMaterializerCommandProcessor;
+use crate::materializers::deferred::MaterializationMethodToProto;
 use crate::materializers::deferred::MaterializerCommand;
+use crate::materializers::deferred::Processing;
 
 pub(super) trait ExtensionCommand<T>: Debug + Sync + Send + 'static {
     fn execute(self: Box<Self>, processor: &mut DeferredMaterializerCommandProcessor<T>);
@@ -281,6 +294,178 @@ impl ExtensionCommand<DefaultIoHandler> for TestIter {
     }
 }
 
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct Stats {
+    #[derivative(Debug = "ignore")]
+    sender: Sender<DeferredMaterializerStats>,
+    largest_subtrees: usize,
+}
+
+impl ExtensionCommand<DefaultIoHandler> for Stats {
+    fn execute(
+        self: Box<Self>,
+        processor: &mut DeferredMaterializerCommandProcessor<DefaultIoHandler>,
+    ) {
+        let mut stats = DeferredMaterializerStats::default();
+
+        for data in processor.tree.iter_without_paths() {
+            match &data.stage {
+                ArtifactMaterializationStage::Declared { entry, method } => {
+                    let declared = stats.declared.entry(method.to_proto()).or_default();
+                    declared.count += 1;
+                    declared.bytes += entry.calc_output_count_and_bytes().bytes;
+                }
+                ArtifactMaterializationStage::Materialized {
+                    metadata, method, ..
+                } => {
+                    let materialized = stats.materialized.entry(*method).or_default();
+                    materialized.count += 1;
+                    materialized.bytes += metadata.size();
+                }
+            }
+        }
+
+        let mut subtrees = Vec::new();
+        materialized_subtree_size(
+            &processor.tree,
+            ForwardRelativePathBuf::empty(),
+            &mut subtrees,
+        );
+        subtrees.sort_by(|a, b| b.1.cmp(&a.1));
+        subtrees.truncate(self.largest_subtrees);
+        stats.largest_subtrees = subtrees;
+
+        let _ignored = self.sender.send(stats);
+    }
+}
+
+/// Returns the size of the materialized artifacts in `tree`, and records the size of `tree` in
+/// `subtrees` if it is an artifact or a directory with several entries. Directories with a single
+/// entry are skipped since they'd just repeat the size of that entry.
+pub(super) fn materialized_subtree_size(
+    tree: &ArtifactTree,
+    path: ForwardRelativePathBuf,
+    subtrees: &mut Vec<(ProjectRelativePathBuf, u64)>,
+) -> u64 {
+    let (size, is_subtree) = match tree {
+        ArtifactTree::Data(data) => match &data.stage {
+            ArtifactMaterializationStage::Materialized { metadata, .. } => (metadata.size(), true),
+            ArtifactMaterializationStage::Declared { .. } => (0, false),
+        },
+        ArtifactTree::Tree(children) => (
+            children
+                .iter()
+                .map(|(name, child)| materialized_subtree_size(child, path.join(name), subtrees))
+                .sum(),
+            children.len() > 1,
+        ),
+    };
+    if is_subtree && size > 0 && !path.is_empty() {
+        subtrees.push((ProjectRelativePathBuf::from(path), size));
+    }
+    size
+}
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct ListMaterialized {
+    #[derivative(Debug = "ignore")]
+    sender: Sender<Vec<(ProjectRelativePathBuf, ArtifactMetadata)>>,
+}
+
+impl ExtensionCommand<DefaultIoHandler> for ListMaterialized {
+    fn execute(
+        self: Box<Self>,
+        processor: &mut DeferredMaterializerCommandProcessor<DefaultIoHandler>,
+    ) {
+        let artifacts = processor
+            .tree
+            .iter_with_paths()
+            .filter_map(|(path, data)| match &data.stage {
+                ArtifactMaterializationStage::Materialized { metadata, .. } => {
+                    Some((ProjectRelativePathBuf::from(path), metadata.dupe()))
+                }
+                ArtifactMaterializationStage::Declared { .. } => None,
+            })
+            .collect();
+        let _ignored = self.sender.send(artifacts);
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct EvictCorrupted {
+    paths: Vec<ProjectRelativePathBuf>,
+    #[derivative(Debug = "ignore")]
+    sender: Sender<Vec<ProjectRelativePathBuf>>,
+}
+
+impl ExtensionCommand<DefaultIoHandler> for EvictCorrupted {
+    fn execute(
+        self: Box<Self>,
+        processor: &mut DeferredMaterializerCommandProcessor<DefaultIoHandler>,
+    ) {
+        // Active artifacts can't be evicted without invalidating DICE, and anything else may have
+        // been materialized again since it was checked.
+        let paths: Vec<_> = self
+            .paths
+            .into_iter()
+            .filter(|path| {
+                let mut path_iter = path.iter();
+                match processor.tree.prefix_get(&mut path_iter) {
+                    Some(data) if path_iter.next().is_none() => {
+                        matches!(
+                            data.stage,
+                            ArtifactMaterializationStage::Materialized { active: false, .. }
+                        ) && matches!(data.processing, Processing::Done(..))
+                    }
+                    _ => false,
+                }
+            })
+            .collect();
+
+        if !paths.is_empty() {
+            processor.evict_artifacts(paths.clone());
+        }
+        let _ignored = self.sender.send(paths);
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum VerifyError {
+    #[error("Artifact is missing on disk")]
+    Missing,
+    #[error("Artifact on disk does not match its recorded digest, expected `{0}`")]
+    Mismatch(String),
+}
+
+/// Check that the artifact at `path` on disk matches `metadata`.
+fn verify_artifact(
+    fs: &ProjectRoot,
+    digest_config: DigestConfig,
+    path: &ProjectRelativePathBuf,
+    metadata: &ArtifactMetadata,
+) -> anyhow::Result<()> {
+    let entry = build_entry_from_disk(
+        fs.resolve(path),
+        FileDigestConfig::build(digest_config.cas_digest_config()),
+    )?
+    .ok_or(VerifyError::Missing)?;
+    let entry = entry.map_dir(|d| {
+        d.fingerprint(digest_config.as_directory_serializer())
+            .shared(&*INTERNER)
+    });
+    if !metadata.matches_entry(&entry) {
+        let expected = match &metadata.0 {
+            DirectoryEntry::Dir(dir) => dir.fingerprint.to_string(),
+            DirectoryEntry::Leaf(leaf) => leaf.to_string(),
+        };
+        return Err(VerifyError::Mismatch(expected).into());
+    }
+    Ok(())
+}
+
 #[async_trait]
 impl DeferredMaterializerExtensions for DeferredMaterializer {
     fn iterate(
@@ -358,6 +543,65 @@ impl DeferredMaterializerExtensions for DeferredMaterializer {
         receiver.await.context("No response from materializer")
     }
 
+    async fn stats(&self, largest_subtrees: usize) -> anyhow::Result<DeferredMaterializerStats> {
+        let (sender, receiver) = oneshot::channel();
+        self.command_sender
+            .send(MaterializerCommand::Extension(Box::new(Stats {
+                sender,
+                largest_subtrees,
+            }) as _))?;
+        receiver.await.context("No response from materializer")
+    }
+
+    async fn verify_digests(&self, repair: bool) -> anyhow::Result<Vec<CorruptedArtifact>> {
+        let (sender, receiver) = oneshot::channel();
+        self.command_sender
+            .send(MaterializerCommand::Extension(
+                Box::new(ListMaterialized { sender }) as _,
+            ))?;
+        let artifacts = receiver.await.context("No response from materializer")?;
+
+        // Hashing happens outside of the command thread, since it can take a long time.
+        let fs = self.fs.dupe();
+        let digest_config = self.digest_config;
+        let mut corrupted = self
+            .io_executor
+            .execute_io_inline(|| {
+                Ok(artifacts
+                    .into_iter()
+                    .filter_map(|(path, metadata)| {
+                        verify_artifact(&fs, digest_config, &path, &metadata)
+                            .err()
+                            .map(|error| CorruptedArtifact {
+                                path,
+                                error,
+                                repaired: false,
+                            })
+                    })
+                    .collect::<Vec<_>>())
+            })
+            .await?;
+
+        if repair && !corrupted.is_empty() {
+            let (sender, receiver) = oneshot::channel();
+            self.command_sender
+                .send(MaterializerCommand::Extension(Box::new(EvictCorrupted {
+                    paths: corrupted.iter().map(|c| c.path.clone()).collect(),
+                    sender,
+                }) as _))?;
+            let evicted: HashSet<_> = receiver
+                .await
+                .context("No response from materializer")?
+                .into_iter()
+                .collect();
+            for artifact in &mut corrupted {
+                artifact.repaired = evicted.contains(&artifact.path);
+            }
+        }
+
+        Ok(corrupted)
+    }
+
     fn queue_size(&self) -> usize {
         self.command_sender.counters.queue_size()
     }
@@ -0,0 +1,60 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use chrono::DateTime;
+use chrono::Utc;
+use dupe::Dupe;
+use parking_lot::Mutex;
+
+/// The commands currently running in the daemon. Artifacts they access are not evicted from
+/// buck-out until they finish.
+#[derive(Default, Allocative)]
+pub struct InFlightCommands {
+    next_id: AtomicU64,
+    #[allocative(skip)]
+    started: Mutex<BTreeMap<u64, DateTime<Utc>>>,
+}
+
+impl InFlightCommands {
+    /// Record that a command is starting. It is in flight until the guard is dropped.
+    pub fn start(self: &Arc<Self>) -> InFlightCommandGuard {
+        let mut started = self.started.lock();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        started.insert(id, Utc::now());
+        drop(started);
+        InFlightCommandGuard {
+            commands: self.dupe(),
+            id,
+        }
+    }
+
+    /// When the oldest command still in flight started. Artifacts accessed since then may be in
+    /// use.
+    pub(crate) fn oldest_start_time(&self) -> Option<DateTime<Utc>> {
+        // Ids increase with start times, so the first entry is the oldest.
+        self.started.lock().values().next().copied()
+    }
+}
+
+pub struct InFlightCommandGuard {
+    commands: Arc<InFlightCommands>,
+    id: u64,
+}
+
+impl Drop for InFlightCommandGuard {
+    fn drop(&mut self) {
+        self.commands.started.lock().remove(&self.id);
+    }
+}
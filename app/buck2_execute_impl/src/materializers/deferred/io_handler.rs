@@ -33,6 +33,7 @@ use buck2_execute::directory::ActionSharedDirectory;
 use buck2_execute::execute::blocking::BlockingExecutor;
 use buck2_execute::execute::blocking::IoRequest;
 use buck2_execute::execute::clean_output_paths::cleanup_path;
+use buck2_execute::execute::clean_output_paths::CleanOutputPaths;
 use buck2_execute::materialize::http::http_download;
 use buck2_execute::output_size::OutputSize;
 use buck2_execute::re::manager::ReConnectionManager;
@@ -53,6 +54,7 @@ use remote_execution::TCode;
 use remote_execution::TDigest;
 use tracing::instrument;
 
+use crate::materializers::blob_cache::BlobCache;
 use crate::materializers::deferred::ArtifactMaterializationMethod;
 use crate::materializers::deferred::ArtifactMaterializationStage;
 use crate::materializers::deferred::ArtifactTree;
@@ -64,6 +66,7 @@ use crate::materializers::deferred::SharedMaterializingError;
 use crate::materializers::deferred::Version;
 use crate::materializers::deferred::WriteFile;
 use crate::materializers::io::materialize_files;
+use crate::materializers::io::LocalCopyMethod;
 use crate::materializers::io::MaterializeTreeStructure;
 
 pub(super) struct DefaultIoHandler {
@@ -74,11 +77,18 @@ pub(super) struct DefaultIoHandler {
     /// Executor for blocking IO operations
     pub(super) io_executor: Arc<dyn BlockingExecutor>,
     pub(super) http_client: Arc<dyn HttpClient>,
+    /// Machine-wide cache of blobs downloaded from CAS, shared with other daemons.
+    pub(super) blob_cache: Option<BlobCache>,
+    pub(super) local_copy_method: LocalCopyMethod,
 }
 
 struct MaterializationStat {
     file_count: u64,
     total_bytes: u64,
+    cloned_file_count: u64,
+    copied_file_count: u64,
+    hard_linked_file_count: u64,
+    blob_cache_hit_count: u64,
 }
 
 #[async_trait]
@@ -115,6 +125,13 @@ pub(super) trait IoHandler: Sized + Sync + Send + 'static {
         min_ttl: Duration,
         digest_config: DigestConfig,
     ) -> Option<BoxFuture<'static, anyhow::Result<()>>>;
+
+    /// Delete paths which are no longer tracked by the materializer.
+    fn clean_untracked_paths<'a>(
+        self: &Arc<Self>,
+        paths: Vec<ProjectRelativePathBuf>,
+        cancellations: &'a CancellationContext,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
 }
 
 impl DefaultIoHandler {
@@ -142,6 +159,12 @@ impl DefaultIoHandler {
         // Materialize files
         match method.as_ref() {
             ArtifactMaterializationMethod::CasDownload { info } => {
+                struct CasFile {
+                    path: ProjectRelativePathBuf,
+                    digest: FileDigest,
+                    is_executable: bool,
+                }
+
                 let mut files = Vec::new();
 
                 {
@@ -149,39 +172,80 @@ impl DefaultIoHandler {
 
                     while let Some((entry_path, entry)) = walk.next() {
                         if let DirectoryEntry::Leaf(ActionDirectoryMember::File(f)) = entry {
-                            let name = path.join_normalized(entry_path.get())?;
-                            let digest = maybe_tombstone_digest(f.digest.data())?.to_re();
-
-                            tracing::trace!(name = %name, digest = %digest, "push download");
-                            let name = self
-                                .fs
-                                .resolve(&name)
-                                .as_maybe_relativized_str()?
-                                .to_owned();
-
-                            files.push(NamedDigestWithPermissions {
-                                named_digest: NamedDigest {
-                                    name,
-                                    digest,
-                                    ..Default::default()
-                                },
+                            files.push(CasFile {
+                                path: path.join_normalized(entry_path.get())?,
+                                digest: maybe_tombstone_digest(f.digest.data())?.dupe(),
                                 is_executable: f.is_executable,
-                                ..Default::default()
                             });
                         }
                     }
                 }
                 stat.file_count = files.len().try_into().unwrap_or_default();
-                stat.total_bytes = files
+                stat.total_bytes = files.iter().map(|x| x.digest.size()).sum();
+
+                // Files already present in the machine-wide blob cache don't need downloading.
+                let files = match &self.blob_cache {
+                    Some(blob_cache) => {
+                        self.io_executor
+                            .execute_io_inline(|| {
+                                let mut misses = Vec::new();
+                                for file in files {
+                                    match blob_cache.link(
+                                        &file.digest,
+                                        file.is_executable,
+                                        &self.fs.resolve(&file.path),
+                                    ) {
+                                        Ok(true) => stat.blob_cache_hit_count += 1,
+                                        Ok(false) => misses.push(file),
+                                        Err(e) => {
+                                            tracing::warn!(
+                                                "Error materializing `{}` from blob cache: {:#}",
+                                                file.path,
+                                                e
+                                            );
+                                            misses.push(file);
+                                        }
+                                    }
+                                }
+                                Ok(misses)
+                            })
+                            .await?
+                    }
+                    None => files,
+                };
+
+                if files.is_empty() {
+                    return Ok(());
+                }
+
+                let named_digests = files
                     .iter()
-                    .map(|x| u64::try_from(x.named_digest.digest.size_in_bytes).unwrap_or_default())
-                    .sum();
+                    .map(|file| {
+                        let digest = file.digest.to_re();
+                        tracing::trace!(name = %file.path, digest = %digest, "push download");
+                        let name = self
+                            .fs
+                            .resolve(&file.path)
+                            .as_maybe_relativized_str()?
+                            .to_owned();
+
+                        anyhow::Ok(NamedDigestWithPermissions {
+                            named_digest: NamedDigest {
+                                name,
+                                digest,
+                                ..Default::default()
+                            },
+                            is_executable: file.is_executable,
+                            ..Default::default()
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
 
                 let connection = self.re_client_manager.get_re_connection();
                 let re_client = connection.get_client();
 
                 re_client
-                    .materialize_files(files, info.re_use_case)
+                    .materialize_files(named_digests, info.re_use_case)
                     .await
                     .map_err(|e| match e.downcast_ref::<REClientError>() {
                         Some(e) if e.code == TCode::NOT_FOUND => MaterializeEntryError::NotFound {
@@ -195,6 +259,31 @@ impl DefaultIoHandler {
                             )
                         })),
                     })?;
+
+                if let Some(blob_cache) = &self.blob_cache {
+                    // Failing to populate the cache doesn't fail the materialization.
+                    self.io_executor
+                        .execute_io_inline(|| {
+                            for file in &files {
+                                if let Err(e) = blob_cache.insert(
+                                    &file.digest,
+                                    file.is_executable,
+                                    &self.fs.resolve(&file.path),
+                                ) {
+                                    tracing::warn!(
+                                        "Error adding `{}` to blob cache: {:#}",
+                                        file.path,
+                                        e
+                                    );
+                                }
+                            }
+                            if let Err(e) = blob_cache.maybe_evict() {
+                                tracing::warn!("Error evicting from blob cache: {:#}", e);
+                            }
+                            Ok(())
+                        })
+                        .await?;
+                }
             }
             ArtifactMaterializationMethod::HttpDownload { info } => {
                 async {
@@ -241,11 +330,16 @@ impl DefaultIoHandler {
                             stat.file_count += count_and_bytes.count;
                             stat.total_bytes += count_and_bytes.bytes;
 
-                            materialize_files(
+                            let copy_stats = materialize_files(
                                 a.dest_entry.as_ref(),
                                 &self.fs.root().join(&a.src),
                                 &self.fs.root().join(&a.dest),
+                                self.local_copy_method
+                                    .for_source(&a.src, &self.buck_out_path),
                             )?;
+                            stat.cloned_file_count += copy_stats.cloned;
+                            stat.copied_file_count += copy_stats.copied;
+                            stat.hard_linked_file_count += copy_stats.hard_linked;
                         }
                         Ok(())
                     })
@@ -338,6 +432,10 @@ impl IoHandler for DefaultIoHandler {
                 let mut stat = MaterializationStat {
                     file_count: 0,
                     total_bytes: 0,
+                    cloned_file_count: 0,
+                    copied_file_count: 0,
+                    hard_linked_file_count: 0,
+                    blob_cache_hit_count: 0,
                 };
                 let res = self
                     .materialize_entry_span(path, method.dupe(), entry, &mut stat, cancellations)
@@ -350,6 +448,10 @@ impl IoHandler for DefaultIoHandler {
                         action_digest: None,
                         file_count: stat.file_count,
                         total_bytes: stat.total_bytes,
+                        cloned_file_count: stat.cloned_file_count,
+                        copied_file_count: stat.copied_file_count,
+                        hard_linked_file_count: stat.hard_linked_file_count,
+                        blob_cache_hit_count: stat.blob_cache_hit_count,
                         path: path_string,
                         success: error.is_none(),
                         error,
@@ -369,6 +471,15 @@ impl IoHandler for DefaultIoHandler {
     ) -> Option<BoxFuture<'static, anyhow::Result<()>>> {
         create_ttl_refresh(tree, &self.re_client_manager, min_ttl, digest_config).map(|f| f.boxed())
     }
+
+    fn clean_untracked_paths<'a>(
+        self: &Arc<Self>,
+        paths: Vec<ProjectRelativePathBuf>,
+        cancellations: &'a CancellationContext,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        self.io_executor
+            .execute_io(Box::new(CleanOutputPaths { paths }), cancellations)
+    }
 }
 
 /// This is used for testing to ingest digests (via BUCK2_TEST_TOMBSTONED_DIGESTS).
@@ -10,6 +10,7 @@
 mod clean_stale;
 mod extension;
 mod file_tree;
+pub mod in_flight;
 mod io_handler;
 mod subscriptions;
 
@@ -96,13 +97,17 @@ use tokio::sync::oneshot::error::TryRecvError;
 use tokio::time::Interval;
 use tracing::instrument;
 
+use crate::materializers::blob_cache::BlobCache;
+use crate::materializers::blob_cache::BlobCacheConfiguration;
 use crate::materializers::deferred::extension::ExtensionCommand;
 use crate::materializers::deferred::file_tree::FileTree;
+use crate::materializers::deferred::in_flight::InFlightCommands;
 use crate::materializers::deferred::io_handler::DefaultIoHandler;
 use crate::materializers::deferred::io_handler::IoHandler;
 use crate::materializers::deferred::subscriptions::MaterializerSubscriptionOperation;
 use crate::materializers::deferred::subscriptions::MaterializerSubscriptions;
 use crate::materializers::immediate;
+use crate::materializers::io::LocalCopyMethod;
 use crate::materializers::sqlite::MaterializerState;
 use crate::materializers::sqlite::MaterializerStateSqliteDb;
 
@@ -162,6 +167,9 @@ pub struct DeferredMaterializerConfigs {
     pub materialize_final_artifacts: bool,
     pub defer_write_actions: bool,
     pub ttl_refresh: TtlRefreshConfiguration,
+    pub disk_budget: Option<DiskBudgetConfiguration>,
+    pub blob_cache: Option<BlobCacheConfiguration>,
+    pub local_copy_method: LocalCopyMethod,
 }
 
 pub struct TtlRefreshConfiguration {
@@ -170,6 +178,14 @@ pub struct TtlRefreshConfiguration {
     pub enabled: bool,
 }
 
+/// Bound on the size of materialized artifacts, enforced by evicting least recently used ones.
+pub struct DiskBudgetConfiguration {
+    pub max_bytes: u64,
+    pub frequency: std::time::Duration,
+    /// Artifacts accessed by these commands are not evicted.
+    pub in_flight_commands: Arc<InFlightCommands>,
+}
+
 #[derive(Copy, Dupe, Clone)]
 struct MaterializerCounters {
     sent: &'static AtomicUsize,
@@ -261,6 +277,9 @@ struct DeferredMaterializerCommandProcessor<T: 'static> {
     ttl_refresh_history: Vec<TtlRefreshHistoryEntry>,
     /// The current ttl_refresh instance, if any exists.
     ttl_refresh_instance: Option<oneshot::Receiver<(DateTime<Utc>, anyhow::Result<()>)>>,
+    /// Deletions of evicted artifacts. They are no longer in the tree, so artifacts declared at
+    /// overlapping paths must wait for them explicitly.
+    evictions: Vec<(ProjectRelativePathBuf, CleaningFuture)>,
     cancellations: &'static CancellationContext,
 }
 
@@ -569,6 +588,16 @@ enum ArtifactMaterializationStage {
         /// Should not be deleted without invalidating DICE nodes, which currently
         /// means killing the daemon.
         active: bool,
+        /// How this artifact was materialized. Not known for artifacts restored from the
+        /// sqlite db or that already existed on disk.
+        method: Option<buck2_data::MaterializationMethod>,
+        /// The entry and method this artifact was declared with by the running daemon, if it
+        /// can be materialized again. Evicting it returns it to `Declared`, so it gets
+        /// materialized again if DICE hands it out later.
+        rematerialize: Option<(
+            ActionDirectoryEntry<ActionSharedDirectory>,
+            Arc<ArtifactMaterializationMethod>,
+        )>,
     },
 }
 
@@ -904,7 +933,7 @@ impl DeferredMaterializer {
 
         let mut tree = ArtifactTree::new();
         if let Some(sqlite_state) = sqlite_state {
-            for (path, (metadata, last_access_time)) in sqlite_state.into_iter() {
+            for (path, (metadata, last_access_time, method)) in sqlite_state.into_iter() {
                 tree.insert(
                     path.iter().map(|f| f.to_owned()),
                     Box::new(ArtifactMaterializationData {
@@ -913,6 +942,8 @@ impl DeferredMaterializer {
                             metadata,
                             last_access_time,
                             active: false,
+                            method,
+                            rematerialize: None,
                         },
                         processing: Processing::Done(Version(0)),
                     }),
@@ -920,6 +951,12 @@ impl DeferredMaterializer {
             }
         }
 
+        let blob_cache = configs
+            .blob_cache
+            .map(BlobCache::new)
+            .transpose()
+            .context("Error initializing blob cache")?;
+
         let command_processor = {
             let command_sender = command_sender.dupe();
             let io_executor = io_executor.dupe();
@@ -933,6 +970,8 @@ impl DeferredMaterializer {
                     re_client_manager,
                     io_executor,
                     http_client,
+                    blob_cache,
+                    local_copy_method: configs.local_copy_method,
                 }),
                 digest_config,
                 sqlite_db,
@@ -945,6 +984,7 @@ impl DeferredMaterializer {
                 subscriptions: MaterializerSubscriptions::new(),
                 ttl_refresh_history: Vec::new(),
                 ttl_refresh_instance: None,
+                evictions: Vec::new(),
                 cancellations,
             }
         };
@@ -960,9 +1000,11 @@ impl DeferredMaterializer {
 
                     let cancellations = CancellationContext::never_cancelled();
 
-                    rt.block_on(
-                        command_processor(cancellations).run(command_receiver, configs.ttl_refresh),
-                    );
+                    rt.block_on(command_processor(cancellations).run(
+                        command_receiver,
+                        configs.ttl_refresh,
+                        configs.disk_budget,
+                    ));
                 }
             })
             .context("Cannot start materializer thread")?;
@@ -1014,12 +1056,14 @@ struct CommandStream<T: 'static> {
     high_priority: UnboundedReceiver<MaterializerCommand<T>>,
     low_priority: UnboundedReceiver<LowPriorityMaterializerCommand>,
     refresh_ttl_ticker: Option<Interval>,
+    evict_ticker: Option<Interval>,
 }
 
 enum Op<T: 'static> {
     Command(MaterializerCommand<T>),
     LowPriorityCommand(LowPriorityMaterializerCommand),
     RefreshTtls,
+    EvictLru,
 }
 
 impl<T: 'static> Stream for CommandStream<T> {
@@ -1042,6 +1086,12 @@ impl<T: 'static> Stream for CommandStream<T> {
             }
         }
 
+        if let Some(ticker) = this.evict_ticker.as_mut() {
+            if let Poll::Ready(..) = ticker.poll_tick(cx) {
+                return Poll::Ready(Some(Op::EvictLru));
+            }
+        }
+
         // We can never be done because we never drop the senders, so let's not bother.
 
         Poll::Pending
@@ -1056,6 +1106,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
         mut self,
         commands: MaterializerReceiver<T>,
         ttl_refresh: TtlRefreshConfiguration,
+        disk_budget: Option<DiskBudgetConfiguration>,
     ) {
         let MaterializerReceiver {
             high_priority,
@@ -1072,10 +1123,18 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
             None
         };
 
+        let evict_ticker = disk_budget.as_ref().map(|disk_budget| {
+            tokio::time::interval_at(
+                tokio::time::Instant::now() + disk_budget.frequency,
+                disk_budget.frequency,
+            )
+        });
+
         let mut stream = CommandStream {
             high_priority,
             low_priority,
             refresh_ttl_ticker,
+            evict_ticker,
         };
 
         while let Some(op) = stream.next().await {
@@ -1127,6 +1186,11 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                         }
                     }
                 }
+                Op::EvictLru => {
+                    if let Some(disk_budget) = &disk_budget {
+                        self.evict_lru_artifacts(disk_budget);
+                    }
+                }
             }
         }
     }
@@ -1213,6 +1277,108 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
         }
     }
 
+    /// Evict least recently used artifacts until the materialized artifacts fit in the budget.
+    /// Artifacts declared by the running daemon are declared again, so that they are
+    /// materialized again if they are used. Others are removed from the state right away. Either
+    /// way, they are deleted from disk in the background.
+    fn evict_lru_artifacts(&mut self, disk_budget: &DiskBudgetConfiguration) {
+        let paths = self.tree.find_lru_artifacts_to_evict(
+            disk_budget.max_bytes,
+            disk_budget.in_flight_commands.oldest_start_time(),
+        );
+        if paths.is_empty() {
+            return;
+        }
+        tracing::debug!(
+            count = paths.len(),
+            "evicting least recently used artifacts"
+        );
+
+        let mut untracked = Vec::new();
+        for path in paths {
+            let declared = match self.tree.prefix_get_mut(&mut path.iter()) {
+                Some(box ArtifactMaterializationData {
+                    deps,
+                    stage:
+                        ArtifactMaterializationStage::Materialized {
+                            rematerialize: Some((entry, method)),
+                            ..
+                        },
+                    ..
+                }) => Some((ArtifactValue::new(entry.dupe(), deps.dupe()), method.dupe())),
+                _ => None,
+            };
+            match declared {
+                Some((value, method)) => self.declare_unmatched(&path, value, method),
+                None => untracked.push(path),
+            }
+        }
+        if !untracked.is_empty() {
+            self.evict_artifacts(untracked);
+        }
+    }
+
+    /// Stop tracking the artifacts at `paths` and delete them from disk. The artifacts must not
+    /// be active, since DICE would still assume they are materialized.
+    fn evict_artifacts(&mut self, paths: Vec<ProjectRelativePathBuf>) {
+        self.evictions.retain(|(_, fut)| fut.peek().is_none());
+
+        let existing_futs = match self
+            .tree
+            .invalidate_paths_and_collect_futures(paths.clone(), self.sqlite_db.as_mut())
+        {
+            Ok(existing_futs) => existing_futs,
+            Err(e) => {
+                soft_error!(
+                    "materializer_evict_error",
+                    e.context(self.log_buffer.clone()),
+                    quiet: true
+                )
+                .unwrap();
+                return;
+            }
+        };
+
+        let fut = self
+            .rt
+            .spawn({
+                let io = self.io.dupe();
+                let paths = paths.clone();
+                let cancellations = self.cancellations;
+                async move {
+                    join_all_existing_futs(existing_futs).await?;
+                    let res = io.clean_untracked_paths(paths, cancellations).await;
+                    if let Err(e) = &res {
+                        tracing::warn!("Failed to delete evicted artifacts: {:#}", e);
+                    }
+                    res.shared_error()
+                }
+            })
+            .map(|r| match r {
+                Ok(r) => r,
+                Err(e) => Err(e.into()), // Turn the JoinError into a SharedError.
+            })
+            .boxed()
+            .shared();
+
+        self.evictions
+            .extend(paths.into_iter().map(|path| (path, fut.clone())));
+    }
+
+    /// Deletions of evicted artifacts overlapping `path` which may still be in progress.
+    fn pending_evictions(
+        &self,
+        path: &ProjectRelativePath,
+    ) -> Vec<(ProjectRelativePathBuf, ProcessingFuture)> {
+        self.evictions
+            .iter()
+            .filter(|(evicted, fut)| {
+                (evicted.starts_with(path) || path.starts_with(evicted)) && fut.peek().is_none()
+            })
+            .map(|(evicted, fut)| (evicted.clone(), ProcessingFuture::Cleaning(fut.clone())))
+            .collect()
+    }
+
     /// Poll the current TTL refresh and remove it if it's done. Add the outcome to
     /// ttl_refresh_history.
     fn poll_current_ttl_refresh(&mut self) {
@@ -1287,6 +1453,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
             path,
             &metadata,
             Utc::now(),
+            None,
             "materializer_declare_existing_error",
         );
 
@@ -1298,6 +1465,8 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                     metadata,
                     last_access_time: Utc::now(),
                     active: true,
+                    method: None,
+                    rematerialize: None,
                 },
                 processing: Processing::Done(self.version_tracker.next()),
             }),
@@ -1317,6 +1486,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                 ArtifactMaterializationStage::Materialized {
                     metadata,
                     last_access_time,
+                    method: materialized_method,
                     ..
                 } => {
                     // NOTE: This is for testing performance when hitting mismatches with disk
@@ -1345,6 +1515,8 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                             metadata: metadata.dupe(),
                             last_access_time: *last_access_time,
                             active: true,
+                            method: *materialized_method,
+                            rematerialize: Some((value.entry().dupe(), Arc::from(method))),
                         };
                         data.deps = deps;
 
@@ -1356,6 +1528,16 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
         }
 
         // We don't have a matching artifact. Declare it.
+        self.declare_unmatched(path, value, Arc::from(method));
+    }
+
+    /// Declare an artifact, replacing whatever is at `path`.
+    fn declare_unmatched(
+        &mut self,
+        path: &ProjectRelativePath,
+        value: ArtifactValue,
+        method: Arc<ArtifactMaterializationMethod>,
+    ) {
         let version = self.version_tracker.next();
 
         tracing::trace!(
@@ -1371,12 +1553,14 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
         // thinks it still exists.
         let existing_futs = self
             .tree
-            .invalidate_paths_and_collect_futures(vec![path.to_owned()], self.sqlite_db.as_mut());
+            .invalidate_paths_and_collect_futures(vec![path.to_owned()], self.sqlite_db.as_mut())
+            .map(|mut existing_futs| {
+                existing_futs.extend(self.pending_evictions(path));
+                existing_futs
+            });
 
         let existing_futs = ExistingFutures(existing_futs);
 
-        let method = Arc::from(method);
-
         // Dispatch Write actions eagerly if possible. We can do this if no cleanup is required. We
         // also check that there are no deps, though for writes there should never be deps.
 
@@ -1727,10 +1911,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                             tracing::debug!("artifact is already materialized");
                             None
                         }
-                        ArtifactMaterializationStage::Declared {
-                            entry,
-                            method: _method,
-                        } => {
+                        ArtifactMaterializationStage::Declared { entry, method } => {
                             let metadata = ArtifactMetadata::new(entry);
                             // NOTE: We only insert this artifact if there isn't an in-progress cleanup
                             // future on this path.
@@ -1741,6 +1922,7 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                                 &artifact_path,
                                 &metadata,
                                 timestamp,
+                                Some(method.to_proto()),
                                 "materializer_finished_error",
                             );
 
@@ -1748,6 +1930,8 @@ impl<T: IoHandler> DeferredMaterializerCommandProcessor<T> {
                                 metadata,
                                 last_access_time: timestamp,
                                 active: true,
+                                method: Some(method.to_proto()),
+                                rematerialize: Some((entry.dupe(), method.dupe())),
                             })
                         }
                     };
@@ -1775,12 +1959,13 @@ fn on_materialization(
     path: &ProjectRelativePath,
     metadata: &ArtifactMetadata,
     timestamp: DateTime<Utc>,
+    method: Option<buck2_data::MaterializationMethod>,
     error_name: &'static str,
 ) {
     if let Some(sqlite_db) = sqlite_db {
         if let Err(e) = sqlite_db
             .materializer_state_table()
-            .insert(path, metadata, timestamp)
+            .insert(path, metadata, timestamp, method)
         {
             soft_error!(error_name, e.context(log_buffer.clone()), quiet: true).unwrap();
         }
@@ -1909,6 +2094,48 @@ impl ArtifactTree {
         }
     }
 
+    /// Paths of the least recently used artifacts to remove so that the total size of materialized
+    /// artifacts fits in `max_bytes`. Artifacts accessed since `in_use_since` (when the oldest
+    /// command in flight started) may be in use and are not candidates. Neither are artifacts
+    /// declared by this daemon that can't be materialized again, since DICE assumes they exist.
+    fn find_lru_artifacts_to_evict(
+        &self,
+        max_bytes: u64,
+        in_use_since: Option<DateTime<Utc>>,
+    ) -> Vec<ProjectRelativePathBuf> {
+        let mut total_bytes = 0;
+        let mut candidates = Vec::new();
+        for (path, data) in self.iter_with_paths() {
+            if let ArtifactMaterializationStage::Materialized {
+                metadata,
+                last_access_time,
+                active,
+                rematerialize,
+                ..
+            } = &data.stage
+            {
+                total_bytes += metadata.size();
+                let in_use = in_use_since.map_or(false, |since| *last_access_time >= since);
+                let evictable = !*active || rematerialize.is_some();
+                if evictable && !in_use && matches!(data.processing, Processing::Done(..)) {
+                    candidates.push((*last_access_time, metadata.size(), path));
+                }
+            }
+        }
+
+        candidates.sort_by_key(|(last_access_time, _, _)| *last_access_time);
+
+        let mut paths = Vec::new();
+        for (_, size, path) in candidates {
+            if total_bytes <= max_bytes {
+                break;
+            }
+            total_bytes -= size;
+            paths.push(ProjectRelativePathBuf::from(path));
+        }
+        paths
+    }
+
     /// Removes paths from tree and returns a pair of two vecs.
     /// First vec is a list of paths removed. Second vec is a list of
     /// pairs of removed paths to futures that haven't finished.
@@ -11,13 +11,18 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use buck2_common::file_ops::FileDigest;
 use buck2_common::file_ops::FileMetadata;
+use buck2_common::file_ops::TrackedFileDigest;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePathBuf;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::directory::insert_file;
 use buck2_execute::directory::ActionDirectoryBuilder;
 use buck2_execute::materialize::materializer::DeferredMaterializerSubscription;
+use chrono::TimeZone;
 use dupe::Dupe;
 
+use super::extension::materialized_subtree_size;
 use super::Version;
 use super::VersionTracker;
 use super::*;
@@ -86,6 +91,143 @@ fn test_remove_path() {
     assert_eq!(removed_subtree.get("a/b/c/e"), Some(&"a/b/c/e".to_owned()));
 }
 
+#[test]
+fn test_find_lru_artifacts_to_evict() {
+    let digest_config = DigestConfig::testing_default();
+
+    let mut tree = ArtifactTree::new();
+    let mut insert = |path: &str, size: u64, last_access_time: i64, active: bool| {
+        let file = ActionDirectoryMember::File(FileMetadata {
+            digest: TrackedFileDigest::new(
+                FileDigest::new_sha1([0; 20], size),
+                digest_config.cas_digest_config(),
+            ),
+            is_executable: false,
+        });
+        // Active artifacts can be evicted if they can be materialized again.
+        let rematerialize = (active && path.ends_with("_cas")).then(|| {
+            (
+                DirectoryEntry::Leaf(file.clone()),
+                Arc::new(ArtifactMaterializationMethod::Test),
+            )
+        });
+        tree.insert(
+            ProjectRelativePath::unchecked_new(path)
+                .iter()
+                .map(|f| f.to_owned()),
+            Box::new(ArtifactMaterializationData {
+                deps: None,
+                stage: ArtifactMaterializationStage::Materialized {
+                    metadata: ArtifactMetadata(DirectoryEntry::Leaf(file)),
+                    last_access_time: Utc.timestamp_opt(last_access_time, 0).unwrap(),
+                    active,
+                    method: None,
+                    rematerialize,
+                },
+                processing: Processing::Done(Version(0)),
+            }),
+        );
+    };
+
+    insert("a/old", 10, 1, false);
+    insert("a/oldest_active_existing", 10, 0, true);
+    insert("a/older_active_cas", 10, 0, true);
+    insert("b/recent", 10, 3, false);
+    insert("b/newest", 10, 4, false);
+
+    let paths = |paths: &[&str]| {
+        paths
+            .iter()
+            .map(|p| ProjectRelativePathBuf::unchecked_new((*p).to_owned()))
+            .collect::<Vec<_>>()
+    };
+
+    // Within budget.
+    assert!(tree.find_lru_artifacts_to_evict(50, None).is_empty());
+
+    // Oldest first, skipping active artifacts that can't be materialized again.
+    assert_eq!(
+        tree.find_lru_artifacts_to_evict(25, None),
+        paths(&["a/older_active_cas", "a/old", "b/recent"])
+    );
+
+    // Artifacts accessed since the oldest command in flight started are kept.
+    assert_eq!(
+        tree.find_lru_artifacts_to_evict(0, Some(Utc.timestamp_opt(3, 0).unwrap())),
+        paths(&["a/older_active_cas", "a/old"])
+    );
+    assert_eq!(
+        tree.find_lru_artifacts_to_evict(0, None),
+        paths(&["a/older_active_cas", "a/old", "b/recent", "b/newest"])
+    );
+}
+
+#[test]
+fn test_materialized_subtree_size() {
+    let digest_config = DigestConfig::testing_default();
+
+    let mut tree = ArtifactTree::new();
+    let mut insert = |path: &str, size: u64| {
+        let metadata = ArtifactMetadata(DirectoryEntry::Leaf(ActionDirectoryMember::File(
+            FileMetadata {
+                digest: TrackedFileDigest::new(
+                    FileDigest::new_sha1([0; 20], size),
+                    digest_config.cas_digest_config(),
+                ),
+                is_executable: false,
+            },
+        )));
+        tree.insert(
+            ProjectRelativePath::unchecked_new(path)
+                .iter()
+                .map(|f| f.to_owned()),
+            Box::new(ArtifactMaterializationData {
+                deps: None,
+                stage: ArtifactMaterializationStage::Materialized {
+                    metadata,
+                    last_access_time: Utc::now(),
+                    active: false,
+                    method: None,
+                    rematerialize: None,
+                },
+                processing: Processing::Done(Version(0)),
+            }),
+        );
+    };
+
+    insert("a/x/one", 10);
+    insert("a/x/two", 20);
+    insert("a/y", 5);
+    insert("b/only/three", 7);
+
+    let mut subtrees = Vec::new();
+    assert_eq!(
+        42,
+        materialized_subtree_size(&tree, ForwardRelativePathBuf::empty(), &mut subtrees)
+    );
+    subtrees.sort_by(|a, b| b.1.cmp(&a.1));
+
+    // `b` and `b/only` have a single entry, so they are not reported.
+    let expected = [
+        ("a", 35),
+        ("a/x", 30),
+        ("a/x/two", 20),
+        ("a/x/one", 10),
+        ("b/only/three", 7),
+        ("a/y", 5),
+    ];
+    assert_eq!(
+        subtrees,
+        expected
+            .iter()
+            .map(|(path, size)| (
+                ProjectRelativePathBuf::unchecked_new((*path).to_owned()),
+                *size
+            ))
+            .collect::<Vec<_>>()
+    );
+}
+
 mod state_machine {
     use std::path::Path;
 
@@ -197,6 +339,17 @@ mod state_machine {
         ) -> Option<BoxFuture<'static, anyhow::Result<()>>> {
             unimplemented!()
         }
+
+        fn clean_untracked_paths<'a>(
+            self: &Arc<Self>,
+            paths: Vec<ProjectRelativePathBuf>,
+            _cancellations: &'a CancellationContext,
+        ) -> BoxFuture<'a, anyhow::Result<()>> {
+            self.log
+                .lock()
+                .extend(paths.into_iter().map(|path| (Op::Clean, path)));
+            futures::future::ready(Ok(())).boxed()
+        }
     }
 
     /// A stub command sender. We are calling materializer methods directly so that's all we need.
@@ -256,6 +409,7 @@ mod state_machine {
                 subscriptions: MaterializerSubscriptions::new(),
                 ttl_refresh_history: Default::default(),
                 ttl_refresh_instance: Default::default(),
+                evictions: Default::default(),
                 cancellations: CancellationContext::testing(),
             },
             command_receiver,
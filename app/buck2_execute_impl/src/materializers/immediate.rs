@@ -46,6 +46,7 @@ use remote_execution::NamedDigest;
 use remote_execution::NamedDigestWithPermissions;
 
 use crate::materializers::io::materialize_files;
+use crate::materializers::io::LocalCopyMethod;
 use crate::materializers::io::MaterializeTreeStructure;
 
 /// Materializer that materializes everything immediately on declare.
@@ -132,6 +133,7 @@ impl Materializer for ImmediateMaterializer {
                         copied_artifact.dest_entry.as_ref(),
                         &self.fs.root().join(&copied_artifact.src),
                         &self.fs.root().join(&copied_artifact.dest),
+                        LocalCopyMethod::Reflink,
                     )?;
                 }
                 Ok(())
@@ -8,24 +8,83 @@
  */
 
 use std::collections::HashMap;
+use std::str::FromStr;
 
+use allocative::Allocative;
 use buck2_core::directory::DirectoryEntry;
 use buck2_core::fs::fs_util;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPath;
 use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_execute::directory::ActionDirectory;
 use buck2_execute::directory::ActionDirectoryEntry;
 use buck2_execute::directory::ActionDirectoryMember;
 use buck2_execute::directory::ActionSharedDirectory;
 use buck2_execute::execute::blocking::IoRequest;
+use dupe::Dupe;
 
 pub struct MaterializeTreeStructure {
     pub path: ProjectRelativePathBuf,
     pub entry: ActionDirectoryEntry<ActionSharedDirectory>,
 }
 
+#[derive(Debug, thiserror::Error)]
+enum LocalCopyMethodError {
+    #[error("Invalid local copy method: `{0}`, expected `reflink` or `hardlink`")]
+    Invalid(String),
+}
+
+/// How the files of local copies (e.g. `ctx.actions.copy_file`) are materialized.
+#[derive(Debug, Copy, Clone, Dupe, Eq, PartialEq, Allocative)]
+pub enum LocalCopyMethod {
+    /// Clone the file if the filesystem supports it, copy its contents otherwise.
+    Reflink,
+    /// Hardlink the file, falling back to `Reflink` if that fails (e.g. across filesystems). The
+    /// copy shares its inode with the source, so this is only suitable when artifacts are
+    /// immutable once materialized. Sources outside of buck-out are always cloned, see
+    /// `for_source`.
+    Hardlink,
+}
+
+impl LocalCopyMethod {
+    /// The method to copy `src` with. Only other artifacts in buck-out are hardlinked: sources
+    /// in the repo may be edited in place, which would silently change the copy while its
+    /// recorded digest is still trusted.
+    pub(crate) fn for_source(
+        self,
+        src: &ProjectRelativePath,
+        buck_out_path: &ProjectRelativePath,
+    ) -> Self {
+        match self {
+            Self::Hardlink if src.starts_with(buck_out_path) => Self::Hardlink,
+            _ => Self::Reflink,
+        }
+    }
+}
+
+impl FromStr for LocalCopyMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "reflink" => Ok(Self::Reflink),
+            "hardlink" => Ok(Self::Hardlink),
+            _ => Err(LocalCopyMethodError::Invalid(s.to_owned()).into()),
+        }
+    }
+}
+
+/// Number of files materialized by hardlinking them, cloning them (copy-on-write) or copying
+/// their contents.
+#[derive(Default, Debug, Clone, Copy)]
+pub(crate) struct CopyStats {
+    pub(crate) hard_linked: u64,
+    pub(crate) cloned: u64,
+    pub(crate) copied: u64,
+}
+
 impl IoRequest for MaterializeTreeStructure {
     fn execute(self: Box<Self>, project_fs: &ProjectRoot) -> anyhow::Result<()> {
         materialize_dirs_and_syms(self.entry.as_ref(), project_fs.root().join(&self.path))?;
@@ -41,12 +100,15 @@ impl IoRequest for MaterializeTreeStructure {
 /// - `file_src`: takes the destination path of a file, and returns its
 ///   source path (where it should be copied from). If it returns [`None`],
 ///   the file is not materialized.
+///
+/// Files are copied with `copy_method`.
 fn materialize<F, D>(
     entry: DirectoryEntry<&D, &ActionDirectoryMember>,
     dest: &AbsNormPath,
     materialize_dirs_and_syms: bool,
     mut file_src: F,
-) -> anyhow::Result<()>
+    copy_method: LocalCopyMethod,
+) -> anyhow::Result<CopyStats>
 where
     F: FnMut(&AbsNormPath) -> Option<AbsNormPathBuf>,
     D: ActionDirectory,
@@ -58,7 +120,16 @@ where
             fs_util::create_dir_all(parent)?;
         }
     }
-    materialize_recursively(entry, &mut dest, materialize_dirs_and_syms, &mut file_src)
+    let mut stats = CopyStats::default();
+    materialize_recursively(
+        entry,
+        &mut dest,
+        materialize_dirs_and_syms,
+        &mut file_src,
+        copy_method,
+        &mut stats,
+    )?;
+    Ok(stats)
 }
 
 /// Materializes the directories and symlinks of an entry at `dest`. Files
@@ -71,7 +142,14 @@ where
     P: AsRef<AbsNormPath>,
     D: ActionDirectory,
 {
-    materialize(entry, dest.as_ref(), true, |_: &AbsNormPath| None)
+    materialize(
+        entry,
+        dest.as_ref(),
+        true,
+        |_: &AbsNormPath| None,
+        LocalCopyMethod::Reflink,
+    )?;
+    Ok(())
 }
 
 /// Materializes the files of an the entry rooted at `dest`.
@@ -82,7 +160,8 @@ pub(crate) fn materialize_files<P, D>(
     entry: DirectoryEntry<&D, &ActionDirectoryMember>,
     src: P,
     dest: P,
-) -> anyhow::Result<()>
+    copy_method: LocalCopyMethod,
+) -> anyhow::Result<CopyStats>
 where
     P: AsRef<AbsNormPath>,
     D: ActionDirectory,
@@ -100,7 +179,7 @@ where
             Some(src.join(subpath))
         }
     };
-    materialize(entry, dest, false, file_src)
+    materialize(entry, dest, false, file_src, copy_method)
 }
 
 /// Materializes the files of an entry rooted at `dest`.
@@ -112,13 +191,14 @@ fn _materialize_files_from_map<P, D>(
     entry: DirectoryEntry<&D, &ActionDirectoryMember>,
     srcs: &mut HashMap<AbsNormPathBuf, AbsNormPathBuf>,
     dest: P,
-) -> anyhow::Result<()>
+    copy_method: LocalCopyMethod,
+) -> anyhow::Result<CopyStats>
 where
     P: AsRef<AbsNormPath>,
     D: ActionDirectory,
 {
     let file_src = |d: &AbsNormPath| srcs.remove(d);
-    materialize(entry, dest.as_ref(), false, file_src)
+    materialize(entry, dest.as_ref(), false, file_src, copy_method)
 }
 
 fn materialize_recursively<F, D>(
@@ -126,6 +206,8 @@ fn materialize_recursively<F, D>(
     dest: &mut AbsNormPathBuf,
     materialize_dirs_and_syms: bool,
     file_src: &mut F,
+    copy_method: LocalCopyMethod,
+    stats: &mut CopyStats,
 ) -> anyhow::Result<()>
 where
     F: FnMut(&AbsNormPath) -> Option<AbsNormPathBuf>,
@@ -138,14 +220,21 @@ where
             }
             for (name, entry) in d.entries() {
                 dest.push(name);
-                materialize_recursively(entry, dest, materialize_dirs_and_syms, file_src)?;
+                materialize_recursively(
+                    entry,
+                    dest,
+                    materialize_dirs_and_syms,
+                    file_src,
+                    copy_method,
+                    stats,
+                )?;
                 dest.pop();
             }
             Ok(())
         }
         DirectoryEntry::Leaf(ActionDirectoryMember::File(_)) => {
             if let Some(src) = file_src(dest) {
-                fs_util::copy(src, dest)?;
+                copy_file(&src, dest, copy_method, stats)?;
             }
             Ok(())
         }
@@ -163,3 +252,51 @@ where
         }
     }
 }
+
+fn copy_file(
+    src: &AbsNormPath,
+    dest: &AbsNormPath,
+    copy_method: LocalCopyMethod,
+    stats: &mut CopyStats,
+) -> anyhow::Result<()> {
+    if copy_method == LocalCopyMethod::Hardlink {
+        match fs_util::hard_link(src, dest) {
+            Ok(()) => {
+                stats.hard_linked += 1;
+                return Ok(());
+            }
+            Err(e) => tracing::debug!("Error hardlinking local copy, cloning instead: {:#}", e),
+        }
+    }
+    if fs_util::clone_or_copy(src, dest)? {
+        stats.cloned += 1;
+    } else {
+        stats.copied += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_hardlink_buck_out_sources() {
+        let buck_out = ProjectRelativePath::unchecked_new("buck-out/v2");
+        let output = ProjectRelativePath::unchecked_new("buck-out/v2/gen/root/foo/out");
+        let source = ProjectRelativePath::unchecked_new("foo/src.txt");
+
+        assert_eq!(
+            LocalCopyMethod::Hardlink,
+            LocalCopyMethod::Hardlink.for_source(output, buck_out)
+        );
+        assert_eq!(
+            LocalCopyMethod::Reflink,
+            LocalCopyMethod::Hardlink.for_source(source, buck_out)
+        );
+        assert_eq!(
+            LocalCopyMethod::Reflink,
+            LocalCopyMethod::Reflink.for_source(output, buck_out)
+        );
+    }
+}
@@ -52,12 +52,27 @@ pub struct MaterializerStateIdentity(String);
 /// materializer state sqlite db schema! If you forget to bump this version,
 /// then you can fix forward by bumping the `buck2.sqlite_materializer_state_version`
 /// buckconfig in the project root's .buckconfig.
-pub const DB_SCHEMA_VERSION: u64 = 6;
+pub const DB_SCHEMA_VERSION: u64 = 7;
+
+/// Key of the schema version in the versions table.
+pub const DB_SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Schema version of dbs that can be upgraded in place to `DB_SCHEMA_VERSION`.
+/// Version 7 only added the nullable `method` column to the materializer state
+/// table, so existing rows remain valid.
+const MIGRATABLE_DB_SCHEMA_VERSION: u64 = 6;
 
 const STATE_TABLE_NAME: &str = "materializer_state";
 const IDENTITY_KEY: &str = "timestamp_on_initialization";
 
-pub type MaterializerState = Vec<(ProjectRelativePathBuf, (ArtifactMetadata, DateTime<Utc>))>;
+pub type MaterializerState = Vec<(
+    ProjectRelativePathBuf,
+    (
+        ArtifactMetadata,
+        DateTime<Utc>,
+        Option<buck2_data::MaterializationMethod>,
+    ),
+)>;
 
 #[derive(Error, Debug, PartialEq, Eq)]
 pub(crate) enum ArtifactMetadataSqliteConversionError {
@@ -317,7 +332,8 @@ impl MaterializerStateSqliteTable {
                 file_is_executable      INTEGER NULL DEFAULT NULL,
                 symlink_target          TEXT NULL DEFAULT NULL,
                 last_access_time        INTEGER NOT NULL,
-                directory_size          INTEGER NULL DEFAULT NULL
+                directory_size          INTEGER NULL DEFAULT NULL,
+                method                  INTEGER NULL DEFAULT NULL
             )",
             STATE_TABLE_NAME,
         );
@@ -329,16 +345,31 @@ impl MaterializerStateSqliteTable {
         Ok(())
     }
 
+    /// Adds the `method` column to a table created with `MIGRATABLE_DB_SCHEMA_VERSION`.
+    fn add_method_column(&self) -> anyhow::Result<()> {
+        let sql = format!(
+            "ALTER TABLE {} ADD COLUMN method INTEGER NULL DEFAULT NULL",
+            STATE_TABLE_NAME,
+        );
+        tracing::trace!(sql = %*sql, "migrating table");
+        self.connection
+            .lock()
+            .execute(&sql, [])
+            .with_context(|| format!("migrating sqlite table {}", STATE_TABLE_NAME))?;
+        Ok(())
+    }
+
     pub(crate) fn insert(
         &self,
         path: &ProjectRelativePath,
         metadata: &ArtifactMetadata,
         timestamp: DateTime<Utc>,
+        method: Option<buck2_data::MaterializationMethod>,
     ) -> anyhow::Result<()> {
         let entry: ArtifactMetadataSqliteEntry = metadata.into();
         static SQL: Lazy<String> = Lazy::new(|| {
             format!(
-                "INSERT INTO {} (path, artifact_type, digest_size, entry_hash, entry_hash_kind, file_is_executable, symlink_target, directory_size, last_access_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                "INSERT INTO {} (path, artifact_type, digest_size, entry_hash, entry_hash_kind, file_is_executable, symlink_target, directory_size, last_access_time, method) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
                 STATE_TABLE_NAME
             )
         });
@@ -357,6 +388,7 @@ impl MaterializerStateSqliteTable {
                     entry.symlink_target,
                     entry.directory_size,
                     timestamp.timestamp(),
+                    method.map(|m| m as i32),
                 ],
             )
             .with_context(|| {
@@ -396,7 +428,7 @@ impl MaterializerStateSqliteTable {
     ) -> anyhow::Result<MaterializerState> {
         static SQL: Lazy<String> = Lazy::new(|| {
             format!(
-                "SELECT path, artifact_type, digest_size, entry_hash, entry_hash_kind, file_is_executable, symlink_target, directory_size, last_access_time FROM {}",
+                "SELECT path, artifact_type, digest_size, entry_hash, entry_hash_kind, file_is_executable, symlink_target, directory_size, last_access_time, method FROM {}",
                 STATE_TABLE_NAME,
             )
         });
@@ -406,7 +438,7 @@ impl MaterializerStateSqliteTable {
         let result = stmt
             .query_map(
                 [],
-                |row| -> rusqlite::Result<(String, ArtifactMetadataSqliteEntry, i64)> {
+                |row| -> rusqlite::Result<(String, ArtifactMetadataSqliteEntry, i64, Option<i32>)> {
                     Ok((
                         row.get(0)?,
                         ArtifactMetadataSqliteEntry::new(
@@ -419,6 +451,7 @@ impl MaterializerStateSqliteTable {
                             row.get(7)?,
                         ),
                         row.get(8)?,
+                        row.get(9)?,
                     ))
                 },
             )?
@@ -427,9 +460,13 @@ impl MaterializerStateSqliteTable {
 
         result
             .into_try_map(
-                |(path, entry, last_access_time)| -> anyhow::Result<(
+                |(path, entry, last_access_time, method)| -> anyhow::Result<(
                     ProjectRelativePathBuf,
-                    (ArtifactMetadata, DateTime<Utc>),
+                    (
+                        ArtifactMetadata,
+                        DateTime<Utc>,
+                        Option<buck2_data::MaterializationMethod>,
+                    ),
                 )> {
                     let path = ProjectRelativePathBuf::unchecked_new(path);
                     let metadata = convert_artifact_metadata(entry, digest_config)?;
@@ -437,7 +474,13 @@ impl MaterializerStateSqliteTable {
                         .timestamp_opt(last_access_time, 0)
                         .single()
                         .with_context(|| "invalid timestamp")?;
-                    Ok((path, (metadata, timestamp)))
+                    let method = method
+                        .map(|m| {
+                            buck2_data::MaterializationMethod::from_i32(m)
+                                .with_context(|| format!("invalid materialization method: {}", m))
+                        })
+                        .transpose()?;
+                    Ok((path, (metadata, timestamp, method)))
                 },
             )
             .with_context(|| format!("error reading row of sqlite table {}", STATE_TABLE_NAME))
@@ -568,8 +611,12 @@ impl MaterializerStateSqliteDb {
 
             let tables = MaterializerStateTables::open(&db_path)?;
 
-            // First check that versions match
-            let read_versions = tables.versions_table.read_all()?;
+            // First check that versions match, upgrading the db in place if
+            // it was written by a schema version we know how to migrate.
+            let mut read_versions = tables.versions_table.read_all()?;
+            if read_versions != versions && tables.migrate(&read_versions, &versions)? {
+                read_versions = tables.versions_table.read_all()?;
+            }
             if read_versions != versions {
                 Err(MaterializerStateSqliteDbError::VersionMismatch {
                     expected: versions.clone(),
@@ -696,6 +743,30 @@ impl MaterializerStateTables {
         })
     }
 
+    /// Upgrades a db written with `MIGRATABLE_DB_SCHEMA_VERSION` to `DB_SCHEMA_VERSION`,
+    /// provided the schema version is the only version that differs from `expected`.
+    /// Returns whether the db was migrated.
+    fn migrate(
+        &self,
+        found: &HashMap<String, String>,
+        expected: &HashMap<String, String>,
+    ) -> anyhow::Result<bool> {
+        let mut upgraded = found.clone();
+        let found_schema_version = upgraded.insert(
+            DB_SCHEMA_VERSION_KEY.to_owned(),
+            DB_SCHEMA_VERSION.to_string(),
+        );
+        if found_schema_version != Some(MIGRATABLE_DB_SCHEMA_VERSION.to_string())
+            || upgraded != *expected
+        {
+            return Ok(false);
+        }
+
+        self.materializer_state_table.add_method_column()?;
+        self.versions_table.insert_all(upgraded)?;
+        Ok(true)
+    }
+
     fn create_all_tables(&self) -> anyhow::Result<()> {
         self.materializer_state_table.create_table()?;
         self.versions_table.create_table()?;
@@ -847,17 +918,23 @@ mod tests {
                 (
                     ArtifactMetadata(DirectoryEntry::Dir(dir_metadata)),
                     now_seconds(),
+                    Some(buck2_data::MaterializationMethod::CasDownload),
                 ),
             ),
             (
                 ProjectRelativePath::unchecked_new("b/c").to_owned(),
-                (ArtifactMetadata(DirectoryEntry::Leaf(file)), now_seconds()),
+                (
+                    ArtifactMetadata(DirectoryEntry::Leaf(file)),
+                    now_seconds(),
+                    Some(buck2_data::MaterializationMethod::LocalCopy),
+                ),
             ),
             (
                 ProjectRelativePath::unchecked_new("d").to_owned(),
                 (
                     ArtifactMetadata(DirectoryEntry::Leaf(symlink)),
                     now_seconds(),
+                    None,
                 ),
             ),
             (
@@ -865,12 +942,15 @@ mod tests {
                 (
                     ArtifactMetadata(DirectoryEntry::Leaf(external_symlink)),
                     now_seconds(),
+                    Some(buck2_data::MaterializationMethod::Write),
                 ),
             ),
         ]);
 
         for (path, metadata) in artifacts.iter() {
-            table.insert(path, &metadata.0, metadata.1).unwrap();
+            table
+                .insert(path, &metadata.0, metadata.1, metadata.2)
+                .unwrap();
         }
 
         let state = table.read_all(digest_config).unwrap();
@@ -978,7 +1058,7 @@ mod tests {
             assert_metadata_matches(db.tables.last_read_by_table.read_all()?, &metadatas[0]);
 
             db.materializer_state_table()
-                .insert(&path, &artifact_metadata, timestamp)
+                .insert(&path, &artifact_metadata, timestamp, None)
                 .unwrap();
         }
 
@@ -989,7 +1069,7 @@ mod tests {
             assert_matches!(
                 loaded_state,
                 Ok(v) => {
-                    assert_eq!(v, vec![(path.clone(), (artifact_metadata.clone(), timestamp))]);
+                    assert_eq!(v, vec![(path.clone(), (artifact_metadata.clone(), timestamp, None))]);
                 }
             );
             assert_metadata_matches(db.tables.created_by_table.read_all()?, &metadatas[0]);
@@ -1020,7 +1100,7 @@ mod tests {
             assert_metadata_matches(db.tables.last_read_by_table.read_all()?, &metadatas[2]);
 
             db.materializer_state_table()
-                .insert(&path, &artifact_metadata, timestamp)
+                .insert(&path, &artifact_metadata, timestamp, None)
                 .unwrap();
         }
 
@@ -1035,7 +1115,7 @@ mod tests {
             assert_matches!(
                 loaded_state,
                 Ok(v) => {
-                    assert_eq!(v, vec![(path, (artifact_metadata, timestamp))]);
+                    assert_eq!(v, vec![(path, (artifact_metadata, timestamp, None))]);
                 }
             );
             assert_metadata_matches(db.tables.created_by_table.read_all()?, &metadatas[2]);
@@ -1069,6 +1149,108 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_migrate_sqlite_db() -> anyhow::Result<()> {
+        let fs = ProjectRootTemp::new()?;
+        let metadata = buck2_events::metadata::collect();
+
+        let versions = |schema_version: u64| {
+            HashMap::from([
+                (DB_SCHEMA_VERSION_KEY.to_owned(), schema_version.to_string()),
+                ("version".to_owned(), "0".to_owned()),
+            ])
+        };
+
+        // Write a db the way `MIGRATABLE_DB_SCHEMA_VERSION` did, without the `method` column.
+        let materializer_state_dir = fs.path().resolve(ProjectRelativePath::unchecked_new(
+            "buck-out/v2/cache/materializer_state",
+        ));
+        fs_util::create_dir_all(&materializer_state_dir)?;
+        {
+            let tables = MaterializerStateTables::open(&materializer_state_dir.join(
+                FileName::unchecked_new(MaterializerStateSqliteDb::DB_FILENAME),
+            ))?;
+            let connection = tables.materializer_state_table.connection.lock();
+            connection.execute(
+                "CREATE TABLE materializer_state (
+                    path                    TEXT NOT NULL PRIMARY KEY,
+                    artifact_type           TEXT NOT NULL,
+                    digest_size             INTEGER NULL DEFAULT NULL,
+                    entry_hash              BLOB NULL DEFAULT NULL,
+                    entry_hash_kind         INTEGER NULL DEFAULT NULL,
+                    file_is_executable      INTEGER NULL DEFAULT NULL,
+                    symlink_target          TEXT NULL DEFAULT NULL,
+                    last_access_time        INTEGER NOT NULL,
+                    directory_size          INTEGER NULL DEFAULT NULL
+                )",
+                [],
+            )?;
+            connection.execute(
+                "INSERT INTO materializer_state (path, artifact_type, symlink_target, last_access_time) VALUES ('foo', 'symlink', 'bar', 0)",
+                [],
+            )?;
+            drop(connection);
+            tables.versions_table.create_table()?;
+            tables.created_by_table.create_table()?;
+            tables.last_read_by_table.create_table()?;
+            tables
+                .versions_table
+                .insert_all(versions(MIGRATABLE_DB_SCHEMA_VERSION))?;
+            tables.created_by_table.insert_all(HashMap::from([(
+                IDENTITY_KEY.to_owned(),
+                "created".to_owned(),
+            )]))?;
+        }
+
+        let (mut db, loaded_state) = testing_materializer_state_sqlite_db(
+            fs.path(),
+            versions(DB_SCHEMA_VERSION),
+            metadata.clone(),
+            None,
+        )?;
+        let loaded_state = loaded_state?;
+        assert_eq!(loaded_state.len(), 1);
+        let (path, (_, _, method)) = &loaded_state[0];
+        assert_eq!(path.as_str(), "foo");
+        assert_eq!(*method, None);
+        assert_eq!(
+            db.tables.versions_table.read_all()?,
+            versions(DB_SCHEMA_VERSION)
+        );
+
+        // The migrated table accepts methods.
+        db.materializer_state_table().insert(
+            ProjectRelativePath::unchecked_new("baz"),
+            &loaded_state[0].1.0,
+            now_seconds(),
+            Some(buck2_data::MaterializationMethod::HttpDownload),
+        )?;
+        drop(db);
+
+        let (_db, loaded_state) = testing_materializer_state_sqlite_db(
+            fs.path(),
+            versions(DB_SCHEMA_VERSION),
+            metadata,
+            None,
+        )?;
+        let methods = loaded_state?
+            .into_iter()
+            .map(|(path, (_, _, method))| (path.as_str().to_owned(), method))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(
+            methods,
+            HashMap::from([
+                ("foo".to_owned(), None),
+                (
+                    "baz".to_owned(),
+                    Some(buck2_data::MaterializationMethod::HttpDownload)
+                ),
+            ])
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_delete_many() -> anyhow::Result<()> {
         let conn = Connection::open_in_memory()?;
@@ -23,6 +23,7 @@ use tonic::Request;
 
 use crate::convert::decode_event_stream;
 use crate::run::decode_command_event_stream;
+use crate::run::CommandEvent;
 use crate::run::GatherOutputStatus;
 
 #[derive(Clone, Dupe, Allocative)]
@@ -48,10 +49,12 @@ impl ForkserverClient {
         }
     }
 
+    /// Run a command. `on_event` is called with the output of the command as it runs.
     pub async fn execute<C>(
         &self,
         req: buck2_forkserver_proto::CommandRequest,
         cancel: C,
+        on_event: impl FnMut(&CommandEvent),
     ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
     where
         C: Future<Output = ()> + Send + 'static,
@@ -74,7 +77,7 @@ impl ForkserverClient {
             .context("Error dispatching command to Forkserver")?
             .into_inner();
         let stream = decode_event_stream(stream);
-        decode_command_event_stream(stream).await
+        decode_command_event_stream(stream, on_event).await
     }
 
     pub async fn set_log_filter(&self, log_filter: String) -> anyhow::Result<()> {
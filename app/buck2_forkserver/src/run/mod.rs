@@ -252,8 +252,11 @@ where
     Ok(CommandEventStream::new(status, stdio).right_stream())
 }
 
+/// Collect the output of a command. `on_event` is called with every event as it is received, e.g.
+/// to show the output of the command while it runs.
 pub(crate) async fn decode_command_event_stream<S>(
     stream: S,
+    mut on_event: impl FnMut(&CommandEvent),
 ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
 where
     S: Stream<Item = anyhow::Result<CommandEvent>>,
@@ -264,6 +267,7 @@ where
     let mut stderr = Vec::<u8>::new();
 
     while let Some(event) = stream.try_next().await? {
+        on_event(&event);
         match event {
             CommandEvent::Stdout(bytes) => stdout.extend(&bytes),
             CommandEvent::Stderr(bytes) => stderr.extend(&bytes),
@@ -280,6 +284,18 @@ pub async fn gather_output<T>(
     cmd: Command,
     cancellation: T,
 ) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
+where
+    T: Future<Output = anyhow::Result<GatherOutputStatus>> + Send,
+{
+    gather_output_observed(cmd, cancellation, |_| {}).await
+}
+
+/// Like [`gather_output`], but calls `on_event` with the output of the command as it runs.
+pub async fn gather_output_observed<T>(
+    cmd: Command,
+    cancellation: T,
+    on_event: impl FnMut(&CommandEvent),
+) -> anyhow::Result<(GatherOutputStatus, Vec<u8>, Vec<u8>)>
 where
     T: Future<Output = anyhow::Result<GatherOutputStatus>> + Send,
 {
@@ -292,7 +308,7 @@ where
         DefaultStatusDecoder,
         DefaultKillProcess,
     )?;
-    decode_command_event_stream(stream).await
+    decode_command_event_stream(stream, on_event).await
 }
 
 /// Dependency injection for kill. We use this in testing.
@@ -654,7 +670,7 @@ mod tests {
             },
         )?;
 
-        let (status, _stdout, _stderr) = decode_command_event_stream(stream).await?;
+        let (status, _stdout, _stderr) = decode_command_event_stream(stream, |_| {}).await?;
         assert!(matches!(status, GatherOutputStatus::TimedOut(..)));
 
         assert!(*killed.lock().unwrap());
@@ -9,7 +9,6 @@
 
 use buck2_interpreter::functions::dedupe::dedupe;
 use buck2_interpreter::functions::sha256::register_sha256;
-use buck2_interpreter::globspec::GlobSpec;
 use buck2_interpreter::selector::register_select;
 use starlark::environment::GlobalsBuilder;
 use starlark::environment::LibraryExtension;
@@ -19,8 +18,18 @@ use starlark::values::list::AllocList;
 use starlark::values::Value;
 
 use crate::interpreter::build_context::BuildContext;
+use crate::interpreter::globs::GlobArgs;
 use crate::interpreter::module_internals::ModuleInternals;
 
+#[derive(Debug, thiserror::Error)]
+enum GlobError {
+    #[error(
+        "glob(include = {0:?}, exclude = {1:?}) did not match any files, \
+        pass `allow_empty = True` if that is expected"
+    )]
+    Empty(Vec<String>, Vec<String>),
+}
+
 #[starlark_module]
 pub fn native_module(builder: &mut GlobalsBuilder) {
     /// The `glob()` function specifies a set of files using patterns.
@@ -42,6 +51,9 @@ pub fn native_module(builder: &mut GlobalsBuilder) {
     ///
     /// This call will remove all `config.h` files from the initial match.
     ///
+    /// Like in Bazel, directories are not returned unless `exclude_directories = 0` is passed,
+    /// and `allow_empty = False` makes it an error for the glob to not match anything.
+    ///
     /// The `glob()` call is evaluated against the list of files owned by this `BUCK` file.
     /// A file is owned by whichever `BUCK` file is closest above it - so given `foo/BUCK` and
     /// `foo/bar/BUCK` the file `foo/file.txt` would be owned by `foo/BUCK` (and available from
@@ -55,12 +67,23 @@ pub fn native_module(builder: &mut GlobalsBuilder) {
     fn glob<'v>(
         include: Vec<String>,
         #[starlark(require = named, default=Vec::new())] exclude: Vec<String>,
+        #[starlark(require = named, default = 1i32)] exclude_directories: i32,
+        #[starlark(require = named, default = true)] allow_empty: bool,
         eval: &mut Evaluator<'v, '_>,
     ) -> anyhow::Result<Value<'v>> {
         let extra = ModuleInternals::from_context(eval, "glob")?;
-        let spec = GlobSpec::new(&include, &exclude)?;
-        let res = extra.resolve_glob(&spec).map(|path| path.as_str());
-        Ok(eval.heap().alloc(AllocList(res)))
+        let args = GlobArgs {
+            include,
+            exclude,
+            exclude_directories: exclude_directories != 0,
+        };
+        let res = extra.resolve_glob(args.clone())?;
+        if res.is_empty() && !allow_empty {
+            return Err(GlobError::Empty(args.include, args.exclude).into());
+        }
+        Ok(eval
+            .heap()
+            .alloc(AllocList(res.iter().map(|path| path.as_str()))))
     }
 
     /// `package_name()` can only be called in `BUCK` files, and returns the name of the package.
@@ -15,12 +15,14 @@ use std::time::Instant;
 
 use allocative::Allocative;
 use async_trait::async_trait;
+use buck2_common::events::HasEvents;
 use buck2_common::result::SharedResult;
 use buck2_common::result::ToSharedResultExt;
 use buck2_common::result::ToUnsharedResultExt;
 use buck2_core::cells::build_file_cell::BuildFileCell;
 use buck2_core::package::PackageLabel;
 use buck2_events::dispatch::async_record_root_spans;
+use buck2_events::dispatch::with_dispatcher;
 use buck2_events::span::SpanId;
 use buck2_interpreter::dice::starlark_profiler::GetStarlarkProfilerInstrumentation;
 use buck2_interpreter::file_loader::LoadedModule;
@@ -38,6 +40,7 @@ use dice::Key;
 use dupe::Dupe;
 use more_futures::cancellation::CancellationContext;
 use smallvec::SmallVec;
+use tokio::runtime::Handle;
 
 use crate::interpreter::dice_calculation_delegate::HasCalculationDelegate;
 
@@ -92,9 +95,24 @@ impl TargetGraphCalculationImpl for TargetGraphCalculationInstance {
             ) -> Self::Value {
                 let now = Instant::now();
 
-                let (result, spans) =
-                    async_record_root_spans(ctx.get_interpreter_results_uncached(self.0.dupe()))
-                        .await;
+                // The build file is evaluated synchronously, and `glob()` blocks on DICE during
+                // evaluation. So like BXL, don't block this future on it: run the evaluation on a
+                // blocking thread, so that this key can still be polled and cancelled meanwhile.
+                let (result, spans) = {
+                    let ctx = ctx.dupe();
+                    let package = self.0.dupe();
+                    let dispatcher = ctx.per_transaction_data().get_dispatcher().dupe();
+                    let handle = Handle::current();
+                    tokio::task::spawn_blocking(move || {
+                        with_dispatcher(dispatcher, || {
+                            handle.block_on(async_record_root_spans(
+                                ctx.get_interpreter_results_uncached(package),
+                            ))
+                        })
+                    })
+                    .await
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+                };
 
                 let result = result.shared_error();
 
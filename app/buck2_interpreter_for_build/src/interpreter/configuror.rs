@@ -30,6 +30,7 @@ use starlark::environment::GlobalsBuilder;
 use crate::attrs::coerce::ctx::BuildAttrCoercionContext;
 use crate::interpreter::build_defs::configure_base_globals;
 use crate::interpreter::functions::host_info::HostInfo;
+use crate::interpreter::globs::GlobResolver;
 use crate::interpreter::module_internals::ModuleInternals;
 use crate::interpreter::module_internals::PackageImplicits;
 use crate::super_package::data::SuperPackage;
@@ -178,6 +179,7 @@ impl BuildInterpreterConfiguror {
         cell_info: &InterpreterCellInfo,
         buildfile_path: BuildFilePath,
         package_listing: PackageListing,
+        globs: GlobResolver,
         super_package: SuperPackage,
         package_boundary_exception: bool,
         loaded_modules: &LoadedModules,
@@ -218,6 +220,7 @@ impl BuildInterpreterConfiguror {
             record_target_call_stack,
             skip_targets_with_duplicate_names,
             package_listing,
+            globs,
             super_package,
         ))
     }
@@ -56,6 +56,7 @@ use starlark::syntax::AstModule;
 use crate::interpreter::cycles::LoadCycleDescriptor;
 use crate::interpreter::dice_calculation_delegate::keys::EvalImportKey;
 use crate::interpreter::global_interpreter_state::HasGlobalInterpreterState;
+use crate::interpreter::globs::GlobResolver;
 use crate::interpreter::interpreter_for_cell::InterpreterForCell;
 use crate::interpreter::interpreter_for_cell::ParseResult;
 use crate::super_package::data::SuperPackage;
@@ -208,7 +209,9 @@ impl<'c> DiceCalculationDelegate<'c> {
     async fn parse_file(&self, starlark_path: StarlarkPath<'_>) -> anyhow::Result<ParseResult> {
         let content =
             <dyn FileOps>::read_file(&self.fs, starlark_path.path().as_ref().as_ref()).await?;
-        self.configs.parse(starlark_path, content)
+        self.configs
+            .parse(starlark_path, content)
+            .context(buck2_data::ErrorCategory::User)
     }
 
     async fn eval_deps(
@@ -246,7 +249,10 @@ impl<'c> DiceCalculationDelegate<'c> {
         starlark_file: StarlarkPath<'_>,
         content: String,
     ) -> anyhow::Result<AstModule> {
-        let ParseResult(ast, _) = self.configs.parse(starlark_file, content)?;
+        let ParseResult(ast, _) = self
+            .configs
+            .parse(starlark_file, content)
+            .context(buck2_data::ErrorCategory::User)?;
         Ok(ast)
     }
 
@@ -285,6 +291,7 @@ impl<'c> DiceCalculationDelegate<'c> {
                         loaded_modules.clone(),
                         provider,
                     )
+                    .context(buck2_data::ErrorCategory::User)
                     .with_context(|| {
                         DiceCalculationDelegateError::EvalModuleError(starlark_file.to_string())
                     })?;
@@ -371,6 +378,7 @@ impl<'c> DiceCalculationDelegate<'c> {
                         deps.get_loaded_modules(),
                         provider,
                     )
+                    .context(buck2_data::ErrorCategory::User)
                     .with_context(|| format!("evaluating Starlark PACKAGE file `{}`", path))
             },
         )
@@ -500,12 +508,14 @@ impl<'c> DiceCalculationDelegate<'c> {
                             &buckconfig,
                             &root_buckconfig,
                             listing,
+                            GlobResolver::dice(self.ctx),
                             super_package,
                             package_boundary_exception,
                             ast,
                             deps.get_loaded_modules(),
                             provider,
                         )
+                        .context(buck2_data::ErrorCategory::User)
                         .with_context(|| {
                             DiceCalculationDelegateError::EvalBuildFileError(build_file_path)
                         });
@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_common::package_listing::dice::HasPackageListingResolver;
+use buck2_common::package_listing::listing::PackageListing;
+use buck2_common::result::SharedResult;
+use buck2_common::result::ToUnsharedResultExt;
+use buck2_core::package::package_relative_path::PackageRelativePath;
+use buck2_core::package::PackageLabel;
+use buck2_interpreter::globspec::GlobSpec;
+use buck2_util::arc_str::ArcS;
+use derive_more::Display;
+use dice::DiceComputations;
+use dice::Key;
+use dupe::Dupe;
+use more_futures::cancellation::CancellationContext;
+
+/// The arguments of a `glob()` call.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Allocative)]
+pub struct GlobArgs {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub exclude_directories: bool,
+}
+
+/// Evaluate a glob against a package listing. Results are sorted.
+fn glob(
+    listing: &PackageListing,
+    args: &GlobArgs,
+) -> anyhow::Result<Vec<ArcS<PackageRelativePath>>> {
+    let spec = GlobSpec::new(&args.include, &args.exclude)?;
+    let mut res: Vec<_> = spec
+        .resolve_glob(listing.files())
+        .map(PackageRelativePath::to_arc)
+        .collect();
+    if !args.exclude_directories {
+        // Subpackages are not in the listing, so they are never matched.
+        res.extend(
+            listing
+                .directories()
+                .filter(|dir| spec.matches(dir.as_str()))
+                .map(|dir| dir.dupe()),
+        );
+        res.sort();
+    }
+    Ok(res)
+}
+
+#[derive(Clone, Display, Debug, Eq, PartialEq, Hash, Allocative)]
+#[display(
+    fmt = "{}:glob({:?}, exclude = {:?}, exclude_directories = {})",
+    package,
+    "args.include",
+    "args.exclude",
+    "args.exclude_directories"
+)]
+struct GlobKey {
+    package: PackageLabel,
+    args: GlobArgs,
+}
+
+#[async_trait]
+impl Key for GlobKey {
+    type Value = SharedResult<Arc<[ArcS<PackageRelativePath>]>>;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let listing = ctx.resolve_package_listing(self.package.dupe()).await?;
+        Ok(glob(&listing, &self.args)?.into())
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        }
+    }
+}
+
+/// Resolves the `glob()` calls of a build file.
+///
+/// When the build file is evaluated as part of a DICE computation, globs are DICE keys
+/// depending on the package listing, so a build file re-evaluated with an unchanged listing
+/// (e.g. because a `.bzl` file it loads changed) reuses them. Otherwise (e.g. in tests) they
+/// are evaluated against the listing directly.
+#[derive(Clone, Dupe)]
+pub struct GlobResolver {
+    ctx: Option<DiceComputations>,
+}
+
+impl Debug for GlobResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlobResolver")
+            .field("dice", &self.ctx.is_some())
+            .finish()
+    }
+}
+
+impl GlobResolver {
+    pub fn dice(ctx: &DiceComputations) -> Self {
+        Self {
+            ctx: Some(ctx.dupe()),
+        }
+    }
+
+    pub fn listing() -> Self {
+        Self { ctx: None }
+    }
+
+    pub(crate) fn resolve(
+        &self,
+        package: PackageLabel,
+        listing: &PackageListing,
+        args: GlobArgs,
+    ) -> anyhow::Result<Arc<[ArcS<PackageRelativePath>]>> {
+        match &self.ctx {
+            Some(ctx) => {
+                // Build files are evaluated synchronously (on a blocking thread when computed by
+                // `InterpreterResultsKey`), so block while DICE computes the glob, like BXL does
+                // for its DICE calls.
+                let key = GlobKey { package, args };
+                tokio::task::block_in_place(|| {
+                    tokio::runtime::Handle::current().block_on(ctx.compute(&key))
+                })?
+                .unshared_error()
+            }
+            None => Ok(glob(listing, &args)?.into()),
+        }
+    }
+}
@@ -56,6 +56,7 @@ use super::print_handler::EventDispatcherPrintHandler;
 use crate::interpreter::build_context::BuildContext;
 use crate::interpreter::build_context::PerFileTypeContext;
 use crate::interpreter::global_interpreter_state::GlobalInterpreterState;
+use crate::interpreter::globs::GlobResolver;
 use crate::interpreter::module_internals::ModuleInternals;
 use crate::super_package::data::SuperPackage;
 use crate::super_package::eval_ctx::PackageFileEvalCtx;
@@ -310,6 +311,7 @@ impl InterpreterForCell {
         &self,
         build_file: &BuildFilePath,
         package_listing: &PackageListing,
+        globs: GlobResolver,
         super_package: SuperPackage,
         package_boundary_exception: bool,
         loaded_modules: &LoadedModules,
@@ -318,6 +320,7 @@ impl InterpreterForCell {
             self.get_cell_config(build_file.build_file_cell()),
             build_file.clone(),
             package_listing.dupe(),
+            globs,
             super_package,
             package_boundary_exception,
             loaded_modules,
@@ -566,13 +569,21 @@ impl InterpreterForCell {
             .keys()
             .cloned()
             .collect::<Vec<_>>();
+        let default_attr_names = package_values
+            .default_attrs
+            .borrow()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        // Package values followed by default attribute values.
         let values = package_values
             .values
             .borrow()
             .values()
+            .chain(package_values.default_attrs.borrow().values())
             .copied()
             .collect::<Vec<_>>();
-        // Starlark list of strings. We only need to freeze values, not keys.
+        // Starlark list of values. We only need to freeze values, not keys.
         // We could freeze full `PackageValues` but if we do that we:
         // * would have have to implement `Freeze` for `PackageValue`
         // * will store a bit more memory in frozen heap.
@@ -588,17 +599,18 @@ impl InterpreterForCell {
         let values = ListRef::from_frozen_value(values)
             .context("extra_value is not a list (internal error)")?;
 
-        let package_values: SmallMap<String, OwnedFrozenValue> = keys
-            .into_iter()
-            .zip(values.content().map(|v| {
-                let frozen_value = v.unpack_frozen().unwrap();
-                unsafe { OwnedFrozenValue::new(env.frozen_heap().dupe(), frozen_value) }
-            }))
-            .collect();
+        let mut values = values.content().iter().map(|v| {
+            let frozen_value = v.unpack_frozen().unwrap();
+            unsafe { OwnedFrozenValue::new(env.frozen_heap().dupe(), frozen_value) }
+        });
+        let package_values: SmallMap<String, OwnedFrozenValue> =
+            keys.into_iter().zip(values.by_ref()).collect();
+        let default_attrs: SmallMap<String, OwnedFrozenValue> =
+            default_attr_names.into_iter().zip(values).collect();
 
         let package_file_eval_ctx = per_file_context.into_package_file()?;
 
-        Ok(package_file_eval_ctx.build_super_package(package_values))
+        Ok(package_file_eval_ctx.build_super_package(package_values, default_attrs))
     }
 
     /// Evaluates the AST for a parsed build file. Loaded modules must contain the
@@ -610,6 +622,7 @@ impl InterpreterForCell {
         buckconfig: &dyn LegacyBuckConfigView,
         root_buckconfig: &dyn LegacyBuckConfigView,
         listing: PackageListing,
+        globs: GlobResolver,
         super_package: SuperPackage,
         package_boundary_exception: bool,
         ast: AstModule,
@@ -619,6 +632,7 @@ impl InterpreterForCell {
         let (env, internals) = self.create_build_env(
             build_file,
             &listing,
+            globs,
             super_package,
             package_boundary_exception,
             &loaded_modules,
@@ -20,18 +20,20 @@ use buck2_core::bzl::ImportPath;
 use buck2_core::package::package_relative_path::PackageRelativePath;
 use buck2_core::target::name::TargetNameRef;
 use buck2_events::dispatch::console_message;
-use buck2_interpreter::globspec::GlobSpec;
 use buck2_interpreter::package_imports::ImplicitImport;
 use buck2_node::nodes::eval_result::EvaluationResult;
 use buck2_node::nodes::targets_map::TargetsMap;
 use buck2_node::nodes::targets_map::TargetsMapRecordError;
 use buck2_node::nodes::unconfigured::TargetNode;
 use buck2_node::package::Package;
+use buck2_util::arc_str::ArcS;
 use dupe::Dupe;
 use starlark::environment::FrozenModule;
 use starlark::values::OwnedFrozenValue;
 
 use crate::attrs::coerce::ctx::BuildAttrCoercionContext;
+use crate::interpreter::globs::GlobArgs;
+use crate::interpreter::globs::GlobResolver;
 use crate::super_package::data::SuperPackage;
 
 impl From<ModuleInternals> for EvaluationResult {
@@ -86,6 +88,7 @@ pub struct ModuleInternals {
     skip_targets_with_duplicate_names: bool,
     /// The files owned by this directory. Is `None` for .bzl files.
     package_listing: PackageListing,
+    globs: GlobResolver,
     pub(crate) super_package: SuperPackage,
 }
 
@@ -126,6 +129,7 @@ impl ModuleInternals {
         record_target_call_stacks: bool,
         skip_targets_with_duplicate_names: bool,
         package_listing: PackageListing,
+        globs: GlobResolver,
         super_package: SuperPackage,
     ) -> Self {
         Self {
@@ -138,6 +142,7 @@ impl ModuleInternals {
             record_target_call_stacks,
             skip_targets_with_duplicate_names,
             package_listing,
+            globs,
             super_package,
         }
     }
@@ -223,11 +228,12 @@ impl ModuleInternals {
         self.record_target_call_stacks
     }
 
-    pub(crate) fn resolve_glob<'a>(
-        &'a self,
-        spec: &'a GlobSpec,
-    ) -> impl Iterator<Item = &'a PackageRelativePath> {
-        spec.resolve_glob(self.package_listing.files())
+    pub(crate) fn resolve_glob(
+        &self,
+        args: GlobArgs,
+    ) -> anyhow::Result<Arc<[ArcS<PackageRelativePath>]>> {
+        self.globs
+            .resolve(self.buildfile_path.package(), &self.package_listing, args)
     }
 }
 
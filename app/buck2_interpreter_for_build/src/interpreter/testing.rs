@@ -45,6 +45,7 @@ use starlark::values::Value;
 use crate::interpreter::configuror::AdditionalGlobalsFn;
 use crate::interpreter::configuror::BuildInterpreterConfiguror;
 use crate::interpreter::global_interpreter_state::GlobalInterpreterState;
+use crate::interpreter::globs::GlobResolver;
 use crate::interpreter::interpreter_for_cell::InterpreterForCell;
 use crate::interpreter::interpreter_for_cell::ParseResult;
 use crate::super_package::data::SuperPackage;
@@ -311,6 +312,7 @@ impl Tester {
             buckconfig,
             root_buckconfig,
             package_listing,
+            GlobResolver::listing(),
             SuperPackage::default(),
             false,
             ast,
@@ -80,6 +80,20 @@ impl AttributeSpecExt for AttributeSpec {
                 Some(_) => param_parser.next_opt(attr_name)?,
                 None => Some(param_parser.next(attr_name)?),
             };
+            // Attributes not set on the target take the default from `PACKAGE` files, if any.
+            // Defaults for attributes the rule doesn't have are ignored, so that a subtree with
+            // different kinds of rules can share them.
+            let (user_value, from_package) = match user_value {
+                Some(v) => (Some(v), false),
+                None => (
+                    internals
+                        .super_package
+                        .default_attrs()
+                        .get(attr_name)
+                        .map(|v| v.value()),
+                    true,
+                ),
+            };
 
             let is_visibility = attr_name == VISIBILITY_ATTRIBUTE_FIELD;
             if let Some(v) = user_value {
@@ -92,7 +106,12 @@ impl AttributeSpecExt for AttributeSpec {
                     )
                     .with_context(|| {
                         format!(
-                            "Error coercing attribute `{}` of `{}:{}`",
+                            "Error coercing {}attribute `{}` of `{}:{}`",
+                            if from_package {
+                                "`PACKAGE` default of "
+                            } else {
+                                ""
+                            },
                             attr_name,
                             internals.buildfile_path().package(),
                             name,
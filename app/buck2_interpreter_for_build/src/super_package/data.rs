@@ -19,6 +19,7 @@ use starlark_map::small_map::SmallMap;
 #[derive(Default, Debug, Allocative)]
 pub(crate) struct SuperPackageData {
     package_values: SmallMap<String, OwnedFrozenValue>,
+    default_attrs: SmallMap<String, OwnedFrozenValue>,
     visibility: VisibilitySpecification,
     within_view: WithinViewSpecification,
 }
@@ -31,11 +32,13 @@ pub(crate) struct SuperPackage(Arc<SuperPackageData>);
 impl SuperPackage {
     pub(crate) fn new(
         package_values: SmallMap<String, OwnedFrozenValue>,
+        default_attrs: SmallMap<String, OwnedFrozenValue>,
         visibility: VisibilitySpecification,
         within_view: WithinViewSpecification,
     ) -> SuperPackage {
         SuperPackage(Arc::new(SuperPackageData {
             package_values,
+            default_attrs,
             visibility,
             within_view,
         }))
@@ -45,6 +48,11 @@ impl SuperPackage {
         &self.0.package_values
     }
 
+    /// Default attribute values for targets in the package, by attribute name.
+    pub(crate) fn default_attrs(&self) -> &SmallMap<String, OwnedFrozenValue> {
+        &self.0.default_attrs
+    }
+
     pub(crate) fn visibility(&self) -> &VisibilitySpecification {
         &self.0.visibility
     }
@@ -58,19 +66,24 @@ impl PartialEq for SuperPackage {
     fn eq(&self, other: &Self) -> bool {
         let SuperPackageData {
             package_values: this_values,
+            default_attrs: this_default_attrs,
             visibility: this_visibility,
             within_view: this_within_view,
         } = &*self.0;
         let SuperPackageData {
             package_values: other_values,
+            default_attrs: other_default_attrs,
             visibility: other_visibility,
             within_view: other_within_view,
         } = &*other.0;
         (this_visibility, this_within_view) == (other_visibility, other_within_view) && {
-            // If either package values are not empty, we cannot compare them
+            // If either package values or default attributes are not empty, we cannot compare them
             // because we cannot reliably compare arbitrary Starlark values.
-            // So if either package values are not empty, we consider super package not equal.
-            this_values.is_empty() && other_values.is_empty()
+            // So if either are not empty, we consider super package not equal.
+            this_values.is_empty()
+                && other_values.is_empty()
+                && this_default_attrs.is_empty()
+                && other_default_attrs.is_empty()
         }
     }
 }
@@ -35,9 +35,12 @@ impl PackageFileEvalCtx {
     pub(crate) fn build_super_package(
         self,
         package_values: SmallMap<String, OwnedFrozenValue>,
+        default_attrs: SmallMap<String, OwnedFrozenValue>,
     ) -> SuperPackage {
         let mut merged_package_values = self.parent.package_values().clone();
         merged_package_values.extend(package_values);
+        let mut merged_default_attrs = self.parent.default_attrs().clone();
+        merged_default_attrs.extend(default_attrs);
 
         let PackageFileVisibilityFields {
             visibility,
@@ -54,6 +57,11 @@ impl PackageFileEvalCtx {
             (visibility, within_view)
         };
 
-        SuperPackage::new(merged_package_values, visibility, within_view)
+        SuperPackage::new(
+            merged_package_values,
+            merged_default_attrs,
+            visibility,
+            within_view,
+        )
     }
 }
@@ -7,9 +7,12 @@
  * of this source tree.
  */
 
+use anyhow::Context;
 use buck2_core::cells::name::CellName;
 use buck2_core::cells::CellResolver;
 use buck2_core::pattern::ParsedPattern;
+use buck2_node::attrs::internal::NAME_ATTRIBUTE_FIELD;
+use buck2_node::attrs::internal::VISIBILITY_ATTRIBUTE_FIELD;
 use buck2_node::visibility::VisibilityPattern;
 use buck2_node::visibility::VisibilitySpecification;
 use buck2_node::visibility::WithinViewSpecification;
@@ -17,11 +20,15 @@ use buck2_util::arc_str::ThinArcSlice;
 use starlark::environment::GlobalsBuilder;
 use starlark::eval::Evaluator;
 use starlark::starlark_module;
+use starlark::values::dict::DictOf;
 use starlark::values::none::NoneType;
+use starlark::values::Value;
+use starlark::values::ValueLike;
 
 use crate::interpreter::build_context::BuildContext;
 use crate::interpreter::build_context::PerFileTypeContext;
 use crate::super_package::eval_ctx::PackageFileVisibilityFields;
+use crate::super_package::package_value::PackageValues;
 
 #[derive(Debug, thiserror::Error)]
 enum PackageFileError {
@@ -32,6 +39,8 @@ enum PackageFileError {
     NotPackage,
     #[error("`package()` function can be used at most once per `PACKAGE` file")]
     AtMostOnce,
+    #[error("Attribute `{0}` cannot have a default value in `PACKAGE` file")]
+    DefaultAttrNotAllowed(String),
 }
 
 fn parse_visibility(
@@ -85,11 +94,21 @@ fn parse_within_view(
 /// Globals for `PACKAGE` files and `bzl` files included from `PACKAGE` files.
 #[starlark_module]
 pub(crate) fn register_package_function(globals: &mut GlobalsBuilder) {
-    fn package(
+    /// Set visibility of the targets in the package and default values of their attributes.
+    ///
+    /// `default_attrs` provides default values for attributes of all the targets
+    /// in this package and its subpackages which do not set them explicitly,
+    /// e.g. `package(default_attrs = {"labels": ["team:foo"]})`.
+    /// Defaults are merged with those of the parent `PACKAGE` file.
+    /// Defaults for attributes that the rule of a target does not have are ignored.
+    /// Labels in default values are resolved relative to the package of the target,
+    /// so they should be absolute.
+    fn package<'v>(
         #[starlark(require=named, default=false)] inherit: bool,
         #[starlark(require=named, default=Vec::new())] visibility: Vec<String>,
         #[starlark(require=named, default=Vec::new())] within_view: Vec<String>,
-        eval: &mut Evaluator,
+        #[starlark(require=named)] default_attrs: Option<DictOf<'v, &'v str, Value<'v>>>,
+        eval: &mut Evaluator<'v, '_>,
     ) -> anyhow::Result<NoneType> {
         let build_context = BuildContext::from_context(eval)?;
         let package_file_eval_ctx = match &build_context.additional {
@@ -118,6 +137,22 @@ pub(crate) fn register_package_function(globals: &mut GlobalsBuilder) {
             }
         };
 
+        if let Some(default_attrs) = default_attrs {
+            let package_values = eval
+                .module()
+                .extra_value()
+                .context("Module extra value was not set (internal error)")?
+                .downcast_ref::<PackageValues>()
+                .context("Module extra value was not a `PackageValues` (internal error)")?;
+            let mut package_default_attrs = package_values.default_attrs.borrow_mut();
+            for (name, value) in default_attrs.collect_entries() {
+                if name == NAME_ATTRIBUTE_FIELD || name == VISIBILITY_ATTRIBUTE_FIELD {
+                    return Err(PackageFileError::DefaultAttrNotAllowed(name.to_owned()).into());
+                }
+                package_default_attrs.insert(name.to_owned(), value);
+            }
+        }
+
         Ok(NoneType)
     }
 }
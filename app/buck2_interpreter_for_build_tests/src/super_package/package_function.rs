@@ -9,7 +9,10 @@
 
 use buck2_core::fs::project::ProjectRootTemp;
 use buck2_core::target::label::TargetLabel;
+use buck2_node::attrs::display::AttrDisplayWithContextExt;
+use buck2_node::attrs::inspect_options::AttrInspectOptions;
 use buck2_node::nodes::frontend::TargetGraphCalculation;
+use buck2_node::nodes::unconfigured::TargetNode;
 use buck2_node::visibility::VisibilitySpecification;
 
 use crate::tests::calculation;
@@ -141,3 +144,148 @@ simple(name = "a")
         a.visibility().unwrap(),
     );
 }
+
+const DEFAULT_ATTRS_RULES_BZL: &str = r#"
+simple = rule(
+    impl = lambda ctx: fail(),
+    attrs = {
+        "color": attrs.string(default = "none"),
+        "shape": attrs.string(default = "none"),
+    },
+)
+
+shapeless = rule(
+    impl = lambda ctx: fail(),
+    attrs = {
+        "color": attrs.string(default = "none"),
+    },
+)
+"#;
+
+fn attr_value(node: &TargetNode, name: &str) -> String {
+    node.attr(name, AttrInspectOptions::All)
+        .unwrap()
+        .unwrap()
+        .as_display_no_ctx()
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_package_default_attrs() {
+    let fs = ProjectRootTemp::new().unwrap();
+
+    fs.write_file("rules.bzl", DEFAULT_ATTRS_RULES_BZL);
+    fs.write_file(
+        "juxtaposition/PACKAGE",
+        r#"
+package(
+    default_attrs = {"color": "red"},
+)
+"#,
+    );
+    fs.write_file(
+        "juxtaposition/BUCK",
+        r#"
+load("//:rules.bzl", "simple")
+simple(name = "a")
+simple(name = "b", color = "blue")
+"#,
+    );
+
+    let ctx = calculation(&fs).await;
+
+    let a = ctx
+        .get_target_node(&TargetLabel::testing_parse("root//juxtaposition:a"))
+        .await
+        .unwrap();
+    let b = ctx
+        .get_target_node(&TargetLabel::testing_parse("root//juxtaposition:b"))
+        .await
+        .unwrap();
+
+    // Inherited from `PACKAGE`.
+    assert_eq!("\"red\"", attr_value(&a, "color"));
+    assert_eq!("\"none\"", attr_value(&a, "shape"));
+    // Overridden by the target.
+    assert_eq!("\"blue\"", attr_value(&b, "color"));
+}
+
+#[tokio::test]
+async fn test_package_default_attrs_merge_with_parent() {
+    let fs = ProjectRootTemp::new().unwrap();
+
+    fs.write_file("rules.bzl", DEFAULT_ATTRS_RULES_BZL);
+    fs.write_file(
+        "PACKAGE",
+        r#"
+package(
+    default_attrs = {"color": "red", "shape": "circle"},
+)
+"#,
+    );
+    fs.write_file(
+        "juxtaposition/PACKAGE",
+        r#"
+package(
+    default_attrs = {"color": "green"},
+)
+"#,
+    );
+    fs.write_file(
+        "juxtaposition/BUCK",
+        r#"
+load("//:rules.bzl", "simple")
+simple(name = "a")
+"#,
+    );
+
+    let ctx = calculation(&fs).await;
+
+    let a = ctx
+        .get_target_node(&TargetLabel::testing_parse("root//juxtaposition:a"))
+        .await
+        .unwrap();
+
+    assert_eq!("\"green\"", attr_value(&a, "color"));
+    assert_eq!("\"circle\"", attr_value(&a, "shape"));
+}
+
+#[tokio::test]
+async fn test_package_default_attrs_mixed_rules() {
+    let fs = ProjectRootTemp::new().unwrap();
+
+    fs.write_file("rules.bzl", DEFAULT_ATTRS_RULES_BZL);
+    fs.write_file(
+        "juxtaposition/PACKAGE",
+        r#"
+package(
+    default_attrs = {"color": "red", "shape": "square", "flavor": "sour"},
+)
+"#,
+    );
+    fs.write_file(
+        "juxtaposition/BUCK",
+        r#"
+load("//:rules.bzl", "shapeless", "simple")
+simple(name = "a")
+shapeless(name = "b")
+"#,
+    );
+
+    let ctx = calculation(&fs).await;
+
+    let a = ctx
+        .get_target_node(&TargetLabel::testing_parse("root//juxtaposition:a"))
+        .await
+        .unwrap();
+    let b = ctx
+        .get_target_node(&TargetLabel::testing_parse("root//juxtaposition:b"))
+        .await
+        .unwrap();
+
+    assert_eq!("\"red\"", attr_value(&a, "color"));
+    assert_eq!("\"square\"", attr_value(&a, "shape"));
+    // Defaults for attributes the rule doesn't have are ignored.
+    assert_eq!("\"red\"", attr_value(&b, "color"));
+    assert!(b.attr("shape", AttrInspectOptions::All).is_err());
+}
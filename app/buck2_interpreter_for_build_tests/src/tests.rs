@@ -10,6 +10,7 @@
 use buck2_build_api::interpreter::rule_defs::register_rule_defs;
 use buck2_common::dice::cells::SetCellResolver;
 use buck2_common::dice::data::testing::SetTestingIoProvider;
+use buck2_common::error_report::CreateErrorReport;
 use buck2_common::legacy_configs::dice::SetLegacyConfigs;
 use buck2_common::legacy_configs::LegacyBuckConfig;
 use buck2_common::legacy_configs::LegacyBuckConfigs;
@@ -20,6 +21,7 @@ use buck2_core::cells::CellResolver;
 use buck2_core::fs::project::ProjectRootTemp;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::package::PackageLabel;
+use buck2_core::target::name::TargetNameRef;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_interpreter::dice::starlark_debug::SetStarlarkDebugger;
 use buck2_interpreter::dice::starlark_profiler::SetStarlarkProfilerInstrumentation;
@@ -234,3 +236,94 @@ async fn test_eval_build_file() {
 
     assert_eq!(vec!["invoke_some-exported", "java"], target_names);
 }
+
+#[tokio::test]
+async fn test_user_errors() {
+    let fs = ProjectRootTemp::new().unwrap();
+    fs.write_file("syntax/BUCK", "foo(\n");
+    fs.write_file("pkg/BUCK", "");
+
+    let ctx = calculation(&fs).await;
+
+    let category = |err: anyhow::Error| err.create_error_report().unwrap().category;
+    let user = Some(buck2_data::ErrorCategory::User as i32);
+
+    let err = ctx
+        .get_interpreter_results(PackageLabel::testing_parse("root//syntax"))
+        .await
+        .unwrap_err();
+    assert_eq!(category(err), user);
+
+    let err = ctx
+        .get_interpreter_results(PackageLabel::testing_parse("root//missing"))
+        .await
+        .unwrap_err();
+    assert_eq!(category(err), user);
+
+    let eval_result = ctx
+        .get_interpreter_results(PackageLabel::testing_parse("root//pkg"))
+        .await
+        .unwrap();
+    let err = eval_result
+        .resolve_target(TargetNameRef::new("nope").unwrap())
+        .unwrap_err();
+    assert_eq!(category(err), user);
+}
+
+#[tokio::test]
+async fn test_glob_exclude_directories_and_allow_empty() {
+    let fs = ProjectRootTemp::new().unwrap();
+    fs.write_file(
+        "expect.bzl",
+        indoc!(
+            r#"
+            def expect(actual, expected):
+                if actual != expected:
+                    fail("Expected {}, got {}".format(expected, actual))
+            "#
+        ),
+    );
+    fs.write_file("pkg/a.txt", "");
+    fs.write_file("pkg/dir/b.txt", "");
+    fs.write_file("pkg/sub/BUCK", "");
+    fs.write_file("pkg/sub/c.txt", "");
+    fs.write_file(
+        "pkg/BUCK",
+        indoc!(
+            r#"
+            load("@root//:expect.bzl", "expect")
+
+            expect(glob(["**"], exclude = ["BUCK"]), ["a.txt", "dir/b.txt"])
+            expect(
+                glob(["**"], exclude = ["BUCK"], exclude_directories = 0),
+                ["a.txt", "dir", "dir/b.txt"],
+            )
+            expect(glob(["d*"], exclude_directories = 0), ["dir"])
+            expect(glob(["*.java"]), [])
+            "#
+        ),
+    );
+    fs.write_file(
+        "empty/BUCK",
+        indoc!(
+            r#"
+            glob(["*.java"], allow_empty = False)
+            "#
+        ),
+    );
+
+    let ctx = calculation(&fs).await;
+
+    ctx.get_interpreter_results(PackageLabel::testing_parse("root//pkg"))
+        .await
+        .unwrap();
+    let err = ctx
+        .get_interpreter_results(PackageLabel::testing_parse("root//empty"))
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("did not match any files"),
+        "{:?}",
+        err
+    );
+}
@@ -128,10 +128,11 @@ impl CqueryUniverse {
                         })
                     }))
                 }
-                PackageSpec::All => Either::Right(
+                PackageSpec::All | PackageSpec::AllExcept(..) => Either::Right(
                     package_universe
-                        .values()
-                        .flatten()
+                        .iter()
+                        .filter(|(name, _)| !spec.is_excluded(name.as_ref()))
+                        .flat_map(|(_, nodes)| nodes)
                         .map(|node| (&node.0, P::default())),
                 ),
             })
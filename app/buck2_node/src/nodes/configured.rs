@@ -197,6 +197,16 @@ struct ConfiguredNodeAttr {
 impl ConfiguredTargetNode {
     /// Creates a minimal ConfiguredTargetNode. Some operations may unexpectedly fail.
     pub fn testing_new(name: ConfiguredTargetLabel, rule_type: &str) -> Self {
+        Self::testing_new_with_deps(name, rule_type, Vec::new(), Vec::new())
+    }
+
+    /// Like `testing_new`, with the given dependencies.
+    pub fn testing_new_with_deps(
+        name: ConfiguredTargetLabel,
+        rule_type: &str,
+        deps: Vec<ConfiguredTargetNode>,
+        exec_deps: Vec<ConfiguredTargetNode>,
+    ) -> Self {
         use crate::nodes::unconfigured::testing::TargetNodeExt;
 
         let rule_type = RuleType::Starlark(Arc::new(StarlarkRuleType {
@@ -214,8 +224,8 @@ impl ConfiguredTargetNode {
             ),
             OrderedMap::new(),
             execution_platform_resolution,
-            Vec::new(),
-            Vec::new(),
+            deps,
+            exec_deps,
             OrderedMap::new(),
         )
     }
@@ -339,6 +349,13 @@ impl ConfiguredTargetNode {
         self.0.deps.iter().chain(self.0.exec_deps.iter())
     }
 
+    /// Configurations produced by applying the transitions declared on the attributes of this node.
+    pub fn transition_configurations(
+        &self,
+    ) -> impl Iterator<Item = (&Arc<TransitionId>, &Arc<TransitionApplied>)> {
+        self.0.resolved_transition_configurations.iter()
+    }
+
     pub fn toolchain_deps(&self) -> impl Iterator<Item = &ConfiguredTargetNode> {
         // Since we validate that all toolchain dependencies are of kind Toolchain,
         // we can use that to filter the deps.
@@ -13,6 +13,7 @@ use std::fmt::Write;
 use std::sync::Arc;
 
 use allocative::Allocative;
+use anyhow::Context;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::bzl::ImportPath;
 use buck2_core::package::PackageLabel;
@@ -67,14 +68,14 @@ impl MissingTargets {
             self.package.dupe(),
             self.all_target_labels.iter().map(|x| x.name()),
         );
-        EvalulationResultError::UnknownTarget {
+        anyhow::Error::from(EvalulationResultError::UnknownTarget {
             target: target.name().to_owned(),
             package: self.package,
             num_targets: self.num_targets,
             buildfile_path: self.buildfile_path,
             similar_targets,
-        }
-        .into()
+        })
+        .context(buck2_data::ErrorCategory::User)
     }
 
     fn gen_missing_target_warning(mut missing_targets: Vec<TargetLabel>) -> String {
@@ -158,7 +159,7 @@ impl EvaluationResult {
 
     pub fn resolve_target<'a>(&'a self, path: &TargetNameRef) -> anyhow::Result<&'a TargetNode> {
         self.get_target(path).ok_or_else(|| {
-            EvalulationResultError::UnknownTarget {
+            anyhow::Error::from(EvalulationResultError::UnknownTarget {
                 target: path.to_owned(),
                 package: self.package().dupe(),
                 num_targets: self.targets.len(),
@@ -168,8 +169,8 @@ impl EvaluationResult {
                     self.package().dupe(),
                     self.targets.keys(),
                 ),
-            }
-            .into()
+            })
+            .context(buck2_data::ErrorCategory::User)
         })
     }
 
@@ -181,9 +182,12 @@ impl EvaluationResult {
         Option<MissingTargets>,
     ) {
         match spec {
-            PackageSpec::All => {
+            spec @ (PackageSpec::All | PackageSpec::AllExcept(..)) => {
                 let mut label_to_node = BTreeMap::new();
                 for target_info in self.targets().values() {
+                    if spec.is_excluded(target_info.label().name()) {
+                        continue;
+                    }
                     label_to_node.insert(
                         (target_info.label().name().to_owned(), T::default()),
                         target_info.dupe(),
@@ -0,0 +1,289 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use buck2_build_api::actions::query::ActionQueryNode;
+use buck2_build_api::query::bxl::BxlAqueryFunctions;
+use buck2_build_api::query::bxl::NEW_BXL_AQUERY_FUNCTIONS;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_core::target::label::TargetLabel;
+use buck2_query::query::syntax::simple::eval::set::TargetSet;
+use buck2_query::query::syntax::simple::functions::helpers::CapturedExpr;
+use buck2_query::query::syntax::simple::functions::DefaultQueryFunctions;
+use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
+use dice::DiceComputations;
+use dupe::Dupe;
+
+use crate::aquery::environment::AqueryEnvironment;
+use crate::aquery::evaluator::get_dice_aquery_delegate;
+use crate::dice::aquery::DiceAqueryDelegate;
+use crate::uquery::environment::QueryLiterals;
+
+fn aquery_functions<'v>() -> DefaultQueryFunctions<AqueryEnvironment<'v>> {
+    DefaultQueryFunctions::new()
+}
+
+struct BxlAqueryFunctionsImpl<'c> {
+    ctx: &'c DiceComputations,
+    target_platform: Option<TargetLabel>,
+    working_dir: ProjectRelativePathBuf,
+}
+
+impl<'c> BxlAqueryFunctionsImpl<'c> {
+    async fn aquery_delegate(&self) -> anyhow::Result<Arc<DiceAqueryDelegate<'c>>> {
+        get_dice_aquery_delegate(self.ctx, &self.working_dir, self.target_platform.dupe()).await
+    }
+
+    async fn aquery_env(&self) -> anyhow::Result<AqueryEnvironment<'c>> {
+        let dice_aquery_delegate = self.aquery_delegate().await?;
+        Ok(AqueryEnvironment::new(
+            dice_aquery_delegate.dupe(),
+            dice_aquery_delegate,
+        ))
+    }
+}
+
+#[async_trait]
+impl<'c> BxlAqueryFunctions<'c> for BxlAqueryFunctionsImpl<'c> {
+    async fn allpaths(
+        &self,
+        from: &TargetSet<ActionQueryNode>,
+        to: &TargetSet<ActionQueryNode>,
+    ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+        Ok(aquery_functions()
+            .allpaths(&self.aquery_env().await?, from, to)
+            .await?)
+    }
+
+    async fn somepath(
+        &self,
+        from: &TargetSet<ActionQueryNode>,
+        to: &TargetSet<ActionQueryNode>,
+    ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+        Ok(aquery_functions()
+            .somepath(&self.aquery_env().await?, from, to)
+            .await?)
+    }
+
+    async fn deps(
+        &self,
+        targets: &TargetSet<ActionQueryNode>,
+        deps: Option<i32>,
+        captured_expr: Option<&CapturedExpr>,
+    ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+        Ok(aquery_functions()
+            .deps(
+                &self.aquery_env().await?,
+                &DefaultQueryFunctionsModule::new(),
+                targets,
+                deps,
+                captured_expr,
+            )
+            .await?)
+    }
+
+    async fn rdeps(
+        &self,
+        universe: &TargetSet<ActionQueryNode>,
+        targets: &TargetSet<ActionQueryNode>,
+        depth: Option<i32>,
+    ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+        Ok(aquery_functions()
+            .rdeps(&self.aquery_env().await?, universe, targets, depth)
+            .await?)
+    }
+
+    async fn eval_literals(&self, literals: &[&str]) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+        self.aquery_delegate().await?.eval_literals(literals).await
+    }
+}
+
+pub(crate) fn init_new_bxl_aquery_functions() {
+    NEW_BXL_AQUERY_FUNCTIONS.init(|ctx, target_platform, cell_name| {
+        Box::pin(async move {
+            let cell_resolver = ctx.get_cell_resolver().await?;
+            let cell = cell_resolver.get(cell_name)?;
+            // TODO(nga): working as as cell root is not right.
+            //   Should be either the project root or user's current working directory.
+            let working_dir = cell.path().as_project_relative_path().to_buf();
+
+            Result::<Box<dyn BxlAqueryFunctions>, _>::Ok(Box::new(BxlAqueryFunctionsImpl {
+                ctx,
+                target_platform,
+                working_dir,
+            }))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use buck2_artifact::actions::key::ActionKey;
+    use buck2_build_api::actions::query::testing::testing_artifact_fs;
+    use buck2_build_api::actions::query::testing::ActionQueryNodeTestingExt;
+    use buck2_build_api::actions::query::ActionQueryNode;
+    use buck2_core::fs::project::ProjectRootTemp;
+    use buck2_query::query::environment::LabeledNode;
+    use buck2_query::query::syntax::simple::eval::set::TargetSet;
+    use buck2_query::query::syntax::simple::functions::DefaultQueryFunctionsModule;
+    use dupe::Dupe;
+
+    use crate::aquery::bxl::aquery_functions;
+    use crate::aquery::environment::AqueryDelegate;
+    use crate::aquery::environment::AqueryEnvironment;
+    use crate::cquery::environment::CqueryDelegate;
+    use crate::uquery::environment::QueryLiterals;
+
+    struct TestAqueryDelegate {
+        nodes: HashMap<ActionKey, ActionQueryNode>,
+    }
+
+    #[async_trait]
+    impl AqueryDelegate for TestAqueryDelegate {
+        fn cquery_delegate(&self) -> &dyn CqueryDelegate {
+            unimplemented!("not used by graph traversals")
+        }
+
+        async fn get_node(&self, key: &ActionKey) -> anyhow::Result<ActionQueryNode> {
+            self.nodes
+                .get(key)
+                .map(|node| node.dupe())
+                .ok_or_else(|| anyhow::anyhow!("unknown action `{}`", key))
+        }
+    }
+
+    #[async_trait]
+    impl QueryLiterals<ActionQueryNode> for TestAqueryDelegate {
+        async fn eval_literals(
+            &self,
+            _literals: &[&str],
+        ) -> anyhow::Result<TargetSet<ActionQueryNode>> {
+            unimplemented!("not used by graph traversals")
+        }
+    }
+
+    /// `bin` links `lib`, which compiles `gen`'s output; `tool` is unrelated.
+    struct TestGraph {
+        env: AqueryEnvironment<'static>,
+        nodes: HashMap<&'static str, ActionQueryNode>,
+    }
+
+    impl TestGraph {
+        fn new(project_root: &ProjectRootTemp) -> Self {
+            let fs = testing_artifact_fs(project_root.path().dupe());
+            let gen = ActionQueryNode::testing_new("gen", &["gen"], &[], fs.dupe());
+            let lib = ActionQueryNode::testing_new("lib", &["cc", "-c"], &[&gen], fs.dupe());
+            let bin = ActionQueryNode::testing_new("bin", &["ld"], &[&lib], fs.dupe());
+            let tool = ActionQueryNode::testing_new("tool", &["tool"], &[], fs);
+
+            let nodes = HashMap::from([("gen", gen), ("lib", lib), ("bin", bin), ("tool", tool)]);
+            let delegate = Arc::new(TestAqueryDelegate {
+                nodes: nodes
+                    .values()
+                    .map(|node| (node.node_ref().dupe(), node.dupe()))
+                    .collect(),
+            });
+            Self {
+                env: AqueryEnvironment::new(delegate.dupe(), delegate),
+                nodes,
+            }
+        }
+
+        fn set(&self, names: &[&str]) -> TargetSet<ActionQueryNode> {
+            let mut set = TargetSet::new();
+            for name in names {
+                set.insert(self.nodes[*name].dupe());
+            }
+            set
+        }
+    }
+
+    fn names(set: &TargetSet<ActionQueryNode>) -> Vec<String> {
+        let mut names: Vec<_> = set
+            .iter()
+            .map(|node| node.action().identifier().unwrap().to_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_deps() -> anyhow::Result<()> {
+        let project_root = ProjectRootTemp::new()?;
+        let graph = TestGraph::new(&project_root);
+        let module = DefaultQueryFunctionsModule::<AqueryEnvironment>::new();
+
+        let deps = aquery_functions()
+            .deps(&graph.env, &module, &graph.set(&["bin"]), None, None)
+            .await?;
+        assert_eq!(names(&deps), vec!["bin", "gen", "lib"]);
+
+        let deps = aquery_functions()
+            .deps(&graph.env, &module, &graph.set(&["bin"]), Some(1), None)
+            .await?;
+        assert_eq!(names(&deps), vec!["bin", "lib"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rdeps() -> anyhow::Result<()> {
+        let project_root = ProjectRootTemp::new()?;
+        let graph = TestGraph::new(&project_root);
+
+        let rdeps = aquery_functions()
+            .rdeps(
+                &graph.env,
+                &graph.set(&["bin", "tool"]),
+                &graph.set(&["gen"]),
+                None,
+            )
+            .await?;
+        assert_eq!(names(&rdeps), vec!["bin", "gen", "lib"]);
+
+        let rdeps = aquery_functions()
+            .rdeps(
+                &graph.env,
+                &graph.set(&["bin", "tool"]),
+                &graph.set(&["gen"]),
+                Some(1),
+            )
+            .await?;
+        assert_eq!(names(&rdeps), vec!["gen", "lib"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_allpaths_and_somepath() -> anyhow::Result<()> {
+        let project_root = ProjectRootTemp::new()?;
+        let graph = TestGraph::new(&project_root);
+
+        let allpaths = aquery_functions()
+            .allpaths(&graph.env, &graph.set(&["bin"]), &graph.set(&["gen"]))
+            .await?;
+        assert_eq!(names(&allpaths), vec!["bin", "gen", "lib"]);
+
+        let somepath = aquery_functions()
+            .somepath(&graph.env, &graph.set(&["bin"]), &graph.set(&["gen"]))
+            .await?;
+        assert_eq!(names(&somepath), vec!["bin", "gen", "lib"]);
+
+        let allpaths = aquery_functions()
+            .allpaths(&graph.env, &graph.set(&["tool"]), &graph.set(&["gen"]))
+            .await?;
+        assert!(allpaths.is_empty());
+        Ok(())
+    }
+}
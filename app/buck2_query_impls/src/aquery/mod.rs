@@ -9,4 +9,5 @@
 
 pub mod environment;
 pub mod evaluator;
+pub(crate) mod bxl;
 pub(crate) mod find_matching_action;
@@ -97,10 +97,14 @@ pub async fn get_cquery_evaluator<'a, 'c: 'a>(
     ctx: &'c DiceComputations,
     working_dir: &'a ProjectRelativePath,
     global_target_platform: Option<TargetLabel>,
+    modifiers: Vec<TargetLabel>,
     owner_behavior: CqueryOwnerBehavior,
 ) -> anyhow::Result<CqueryEvaluator<'c>> {
-    let dice_query_delegate =
-        Arc::new(get_dice_query_delegate(ctx, working_dir, global_target_platform).await?);
+    let dice_query_delegate = Arc::new(
+        get_dice_query_delegate(ctx, working_dir, global_target_platform)
+            .await?
+            .with_modifiers(modifiers),
+    );
     let functions = DefaultQueryFunctionsModule::new();
     Ok(CqueryEvaluator {
         dice_query_delegate,
@@ -37,6 +37,7 @@ use buck2_core::fs::paths::file_name::FileNameBuf;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::package::PackageLabel;
+use buck2_core::pattern::modifiers::split_modifiers;
 use buck2_core::pattern::pattern_type::ProvidersPatternExtra;
 use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_core::pattern::query_file_literal::parse_query_file_literal;
@@ -58,6 +59,7 @@ use dice::DiceComputations;
 use dupe::Dupe;
 use gazebo::prelude::*;
 use indexmap::indexset;
+use indexmap::IndexMap;
 
 use crate::cquery::environment::CqueryDelegate;
 use crate::uquery::environment::QueryLiterals;
@@ -122,6 +124,11 @@ impl LiteralParser {
         )
     }
 
+    /// Parses a configuration modifier, which must be a target label or a target alias.
+    fn parse_modifier(&self, value: &str) -> anyhow::Result<TargetLabel> {
+        self.parse_target_pattern(value)?.as_target_label(value)
+    }
+
     fn parse_file_literal(&self, literal: &str) -> anyhow::Result<CellPath> {
         parse_query_file_literal(
             literal,
@@ -140,6 +147,8 @@ pub struct DiceQueryDelegate<'c> {
     cell_resolver: CellResolver,
     literal_parser: Arc<LiteralParser>,
     global_target_platform: Option<TargetLabel>,
+    /// Configuration modifiers applied to every target configured by this delegate.
+    modifiers: Vec<TargetLabel>,
     package_boundary_exceptions: Arc<PackageBoundaryExceptions>,
 }
 
@@ -163,6 +172,7 @@ impl<'c> DiceQueryDelegate<'c> {
         Ok(Self {
             ctx,
             global_target_platform,
+            modifiers: Vec::new(),
             cell_resolver: cell_resolver.dupe(),
             literal_parser: Arc::new(LiteralParser {
                 working_dir_abs,
@@ -176,6 +186,13 @@ impl<'c> DiceQueryDelegate<'c> {
         })
     }
 
+    /// Applies the given configuration modifiers (e.g. from `--modifier`) to every target
+    /// configured by this delegate.
+    pub(crate) fn with_modifiers(mut self, modifiers: Vec<TargetLabel>) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
     pub(crate) fn ctx(&self) -> &DiceComputations {
         self.ctx
     }
@@ -263,10 +280,7 @@ impl<'c> CqueryDelegate for DiceQueryDelegate<'c> {
         &self,
         target: &TargetLabel,
     ) -> anyhow::Result<MaybeCompatible<ConfiguredTargetNode>> {
-        let target = self
-            .ctx
-            .get_configured_target(target, self.global_target_platform.as_ref())
-            .await?;
+        let target = self.get_configured_target(target).await?;
         Ok(self.ctx.get_configured_target_node(&target).await?)
     }
 
@@ -294,7 +308,11 @@ impl<'c> CqueryDelegate for DiceQueryDelegate<'c> {
         target: &TargetLabel,
     ) -> anyhow::Result<ConfiguredTargetLabel> {
         self.ctx
-            .get_configured_target(target, self.global_target_platform.as_ref())
+            .get_configured_target_with_modifiers(
+                target,
+                self.global_target_platform.as_ref(),
+                &self.modifiers,
+            )
             .await
     }
 }
@@ -305,14 +323,36 @@ impl<'c> QueryLiterals<ConfiguredTargetNode> for DiceQueryDelegate<'c> {
         &self,
         literals: &[&str],
     ) -> anyhow::Result<TargetSet<ConfiguredTargetNode>> {
-        let parsed_patterns = literals.try_map(|p| self.literal_parser.parse_target_pattern(p))?;
-        load_compatible_patterns(
-            self.ctx,
-            parsed_patterns,
-            self.global_target_platform.dupe(),
-            MissingTargetBehavior::Fail,
-        )
-        .await
+        // Literals may carry their own modifiers (`//foo:bar?linux-arm64`), which are applied
+        // after the global ones. Literals sharing the same modifiers are configured together.
+        let mut patterns_by_modifiers: IndexMap<Vec<TargetLabel>, Vec<_>> = IndexMap::new();
+        for literal in literals {
+            let (pattern, literal_modifiers) = split_modifiers(literal)?;
+            let mut modifiers = self.modifiers.clone();
+            for modifier in literal_modifiers {
+                modifiers.push(self.literal_parser.parse_modifier(modifier)?);
+            }
+            patterns_by_modifiers
+                .entry(modifiers)
+                .or_default()
+                .push(self.literal_parser.parse_target_pattern(pattern)?);
+        }
+
+        let mut target_set = TargetSet::new();
+        for (modifiers, parsed_patterns) in patterns_by_modifiers {
+            target_set.extend(
+                load_compatible_patterns(
+                    self.ctx,
+                    parsed_patterns,
+                    self.global_target_platform.dupe(),
+                    &modifiers,
+                    MissingTargetBehavior::Fail,
+                )
+                .await?
+                .into_iter(),
+            );
+        }
+        Ok(target_set)
     }
 }
 
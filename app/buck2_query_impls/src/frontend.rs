@@ -55,10 +55,17 @@ impl QueryFrontend for QueryFrontendImpl {
         query: &str,
         query_args: &[String],
         global_target_platform: Option<TargetLabel>,
+        modifiers: Vec<TargetLabel>,
         target_universe: Option<&[String]>,
     ) -> anyhow::Result<QueryEvaluationResult<ConfiguredTargetNode>> {
-        let evaluator =
-            get_cquery_evaluator(ctx, working_dir, global_target_platform, owner_behavior).await?;
+        let evaluator = get_cquery_evaluator(
+            ctx,
+            working_dir,
+            global_target_platform,
+            modifiers,
+            owner_behavior,
+        )
+        .await?;
 
         // TODO(nga): this should support configured target patterns
         //   similarly to what we do for `build` command.
@@ -90,8 +97,11 @@ impl QueryFrontend for QueryFrontendImpl {
         cwd: &ProjectRelativePath,
         literals: &[String],
         global_target_platform: Option<TargetLabel>,
+        modifiers: Vec<TargetLabel>,
     ) -> anyhow::Result<CqueryUniverse> {
-        let query_delegate = get_dice_query_delegate(ctx, cwd, global_target_platform).await?;
+        let query_delegate = get_dice_query_delegate(ctx, cwd, global_target_platform)
+            .await?
+            .with_modifiers(modifiers);
         Ok(
             preresolve_literals_and_build_universe(&query_delegate, literals)
                 .await?
@@ -66,6 +66,7 @@ use nom::character::complete::multispace0;
 use nom::character::complete::multispace1;
 use nom::combinator::all_consuming;
 use nom::combinator::cut;
+use nom::combinator::opt;
 use nom::combinator::recognize;
 use nom::error::context;
 use nom::error::convert_error;
@@ -74,6 +75,7 @@ use nom::error::VerboseError;
 use nom::multi::many0;
 use nom::multi::many1;
 use nom::multi::separated_list0;
+use nom::multi::separated_list1;
 use nom::sequence::delimited;
 use nom::sequence::pair;
 use nom::sequence::preceded;
@@ -353,7 +355,17 @@ fn expr_int<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, SpannedE
 
 fn word<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Span<'a>, E> {
     fn non_quoted_word<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Span<'a>, E> {
-        recognize(many1(alt((alphanumeric1, is_a("*/@.-_:$#%")))))(input)
+        fn chars<'a, E: NomParseError<'a>>(input: Span<'a>) -> NomResult<'a, Span<'a>, E> {
+            recognize(many1(alt((alphanumeric1, is_a("*/@.-_:$#%")))))(input)
+        }
+
+        // Target patterns may be followed by configuration modifiers, e.g.
+        // `//foo:bar?linux-arm64` or `//foo:bar?//os:linux,//cpu:arm64`. A `,` is only part of
+        // the word between two modifiers, so that it still separates function arguments.
+        recognize(pair(
+            chars,
+            opt(pair(char('?'), separated_list1(char(','), chars))),
+        ))(input)
     }
 
     alt((
@@ -539,6 +551,26 @@ mod tests {
             v => panic!("expected '//:tgt', got `{:?}`", v),
         }
 
+        match parse_expr("deps(//foo:bar?linux-arm64, 1)") {
+            Ok(Spanned {
+                value: Expr::Function { args, .. },
+                ..
+            }) if matches!(
+                args.as_slice(),
+                [
+                    Spanned {
+                        value: Expr::String("//foo:bar?linux-arm64"),
+                        ..
+                    },
+                    Spanned {
+                        value: Expr::Integer(1),
+                        ..
+                    },
+                ]
+            ) => {}
+            v => panic!("expected function with modifiers in args, got `{:?}`", v),
+        }
+
         Ok(())
     }
 
@@ -612,9 +644,11 @@ mod tests {
                 r#""double^  -quoted""#,
                 "%s",
                 "%Ss",
+                "//foo:bar?linux-arm64",
+                "//foo:bar?//os:linux,//cpu:arm64",
             ],
             // there's not a lot of errors
-            &["^word", "", ",word"],
+            &["^word", "", ",word", "?word"],
             // unfinished quotes are unrecoverable.
             &["'unfinished quote", "'wrong quote end\""],
         );
@@ -19,6 +19,8 @@ static BUCK2_RE_CLIENT_CFG_SECTION: &str = "buck2_re_client";
 pub trait RemoteExecutionStaticMetadataImpl: Sized {
     fn from_legacy_config(legacy_config: &LegacyBuckConfig) -> anyhow::Result<Self>;
     fn cas_semaphore_size(&self) -> usize;
+    /// Whether actions can be executed remotely. If not, the remote is only used as a cache.
+    fn execution_enabled(&self) -> bool;
 }
 
 #[allow(unused)]
@@ -103,6 +105,10 @@ mod fbcode {
         fn cas_semaphore_size(&self) -> usize {
             self.cas_connection_count as usize * 30
         }
+
+        fn execution_enabled(&self) -> bool {
+            true
+        }
     }
 }
 
@@ -125,6 +131,10 @@ mod not_fbcode {
             // FIXME: make this configurable?
             1024
         }
+
+        fn execution_enabled(&self) -> bool {
+            self.0.engine_address.is_some()
+        }
     }
 }
 
@@ -133,11 +143,16 @@ mod not_fbcode {
 #[derive(Clone, Debug, Default, Allocative)]
 pub struct Buck2OssReConfiguration {
     /// Address for RBE Content Addresable Storage service (including bytestream uploads service).
+    /// This and the other addresses may also be a unix domain socket, e.g. `unix:///path/to/socket`.
     pub cas_address: Option<String>,
-    /// Address for RBE Engine service (including capabilities service).
+    /// Address for RBE Engine service (including capabilities service). If unset, the remote is
+    /// only used as a cache and actions run locally.
     pub engine_address: Option<String>,
     /// Address for RBE Action Cache service.
     pub action_cache_address: Option<String>,
+    /// Address for the Remote Asset API's Fetch service. Unlike the other services, this doesn't
+    /// default to `address`, since few RBE backends implement it.
+    pub remote_asset_address: Option<String>,
     /// Whether to use TLS to interact with remote execution.
     pub tls: bool,
     /// Path to a CA certificates bundle. This must be PEM-encoded. If none is set, a default
@@ -162,6 +177,14 @@ pub struct Buck2OssReConfiguration {
     pub capabilities: Option<bool>,
     /// The instance name to use in requests.
     pub instance_name: Option<String>,
+    /// Whether to compress blobs transferred with the CAS. Compression is only used if the RBE
+    /// backend advertises support for zstd in its capabilities.
+    pub compression: bool,
+    /// How many times to retry RPCs that fail with a transient error such as `UNAVAILABLE`. This
+    /// also bounds how many times an interrupted execution is resumed with `WaitExecution`.
+    pub max_retries: usize,
+    /// Delay before the first retry, in milliseconds. It doubles with every retry, with jitter.
+    pub retry_backoff_ms: u64,
 }
 
 #[derive(Clone, Debug, Default, Allocative)]
@@ -205,6 +228,8 @@ impl Buck2OssReConfiguration {
             action_cache_address: legacy_config
                 .parse(BUCK2_RE_CLIENT_CFG_SECTION, "action_cache_address")?
                 .or(default_address),
+            remote_asset_address: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "remote_asset_address")?,
             tls: legacy_config
                 .parse(BUCK2_RE_CLIENT_CFG_SECTION, "tls")?
                 .unwrap_or(true),
@@ -215,6 +240,15 @@ impl Buck2OssReConfiguration {
                 .unwrap_or_default(), // Empty list is as good None.
             capabilities: legacy_config.parse(BUCK2_RE_CLIENT_CFG_SECTION, "capabilities")?,
             instance_name: legacy_config.parse(BUCK2_RE_CLIENT_CFG_SECTION, "instance_name")?,
+            compression: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "compression")?
+                .unwrap_or(false),
+            max_retries: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "max_retries")?
+                .unwrap_or(3),
+            retry_backoff_ms: legacy_config
+                .parse(BUCK2_RE_CLIENT_CFG_SECTION, "retry_backoff_ms")?
+                .unwrap_or(1000),
         })
     }
 }
@@ -79,7 +79,9 @@ use buck2_execute::re::client::RemoteExecutionClient;
 use buck2_execute::re::manager::ReConnectionHandle;
 use buck2_execute::re::manager::ReConnectionManager;
 use buck2_execute::re::manager::ReConnectionObserver;
+use buck2_execute_impl::executors::determinism::DeterminismCheckOptions;
 use buck2_execute_impl::low_pass_filter::LowPassFilter;
+use buck2_execute_impl::materializers::deferred::in_flight::InFlightCommandGuard;
 use buck2_forkserver::client::ForkserverClient;
 use buck2_interpreter::dice::starlark_debug::SetStarlarkDebugger;
 use buck2_interpreter::dice::starlark_profiler::StarlarkProfilerConfiguration;
@@ -156,6 +158,8 @@ pub struct BaseServerCommandContext {
     pub events: EventDispatcher,
     /// Removes this command from the set of active commands when dropped.
     pub _drop_guard: ActiveCommandDropGuard,
+    /// Keeps the artifacts this command uses from being evicted until it is dropped.
+    pub _in_flight_command: InFlightCommandGuard,
     /// The file watcher that keeps buck2 up to date with disk changes.
     pub file_watcher: Arc<dyn FileWatcher>,
     /// Whether or not to hash all commands
@@ -231,6 +235,9 @@ pub struct ServerCommandContext<'a> {
     cancellations: &'a CancellationContext,
 
     exit_when_different_state: bool,
+
+    /// Whether the client shows the output of local commands while they run.
+    local_command_output: bool,
 }
 
 impl<'a> ServerCommandContext<'a> {
@@ -336,6 +343,7 @@ impl<'a> ServerCommandContext<'a> {
             debugger_handle,
             cancellations,
             exit_when_different_state: client_context.exit_when_different_state,
+            local_command_output: client_context.local_command_output,
         })
     }
 
@@ -360,6 +368,16 @@ impl<'a> ServerCommandContext<'a> {
             .map(|opts| opts.skip_cache_write)
             .unwrap_or_default();
 
+        let check_determinism = self
+            .build_options
+            .as_ref()
+            .filter(|opts| opts.check_determinism)
+            .map(|opts| {
+                Arc::new(DeterminismCheckOptions {
+                    categories: opts.check_determinism_categories.clone(),
+                })
+            });
+
         let mut run_action_knobs = RunActionKnobs {
             hash_all_commands: self.base_context.hash_all_commands,
             use_network_action_output_cache: self.base_context.use_network_action_output_cache,
@@ -407,6 +425,7 @@ impl<'a> ServerCommandContext<'a> {
             upload_all_actions,
             skip_cache_read,
             skip_cache_write,
+            check_determinism,
             create_unhashed_symlink_lock,
             starlark_debugger: self.debugger_handle.dupe(),
             keep_going: self
@@ -509,6 +528,7 @@ struct DiceCommandDataProvider {
     run_action_knobs: RunActionKnobs,
     skip_cache_read: bool,
     skip_cache_write: bool,
+    check_determinism: Option<Arc<DeterminismCheckOptions>>,
     create_unhashed_symlink_lock: Arc<Mutex<()>>,
     starlark_debugger: Option<BuckStarlarkDebuggerHandle>,
     keep_going: bool,
@@ -545,7 +565,10 @@ impl DiceDataProvider for DiceCommandDataProvider {
             .unwrap_or_else(RolloutPercentage::always)
             .roll();
 
-        let executor_global_knobs = ExecutorGlobalKnobs { enable_miniperf };
+        let executor_global_knobs = ExecutorGlobalKnobs {
+            enable_miniperf,
+            forward_local_command_output: self.local_command_output,
+        };
 
         let host_sharing_broker =
             HostSharingBroker::new(HostSharingStrategy::SmallerTasksFirst, concurrency);
@@ -581,6 +604,10 @@ impl DiceDataProvider for DiceCommandDataProvider {
             run_action_knobs.enforce_re_timeouts = enforce_re_timeouts;
         }
 
+        run_action_knobs.download_file_via_remote_asset = root_config
+            .parse::<bool>("buck2", "download_file_via_remote_asset")?
+            .unwrap_or(false);
+
         let mut data = UserComputationData {
             data,
             tracker: Arc::new(BuckDiceTracker::new(self.events.dupe())),
@@ -603,6 +630,7 @@ impl DiceDataProvider for DiceCommandDataProvider {
             self.forkserver.dupe(),
             self.skip_cache_read,
             self.skip_cache_write,
+            self.check_determinism.dupe(),
             ctx.global_data()
                 .get_io_provider()
                 .project_root()
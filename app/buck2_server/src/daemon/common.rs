@@ -35,6 +35,7 @@ use buck2_execute::knobs::ExecutorGlobalKnobs;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute::re::manager::ReConnectionHandle;
 use buck2_execute_impl::executors::caching::CachingExecutor;
+use buck2_execute_impl::executors::determinism::DeterminismCheckOptions;
 use buck2_execute_impl::executors::hybrid::HybridExecutor;
 use buck2_execute_impl::executors::local::LocalExecutor;
 use buck2_execute_impl::executors::re::ReExecutor;
@@ -73,6 +74,8 @@ pub struct CommandExecutorFactory {
     pub forkserver: Option<ForkserverClient>,
     pub skip_cache_read: bool,
     pub skip_cache_write: bool,
+    /// When set, all actions run locally without caching, and some of them run twice.
+    pub check_determinism: Option<Arc<DeterminismCheckOptions>>,
     project_root: ProjectRoot,
 }
 
@@ -89,6 +92,7 @@ impl CommandExecutorFactory {
         forkserver: Option<ForkserverClient>,
         skip_cache_read: bool,
         skip_cache_write: bool,
+        check_determinism: Option<Arc<DeterminismCheckOptions>>,
         project_root: ProjectRoot,
     ) -> Self {
         Self {
@@ -103,6 +107,7 @@ impl CommandExecutorFactory {
             forkserver,
             skip_cache_read,
             skip_cache_write,
+            check_determinism,
             project_root,
         }
     }
@@ -123,9 +128,24 @@ impl HasCommandExecutor for CommandExecutorFactory {
                 self.project_root.root().to_owned(),
                 self.forkserver.dupe(),
                 self.executor_global_knobs.dupe(),
+                self.check_determinism.dupe(),
             )
         };
 
+        if self.check_determinism.is_some() {
+            if self.strategy.ban_local() {
+                return Err(anyhow::anyhow!(
+                    "The desired execution strategy (`{:?}`) is incompatible with checking determinism, which executes actions locally",
+                    self.strategy,
+                ));
+            }
+
+            return Ok(CommandExecutorResponse {
+                executor: Arc::new(local_executor_new(&LocalExecutorOptions::default())),
+                platform: Default::default(),
+            });
+        }
+
         if !buck2_core::is_open_source() && !cfg!(fbcode_build) {
             static WARN: OnceCell<()> = OnceCell::new();
             WARN.get_or_init(|| {
@@ -186,6 +206,34 @@ impl HasCommandExecutor for CommandExecutorFactory {
                 cache_upload_behavior,
                 remote_cache_enabled,
             } => {
+                // Without a remote execution engine, the remote is only used as a cache: actions
+                // run locally and their results are uploaded for other builds to reuse.
+                let cache_only = !self.re_connection.execution_enabled();
+                let cache_only_executor;
+                let (executor, cache_upload_behavior) = if cache_only {
+                    if self.strategy.ban_local() {
+                        return Err(anyhow::anyhow!(
+                            "The desired execution strategy (`{:?}`) requires remote execution, but no `engine_address` is configured",
+                            self.strategy,
+                        ));
+                    }
+
+                    cache_only_executor = RemoteEnabledExecutor::Local(match executor {
+                        RemoteEnabledExecutor::Local(local)
+                        | RemoteEnabledExecutor::Hybrid { local, .. } => local.dupe(),
+                        RemoteEnabledExecutor::Remote(_) => LocalExecutorOptions::default(),
+                    });
+                    let cache_upload_behavior = match cache_upload_behavior {
+                        CacheUploadBehavior::Disabled if !self.skip_cache_write => {
+                            CacheUploadBehavior::Enabled { max_bytes: None }
+                        }
+                        behavior => *behavior,
+                    };
+                    (&cache_only_executor, cache_upload_behavior)
+                } else {
+                    (executor, *cache_upload_behavior)
+                };
+
                 let inner_executor: Option<Arc<dyn PreparedCommandExecutor>> = match &executor {
                     RemoteEnabledExecutor::Local(local) if !self.strategy.ban_local() => {
                         Some(Arc::new(local_executor_new(local)))
@@ -233,7 +281,7 @@ impl HasCommandExecutor for CommandExecutorFactory {
                             re_use_case: *re_use_case,
                             upload_all_actions: self.upload_all_actions,
                             knobs: self.executor_global_knobs.dupe(),
-                            cache_upload_behavior: *cache_upload_behavior,
+                            cache_upload_behavior,
                         }) as _
                     })
                 };
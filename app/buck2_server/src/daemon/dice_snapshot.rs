@@ -0,0 +1,139 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Carrying the DICE graph over daemon restarts when `buck2.dice_snapshot` is enabled.
+//!
+//! The daemon writes a snapshot on a graceful shutdown, together with the file watcher's token
+//! for what DICE was last synced to. The next daemon reads (and deletes) it on startup, and only
+//! restores it if the cells are unchanged and its file watcher can resume from that token, so
+//! that the first sync invalidates whatever changed in between.
+
+use std::io::BufWriter;
+use std::io::Write;
+
+use allocative::Allocative;
+use anyhow::Context as _;
+use buck2_build_api::configure_dice::dice_snapshot_registry;
+use buck2_common::invocation_paths::InvocationPaths;
+use buck2_core::cells::CellResolver;
+use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_core::fs::paths::forward_rel_path::ForwardRelativePath;
+use dice::Dice;
+use dice::DiceSnapshot;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::file_watcher::FileWatcher;
+
+/// The validation token of the snapshots written by the daemon.
+#[derive(Serialize, Deserialize)]
+struct DiceSnapshotToken {
+    cells: String,
+    file_watcher: String,
+}
+
+#[derive(Allocative)]
+pub(crate) struct DiceSnapshotter {
+    dir: AbsNormPathBuf,
+    /// The cell names and paths, since restored keys refer to files by cell.
+    cells: String,
+}
+
+impl DiceSnapshotter {
+    pub(crate) fn new(paths: &InvocationPaths, cells: &CellResolver) -> Self {
+        let mut cells: Vec<String> = cells
+            .cells()
+            .map(|(name, instance)| format!("{}={}", name, instance.path()))
+            .collect();
+        cells.sort();
+
+        Self {
+            dir: paths.dice_snapshot_path(),
+            cells: cells.join(","),
+        }
+    }
+
+    fn path(&self) -> AbsNormPathBuf {
+        self.dir
+            .join(ForwardRelativePath::unchecked_new("snapshot"))
+    }
+
+    /// Reads the snapshot written by the previous daemon, if it can be restored. This resumes the
+    /// file watcher, so it must be called before the file watcher is first synced.
+    pub(crate) fn read(&self, file_watcher: &dyn FileWatcher) -> Option<DiceSnapshot> {
+        match self.read_impl(file_watcher) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                tracing::warn!("Error reading DICE snapshot: {:#}", e);
+                None
+            }
+        }
+    }
+
+    fn read_impl(&self, file_watcher: &dyn FileWatcher) -> anyhow::Result<Option<DiceSnapshot>> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        // The snapshot is only valid for the daemon that directly follows the one that wrote it.
+        let bytes = fs_util::read(&path)?;
+        fs_util::remove_file(&path)?;
+
+        let snapshot = DiceSnapshot::read(bytes.as_slice()).context("Reading snapshot")?;
+        let token: DiceSnapshotToken =
+            serde_json::from_str(snapshot.validation_token()).context("Reading snapshot token")?;
+
+        if token.cells != self.cells {
+            tracing::info!("Not restoring DICE snapshot: cells have changed");
+            return Ok(None);
+        }
+        if !file_watcher.resume(&token.file_watcher)? {
+            tracing::info!("Not restoring DICE snapshot: the file watcher can't resume");
+            return Ok(None);
+        }
+
+        Ok(Some(snapshot))
+    }
+
+    /// Writes a snapshot of `dice` for the next daemon. Nothing is written if the file watcher
+    /// can't be resumed, since the snapshot could never be validated.
+    pub(crate) async fn write(
+        &self,
+        dice: &Dice,
+        file_watcher: &dyn FileWatcher,
+    ) -> anyhow::Result<()> {
+        let file_watcher_token = match file_watcher.snapshot_token().await? {
+            Some(token) => token,
+            None => return Ok(()),
+        };
+        let token = serde_json::to_string(&DiceSnapshotToken {
+            cells: self.cells.clone(),
+            file_watcher: file_watcher_token,
+        })?;
+
+        let snapshot = dice.snapshot(&dice_snapshot_registry(), token).await?;
+
+        // Write to a temporary file first so that a daemon killed mid-write doesn't leave a
+        // truncated snapshot behind.
+        fs_util::create_dir_all(&self.dir)?;
+        let tmp = self
+            .dir
+            .join(ForwardRelativePath::unchecked_new("snapshot.tmp"));
+        let mut writer = BufWriter::new(fs_util::create_file(&tmp)?);
+        snapshot.write(&mut writer)?;
+        writer.flush().context("Flushing snapshot")?;
+        drop(writer);
+        fs_util::rename(&tmp, self.path())?;
+
+        tracing::info!("Wrote {} DICE nodes to snapshot", snapshot.len());
+        Ok(())
+    }
+}
@@ -26,6 +26,7 @@ use buck2_execute_impl::materializers::deferred::DeferredMaterializerConfigs;
 use buck2_execute_impl::materializers::sqlite::MaterializerState;
 use buck2_execute_impl::materializers::sqlite::MaterializerStateSqliteDb;
 use buck2_execute_impl::materializers::sqlite::DB_SCHEMA_VERSION;
+use buck2_execute_impl::materializers::sqlite::DB_SCHEMA_VERSION_KEY;
 
 use crate::daemon::server::BuckdServerInitPreferences;
 
@@ -76,7 +77,10 @@ pub(crate) async fn maybe_initialize_materializer_sqlite_db(
     let metadata = buck2_events::metadata::collect();
 
     let mut versions = HashMap::from([
-        ("schema_version".to_owned(), DB_SCHEMA_VERSION.to_string()),
+        (
+            DB_SCHEMA_VERSION_KEY.to_owned(),
+            DB_SCHEMA_VERSION.to_string(),
+        ),
         (
             "defer_write_actions".to_owned(),
             deferred_materializer_configs
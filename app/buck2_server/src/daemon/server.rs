@@ -29,6 +29,7 @@ use buck2_build_api::spawner::BuckSpawner;
 use buck2_cli_proto::daemon_api_server::*;
 use buck2_cli_proto::*;
 use buck2_common::buckd_connection::BUCK_AUTH_TOKEN_HEADER;
+use buck2_common::error_report::CreateErrorReport;
 use buck2_common::events::HasEvents;
 use buck2_common::invocation_paths::InvocationPaths;
 use buck2_common::io::trace::TracingIoProvider;
@@ -53,6 +54,7 @@ use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 use dice::DetectCycles;
 use dice::Dice;
+use dice::DiceSnapshot;
 use dice::WhichDice;
 use dupe::Dupe;
 use futures::channel::mpsc;
@@ -85,9 +87,11 @@ use crate::ctx::ServerCommandContext;
 use crate::daemon::multi_event_stream::MultiEventStream;
 use crate::daemon::server_allocative::spawn_allocative;
 use crate::daemon::state::DaemonState;
+use crate::explain_recompute::explain_recompute_command;
 use crate::file_status::file_status_command;
 use crate::lsp::run_lsp_server_command;
 use crate::materialize::materialize_command;
+use crate::materializer_stats::materializer_stats_command;
 use crate::snapshot;
 use crate::starlark_debug::run::run_dap_server_command;
 use crate::streaming_request_handler::StreamingRequestHandler;
@@ -147,6 +151,7 @@ impl BuckdServerInitPreferences {
         io: Arc<dyn IoProvider>,
         digest_config: DigestConfig,
         root_config: &LegacyBuckConfig,
+        snapshot: Option<DiceSnapshot>,
     ) -> anyhow::Result<Arc<Dice>> {
         configure_dice_for_buck(
             io,
@@ -154,6 +159,7 @@ impl BuckdServerInitPreferences {
             Some(root_config),
             self.detect_cycles,
             self.which_dice,
+            snapshot,
         )
         .await
     }
@@ -327,6 +333,7 @@ impl BuckdServer {
         let (command_channel, command_receiver): (UnboundedSender<()>, _) = mpsc::unbounded();
 
         let daemon_state = Arc::new(DaemonState::new(fb, paths, init_ctx).await);
+        let snapshot_daemon_state = daemon_state.dupe();
 
         let auth_token = process_info.auth_token.clone();
         let api_server = BuckdServer(Arc::new(BuckdServerData {
@@ -356,6 +363,9 @@ impl BuckdServer {
 
         server.await?;
 
+        // Only reached on a graceful shutdown, once in-flight commands have finished.
+        snapshot_daemon_state.write_dice_snapshot().await;
+
         Ok(())
     }
 
@@ -571,9 +581,13 @@ fn convert_positive_duration(proto_duration: &prost_types::Duration) -> Result<D
 
 fn error_to_command_result(e: anyhow::Error) -> CommandResult {
     let messages = vec![format!("{:?}", e)];
+    let error_reports = e.create_error_report().into_iter().collect();
 
     CommandResult {
-        result: Some(command_result::Result::Error(CommandError { messages })),
+        result: Some(command_result::Result::Error(CommandError {
+            messages,
+            error_reports,
+        })),
     }
 }
 
@@ -1402,6 +1416,36 @@ impl DaemonApi for BuckdServer {
         )
         .await
     }
+
+    type MaterializerStatsStream = ResponseStream;
+    async fn materializer_stats(
+        &self,
+        req: Request<MaterializerStatsRequest>,
+    ) -> Result<Response<ResponseStream>, Status> {
+        self.run_streaming(
+            req,
+            DefaultCommandOptions,
+            |context, partial_result_dispatcher, req| {
+                materializer_stats_command(context, partial_result_dispatcher, req).boxed()
+            },
+        )
+        .await
+    }
+
+    type ExplainRecomputeStream = ResponseStream;
+    async fn explain_recompute(
+        &self,
+        req: Request<ExplainRecomputeRequest>,
+    ) -> Result<Response<ResponseStream>, Status> {
+        self.run_streaming(
+            req,
+            DefaultCommandOptions,
+            |context, partial_result_dispatcher, req| {
+                explain_recompute_command(context, partial_result_dispatcher, req).boxed()
+            },
+        )
+        .await
+    }
 }
 
 /// Options to configure the execution of a oneshot command (i.e. what happens in `oneshot()`).
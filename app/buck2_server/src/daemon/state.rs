@@ -31,10 +31,13 @@ use buck2_core::cells::name::CellName;
 use buck2_core::env_helper::EnvHelper;
 use buck2_core::facebook_only;
 use buck2_core::fs::fs_util;
+use buck2_core::fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
 use buck2_core::rollout_percentage::RolloutPercentage;
 use buck2_core::tag_result;
+use buck2_credential_helper::CredentialHelper;
+use buck2_credential_helper::ScopedCredentialHelper;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_events::sink::scribe;
 use buck2_events::sink::tee::TeeSink;
@@ -46,10 +49,15 @@ use buck2_execute::execute::blocking::BuckBlockingExecutor;
 use buck2_execute::materialize::materializer::MaterializationMethod;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute::re::manager::ReConnectionManager;
+use buck2_execute_impl::materializers::blob_cache::BlobCacheConfiguration;
+use buck2_execute_impl::materializers::blob_cache::BlobCacheLinkMode;
+use buck2_execute_impl::materializers::deferred::in_flight::InFlightCommands;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializer;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializerConfigs;
+use buck2_execute_impl::materializers::deferred::DiskBudgetConfiguration;
 use buck2_execute_impl::materializers::deferred::TtlRefreshConfiguration;
 use buck2_execute_impl::materializers::immediate::ImmediateMaterializer;
+use buck2_execute_impl::materializers::io::LocalCopyMethod;
 use buck2_execute_impl::materializers::sqlite::MaterializerState;
 use buck2_execute_impl::materializers::sqlite::MaterializerStateIdentity;
 use buck2_execute_impl::materializers::sqlite::MaterializerStateSqliteDb;
@@ -70,6 +78,7 @@ use tokio::sync::Mutex;
 use crate::active_commands::ActiveCommandDropGuard;
 use crate::ctx::BaseServerCommandContext;
 use crate::daemon::check_working_dir;
+use crate::daemon::dice_snapshot::DiceSnapshotter;
 use crate::daemon::disk_state::delete_unknown_disk_state;
 use crate::daemon::disk_state::maybe_initialize_materializer_sqlite_db;
 use crate::daemon::disk_state::DiskStateOptions;
@@ -103,6 +112,9 @@ pub struct DaemonStateData {
     /// Synced every time we run a command.
     file_watcher: Arc<dyn FileWatcher>,
 
+    /// Writes the DICE graph to disk on shutdown, if `buck2.dice_snapshot` is enabled.
+    dice_snapshotter: Option<DiceSnapshotter>,
+
     /// Settled every time we run a command.
     pub io: Arc<dyn IoProvider>,
 
@@ -161,6 +173,9 @@ pub struct DaemonStateData {
 
     /// Are we using buck-out as our cwd?
     pub cwd_buck_out: bool,
+
+    /// Commands currently running, whose artifacts the materializer must not evict.
+    pub in_flight_commands: Arc<InFlightCommands>,
 }
 
 impl DaemonStateData {
@@ -286,6 +301,7 @@ impl DaemonState {
         let cache_dir_path = paths.cache_dir_path();
         let valid_cache_dirs = paths.valid_cache_dirs();
         let fs_duped = fs.dupe();
+        let in_flight_commands = Arc::new(InFlightCommands::default());
 
         let deferred_materializer_configs = {
             let defer_write_actions = root_config
@@ -308,6 +324,44 @@ impl DaemonState {
                 .unwrap_or_else(RolloutPercentage::never)
                 .roll();
 
+            // When set, least recently used artifacts are evicted from buck-out to keep it
+            // under this size.
+            let disk_budget = root_config
+                .parse::<u64>("buck2", "buck_out_max_bytes")?
+                .map(|max_bytes| {
+                    let frequency = root_config
+                        .parse("buck2", "buck_out_eviction_frequency_seconds")?
+                        .unwrap_or(600);
+                    anyhow::Ok(DiskBudgetConfiguration {
+                        max_bytes,
+                        frequency: std::time::Duration::from_secs(frequency),
+                        in_flight_commands: in_flight_commands.dupe(),
+                    })
+                })
+                .transpose()?;
+
+            // A directory shared by all the checkouts on the machine, where blobs downloaded from
+            // CAS are cached so that other checkouts don't download them again.
+            let blob_cache = root_config
+                .get("buck2", "blob_cache_dir")
+                .map(|root| {
+                    anyhow::Ok(BlobCacheConfiguration {
+                        root: AbsNormPathBuf::try_from(root.to_owned())?,
+                        max_bytes: root_config.parse("buck2", "blob_cache_max_bytes")?,
+                        link_mode: root_config
+                            .parse("buck2", "blob_cache_link_mode")?
+                            .unwrap_or(BlobCacheLinkMode::Reflink),
+                    })
+                })
+                .transpose()?;
+
+            // Local copies are cloned or copied by default. Hardlinking them is cheaper, but
+            // requires artifacts to never be modified in place. Only the deferred materializer
+            // hardlinks, and only copies of other artifacts in buck-out.
+            let local_copy_method = root_config
+                .parse("buck2", "local_copy_method")?
+                .unwrap_or(LocalCopyMethod::Reflink);
+
             DeferredMaterializerConfigs {
                 materialize_final_artifacts: matches!(
                     materialization_method,
@@ -319,6 +373,9 @@ impl DaemonState {
                     min_ttl: chrono::Duration::seconds(ttl_refresh_min_ttl),
                     enabled: ttl_refresh_enabled,
                 },
+                disk_budget,
+                blob_cache,
+                local_copy_method,
             }
         };
 
@@ -349,7 +406,16 @@ impl DaemonState {
         )
         .await?;
 
-        let http_client = http_client()?;
+        // Shared by HTTP downloads and RE, so the helper only runs once per host.
+        let credential_helper = root_config
+            .parse_list::<ScopedCredentialHelper>("buck2", "credential_helper")?
+            .map(|helpers| {
+                Arc::new(CredentialHelper::new(
+                    helpers,
+                    paths.project_root().root().as_path().to_owned(),
+                ))
+            });
+        let http_client = http_client(credential_helper.dupe())?;
 
         let materializer_state_identity = materializer_db.as_ref().map(|d| d.identity().clone());
 
@@ -360,6 +426,7 @@ impl DaemonState {
             static_metadata,
             Some(paths.re_logs_dir()),
             paths.buck_out_path(),
+            credential_helper,
         ));
         let materializer = Self::create_materializer(
             fb,
@@ -380,10 +447,6 @@ impl DaemonState {
         let forkserver =
             maybe_launch_forkserver(root_config, &paths.forkserver_state_dir()).await?;
 
-        let dice = init_ctx
-            .construct_dice(io.dupe(), digest_config, root_config)
-            .await?;
-
         // TODO(cjhopman): We want to use Expr::True here, but we need to workaround
         // https://github.com/facebook/watchman/issues/911. Adding other filetypes to
         // this list should be safe until we can revert it to Expr::True.
@@ -401,6 +464,19 @@ impl DaemonState {
             )
         })?;
 
+        let dice_snapshotter = root_config
+            .parse::<bool>("buck2", "dice_snapshot")?
+            .unwrap_or(false)
+            .then(|| DiceSnapshotter::new(paths, &cells));
+        // The snapshot has to be read before the file watcher is first synced.
+        let snapshot = dice_snapshotter
+            .as_ref()
+            .and_then(|snapshotter| snapshotter.read(&*file_watcher));
+
+        let dice = init_ctx
+            .construct_dice(io.dupe(), digest_config, root_config, snapshot)
+            .await?;
+
         let hash_all_commands = root_config
             .parse::<RolloutPercentage>("buck2", "hash_all_commands")?
             .unwrap_or_else(RolloutPercentage::never)
@@ -467,6 +543,7 @@ impl DaemonState {
                 cleanup_config,
             ),
             file_watcher,
+            dice_snapshotter,
             io,
             re_client_manager,
             blocking_executor,
@@ -483,6 +560,7 @@ impl DaemonState {
             enable_restarter,
             http_client,
             cwd_buck_out,
+            in_flight_commands,
         }))
     }
 
@@ -671,6 +749,7 @@ impl DaemonState {
             hash_all_commands: data.hash_all_commands,
             use_network_action_output_cache: data.use_network_action_output_cache,
             _drop_guard: drop_guard,
+            _in_flight_command: data.in_flight_commands.start(),
             daemon_start_time: data.start_time,
             create_unhashed_outputs_lock: data.create_unhashed_outputs_lock.dupe(),
             http_client: data.http_client.dupe(),
@@ -681,6 +760,23 @@ impl DaemonState {
         Ok(self.data.dupe()?)
     }
 
+    /// Writes the DICE snapshot for the next daemon, if `buck2.dice_snapshot` is enabled. Errors
+    /// are only logged since the daemon is shutting down anyway.
+    pub async fn write_dice_snapshot(&self) {
+        let data = match &self.data {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        if let Some(snapshotter) = &data.dice_snapshotter {
+            if let Err(e) = snapshotter
+                .write(data.dice_manager.unsafe_dice(), &*data.file_watcher)
+                .await
+            {
+                tracing::warn!("Error writing DICE snapshot: {:#}", e);
+            }
+        }
+    }
+
     fn validate_buck_out_mount(&self) -> anyhow::Result<()> {
         #[cfg(any(fbcode_build, cargo_internal_build))]
         {
@@ -0,0 +1,133 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io::Write;
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_build_api::actions::calculation::BuildKey;
+use buck2_build_api::analysis::calculation::AnalysisKey;
+use buck2_build_api::calculation::Calculation;
+use buck2_build_api::nodes::calculation::ConfiguredTargetNodeKey;
+use buck2_cli_proto::HasClientContext;
+use buck2_core::base_deferred_key::BaseDeferredKey;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
+use buck2_server_ctx::pattern::target_platform_from_client_context;
+use buck2_server_ctx::template::run_server_command;
+use buck2_server_ctx::template::ServerCommandTemplate;
+use dice::Dice;
+use dice::DiceTransaction;
+use dupe::Dupe;
+
+use crate::ctx::ServerCommandContext;
+
+pub(crate) async fn explain_recompute_command(
+    ctx: &ServerCommandContext<'_>,
+    partial_result_dispatcher: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+    req: buck2_cli_proto::ExplainRecomputeRequest,
+) -> anyhow::Result<buck2_cli_proto::GenericResponse> {
+    run_server_command(
+        ExplainRecomputeServerCommand {
+            req,
+            dice: ctx.base_context.dice_manager.unsafe_dice().dupe(),
+        },
+        ctx,
+        partial_result_dispatcher,
+    )
+    .await
+}
+
+struct ExplainRecomputeServerCommand {
+    req: buck2_cli_proto::ExplainRecomputeRequest,
+    /// The recorded invalidations are read from the DICE graph itself rather than from a
+    /// transaction.
+    dice: Arc<Dice>,
+}
+
+#[async_trait]
+impl ServerCommandTemplate for ExplainRecomputeServerCommand {
+    type StartEvent = buck2_data::ExplainRecomputeCommandStart;
+    type EndEvent = buck2_data::ExplainRecomputeCommandEnd;
+    type Response = buck2_cli_proto::GenericResponse;
+    type PartialResult = buck2_cli_proto::StdoutBytes;
+
+    async fn command(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut partial_result_dispatcher: PartialResultDispatcher<Self::PartialResult>,
+        ctx: DiceTransaction,
+    ) -> anyhow::Result<Self::Response> {
+        let target_platform =
+            target_platform_from_client_context(self.req.client_context()?, server_ctx, &ctx)
+                .await?;
+
+        let label = parse_patterns_from_cli_args::<TargetPatternExtra>(
+            &ctx,
+            &[buck2_data::TargetPattern {
+                value: self.req.target.clone(),
+            }],
+            server_ctx.working_dir(),
+        )
+        .await?
+        .into_iter()
+        .next()
+        .context("Parsing patterns returned nothing")?
+        .as_target_label(&self.req.target)?;
+
+        let label = ctx
+            .get_configured_target(&label, target_platform.as_ref())
+            .await?;
+
+        let mut paths = Vec::new();
+        if self.req.action_key.is_none() {
+            paths.extend(
+                self.dice
+                    .explain_invalidations(|k: &ConfiguredTargetNodeKey| k.0 == label)
+                    .await?,
+            );
+            paths.extend(
+                self.dice
+                    .explain_invalidations(|k: &AnalysisKey| k.0 == label)
+                    .await?,
+            );
+        }
+        let owner = BaseDeferredKey::TargetLabel(label.dupe());
+        paths.extend(
+            self.dice
+                .explain_invalidations(|k: &BuildKey| {
+                    k.0.owner() == &owner
+                        && self.req.action_key.as_ref().map_or(true, |action_key| {
+                            &k.0.deferred_key().action_key() == action_key
+                        })
+                })
+                .await?,
+        );
+
+        if paths.is_empty() {
+            let mut stderr = server_ctx.stderr()?;
+            writeln!(stderr, "No recorded invalidations of `{}`", label)?;
+        } else {
+            let mut stdout = partial_result_dispatcher.as_writer();
+            for path in paths {
+                writeln!(stdout, "{}", path)?;
+            }
+        }
+
+        Ok(buck2_cli_proto::GenericResponse {})
+    }
+
+    fn is_success(&self, _response: &Self::Response) -> bool {
+        // No response if we failed.
+        true
+    }
+}
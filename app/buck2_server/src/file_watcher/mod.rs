@@ -31,6 +31,19 @@ mod watchman;
 #[async_trait]
 pub trait FileWatcher: Allocative + Send + Sync + 'static {
     async fn sync(&self, dice: DiceTransactionUpdater) -> anyhow::Result<DiceTransactionUpdater>;
+
+    /// Returns a token for the file system state that DICE was last synced to, which a later file
+    /// watcher can `resume` from. `None` if this file watcher can't resume.
+    async fn snapshot_token(&self) -> anyhow::Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Makes the first `sync` report the changes made since `token` was taken, instead of
+    /// invalidating everything. Returns false if this file watcher can't resume, in which case
+    /// anything restored into DICE must be discarded.
+    fn resume(&self, _token: &str) -> anyhow::Result<bool> {
+        Ok(false)
+    }
 }
 
 impl dyn FileWatcher {
@@ -20,6 +20,7 @@ use buck2_core::env_helper::EnvHelper;
 use dupe::Dupe;
 use futures::future::Future;
 use serde::Deserialize;
+use serde::Serialize;
 use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::oneshot;
@@ -131,7 +132,10 @@ impl WatchmanClient {
         connector: &Connector,
         path: CanonicalPath,
     ) -> anyhow::Result<WatchmanClient> {
-        let client = connector.connect().await?;
+        let client = connector
+            .connect()
+            .await
+            .context(buck2_data::ErrorCause::FileWatcherUnavailable)?;
         let root = client.resolve_root(path).await?;
         Ok(Self(Arc::new((client, root))))
     }
@@ -188,6 +192,19 @@ pub trait SyncableQueryProcessor: Send + Sync {
 /// commands to be sent to the SyncableQueryHandler.
 enum SyncableQueryCommand<T, P> {
     Sync(P, oneshot::Sender<anyhow::Result<(T, P)>>),
+    /// Returns the clock the processor was last synced to, if it has been synced since connecting
+    /// to watchman.
+    Clock(oneshot::Sender<Option<WatchmanClock>>),
+    /// Makes the next sync query for the changes since this clock. Ignored if the processor has
+    /// already been synced.
+    Resume(WatchmanClock),
+}
+
+/// The point that a SyncableQuery has been synced to, as used to resume it in another daemon.
+#[derive(Serialize, Deserialize)]
+pub struct WatchmanClock {
+    clock: ClockSpec,
+    mergebase: Option<String>,
 }
 
 /// A SyncableQuery is similar to a subscription. When created, it accepts a query expression
@@ -257,6 +274,15 @@ where
                     // job. That's fine.
                     let _ignore = sync_tx.send(res);
                 }
+                Some(SyncableQueryCommand::Clock(clock_tx)) => {
+                    let _ignore = clock_tx.send(self.clock());
+                }
+                Some(SyncableQueryCommand::Resume(clock)) => {
+                    if self.clock().is_none() {
+                        self.last_clock = clock.clock;
+                        self.last_mergebase = clock.mergebase;
+                    }
+                }
                 None => {
                     // This indicates the controlling SyncableQuery has been dropped.
                     return;
@@ -323,6 +349,22 @@ where
         Ok(res)
     }
 
+    fn clock(&self) -> Option<WatchmanClock> {
+        // The clock is reset to the null clock whenever we (re)connect, until the next sync.
+        let is_null = matches!(
+            (&self.last_clock, ClockSpec::null()),
+            (ClockSpec::StringClock(clock), ClockSpec::StringClock(null)) if *clock == null
+        );
+        if is_null {
+            None
+        } else {
+            Some(WatchmanClock {
+                clock: self.last_clock.clone(),
+                mergebase: self.last_mergebase.clone(),
+            })
+        }
+    }
+
     async fn reconnect(&mut self, client: &mut Option<WatchmanClient>) -> anyhow::Result<()> {
         self.last_clock = Default::default();
         self.last_mergebase = None;
@@ -447,6 +489,29 @@ where
         }
     }
 
+    /// Returns the clock that the processor was last synced to.
+    pub fn clock(
+        &self,
+    ) -> impl Future<Output = anyhow::Result<Option<WatchmanClock>>> + Send + 'static {
+        let (clock_tx, clock_rx) = tokio::sync::oneshot::channel();
+        let tx_res = self.control_tx.send(SyncableQueryCommand::Clock(clock_tx));
+
+        async move {
+            tx_res.ok().context("SyncableQueryHandler has exited")?;
+            clock_rx
+                .await
+                .context("SyncableQueryHandler did not return a response for clock request")
+        }
+    }
+
+    /// Makes the first sync process the changes made since `clock` rather than a fresh instance.
+    pub fn resume(&self, clock: WatchmanClock) -> anyhow::Result<()> {
+        self.control_tx
+            .send(SyncableQueryCommand::Resume(clock))
+            .ok()
+            .context("SyncableQueryHandler has exited")
+    }
+
     pub fn new(
         connector: Connector,
         path: impl AsRef<Path>,
@@ -334,4 +334,19 @@ impl FileWatcher for WatchmanFileWatcher {
         )
         .await
     }
+
+    async fn snapshot_token(&self) -> anyhow::Result<Option<String>> {
+        self.query
+            .clock()
+            .await?
+            .map(|clock| serde_json::to_string(&clock))
+            .transpose()
+            .context("Serializing watchman clock")
+    }
+
+    fn resume(&self, token: &str) -> anyhow::Result<bool> {
+        let clock = serde_json::from_str(token).context("Deserializing watchman clock")?;
+        self.query.resume(clock)?;
+        Ok(true)
+    }
 }
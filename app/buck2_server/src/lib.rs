@@ -17,6 +17,7 @@ mod configs;
 mod ctx;
 pub mod daemon;
 mod dice_tracker;
+mod explain_recompute;
 mod file_status;
 mod file_watcher;
 mod heartbeat_guard;
@@ -24,6 +25,7 @@ mod host_info;
 mod jemalloc_stats;
 pub mod lsp;
 mod materialize;
+mod materializer_stats;
 mod net_io;
 pub mod profile;
 mod snapshot;
@@ -0,0 +1,154 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::io::Write;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use buck2_event_observer::humanized::HumanizedBytes;
+use buck2_execute::output_size::OutputCountAndBytes;
+use buck2_server_ctx::ctx::ServerCommandContextTrait;
+use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::template::run_server_command;
+use buck2_server_ctx::template::ServerCommandTemplate;
+use dice::DiceTransaction;
+
+use crate::ctx::ServerCommandContext;
+
+pub(crate) async fn materializer_stats_command(
+    ctx: &ServerCommandContext<'_>,
+    partial_result_dispatcher: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+    req: buck2_cli_proto::MaterializerStatsRequest,
+) -> anyhow::Result<buck2_cli_proto::GenericResponse> {
+    run_server_command(
+        MaterializerStatsServerCommand { req },
+        ctx,
+        partial_result_dispatcher,
+    )
+    .await
+}
+
+struct MaterializerStatsServerCommand {
+    req: buck2_cli_proto::MaterializerStatsRequest,
+}
+
+fn method_name(method: buck2_data::MaterializationMethod) -> &'static str {
+    match method {
+        buck2_data::MaterializationMethod::CasDownload => "cas download",
+        buck2_data::MaterializationMethod::LocalCopy => "local copy",
+        buck2_data::MaterializationMethod::HttpDownload => "http download",
+        buck2_data::MaterializationMethod::Write => "write",
+    }
+}
+
+fn write_count_and_bytes(
+    out: &mut impl Write,
+    name: &str,
+    count_and_bytes: &OutputCountAndBytes,
+) -> anyhow::Result<()> {
+    writeln!(
+        out,
+        "  {:<16}{:>10} artifacts{:>12}",
+        name,
+        count_and_bytes.count,
+        HumanizedBytes::new(count_and_bytes.bytes).to_string(),
+    )?;
+    Ok(())
+}
+
+#[async_trait]
+impl ServerCommandTemplate for MaterializerStatsServerCommand {
+    type StartEvent = buck2_data::MaterializerStatsCommandStart;
+    type EndEvent = buck2_data::MaterializerStatsCommandEnd;
+    type Response = buck2_cli_proto::GenericResponse;
+    type PartialResult = buck2_cli_proto::StdoutBytes;
+
+    async fn command(
+        &self,
+        server_ctx: &dyn ServerCommandContextTrait,
+        mut partial_result_dispatcher: PartialResultDispatcher<Self::PartialResult>,
+        _ctx: DiceTransaction,
+    ) -> anyhow::Result<Self::Response> {
+        let mut stdout = partial_result_dispatcher.as_writer();
+
+        let materializer = server_ctx.materializer();
+        let deferred_materializer = materializer
+            .as_deferred_materializer_extension()
+            .context("Deferred materializer is not in use")?;
+
+        let stats = deferred_materializer
+            .stats(self.req.largest_subtrees.try_into()?)
+            .await
+            .context("Failed to get materializer stats")?;
+
+        let mut total = OutputCountAndBytes::default();
+        for count_and_bytes in stats.materialized.values() {
+            total.count += count_and_bytes.count;
+            total.bytes += count_and_bytes.bytes;
+        }
+
+        writeln!(stdout, "Materialized:")?;
+        write_count_and_bytes(&mut stdout, "total", &total)?;
+        for (method, count_and_bytes) in &stats.materialized {
+            let name = method.map_or("unknown", method_name);
+            write_count_and_bytes(&mut stdout, name, count_and_bytes)?;
+        }
+
+        writeln!(stdout, "Declared, not materialized:")?;
+        for (method, count_and_bytes) in &stats.declared {
+            write_count_and_bytes(&mut stdout, method_name(*method), count_and_bytes)?;
+        }
+
+        if !stats.largest_subtrees.is_empty() {
+            writeln!(stdout, "Largest subtrees:")?;
+            for (path, bytes) in &stats.largest_subtrees {
+                writeln!(
+                    stdout,
+                    "  {:>10}  {}",
+                    HumanizedBytes::new(*bytes).to_string(),
+                    path
+                )?;
+            }
+        }
+
+        if self.req.verify_digests || self.req.repair {
+            let corrupted = deferred_materializer
+                .verify_digests(self.req.repair)
+                .await
+                .context("Failed to verify digests")?;
+
+            writeln!(stdout, "Corrupted artifacts: {}", corrupted.len())?;
+            for artifact in &corrupted {
+                writeln!(
+                    stdout,
+                    "  {}\t{:#}{}",
+                    artifact.path,
+                    artifact.error,
+                    if artifact.repaired { " (evicted)" } else { "" }
+                )?;
+            }
+
+            if self.req.repair && corrupted.iter().any(|a| !a.repaired) {
+                let mut stderr = server_ctx.stderr()?;
+                writeln!(
+                    stderr,
+                    "Some artifacts are in use by the daemon and could not be evicted, \
+                    run `buck2 kill` and try again"
+                )?;
+            }
+        }
+
+        Ok(buck2_cli_proto::GenericResponse {})
+    }
+
+    fn is_success(&self, _response: &Self::Response) -> bool {
+        // No response if we failed.
+        true
+    }
+}
@@ -54,7 +54,9 @@ async fn generate_profile_analysis(
 ) -> anyhow::Result<Arc<StarlarkProfileDataAndStats>> {
     let (target, TargetPatternExtra) = match spec {
         PackageSpec::Targets(targets) => one(targets).context("Invalid targets"),
-        PackageSpec::All => Err(anyhow::Error::msg("Cannot use a package")),
+        PackageSpec::All | PackageSpec::AllExcept(..) => {
+            Err(anyhow::Error::msg("Cannot use a package"))
+        }
     }
     .context("Did not find exactly one target")?;
 
@@ -90,7 +92,7 @@ async fn generate_profile_loading(
         PackageSpec::Targets(..) => {
             return Err(anyhow::Error::msg("Must use a package"));
         }
-        PackageSpec::All => {}
+        PackageSpec::All | PackageSpec::AllExcept(..) => {}
     }
 
     let calculation = ctx
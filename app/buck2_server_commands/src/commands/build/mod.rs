@@ -29,6 +29,7 @@ use buck2_cli_proto::build_request::Materializations;
 use buck2_cli_proto::HasClientContext;
 use buck2_common::dice::cells::HasCellResolver;
 use buck2_common::dice::file_ops::HasFileOps;
+use buck2_common::error_report::CreateErrorReport;
 use buck2_common::legacy_configs::dice::HasLegacyConfigs;
 use buck2_common::pattern::resolve::resolve_target_patterns;
 use buck2_common::pattern::resolve::ResolvedPattern;
@@ -51,7 +52,8 @@ use buck2_node::nodes::unconfigured::TargetNode;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
-use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
+use buck2_server_ctx::pattern::modifiers_from_client_context;
+use buck2_server_ctx::pattern::parse_patterns_with_modifiers_from_cli_args;
 use buck2_server_ctx::pattern::target_platform_from_client_context;
 use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
@@ -64,6 +66,7 @@ use futures::stream::futures_unordered::FuturesUnordered;
 use futures::stream::Stream;
 use futures::stream::StreamExt;
 use itertools::Itertools;
+use starlark_map::small_map::SmallMap;
 
 use crate::commands::build::results::build_report::BuildReportCollector;
 use crate::commands::build::results::providers::ProvidersPrinter;
@@ -115,6 +118,12 @@ impl ServerCommandTemplate for BuildServerCommand {
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+enum BuildError {
+    #[error("Per-pattern configuration modifiers cannot be used with `--target-universe`")]
+    PatternModifiersWithUniverse,
+}
+
 enum TargetResolutionConfig {
     /// Resolve using target platform.
     Default(Option<TargetLabel>),
@@ -141,21 +150,57 @@ async fn build(
     let client_ctx = request.client_context()?;
     let global_target_platform =
         target_platform_from_client_context(client_ctx, server_ctx, &ctx).await?;
+    let global_modifiers = modifiers_from_client_context(client_ctx, server_ctx, &ctx).await?;
 
     let should_create_unhashed_links = ctx
         .parse_legacy_config_property(cell_resolver.root_cell(), "buck2", "create_unhashed_links")
         .await?;
 
-    let parsed_patterns: Vec<ParsedPattern<ConfiguredProvidersPatternExtra>> =
-        parse_patterns_from_cli_args(&ctx, &request.target_patterns, cwd).await?;
-    server_ctx.log_target_pattern(&parsed_patterns);
+    let (parsed_patterns_with_modifiers, exclusions): (
+        Vec<(
+            ParsedPattern<ConfiguredProvidersPatternExtra>,
+            Vec<TargetLabel>,
+        )>,
+        _,
+    ) = parse_patterns_with_modifiers_from_cli_args(&ctx, &request.target_patterns, cwd).await?;
+    server_ctx.log_target_pattern(
+        &parsed_patterns_with_modifiers
+            .iter()
+            .map(|(pattern, _)| pattern.clone())
+            .collect::<Vec<_>>(),
+    );
+
+    if !request.target_universe.is_empty()
+        && parsed_patterns_with_modifiers
+            .iter()
+            .any(|(_, modifiers)| !modifiers.is_empty())
+    {
+        return Err(BuildError::PatternModifiersWithUniverse.into());
+    }
 
     ctx.per_transaction_data()
         .get_materializer()
         .log_materializer_state(server_ctx.events());
 
-    let resolved_pattern: ResolvedPattern<ConfiguredProvidersPatternExtra> =
-        resolve_target_patterns(&cell_resolver, &parsed_patterns, &ctx.file_ops()).await?;
+    // Patterns are resolved in groups sharing the same modifiers: the global ones (`--modifier`)
+    // followed by the ones attached to the pattern, so that the latter take precedence.
+    let mut patterns_by_modifiers: SmallMap<Vec<TargetLabel>, Vec<_>> = SmallMap::new();
+    for (pattern, pattern_modifiers) in parsed_patterns_with_modifiers {
+        let mut modifiers = global_modifiers.clone();
+        modifiers.extend(pattern_modifiers);
+        patterns_by_modifiers
+            .entry(modifiers)
+            .or_default()
+            .push(pattern);
+    }
+    let mut resolved_patterns = Vec::with_capacity(patterns_by_modifiers.len());
+    for (modifiers, parsed_patterns) in patterns_by_modifiers {
+        let resolved_pattern: ResolvedPattern<ConfiguredProvidersPatternExtra> =
+            resolve_target_patterns(&cell_resolver, &parsed_patterns, &ctx.file_ops())
+                .await?
+                .exclude(&exclusions);
+        resolved_patterns.push((resolved_pattern, modifiers));
+    }
 
     let target_resolution_config: TargetResolutionConfig = if request.target_universe.is_empty() {
         TargetResolutionConfig::Default(global_target_platform)
@@ -163,7 +208,13 @@ async fn build(
         TargetResolutionConfig::Universe(
             QUERY_FRONTEND
                 .get()?
-                .universe_from_literals(&ctx, cwd, &request.target_universe, global_target_platform)
+                .universe_from_literals(
+                    &ctx,
+                    cwd,
+                    &request.target_universe,
+                    global_target_platform,
+                    global_modifiers,
+                )
                 .await?,
         )
     };
@@ -233,7 +284,7 @@ async fn build(
     let mut provider_artifacts = Vec::new();
     for (k, v) in build_targets(
         &ctx,
-        resolved_pattern,
+        resolved_patterns,
         target_resolution_config,
         build_providers,
         &materialization_context,
@@ -289,18 +340,22 @@ async fn build(
     //            data back to the CLI client, and all build report generation will happen there.
     //            For now, we're going to be a little hacky to remove some stdout printing that
     //            used to exist here.
-    let (build_targets, error_messages) = match result_collector.results() {
+    let (build_targets, error_reports) = match result_collector.results() {
         Ok(targets) => (targets, Vec::new()),
         Err(errors) => {
-            let error_strings = errors
+            let error_reports: Vec<_> = errors
                 .errors
                 .iter()
-                .map(|e| format!("{:#}", e))
-                .unique()
+                .filter_map(|e| e.create_error_report())
+                .unique_by(|r| r.error_message.clone())
                 .collect();
-            (vec![], error_strings)
+            (vec![], error_reports)
         }
     };
+    let error_messages = error_reports
+        .iter()
+        .map(|r| r.error_message.clone())
+        .collect();
 
     let project_root = server_ctx.project_root().to_string();
 
@@ -309,12 +364,16 @@ async fn build(
         project_root,
         serialized_build_report: serialized_build_report.unwrap_or_default(),
         error_messages,
+        error_reports,
     })
 }
 
 async fn build_targets(
     ctx: &DiceComputations,
-    spec: ResolvedPattern<ConfiguredProvidersPatternExtra>,
+    specs: Vec<(
+        ResolvedPattern<ConfiguredProvidersPatternExtra>,
+        Vec<TargetLabel>,
+    )>,
     target_resolution_config: TargetResolutionConfig,
     build_providers: Arc<BuildProviders>,
     materialization_context: &MaterializationContext,
@@ -324,23 +383,29 @@ async fn build_targets(
 ) -> anyhow::Result<BTreeMap<ConfiguredProvidersLabel, BuildTargetResult>> {
     let stream = match target_resolution_config {
         TargetResolutionConfig::Default(global_target_platform) => {
-            let spec = spec.convert_pattern().context(
-                "Cannot build with explicit configurations when universe is not specified",
-            )?;
-            build_targets_with_global_target_platform(
-                ctx,
-                spec,
-                global_target_platform,
-                build_providers,
-                materialization_context,
-                missing_target_behavior,
-                skip_incompatible_targets,
-            )
-            .left_stream()
+            let streams = specs
+                .into_iter()
+                .map(|(spec, modifiers)| {
+                    let spec = spec.convert_pattern().context(
+                        "Cannot build with explicit configurations when universe is not specified",
+                    )?;
+                    anyhow::Ok(build_targets_with_global_target_platform(
+                        ctx,
+                        spec,
+                        global_target_platform.dupe(),
+                        modifiers,
+                        build_providers.dupe(),
+                        materialization_context,
+                        missing_target_behavior,
+                        skip_incompatible_targets,
+                    ))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            futures::stream::select_all(streams).left_stream()
         }
         TargetResolutionConfig::Universe(universe) => build_targets_in_universe(
             ctx,
-            spec,
+            specs.into_iter().map(|(spec, _modifiers)| spec).collect(),
             universe,
             build_providers,
             materialization_context,
@@ -360,13 +425,16 @@ async fn build_targets(
 
 fn build_targets_in_universe<'a>(
     ctx: &'a DiceComputations,
-    spec: ResolvedPattern<ConfiguredProvidersPatternExtra>,
+    specs: Vec<ResolvedPattern<ConfiguredProvidersPatternExtra>>,
     universe: CqueryUniverse,
     build_providers: Arc<BuildProviders>,
     materialization_context: &'a MaterializationContext,
 ) -> impl Stream<Item = anyhow::Result<BuildEvent>> + Unpin + 'a {
     let providers_to_build = build_providers_to_providers_to_build(&build_providers);
-    let provider_labels = universe.get_provider_labels(&spec);
+    let provider_labels = specs
+        .iter()
+        .flat_map(|spec| universe.get_provider_labels(spec))
+        .collect::<Vec<_>>();
     provider_labels
         .into_iter()
         .map(|p| {
@@ -401,6 +469,7 @@ fn build_targets_with_global_target_platform<'a>(
     ctx: &'a DiceComputations,
     spec: ResolvedPattern<ProvidersPatternExtra>,
     global_target_platform: Option<TargetLabel>,
+    modifiers: Vec<TargetLabel>,
     build_providers: Arc<BuildProviders>,
     materialization_context: &'a MaterializationContext,
     missing_target_behavior: MissingTargetBehavior,
@@ -411,12 +480,14 @@ fn build_targets_with_global_target_platform<'a>(
         .map(|(package, spec)| {
             let build_providers = build_providers.dupe();
             let global_target_platform = global_target_platform.dupe();
+            let modifiers = modifiers.clone();
             async move {
                 let res = ctx.get_interpreter_results(package.dupe()).await?;
                 anyhow::Ok(build_targets_for_spec(
                     ctx,
                     spec,
                     global_target_platform,
+                    modifiers,
                     res,
                     build_providers,
                     materialization_context,
@@ -437,6 +508,7 @@ struct TargetBuildSpec {
     target: TargetNode,
     providers: ProvidersName,
     global_target_platform: Option<TargetLabel>,
+    modifiers: Vec<TargetLabel>,
     // Indicates whether this target was explicitly requested or not. If it's the result
     // of something like `//foo/...` we can skip it (for example if it's incompatible with
     // the target platform).
@@ -466,6 +538,7 @@ fn build_targets_for_spec<'a>(
     ctx: &'a DiceComputations,
     spec: PackageSpec<ProvidersPatternExtra>,
     global_target_platform: Option<TargetLabel>,
+    modifiers: Vec<TargetLabel>,
     res: Arc<EvaluationResult>,
     build_providers: Arc<BuildProviders>,
     materialization_context: &'a MaterializationContext,
@@ -475,7 +548,7 @@ fn build_targets_for_spec<'a>(
     async move {
         let skippable = match spec {
             PackageSpec::Targets(..) => skip_incompatible_targets,
-            PackageSpec::All => true,
+            PackageSpec::All | PackageSpec::AllExcept(..) => true,
         };
 
         let (targets, missing) = res.apply_spec(spec);
@@ -497,6 +570,7 @@ fn build_targets_for_spec<'a>(
                 target,
                 providers: extra.providers,
                 global_target_platform: global_target_platform.dupe(),
+                modifiers: modifiers.clone(),
                 skippable,
             })
             .collect();
@@ -539,9 +613,10 @@ async fn build_target(
 ) -> impl Stream<Item = anyhow::Result<BuildEvent>> + 'static {
     let res = async {
         let providers_label = ctx
-            .get_configured_target(
+            .get_configured_target_with_modifiers(
                 &ProvidersLabel::new(spec.target.label().dupe(), spec.providers),
                 spec.global_target_platform.as_ref(),
+                &spec.modifiers,
             )
             .await?;
 
@@ -20,6 +20,7 @@ use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::pattern::modifiers_from_client_context;
 use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
 use buck2_server_ctx::pattern::target_platform_from_client_context;
 use buck2_server_ctx::template::run_server_command;
@@ -80,6 +81,7 @@ impl ServerCommandTemplate for ConfiguredTargetsServerCommand {
 
         let global_target_platform =
             target_platform_from_client_context(client_ctx, server_ctx, &ctx).await?;
+        let modifiers = modifiers_from_client_context(client_ctx, server_ctx, &ctx).await?;
 
         let skip_missing_targets = MissingTargetBehavior::from_skip(self.req.skip_missing_targets);
 
@@ -87,6 +89,7 @@ impl ServerCommandTemplate for ConfiguredTargetsServerCommand {
             &ctx,
             parsed_patterns,
             global_target_platform,
+            &modifiers,
             skip_missing_targets,
         )
         .await?;
@@ -66,7 +66,7 @@ use buck2_node::nodes::frontend::TargetGraphCalculation;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
-use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
+use buck2_server_ctx::pattern::parse_patterns_with_exclusions_from_cli_args;
 use buck2_server_ctx::pattern::target_platform_from_client_context;
 use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
@@ -188,11 +188,9 @@ async fn install(
     let materializations = &materializations; // Don't move this below.
 
     // Note <TargetName> does not return the providers
-    let parsed_patterns = parse_patterns_from_cli_args::<ConfiguredProvidersPatternExtra>(
-        &ctx,
-        &request.target_patterns,
-        cwd,
-    )
+    let (parsed_patterns, exclusions) = parse_patterns_with_exclusions_from_cli_args::<
+        ConfiguredProvidersPatternExtra,
+    >(&ctx, &request.target_patterns, cwd)
     .await?;
     server_ctx.log_target_pattern(&parsed_patterns);
 
@@ -201,7 +199,9 @@ async fn install(
         .log_materializer_state(server_ctx.events());
 
     let resolved_pattern =
-        resolve_target_patterns(&cell_resolver, &parsed_patterns, &ctx.file_ops()).await?;
+        resolve_target_patterns(&cell_resolver, &parsed_patterns, &ctx.file_ops())
+            .await?
+            .exclude(&exclusions);
 
     let resolved_pattern = resolved_pattern
         .convert_pattern()
@@ -212,11 +212,13 @@ async fn install(
         let ctx = &ctx;
         let targets: Vec<(TargetName, ProvidersPatternExtra)> = match spec {
             buck2_core::pattern::PackageSpec::Targets(targets) => targets,
-            buck2_core::pattern::PackageSpec::All => {
+            spec @ (buck2_core::pattern::PackageSpec::All
+            | buck2_core::pattern::PackageSpec::AllExcept(..)) => {
                 let interpreter_results = ctx.get_interpreter_results(package.dupe()).await?;
                 interpreter_results
                     .targets()
                     .keys()
+                    .filter(|target| !spec.is_excluded(target))
                     .map(|target| {
                         (
                             target.to_owned(),
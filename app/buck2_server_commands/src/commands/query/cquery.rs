@@ -25,6 +25,7 @@ use buck2_query::query::compatibility::MaybeCompatible;
 use buck2_query::query::syntax::simple::eval::values::QueryEvaluationResult;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use buck2_server_ctx::pattern::modifiers_from_client_context;
 use buck2_server_ctx::pattern::target_platform_from_client_context;
 use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
@@ -120,6 +121,7 @@ async fn cquery(
 
     let global_target_platform =
         target_platform_from_client_context(client_ctx, server_ctx, &ctx).await?;
+    let modifiers = modifiers_from_client_context(client_ctx, server_ctx, &ctx).await?;
 
     let owner_behavior = match correct_owner {
         true => CqueryOwnerBehavior::Correct,
@@ -135,6 +137,7 @@ async fn cquery(
             query,
             query_args,
             global_target_platform,
+            modifiers,
             target_universe,
         )
         .await?;
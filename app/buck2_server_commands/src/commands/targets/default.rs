@@ -13,7 +13,7 @@
 use std::io::Write;
 use std::path::Path;
 
-use buck2_build_api::calculation::load_patterns;
+use buck2_build_api::calculation::load_patterns_with_exclusions;
 use buck2_build_api::calculation::MissingTargetBehavior;
 use buck2_build_api::nodes::lookup::ConfiguredTargetNodeLookup;
 use buck2_build_api::nodes::lookup::TargetNodeLookup;
@@ -21,6 +21,7 @@ use buck2_cli_proto::targets_request;
 use buck2_cli_proto::targets_request::TargetHashFileMode;
 use buck2_cli_proto::targets_request::TargetHashGraphType;
 use buck2_cli_proto::TargetsResponse;
+use buck2_common::pattern::resolve::PatternExclusions;
 use buck2_core::cells::CellResolver;
 use buck2_core::fs::paths::abs_path::AbsPath;
 use buck2_core::fs::project::ProjectRoot;
@@ -87,11 +88,19 @@ pub(crate) async fn targets_batch(
     dice: DiceTransaction,
     formatter: &dyn TargetFormatter,
     parsed_patterns: Vec<ParsedPattern<TargetPatternExtra>>,
+    exclusions: &PatternExclusions,
     target_platform: Option<TargetLabel>,
+    modifiers: Vec<TargetLabel>,
     hash_options: TargetHashOptions,
     keep_going: bool,
 ) -> anyhow::Result<TargetsResponse> {
-    let results = load_patterns(&dice, parsed_patterns, MissingTargetBehavior::Fail).await?;
+    let results = load_patterns_with_exclusions(
+        &dice,
+        parsed_patterns,
+        exclusions,
+        MissingTargetBehavior::Fail,
+    )
+    .await?;
 
     let target_hashes = match hash_options.graph_type {
         TargetHashGraphType::Configured => Some(
@@ -100,6 +109,7 @@ pub(crate) async fn targets_batch(
                 ConfiguredTargetNodeLookup(&dice),
                 results.iter_loaded_targets_by_package().collect(),
                 target_platform,
+                &modifiers,
                 hash_options.file_mode,
                 hash_options.fast_hash,
                 hash_options.recursive,
@@ -112,6 +122,7 @@ pub(crate) async fn targets_batch(
                 TargetNodeLookup(&dice),
                 results.iter_loaded_targets_by_package().collect(),
                 target_platform,
+                &modifiers,
                 hash_options.file_mode,
                 hash_options.fast_hash,
                 hash_options.recursive,
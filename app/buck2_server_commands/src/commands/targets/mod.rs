@@ -27,7 +27,8 @@ use buck2_common::dice::cells::HasCellResolver;
 use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
-use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
+use buck2_server_ctx::pattern::modifiers_from_client_context;
+use buck2_server_ctx::pattern::parse_patterns_with_exclusions_from_cli_args;
 use buck2_server_ctx::pattern::target_platform_from_client_context;
 use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
@@ -43,6 +44,8 @@ use crate::commands::targets::streaming::targets_streaming;
 enum TargetsCommandError {
     #[error("Missing field in proto request (internal error)")]
     MissingField,
+    #[error("Excluded patterns cannot be used with `--resolve-alias`")]
+    ExclusionsWithResolveAlias,
 }
 
 pub(crate) enum Outputter {
@@ -161,14 +164,18 @@ async fn targets(
 
     let cwd = server_ctx.working_dir();
     let cell_resolver = dice.get_cell_resolver().await?;
-    let parsed_target_patterns =
-        parse_patterns_from_cli_args::<TargetPatternExtra>(&dice, &request.target_patterns, cwd)
-            .await?;
+    let (parsed_target_patterns, exclusions) = parse_patterns_with_exclusions_from_cli_args::<
+        TargetPatternExtra,
+    >(&dice, &request.target_patterns, cwd)
+    .await?;
 
     let mut outputter = Outputter::new(request)?;
 
     let response = match &request.targets {
         Some(targets_request::Targets::ResolveAlias(_)) => {
+            if !exclusions.is_empty() {
+                return Err(TargetsCommandError::ExclusionsWithResolveAlias.into());
+            }
             targets_resolve_aliases(dice, request, parsed_target_patterns).await?
         }
         Some(targets_request::Targets::Other(other)) => {
@@ -188,6 +195,7 @@ async fn targets(
                     formatter,
                     &mut outputter,
                     parsed_target_patterns,
+                    exclusions,
                     other.keep_going,
                     other.cached,
                     other.imports,
@@ -203,13 +211,17 @@ async fn targets(
                 let client_ctx = request.client_context()?;
                 let target_platform =
                     target_platform_from_client_context(client_ctx, server_ctx, &dice).await?;
+                let modifiers =
+                    modifiers_from_client_context(client_ctx, server_ctx, &dice).await?;
                 let fs = server_ctx.project_root();
                 targets_batch(
                     server_ctx,
                     dice,
                     &*formatter,
                     parsed_target_patterns,
+                    &exclusions,
                     target_platform,
+                    modifiers,
                     TargetHashOptions::new(other, &cell_resolver, fs)?,
                     other.keep_going,
                 )
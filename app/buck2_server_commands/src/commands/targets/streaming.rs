@@ -17,6 +17,7 @@ use std::sync::Mutex;
 
 use buck2_cli_proto::TargetsResponse;
 use buck2_common::pattern::package_roots::find_package_roots_stream;
+use buck2_common::pattern::resolve::PatternExclusions;
 use buck2_common::pattern::resolve::ResolvedPattern;
 use buck2_core::bzl::ImportPath;
 use buck2_core::package::PackageLabel;
@@ -60,6 +61,7 @@ pub(crate) async fn targets_streaming(
     formatter: Arc<dyn TargetFormatter>,
     outputter: &mut Outputter,
     parsed_patterns: Vec<ParsedPattern<TargetPatternExtra>>,
+    exclusions: PatternExclusions,
     keep_going: bool,
     cached: bool,
     imports: bool,
@@ -76,7 +78,7 @@ pub(crate) async fn targets_streaming(
     let imported = Arc::new(Mutex::new(SmallSet::new()));
     let threads = Arc::new(Semaphore::new(threads.unwrap_or(Semaphore::MAX_PERMITS)));
 
-    let mut packages = stream_packages(&dice, parsed_patterns)
+    let mut packages = stream_packages(&dice, parsed_patterns, exclusions)
         .map(|x| {
             let formatter = formatter.dupe();
             let imported = imported.dupe();
@@ -226,10 +228,12 @@ pub(crate) async fn targets_streaming(
     })
 }
 
-/// Given the patterns, separate into those which have an explicit package, and those which are recursive
+/// Given the patterns, separate into those which have an explicit package, and those which are recursive.
+/// Packages and targets matched by `exclusions` are skipped.
 fn stream_packages<T: PatternType>(
     dice: &DiceComputations,
     patterns: Vec<ParsedPattern<T>>,
+    exclusions: PatternExclusions,
 ) -> impl Stream<Item = anyhow::Result<(PackageLabel, PackageSpec<T>)>> {
     let mut spec = ResolvedPattern::<T>::new();
     let mut recursive_paths = Vec::new();
@@ -248,8 +252,16 @@ fn stream_packages<T: PatternType>(
         }
     }
 
-    futures::stream::iter(spec.specs.into_iter().map(Ok))
-        .chain(find_package_roots_stream(dice, recursive_paths).map(|x| Ok((x?, PackageSpec::All))))
+    futures::stream::iter(spec.exclude(&exclusions).specs.into_iter().map(Ok)).chain(
+        find_package_roots_stream(dice, recursive_paths).filter_map(move |x| {
+            futures::future::ready(match x {
+                Ok(package) => exclusions
+                    .apply_to_spec(&package, PackageSpec::All)
+                    .map(|spec| Ok((package, spec))),
+                Err(e) => Some(Err(e)),
+            })
+        }),
+    )
 }
 
 #[derive(Error, Debug)]
@@ -305,8 +317,13 @@ async fn load_targets(
                 Ok((result, targets, None))
             }
         }
-        PackageSpec::All => {
-            let targets = result.targets().values().duped().collect();
+        spec @ (PackageSpec::All | PackageSpec::AllExcept(..)) => {
+            let targets = result
+                .targets()
+                .values()
+                .filter(|target| !spec.is_excluded(target.label().name()))
+                .duped()
+                .collect();
             Ok((result, targets, None))
         }
     }
@@ -20,6 +20,7 @@ use buck2_cli_proto::TargetsShowOutputsResponse;
 use buck2_common::dice::cells::HasCellResolver;
 use buck2_common::dice::file_ops::HasFileOps;
 use buck2_common::pattern::resolve::resolve_target_patterns;
+use buck2_common::pattern::resolve::PatternExclusions;
 use buck2_common::pattern::resolve::ResolvedPattern;
 use buck2_core::cells::CellResolver;
 use buck2_core::package::PackageLabel;
@@ -35,7 +36,7 @@ use buck2_node::nodes::frontend::TargetGraphCalculation;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
-use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
+use buck2_server_ctx::pattern::parse_patterns_with_exclusions_from_cli_args;
 use buck2_server_ctx::pattern::target_platform_from_client_context;
 use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
@@ -103,9 +104,10 @@ async fn targets_show_outputs(
     let client_ctx = request.client_context()?;
     let target_platform = target_platform_from_client_context(client_ctx, server_ctx, &ctx).await?;
 
-    let parsed_patterns =
-        parse_patterns_from_cli_args::<ProvidersPatternExtra>(&ctx, &request.target_patterns, cwd)
-            .await?;
+    let (parsed_patterns, exclusions) = parse_patterns_with_exclusions_from_cli_args::<
+        ProvidersPatternExtra,
+    >(&ctx, &request.target_patterns, cwd)
+    .await?;
 
     let artifact_fs = ctx.get_artifact_fs().await?;
 
@@ -115,6 +117,7 @@ async fn targets_show_outputs(
         &ctx,
         &target_platform,
         &parsed_patterns,
+        &exclusions,
         &cell_resolver,
     )
     .await?
@@ -137,10 +140,12 @@ async fn retrieve_targets_artifacts_from_patterns(
     ctx: &DiceComputations,
     global_target_platform: &Option<TargetLabel>,
     parsed_patterns: &[ParsedPattern<ProvidersPatternExtra>],
+    exclusions: &PatternExclusions,
     cell_resolver: &CellResolver,
 ) -> anyhow::Result<Vec<TargetsArtifacts>> {
-    let resolved_pattern =
-        resolve_target_patterns(cell_resolver, parsed_patterns, &ctx.file_ops()).await?;
+    let resolved_pattern = resolve_target_patterns(cell_resolver, parsed_patterns, &ctx.file_ops())
+        .await?
+        .exclude(exclusions);
 
     retrieve_artifacts_for_targets(ctx, resolved_pattern, global_target_platform.to_owned()).await
 }
@@ -194,8 +199,9 @@ async fn retrieve_artifacts_for_spec(
     let available_targets = res.targets();
 
     let todo_targets: Vec<(ProvidersLabel, Option<TargetLabel>)> = match spec {
-        PackageSpec::All => available_targets
+        spec @ (PackageSpec::All | PackageSpec::AllExcept(..)) => available_targets
             .keys()
+            .filter(|t| !spec.is_excluded(t))
             .map(|t| {
                 (
                     ProvidersLabel::default_for(TargetLabel::new(package.dupe(), t)),
@@ -195,6 +195,7 @@ pub trait TargetHashingTargetNode: QueryTarget {
         dice: &DiceComputations,
         loaded_targets: Vec<(PackageLabel, anyhow::Result<Vec<TargetNode>>)>,
         global_target_platform: Option<TargetLabel>,
+        modifiers: &[TargetLabel],
     ) -> anyhow::Result<TargetSet<Self>>;
 }
 
@@ -208,8 +209,15 @@ impl TargetHashingTargetNode for ConfiguredTargetNode {
         dice: &DiceComputations,
         loaded_targets: Vec<(PackageLabel, anyhow::Result<Vec<TargetNode>>)>,
         global_target_platform: Option<TargetLabel>,
+        modifiers: &[TargetLabel],
     ) -> anyhow::Result<TargetSet<Self>> {
-        get_compatible_targets(dice, loaded_targets.into_iter(), global_target_platform).await
+        get_compatible_targets(
+            dice,
+            loaded_targets.into_iter(),
+            global_target_platform,
+            modifiers,
+        )
+        .await
     }
 }
 
@@ -223,6 +231,7 @@ impl TargetHashingTargetNode for TargetNode {
         _dice: &DiceComputations,
         loaded_targets: Vec<(PackageLabel, anyhow::Result<Vec<TargetNode>>)>,
         _global_target_platform: Option<TargetLabel>,
+        _modifiers: &[TargetLabel],
     ) -> anyhow::Result<TargetSet<Self>> {
         let mut target_set = TargetSet::new();
         for (_package, result) in loaded_targets {
@@ -435,6 +444,7 @@ impl TargetHashes {
         lookup: L,
         targets: Vec<(PackageLabel, anyhow::Result<Vec<TargetNode>>)>,
         global_target_platform: Option<TargetLabel>,
+        modifiers: &[TargetLabel],
         file_hash_mode: TargetHashesFileMode,
         use_fast_hash: bool,
         target_hash_recursive: bool,
@@ -442,7 +452,8 @@ impl TargetHashes {
     where
         T::NodeRef: ConfiguredOrUnconfiguredTargetLabel,
     {
-        let targets = T::get_target_nodes(&dice, targets, global_target_platform).await?;
+        let targets =
+            T::get_target_nodes(&dice, targets, global_target_platform, modifiers).await?;
         let file_hasher = Self::new_file_hasher(dice.dupe(), file_hash_mode);
         if target_hash_recursive {
             Self::compute_recursive_target_hashes(dice, lookup, targets, file_hasher, use_fast_hash)
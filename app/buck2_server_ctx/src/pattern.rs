@@ -7,14 +7,18 @@
  * of this source tree.
  */
 
+use anyhow::Context;
 use buck2_cli_proto::ClientContext;
 use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::pattern::resolve::PatternExclusions;
 use buck2_common::target_aliases::BuckConfigTargetAliasResolver;
 use buck2_common::target_aliases::HasTargetAliasResolver;
 use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::CellResolver;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::pattern::modifiers::split_modifiers;
 use buck2_core::pattern::pattern_type::PatternType;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_core::pattern::ParsedPattern;
 use buck2_core::target::label::TargetLabel;
 use dice::DiceComputations;
@@ -23,6 +27,12 @@ use gazebo::prelude::*;
 
 use crate::ctx::ServerCommandContextTrait;
 
+#[derive(Debug, thiserror::Error)]
+enum PatternParseError {
+    #[error("Configuration modifiers cannot be used on excluded pattern `{0}`")]
+    ModifiersOnExclusion(String),
+}
+
 pub struct PatternParser {
     cell_resolver: CellResolver,
     cwd: CellPath,
@@ -52,6 +62,20 @@ impl PatternParser {
             pattern,
             &self.cell_resolver,
         )
+        .context(buck2_data::ErrorCategory::User)
+    }
+
+    /// Resolves configuration modifiers to the labels of the targets providing them. Modifiers
+    /// may be target labels or target aliases.
+    pub fn parse_modifiers(
+        &self,
+        modifiers: &[impl AsRef<str>],
+    ) -> anyhow::Result<Vec<TargetLabel>> {
+        modifiers.try_map(|modifier| {
+            let modifier = modifier.as_ref();
+            self.parse_pattern::<TargetPatternExtra>(modifier)?
+                .as_target_label(modifier)
+        })
     }
 }
 
@@ -70,6 +94,74 @@ pub async fn parse_patterns_from_cli_args<T: PatternType>(
     target_patterns.try_map(|value| parser.parse_pattern(&value.value))
 }
 
+/// Like [`parse_patterns_from_cli_args`], but also accepts exclusions: patterns prefixed with
+/// `-` (e.g. `-//third-party/...`) remove matching packages and targets from the other patterns.
+pub async fn parse_patterns_with_exclusions_from_cli_args<T: PatternType>(
+    ctx: &DiceComputations,
+    target_patterns: &[buck2_data::TargetPattern],
+    cwd: &ProjectRelativePath,
+) -> anyhow::Result<(Vec<ParsedPattern<T>>, PatternExclusions)> {
+    let parser = PatternParser::new(ctx, cwd).await?;
+
+    let mut patterns = Vec::new();
+    let mut exclusions = Vec::new();
+    for value in target_patterns {
+        match value.value.strip_prefix('-') {
+            Some(excluded) => exclusions.push(parser.parse_pattern(excluded)?),
+            None => patterns.push(parser.parse_pattern(&value.value)?),
+        }
+    }
+    Ok((patterns, PatternExclusions::new(exclusions)))
+}
+
+/// Like [`parse_patterns_with_exclusions_from_cli_args`], but also accepts configuration
+/// modifiers appended to each pattern (e.g. `//foo:bar?linux-arm64`). Returns each pattern with
+/// its modifiers resolved to target labels.
+pub async fn parse_patterns_with_modifiers_from_cli_args<T: PatternType>(
+    ctx: &DiceComputations,
+    target_patterns: &[buck2_data::TargetPattern],
+    cwd: &ProjectRelativePath,
+) -> anyhow::Result<(Vec<(ParsedPattern<T>, Vec<TargetLabel>)>, PatternExclusions)> {
+    let parser = PatternParser::new(ctx, cwd).await?;
+
+    let mut patterns = Vec::new();
+    let mut exclusions = Vec::new();
+    for value in target_patterns {
+        let (pattern, modifiers) =
+            split_modifiers(&value.value).context(buck2_data::ErrorCategory::User)?;
+        match pattern.strip_prefix('-') {
+            Some(excluded) => {
+                if !modifiers.is_empty() {
+                    return Err(anyhow::Error::from(PatternParseError::ModifiersOnExclusion(
+                        value.value.clone(),
+                    ))
+                    .context(buck2_data::ErrorCategory::User));
+                }
+                exclusions.push(parser.parse_pattern(excluded)?);
+            }
+            None => patterns.push((
+                parser.parse_pattern(pattern)?,
+                parser.parse_modifiers(&modifiers)?,
+            )),
+        }
+    }
+    Ok((patterns, PatternExclusions::new(exclusions)))
+}
+
+/// Extract global configuration modifiers (`--modifier`) from [`ClientContext`].
+pub async fn modifiers_from_client_context(
+    client_ctx: &ClientContext,
+    server_ctx: &dyn ServerCommandContextTrait,
+    dice_ctx: &DiceComputations,
+) -> anyhow::Result<Vec<TargetLabel>> {
+    if client_ctx.modifiers.is_empty() {
+        return Ok(Vec::new());
+    }
+    PatternParser::new(dice_ctx, server_ctx.working_dir())
+        .await?
+        .parse_modifiers(&client_ctx.modifiers)
+}
+
 /// Extract target configuration (platform) label from [`ClientContext`].
 pub async fn target_platform_from_client_context(
     client_ctx: &ClientContext,
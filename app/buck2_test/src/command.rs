@@ -27,6 +27,7 @@ use buck2_cli_proto::TestRequest;
 use buck2_cli_proto::TestResponse;
 use buck2_common::dice::cells::HasCellResolver;
 use buck2_common::dice::file_ops::HasFileOps;
+use buck2_common::error_report::CreateErrorReport;
 use buck2_common::events::HasEvents;
 use buck2_common::legacy_configs::dice::HasLegacyConfigs;
 use buck2_common::liveliness_observer::LivelinessGuard;
@@ -57,7 +58,7 @@ use buck2_query::query::compatibility::MaybeCompatible;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::partial_result_dispatcher::NoPartialResult;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
-use buck2_server_ctx::pattern::parse_patterns_from_cli_args;
+use buck2_server_ctx::pattern::parse_patterns_with_exclusions_from_cli_args;
 use buck2_server_ctx::pattern::target_platform_from_client_context;
 use buck2_server_ctx::template::run_server_command;
 use buck2_server_ctx::template::ServerCommandTemplate;
@@ -99,7 +100,7 @@ pub(crate) struct TestReport {
 }
 
 struct TestOutcome {
-    error_messages: Vec<String>,
+    errors: Vec<buck2_data::ErrorReport>,
     executor_report: ExecutorReport,
     executor_stdout: String,
     executor_stderr: String,
@@ -107,7 +108,7 @@ struct TestOutcome {
 
 impl TestOutcome {
     pub(crate) fn exit_code(&self) -> anyhow::Result<Option<i32>> {
-        if !self.error_messages.is_empty() {
+        if !self.errors.is_empty() {
             // Some tests failed to build. Send `None` back to
             // the client to delegate the exit code generation.
             return Ok(None);
@@ -277,7 +278,8 @@ async fn test(
         }
     };
 
-    let parsed_patterns = parse_patterns_from_cli_args(&ctx, &request.target_patterns, cwd).await?;
+    let (parsed_patterns, exclusions) =
+        parse_patterns_with_exclusions_from_cli_args(&ctx, &request.target_patterns, cwd).await?;
     server_ctx.log_target_pattern(&parsed_patterns);
 
     ctx.per_transaction_data()
@@ -285,7 +287,9 @@ async fn test(
         .log_materializer_state(server_ctx.events());
 
     let resolved_pattern =
-        resolve_target_patterns(&cell_resolver, &parsed_patterns, &ctx.file_ops()).await?;
+        resolve_target_patterns(&cell_resolver, &parsed_patterns, &ctx.file_ops())
+            .await?
+            .exclude(&exclusions);
 
     let launcher: Box<dyn ExecutorLauncher> = Box::new(OutOfProcessTestExecutor {
         executable: test_executor,
@@ -372,7 +376,12 @@ async fn test(
 
     Ok(TestResponse {
         exit_code,
-        error_messages: test_outcome.error_messages,
+        error_messages: test_outcome
+            .errors
+            .iter()
+            .map(|e| e.error_message.clone())
+            .collect(),
+        error_reports: test_outcome.errors,
         test_statuses: Some(test_statuses),
         executor_stdout: test_outcome.executor_stdout,
         executor_stderr: test_outcome.executor_stderr,
@@ -538,7 +547,7 @@ async fn test_targets(
         .context("Failed to collect executor report")??;
 
     Ok(TestOutcome {
-        error_messages: build_errors,
+        errors: build_errors,
         executor_stdout: executor_output.stdout,
         executor_stderr: executor_output.stderr,
         executor_report,
@@ -576,7 +585,7 @@ struct TestDriver<'a, 'e> {
     state: TestDriverState<'a, 'e>,
     work: FuturesUnordered<BoxFuture<'a, anyhow::Result<TestDriverTask>>>,
     labels_seen: HashSet<ConfiguredProvidersLabel>,
-    build_errors: Vec<String>,
+    build_errors: Vec<buck2_data::ErrorReport>,
 }
 
 impl<'a, 'e> TestDriver<'a, 'e> {
@@ -620,7 +629,7 @@ impl<'a, 'e> TestDriver<'a, 'e> {
                 }
                 Err(e) => {
                     // TODO(brasselsprouts): filter out duplicate errors.
-                    self.build_errors.push(format!("{:#}", e));
+                    self.build_errors.extend(e.create_error_report());
                 }
             };
         }
@@ -729,9 +738,10 @@ fn spec_to_targets(
     let available_targets = res.targets();
 
     match spec {
-        PackageSpec::All => {
+        spec @ (PackageSpec::All | PackageSpec::AllExcept(..)) => {
             let labels = available_targets
                 .keys()
+                .filter(|target| !spec.is_excluded(target))
                 .map(|target| {
                     (
                         target.to_owned(),
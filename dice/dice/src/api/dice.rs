@@ -194,9 +194,14 @@ use futures::future::Future;
 use serde::Serializer;
 
 use crate::api::cycles::DetectCycles;
+use crate::api::eviction::DiceEvictionPolicy;
+use crate::api::key::Key;
+use crate::api::persistence::DiceSnapshot;
+use crate::api::persistence::DiceSnapshotRegistry;
 use crate::api::transaction::DiceTransactionUpdater;
 use crate::api::user_data::UserComputationData;
 use crate::api::which::WhichSpawner;
+use crate::introspection::invalidations::InvalidationPath;
 use crate::metrics::Metrics;
 use crate::DiceDataBuilderImpl;
 use crate::DiceImplementation;
@@ -250,6 +255,52 @@ impl Dice {
         self.implementation.serialize_serde(serializer)
     }
 
+    /// Writes every verified node whose key, and whose transitive deps' keys, are registered
+    /// in `registry` to a snapshot that can later be restored into a fresh DICE.
+    pub async fn snapshot(
+        &self,
+        registry: &DiceSnapshotRegistry,
+        validation_token: String,
+    ) -> anyhow::Result<DiceSnapshot> {
+        self.implementation
+            .snapshot(registry, validation_token)
+            .await
+    }
+
+    /// Restores a snapshot into this DICE, which must not have been updated yet. The restored
+    /// nodes are verified at the initial version, so the caller is responsible for checking the
+    /// snapshot's validation token and invalidating anything that changed since it was taken.
+    /// Returns the number of restored nodes.
+    pub async fn restore(
+        &self,
+        registry: &DiceSnapshotRegistry,
+        snapshot: DiceSnapshot,
+    ) -> anyhow::Result<usize> {
+        self.implementation.restore(registry, snapshot).await
+    }
+
+    /// Sets when computed values of keys that are no longer requested are dropped from memory.
+    /// Evicted keys are recomputed the next time they are requested.
+    pub fn set_eviction_policy(&self, policy: DiceEvictionPolicy) -> anyhow::Result<()> {
+        self.implementation.set_eviction_policy(policy)
+    }
+
+    /// Starts or stops recording why each key is invalidated, so that recomputations can later be
+    /// explained with `explain_invalidations`. Stopping discards everything recorded so far.
+    pub fn record_invalidations(&self, enabled: bool) -> anyhow::Result<()> {
+        self.implementation.record_invalidations(enabled)
+    }
+
+    /// Explains the most recent recorded invalidation of every key of type `K` accepted by
+    /// `filter`, as the path from the changed key that caused it. Fails if invalidations are not
+    /// being recorded.
+    pub async fn explain_invalidations<K: Key>(
+        &self,
+        filter: impl Fn(&K) -> bool,
+    ) -> anyhow::Result<Vec<InvalidationPath>> {
+        self.implementation.explain_invalidations(filter).await
+    }
+
     pub fn detect_cycles(&self) -> &DetectCycles {
         self.implementation.detect_cycles()
     }
@@ -0,0 +1,39 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//!
+//! Dropping computed values that are no longer being requested.
+
+use std::time::Duration;
+
+use allocative::Allocative;
+use dupe::Dupe;
+
+/// When DICE drops the computed values of keys that have not been requested recently. Evicted
+/// keys are transparently recomputed the next time they are requested. Values set via
+/// `changed_to` are never evicted, since they can't be recomputed.
+///
+/// The default policy never evicts anything.
+#[derive(Allocative, Clone, Copy, Dupe, Debug, Default, PartialEq, Eq)]
+pub struct DiceEvictionPolicy {
+    /// Evict keys that were not requested in any of this many most recent versions.
+    pub max_unused_versions: Option<usize>,
+    /// Evict the least recently requested keys whenever the graph uses more than this many bytes.
+    /// Measuring this walks the whole graph, so it is only done on a new version at most once
+    /// every `DiceEvictionPolicy::MEMORY_CHECK_INTERVAL`.
+    pub memory_budget_bytes: Option<usize>,
+}
+
+impl DiceEvictionPolicy {
+    pub const MEMORY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.max_unused_versions.is_some() || self.memory_budget_bytes.is_some()
+    }
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//!
+//! On-disk snapshots of the DICE graph.
+//!
+//! Keys opt into persistence by implementing `PersistentKey` and being registered in a
+//! `DiceSnapshotRegistry`. A snapshot only ever contains nodes whose key is registered AND whose
+//! transitive dependencies are all registered, so that restoring it yields a closed graph in which
+//! later invalidations of any of the restored keys propagate exactly as they would have in the
+//! original DICE.
+
+use std::any::TypeId;
+use std::io::Read;
+use std::io::Write;
+use std::sync::Arc;
+
+use dupe::Dupe;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::api::key::Key;
+use crate::impls::persistence::PersistentKeyDyn;
+use crate::impls::persistence::PersistentKeyImpl;
+use crate::impls::persistence::SerializedNode;
+use crate::HashMap;
+
+/// A `Key` whose key and value can be written to a DICE snapshot.
+pub trait PersistentKey: Key + Serialize + DeserializeOwned {
+    /// A stable identifier for this key type in snapshots. This must be unique amongst all
+    /// registered keys, and should be changed whenever the serialized representation changes.
+    const PERSISTENT_ID: &'static str;
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Vec<u8>>;
+
+    fn deserialize_value(bytes: &[u8]) -> anyhow::Result<Self::Value>;
+}
+
+/// The set of key types that are written to and read from snapshots.
+#[derive(Default)]
+pub struct DiceSnapshotRegistry {
+    pub(crate) by_type: HashMap<TypeId, Arc<dyn PersistentKeyDyn>>,
+    pub(crate) by_id: HashMap<&'static str, Arc<dyn PersistentKeyDyn>>,
+}
+
+impl DiceSnapshotRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<K: PersistentKey>(&mut self) {
+        let persistence: Arc<dyn PersistentKeyDyn> = Arc::new(PersistentKeyImpl::<K>::new());
+
+        assert!(
+            self.by_id
+                .insert(K::PERSISTENT_ID, persistence.dupe())
+                .is_none(),
+            "persistent id `{}` registered twice",
+            K::PERSISTENT_ID
+        );
+        self.by_type.insert(TypeId::of::<K>(), persistence);
+    }
+}
+
+/// A serialized DICE graph.
+///
+/// The snapshot carries an opaque validation token chosen by the writer, typically the file
+/// watcher's view of the repository (e.g. a watchman clock or source control revision). Readers
+/// must compare it against the current state, and invalidate anything that changed since, before
+/// handing the snapshot to `Dice::restore`.
+pub struct DiceSnapshot {
+    pub(crate) validation_token: String,
+    pub(crate) nodes: Vec<SerializedNode>,
+}
+
+impl DiceSnapshot {
+    /// Bumped whenever the layout of `DiceSnapshot` changes.
+    pub(crate) const FORMAT_VERSION: u32 = 1;
+
+    pub fn validation_token(&self) -> &str {
+        &self.validation_token
+    }
+
+    /// Number of nodes stored in the snapshot.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> anyhow::Result<()> {
+        bincode::serialize_into(&mut writer, &Self::FORMAT_VERSION)?;
+        bincode::serialize_into(&mut writer, &self.validation_token)?;
+        bincode::serialize_into(&mut writer, &self.nodes)?;
+        Ok(())
+    }
+
+    pub fn read(mut reader: impl Read) -> anyhow::Result<Self> {
+        // The format version is read on its own so that snapshots written by an incompatible
+        // version are reported as such rather than as a deserialization failure.
+        let format_version: u32 = bincode::deserialize_from(&mut reader)?;
+        if format_version != Self::FORMAT_VERSION {
+            return Err(DiceSnapshotError::FormatVersionMismatch {
+                expected: Self::FORMAT_VERSION,
+                actual: format_version,
+            }
+            .into());
+        }
+        Ok(Self {
+            validation_token: bincode::deserialize_from(&mut reader)?,
+            nodes: bincode::deserialize_from(&mut reader)?,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum DiceSnapshotError {
+    #[error("DICE snapshots are only supported by the modern DICE implementation")]
+    Unsupported,
+    #[error("DICE snapshots can only be restored into a DICE that has never been updated")]
+    NotEmpty,
+    #[error("DICE snapshot has format version {actual}, but expected {expected}")]
+    FormatVersionMismatch { expected: u32, actual: u32 },
+    #[error(
+        "DICE snapshot is corrupt: node {node} refers to dependency {dep} that is not before it"
+    )]
+    InvalidDependency { node: usize, dep: u32 },
+}
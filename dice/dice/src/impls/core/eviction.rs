@@ -0,0 +1,161 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::cmp;
+use std::time::Instant;
+
+use thiserror::Error;
+
+use crate::api::eviction::DiceEvictionPolicy;
+use crate::impls::core::graph::storage::VersionedGraph;
+use crate::impls::key::DiceKey;
+use crate::versions::VersionNumber;
+use crate::HashMap;
+use crate::HashSet;
+
+#[derive(Debug, Error)]
+pub(crate) enum DiceEvictionError {
+    #[error("Eviction is only supported by the modern DICE implementation")]
+    Unsupported,
+}
+
+/// Tracks when keys were last requested, and evicts the ones that fall out of the
+/// `DiceEvictionPolicy`.
+pub(super) struct EvictionTracker {
+    policy: DiceEvictionPolicy,
+    /// the latest version at which each key was looked up or computed
+    last_requested: HashMap<DiceKey, VersionNumber>,
+    /// keys whose values can't be recomputed, because they were set rather than computed
+    pinned: HashSet<DiceKey>,
+    evicted_key_count: usize,
+    /// when the size of the graph was last measured for the memory budget
+    last_memory_check: Option<Instant>,
+}
+
+impl EvictionTracker {
+    pub(super) fn new() -> Self {
+        Self {
+            policy: DiceEvictionPolicy::default(),
+            last_requested: HashMap::default(),
+            pinned: HashSet::default(),
+            evicted_key_count: 0,
+            last_memory_check: None,
+        }
+    }
+
+    pub(super) fn set_policy(&mut self, policy: DiceEvictionPolicy) {
+        self.policy = policy;
+    }
+
+    pub(super) fn requested(&mut self, key: DiceKey, v: VersionNumber) {
+        let last = self.last_requested.entry(key).or_insert(v);
+        *last = cmp::max(*last, v);
+    }
+
+    pub(super) fn pin(&mut self, key: DiceKey) {
+        self.pinned.insert(key);
+    }
+
+    pub(super) fn evicted_key_count(&self) -> usize {
+        self.evicted_key_count
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.last_requested.clear();
+        self.pinned.clear();
+    }
+
+    /// Evicts the keys that fall out of the policy as of version `current`. Keys requested at or
+    /// after `oldest_active` are never evicted, since in-flight computations at that version may
+    /// still record them as deps.
+    pub(super) fn evict(
+        &mut self,
+        graph: &mut VersionedGraph,
+        current: VersionNumber,
+        oldest_active: Option<VersionNumber>,
+    ) {
+        if !self.policy.is_enabled() {
+            return;
+        }
+
+        let in_use_since = oldest_active.map_or(current, |v| cmp::min(v, current));
+
+        if let Some(max_unused_versions) = self.policy.max_unused_versions {
+            let unused_before = VersionNumber::new(current.0.saturating_sub(max_unused_versions));
+            let candidates = self.candidates(cmp::min(unused_before, in_use_since));
+            self.evict_candidates(graph, candidates, None);
+        }
+
+        if let Some(budget) = self.policy.memory_budget_bytes {
+            if self.last_memory_check.map_or(false, |last| {
+                last.elapsed() < DiceEvictionPolicy::MEMORY_CHECK_INTERVAL
+            }) {
+                return;
+            }
+            self.last_memory_check = Some(Instant::now());
+
+            let size = allocative::size_of_unique_allocated_data(graph);
+            if size > budget {
+                let candidates = self.candidates(in_use_since);
+                self.evict_candidates(graph, candidates, Some(size - budget));
+            }
+        }
+    }
+
+    /// Keys that may be evicted because they were last requested before `cutoff`, least recently
+    /// requested first.
+    fn candidates(&self, cutoff: VersionNumber) -> Vec<DiceKey> {
+        let mut candidates = self
+            .last_requested
+            .iter()
+            .filter(|(k, v)| **v < cutoff && !self.pinned.contains(k))
+            .map(|(k, v)| (*v, *k))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(v, _)| *v);
+
+        candidates.into_iter().map(|(_, k)| k).collect()
+    }
+
+    /// Evicts candidates until `to_free` bytes have been freed, or all of them if unset. A key
+    /// can only be evicted once nothing left in the graph depends on it, otherwise invalidations
+    /// of its deps would not reach its rdeps, so evicting a key makes its deps eligible again.
+    fn evict_candidates(
+        &mut self,
+        graph: &mut VersionedGraph,
+        candidates: Vec<DiceKey>,
+        mut to_free: Option<usize>,
+    ) {
+        let eligible: HashSet<DiceKey> = candidates.iter().copied().collect();
+        let mut queue: Vec<DiceKey> = candidates.into_iter().rev().collect();
+        let mut evicted = 0;
+
+        while let Some(key) = queue.pop() {
+            if to_free == Some(0) {
+                break;
+            }
+
+            if let Some((freed, deps)) = graph.evict(key) {
+                self.last_requested.remove(&key);
+                evicted += 1;
+                if let Some(to_free) = &mut to_free {
+                    *to_free = to_free.saturating_sub(freed);
+                }
+
+                queue.extend(deps.into_iter().filter(|dep| eligible.contains(dep)));
+            }
+        }
+
+        self.evicted_key_count += evicted;
+        debug!(
+            msg = "evicted dice keys",
+            evicted = evicted,
+            eligible = eligible.len()
+        );
+    }
+}
@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//!
+//! Records why keys were invalidated, so that recomputations can be explained.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use allocative::Allocative;
+
+use crate::impls::key::DiceKey;
+use crate::versions::VersionNumber;
+use crate::HashMap;
+
+/// Why a key was invalidated at some version
+#[derive(Allocative, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum InvalidationCause {
+    /// The key itself was changed or dirtied
+    Changed,
+    /// The given dependency of the key was invalidated
+    Dep(DiceKey),
+}
+
+/// The most recent invalidation of each key, as `(version, cause)`
+pub(crate) type InvalidationCauses = HashMap<DiceKey, (VersionNumber, InvalidationCause)>;
+
+/// The most recent invalidation cause of each key invalidated by one of the last
+/// `RecordedInvalidations::MAX_VERSIONS` versions that invalidated anything. Older invalidations
+/// are forgotten so that recording can stay enabled on a long running daemon.
+#[derive(Allocative, Default)]
+pub(crate) struct RecordedInvalidations {
+    /// Shared with readers, and only copied if they still hold on to it when the next
+    /// invalidation is recorded.
+    causes: Arc<InvalidationCauses>,
+    /// the keys invalidated at each recorded version. A key may also be listed at older versions
+    /// after being invalidated again, which `causes` tells apart.
+    by_version: BTreeMap<VersionNumber, Vec<DiceKey>>,
+}
+
+impl RecordedInvalidations {
+    pub(crate) const MAX_VERSIONS: usize = 16;
+
+    pub(crate) fn insert(&mut self, key: DiceKey, v: VersionNumber, cause: InvalidationCause) {
+        Arc::make_mut(&mut self.causes).insert(key, (v, cause));
+        self.by_version.entry(v).or_default().push(key);
+
+        while self.by_version.len() > Self::MAX_VERSIONS {
+            let Some((oldest, keys)) = self.by_version.pop_first() else {
+                break;
+            };
+            let causes = Arc::make_mut(&mut self.causes);
+            for key in keys {
+                if causes.get(&key).map_or(false, |(v, _)| *v == oldest) {
+                    causes.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Forgets the key, e.g. because it was evicted from the graph.
+    pub(crate) fn remove(&mut self, key: DiceKey) {
+        if self.causes.contains_key(&key) {
+            Arc::make_mut(&mut self.causes).remove(&key);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub(crate) fn causes(&self) -> Arc<InvalidationCauses> {
+        self.causes.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::impls::core::graph::invalidations::InvalidationCause;
+    use crate::impls::core::graph::invalidations::RecordedInvalidations;
+    use crate::impls::key::DiceKey;
+    use crate::versions::VersionNumber;
+
+    #[test]
+    fn forgets_old_versions() {
+        let mut recorded = RecordedInvalidations::default();
+        let key = |index| DiceKey { index };
+
+        recorded.insert(key(0), VersionNumber::new(1), InvalidationCause::Changed);
+        recorded.insert(key(1), VersionNumber::new(1), InvalidationCause::Changed);
+        // key 1 is invalidated again later, so must outlive version 1
+        recorded.insert(key(1), VersionNumber::new(2), InvalidationCause::Changed);
+
+        let causes = recorded.causes();
+        for v in 3..=RecordedInvalidations::MAX_VERSIONS + 1 {
+            recorded.insert(key(2), VersionNumber::new(v), InvalidationCause::Changed);
+        }
+
+        let now = recorded.causes();
+        assert!(!now.contains_key(&key(0)));
+        assert_eq!(
+            now.get(&key(1)),
+            Some(&(VersionNumber::new(2), InvalidationCause::Changed))
+        );
+        // readers keep what they were handed out
+        assert!(causes.contains_key(&key(0)));
+
+        recorded.remove(key(1));
+        assert!(!recorded.causes().contains_key(&key(1)));
+    }
+}
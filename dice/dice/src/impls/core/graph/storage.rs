@@ -30,6 +30,9 @@ use crate::arc::Arc;
 use crate::impls::core::graph::dependencies::VersionedDependencies;
 use crate::impls::core::graph::history::CellHistory;
 use crate::impls::core::graph::history::HistoryState;
+use crate::impls::core::graph::invalidations::InvalidationCause;
+use crate::impls::core::graph::invalidations::InvalidationCauses;
+use crate::impls::core::graph::invalidations::RecordedInvalidations;
 use crate::impls::core::graph::nodes::OccupiedGraphNode;
 use crate::impls::core::graph::nodes::VacantGraphNode;
 use crate::impls::core::graph::nodes::VersionedGraphNode;
@@ -37,6 +40,7 @@ use crate::impls::core::graph::types::VersionedGraphKey;
 use crate::impls::core::graph::types::VersionedGraphResult;
 use crate::impls::core::graph::types::VersionedGraphResultMismatch;
 use crate::impls::key::DiceKey;
+use crate::impls::persistence::PersistedNode;
 use crate::impls::value::DiceComputedValue;
 use crate::impls::value::DiceValidValue;
 use crate::versions::VersionNumber;
@@ -56,12 +60,38 @@ pub(crate) struct VersionedGraph {
     /// VacantGraphEntries can only be present when no other entries are present for the key at
     /// any version.
     pub(crate) last_n: HashMap<DiceKey, SortedVectorMap<VersionNumber, VersionedGraphNode>>,
+    /// when enabled, the most recent reason each key was invalidated
+    invalidations: Option<RecordedInvalidations>,
 }
 
 impl VersionedGraph {
     pub(crate) fn new() -> Self {
         Self {
             last_n: Default::default(),
+            invalidations: None,
+        }
+    }
+
+    /// Starts or stops recording the cause of every invalidation. Stopping discards everything
+    /// recorded so far.
+    pub(crate) fn record_invalidations(&mut self, enabled: bool) {
+        match (enabled, &self.invalidations) {
+            (true, None) => self.invalidations = Some(RecordedInvalidations::default()),
+            (false, _) => self.invalidations = None,
+            (true, Some(_)) => {}
+        }
+    }
+
+    /// The recorded cause of the latest invalidation of each key, if recording is enabled
+    pub(crate) fn invalidations(&self) -> Option<std::sync::Arc<InvalidationCauses>> {
+        self.invalidations.as_ref().map(|i| i.causes())
+    }
+
+    /// Drops all nodes, and anything recorded about them
+    pub(crate) fn clear(&mut self) {
+        self.last_n.clear();
+        if let Some(invalidations) = &mut self.invalidations {
+            invalidations.clear();
         }
     }
 
@@ -128,6 +158,31 @@ impl VersionedGraph {
         }
     }
 
+    /// Collects the node of every key whose value is verified at the given version.
+    pub(crate) fn verified_nodes_at(&self, v: VersionNumber) -> Vec<PersistedNode> {
+        self.last_n
+            .iter()
+            .filter_map(|(k, versioned)| {
+                match versioned
+                    .range((Bound::Included(VersionNumber::new(0)), Bound::Included(v)))
+                    .next_back()
+                {
+                    Some((_, VersionedGraphNode::Occupied(entry))) => {
+                        match entry.metadata().hist.get_history(&v) {
+                            HistoryState::Verified => Some(PersistedNode {
+                                key: *k,
+                                value: entry.val().dupe(),
+                                deps: entry.metadata().deps.deps(),
+                            }),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
     /// gets the cache entry corresponding to the cache entry if up to date.
     /// returns 'None' if entry is missing or versions are out of date.
     fn get_internal<'a>(
@@ -354,6 +409,36 @@ impl VersionedGraph {
         (ret, any_invalidated)
     }
 
+    /// Drops every node of the key, as long as no key remaining in the graph depends on it.
+    /// Returns an estimate of the bytes freed, and the deps of the evicted nodes.
+    pub(crate) fn evict(&mut self, key: DiceKey) -> Option<(usize, Vec<DiceKey>)> {
+        let versioned_map = self.last_n.get(&key)?;
+
+        let mut deps = Vec::new();
+        for node in versioned_map.values() {
+            if let VersionedGraphNode::Occupied(occ) = node {
+                if occ
+                    .metadata()
+                    .rdeps
+                    .rdeps()
+                    .keys()
+                    .any(|rdep| self.last_n.contains_key(rdep))
+                {
+                    return None;
+                }
+                deps.extend(occ.metadata().deps.deps().iter().copied());
+            }
+        }
+
+        let freed = allocative::size_of_unique_allocated_data(versioned_map);
+        self.last_n.remove(&key);
+        if let Some(invalidations) = &mut self.invalidations {
+            invalidations.remove(key);
+        }
+
+        Some((freed, deps))
+    }
+
     /// Invalidates an entry and its transitive rdeps. Returning true if this caused any type of
     /// change
     pub(crate) fn invalidate(
@@ -361,6 +446,16 @@ impl VersionedGraph {
         key: VersionedGraphKey,
         invalidate: InvalidateKind,
     ) -> bool {
+        let changed = self.invalidate_impl(key, invalidate);
+        if changed {
+            if let Some(invalidations) = &mut self.invalidations {
+                invalidations.insert(key.k, key.v, InvalidationCause::Changed);
+            }
+        }
+        changed
+    }
+
+    fn invalidate_impl(&mut self, key: VersionedGraphKey, invalidate: InvalidateKind) -> bool {
         let rdeps = {
             match invalidate {
                 invalidate @ (InvalidateKind::ForceDirty | InvalidateKind::Invalidate) => {
@@ -481,18 +576,28 @@ impl VersionedGraph {
             }
         };
 
-        self.invalidate_rdeps(key.v, rdeps);
+        self.invalidate_rdeps(key.v, key.k, rdeps);
         true
     }
 
     fn invalidate_rdeps(
         &mut self,
         version: VersionNumber,
-        mut queue: Vec<(DiceKey, VersionNumber)>,
+        changed: DiceKey,
+        rdeps: Vec<(DiceKey, VersionNumber)>,
     ) {
-        while let Some((rdep, relevant_version)) = queue.pop() {
+        // each queued rdep is paired with the dep through which it was reached
+        let mut queue = rdeps
+            .into_iter()
+            .map(|(rdep, v)| (rdep, v, changed))
+            .collect::<Vec<_>>();
+
+        while let Some((rdep, relevant_version, dep)) = queue.pop() {
+            let mut invalidated = false;
             if let Some(node) = self.get_internal(VersionedGraphKey::new(relevant_version, rdep)) {
                 if node.mark_invalidated(version) {
+                    invalidated = true;
+
                     // since dirty always occurs in increasing order, it must be the case that if
                     // the history was already dirtied, it was by a version number less than the
                     // current version number.
@@ -506,12 +611,18 @@ impl VersionedGraph {
 
                             rdeps
                                 .iter()
-                                .map(|(r, v)| (r.dupe(), *v))
+                                .map(|(r, v)| (r.dupe(), *v, rdep))
                                 .collect::<Vec<_>>()
                         })
                     }
                 }
             }
+
+            if invalidated {
+                if let Some(invalidations) = &mut self.invalidations {
+                    invalidations.insert(rdep, version, InvalidationCause::Dep(dep));
+                }
+            }
         }
     }
 }
@@ -655,6 +766,7 @@ mod tests {
     use crate::api::computations::DiceComputations;
     use crate::api::key::Key;
     use crate::arc::Arc;
+    use crate::impls::core::graph::invalidations::InvalidationCause;
     use crate::impls::core::graph::storage::testing::VersionedCacheResultAssertsExt;
     use crate::impls::core::graph::storage::InvalidateKind;
     use crate::impls::core::graph::storage::StorageType;
@@ -1274,6 +1386,88 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn records_invalidation_causes() -> anyhow::Result<()> {
+        let mut cache = VersionedGraph::new();
+        cache.record_invalidations(true);
+        let res = DiceValidValue::testing_new(DiceKeyValue::<K>::new(100));
+
+        let key = VersionedGraphKey::new(VersionNumber::new(0), DiceKey { index: 0 });
+        cache.update(key, res.dupe(), Arc::new(vec![]), StorageType::LastN(1));
+
+        let key1 = VersionedGraphKey::new(VersionNumber::new(0), DiceKey { index: 1 });
+        cache.update(
+            key1,
+            res.dupe(),
+            Arc::new(vec![DiceKey { index: 0 }]),
+            StorageType::LastN(1),
+        );
+
+        let key2 = VersionedGraphKey::new(VersionNumber::new(0), DiceKey { index: 2 });
+        cache.update(
+            key2,
+            res.dupe(),
+            Arc::new(vec![DiceKey { index: 1 }]),
+            StorageType::LastN(1),
+        );
+
+        assert!(cache.invalidate(
+            VersionedGraphKey::new(VersionNumber::new(1), DiceKey { index: 0 }),
+            InvalidateKind::ForceDirty
+        ));
+
+        let invalidations = cache.invalidations().unwrap();
+        assert_eq!(invalidations.len(), 3);
+        assert_eq!(
+            invalidations.get(&DiceKey { index: 0 }),
+            Some(&(VersionNumber::new(1), InvalidationCause::Changed))
+        );
+        assert_eq!(
+            invalidations.get(&DiceKey { index: 1 }),
+            Some(&(
+                VersionNumber::new(1),
+                InvalidationCause::Dep(DiceKey { index: 0 })
+            ))
+        );
+        assert_eq!(
+            invalidations.get(&DiceKey { index: 2 }),
+            Some(&(
+                VersionNumber::new(1),
+                InvalidationCause::Dep(DiceKey { index: 1 })
+            ))
+        );
+
+        cache.record_invalidations(false);
+        assert!(cache.invalidations().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn evict_forgets_invalidations() -> anyhow::Result<()> {
+        let mut cache = VersionedGraph::new();
+        cache.record_invalidations(true);
+        let res = DiceValidValue::testing_new(DiceKeyValue::<K>::new(100));
+
+        let key = DiceKey { index: 0 };
+        cache.update(
+            VersionedGraphKey::new(VersionNumber::new(0), key),
+            res,
+            Arc::new(vec![]),
+            StorageType::LastN(1),
+        );
+        assert!(cache.invalidate(
+            VersionedGraphKey::new(VersionNumber::new(1), key),
+            InvalidateKind::ForceDirty
+        ));
+        assert!(cache.invalidations().unwrap().contains_key(&key));
+
+        assert!(cache.evict(key).is_some());
+        assert!(!cache.invalidations().unwrap().contains_key(&key));
+
+        Ok(())
+    }
+
     #[test]
     fn dirty_same_nodes() -> anyhow::Result<()> {
         let mut cache = VersionedGraph::new();
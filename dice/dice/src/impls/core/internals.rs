@@ -11,9 +11,13 @@ use more_futures::cancellation::future::TerminationObserver;
 
 use crate::api::error::DiceError;
 use crate::api::error::DiceResult;
+use crate::api::eviction::DiceEvictionPolicy;
+use crate::api::persistence::DiceSnapshotError;
 use crate::api::storage_type::StorageType;
 use crate::arc::Arc;
 use crate::impls::cache::SharedCache;
+use crate::impls::core::eviction::EvictionTracker;
+use crate::impls::core::graph::invalidations::InvalidationCauses;
 use crate::impls::core::graph::storage::InvalidateKind;
 use crate::impls::core::graph::storage::VersionedGraph;
 use crate::impls::core::graph::types::VersionedGraphKey;
@@ -21,6 +25,7 @@ use crate::impls::core::graph::types::VersionedGraphResult;
 use crate::impls::core::versions::VersionEpoch;
 use crate::impls::core::versions::VersionTracker;
 use crate::impls::key::DiceKey;
+use crate::impls::persistence::PersistedNode;
 use crate::impls::transaction::ChangeType;
 use crate::impls::value::DiceComputedValue;
 use crate::impls::value::DiceValidValue;
@@ -35,6 +40,7 @@ use crate::HashMap;
 pub(super) struct CoreState {
     version_tracker: VersionTracker,
     graph: VersionedGraph,
+    eviction: EvictionTracker,
     pending_termination_tasks: Vec<TerminationObserver>,
 }
 
@@ -43,6 +49,7 @@ impl CoreState {
         Self {
             version_tracker: VersionTracker::new(),
             graph: VersionedGraph::new(),
+            eviction: EvictionTracker::new(),
             pending_termination_tasks: Vec::new(),
         }
     }
@@ -60,14 +67,20 @@ impl CoreState {
                 VersionedGraphKey::new(v, key),
                 match change {
                     ChangeType::Invalidate => InvalidateKind::ForceDirty,
-                    ChangeType::UpdateValue(v, s) => InvalidateKind::Update(v, s),
+                    ChangeType::UpdateValue(v, s) => {
+                        self.eviction.pin(key);
+                        InvalidateKind::Update(v, s)
+                    }
                     #[cfg(test)]
                     ChangeType::TestingSoftDirty => InvalidateKind::Invalidate,
                 },
             );
         }
         if changes_recorded {
-            version_update.commit()
+            let v = version_update.commit();
+            self.eviction
+                .evict(&mut self.graph, v, self.version_tracker.oldest_active());
+            v
         } else {
             version_update.undo()
         }
@@ -91,6 +104,7 @@ impl CoreState {
     }
 
     pub(super) fn lookup_key(&mut self, key: VersionedGraphKey) -> VersionedGraphResult {
+        self.eviction.requested(key.k, key.v);
         self.graph.get(key)
     }
 
@@ -105,6 +119,7 @@ impl CoreState {
         if self.version_tracker.is_relevant(key.v, epoch) {
             debug!(msg = "update graph entry", k = ?key.k, v = %key.v, v_epoch = %epoch);
 
+            self.eviction.requested(key.k, key.v);
             Ok(self.graph.update(key, value, deps, storage).0)
         } else {
             debug!(msg = "update is rejected due to outdated epoch", k = ?key.k, v = %key.v, v_epoch = %epoch);
@@ -122,7 +137,12 @@ impl CoreState {
 
     pub(super) fn unstable_drop_everything(&mut self) {
         self.version_tracker.write().commit();
-        self.graph.last_n.clear();
+        self.graph.clear();
+        self.eviction.clear();
+    }
+
+    pub(super) fn set_eviction_policy(&mut self, policy: DiceEvictionPolicy) {
+        self.eviction.set_policy(policy)
     }
 
     pub(super) fn metrics(&self) -> Metrics {
@@ -137,11 +157,52 @@ impl CoreState {
 
         Metrics {
             key_count: self.graph.last_n.len(),
+            evicted_key_count: self.eviction.evicted_key_count(),
             currently_active_key_count: currently_running_key_count,
             active_transaction_count: active_transaction_count as u32, // probably won't support more than u32 transactions
         }
     }
 
+    pub(super) fn snapshot(&self) -> Vec<PersistedNode> {
+        self.graph.verified_nodes_at(self.version_tracker.current())
+    }
+
+    pub(super) fn restore(
+        &mut self,
+        nodes: Vec<(PersistedNode, StorageType)>,
+    ) -> anyhow::Result<()> {
+        if self.version_tracker.current() != VersionNumber::ZERO
+            || self.version_tracker.currently_active().next().is_some()
+            || !self.graph.last_n.is_empty()
+        {
+            return Err(DiceSnapshotError::NotEmpty.into());
+        }
+
+        for (node, storage) in nodes {
+            // restored keys without deps may well have been injected, and so can't be recomputed
+            if node.deps.is_empty() {
+                self.eviction.pin(node.key);
+            }
+            self.eviction.requested(node.key, VersionNumber::ZERO);
+            self.graph.update(
+                VersionedGraphKey::new(VersionNumber::ZERO, node.key),
+                node.value,
+                node.deps,
+                storage,
+            );
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn record_invalidations(&mut self, enabled: bool) {
+        self.graph.record_invalidations(enabled)
+    }
+
+    pub(super) fn invalidations(&self) -> Option<std::sync::Arc<InvalidationCauses>> {
+        self.graph.invalidations()
+    }
+
     pub(super) fn introspection(&self, key_map: HashMap<DiceKey, AnyKey>) -> GraphIntrospectable {
         let graph = self.graph.introspect(key_map.clone());
         let version_data = self.version_tracker.introspect();
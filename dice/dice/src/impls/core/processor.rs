@@ -85,6 +85,19 @@ impl StateProcessor {
             StateRequest::Metrics { resp } => {
                 let _ignored = resp.send(self.state.metrics());
             }
+            StateRequest::Snapshot { resp } => {
+                let _ignored = resp.send(self.state.snapshot());
+            }
+            StateRequest::Restore { nodes, resp } => {
+                let _ignored = resp.send(self.state.restore(nodes));
+            }
+            StateRequest::SetEvictionPolicy { policy } => self.state.set_eviction_policy(policy),
+            StateRequest::RecordInvalidations { enabled } => {
+                self.state.record_invalidations(enabled)
+            }
+            StateRequest::Invalidations { resp } => {
+                let _ignored = resp.send(self.state.invalidations());
+            }
             StateRequest::Introspection { resp, key_map } => {
                 let _ignored = resp.send(self.state.introspection(key_map));
             }
@@ -15,14 +15,17 @@ use more_futures::cancellation::future::TerminationObserver;
 use tokio::sync::oneshot::Sender;
 
 use crate::api::error::DiceResult;
+use crate::api::eviction::DiceEvictionPolicy;
 use crate::api::storage_type::StorageType;
 use crate::arc::Arc;
+use crate::impls::core::graph::invalidations::InvalidationCauses;
 use crate::impls::core::graph::types::VersionedGraphKey;
 use crate::impls::core::graph::types::VersionedGraphResult;
 use crate::impls::core::processor::StateProcessor;
 use crate::impls::core::versions::VersionEpoch;
 use crate::impls::ctx::SharedLiveTransactionCtx;
 use crate::impls::key::DiceKey;
+use crate::impls::persistence::PersistedNode;
 use crate::impls::transaction::ActiveTransactionGuard;
 use crate::impls::transaction::ChangeType;
 use crate::impls::value::DiceComputedValue;
@@ -81,6 +84,27 @@ pub(crate) enum StateRequest {
     UnstableDropEverything,
     /// Collect metrics
     Metrics { resp: Sender<Metrics> },
+    /// Collects every node that is verified at the current version, for writing a snapshot
+    Snapshot {
+        #[derivative(Debug = "ignore")]
+        resp: Sender<Vec<PersistedNode>>,
+    },
+    /// Restores the nodes of a snapshot into an empty graph. The nodes must be ordered such that
+    /// every node comes after all of its deps.
+    Restore {
+        #[derivative(Debug = "ignore")]
+        nodes: Vec<(PersistedNode, StorageType)>,
+        resp: Sender<anyhow::Result<()>>,
+    },
+    /// Replaces the policy for evicting unused keys
+    SetEvictionPolicy { policy: DiceEvictionPolicy },
+    /// Starts or stops recording why keys are invalidated
+    RecordInvalidations { enabled: bool },
+    /// Collects the recorded invalidation causes, or `None` if recording is disabled
+    Invalidations {
+        #[derivative(Debug = "ignore")]
+        resp: Sender<Option<std::sync::Arc<InvalidationCauses>>>,
+    },
     /// Collects the introspectable dice state
     Introspection {
         resp: Sender<GraphIntrospectable>,
@@ -79,6 +79,11 @@ impl VersionTracker {
             .map(|data| (data.ref_count, &data.per_transaction_data))
     }
 
+    /// the oldest version that still has a live transaction, if any
+    pub(crate) fn oldest_active(&self) -> Option<VersionNumber> {
+        self.active_versions.keys().min().copied()
+    }
+
     /// hands out the current "latest" committed version's associated transaction context
     pub(crate) fn current(&self) -> VersionNumber {
         self.current
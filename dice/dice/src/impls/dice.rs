@@ -16,6 +16,8 @@ use dupe::Dupe;
 
 use crate::api::cycles::DetectCycles;
 use crate::api::data::DiceData;
+use crate::api::eviction::DiceEvictionPolicy;
+use crate::api::key::Key;
 use crate::api::user_data::UserComputationData;
 use crate::impls::core::state::init_state;
 use crate::impls::core::state::CoreStateHandle;
@@ -23,6 +25,9 @@ use crate::impls::core::state::StateRequest;
 use crate::impls::key_index::DiceKeyIndex;
 use crate::impls::transaction::TransactionUpdater;
 use crate::introspection::graph::GraphIntrospectable;
+use crate::introspection::invalidations::invalidation_path;
+use crate::introspection::invalidations::InvalidationPath;
+use crate::introspection::invalidations::InvalidationRecordingError;
 use crate::metrics::Metrics;
 
 #[derive(Allocative)]
@@ -100,6 +105,48 @@ impl DiceModern {
         rx.blocking_recv().unwrap()
     }
 
+    pub(crate) fn set_eviction_policy(&self, policy: DiceEvictionPolicy) {
+        self.state_handle
+            .request(StateRequest::SetEvictionPolicy { policy });
+    }
+
+    pub(crate) fn record_invalidations(&self, enabled: bool) {
+        self.state_handle
+            .request(StateRequest::RecordInvalidations { enabled });
+    }
+
+    pub(crate) async fn explain_invalidations<K: Key>(
+        &self,
+        filter: impl Fn(&K) -> bool,
+    ) -> anyhow::Result<Vec<InvalidationPath>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        self.state_handle
+            .request(StateRequest::Invalidations { resp: tx });
+
+        let recorded = rx.await?.ok_or(InvalidationRecordingError::NotRecording)?;
+
+        let mut paths = recorded
+            .keys()
+            .filter(|k| {
+                self.key_index
+                    .get(**k)
+                    .as_any()
+                    .downcast_ref::<K>()
+                    .map_or(false, &filter)
+            })
+            .map(|k| invalidation_path(*k, &recorded, |k| self.key_index.get(k).introspect()))
+            .collect::<Vec<_>>();
+        paths.sort_by(|a, b| {
+            a.keys
+                .last()
+                .map(|k| &k.key)
+                .cmp(&b.keys.last().map(|k| &k.key))
+        });
+
+        Ok(paths)
+    }
+
     /// Note: modern dice does not support cycle detection yet
     pub fn detect_cycles(&self) -> &DetectCycles {
         // TODO(bobyf) actually have cycles for dice modern
@@ -19,6 +19,7 @@ pub(crate) mod incremental;
 pub(crate) mod key;
 mod key_index;
 pub(crate) mod opaque;
+pub(crate) mod persistence;
 pub(crate) mod task;
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,237 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Writing and restoring snapshots of the modern DICE graph
+
+use std::any::Any;
+use std::marker::PhantomData;
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::api::persistence::DiceSnapshot;
+use crate::api::persistence::DiceSnapshotError;
+use crate::api::persistence::DiceSnapshotRegistry;
+use crate::api::persistence::PersistentKey;
+use crate::api::storage_type::StorageType;
+use crate::arc::Arc;
+use crate::impls::core::state::StateRequest;
+use crate::impls::dice::DiceModern;
+use crate::impls::key::CowDiceKeyHashed;
+use crate::impls::key::DiceKey;
+use crate::impls::key::DiceKeyErased;
+use crate::impls::value::DiceValidValue;
+use crate::HashMap;
+
+/// A node as stored in a `DiceSnapshot`. Dependencies are indexes of nodes that appear earlier
+/// in the snapshot.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SerializedNode {
+    persistent_id: String,
+    key: Vec<u8>,
+    value: Vec<u8>,
+    deps: Vec<u32>,
+}
+
+/// A verified node of the graph, as handed out by or to the core state.
+pub(crate) struct PersistedNode {
+    pub(crate) key: DiceKey,
+    pub(crate) value: DiceValidValue,
+    pub(crate) deps: Arc<Vec<DiceKey>>,
+}
+
+/// Type erased `PersistentKey`
+pub(crate) trait PersistentKeyDyn: Send + Sync + 'static {
+    fn serialize(&self, key: &dyn Any, value: &DiceValidValue) -> anyhow::Result<SerializedNode>;
+
+    fn deserialize(
+        &self,
+        node: &SerializedNode,
+    ) -> anyhow::Result<(CowDiceKeyHashed<'static>, DiceValidValue, StorageType)>;
+}
+
+pub(crate) struct PersistentKeyImpl<K>(PhantomData<fn() -> K>);
+
+impl<K> PersistentKeyImpl<K> {
+    pub(crate) fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<K: PersistentKey> PersistentKeyDyn for PersistentKeyImpl<K> {
+    fn serialize(&self, key: &dyn Any, value: &DiceValidValue) -> anyhow::Result<SerializedNode> {
+        let key = key
+            .downcast_ref::<K>()
+            .context("registered persistent key of the wrong type")?;
+        let value = value
+            .downcast_ref::<K::Value>()
+            .context("value of the wrong type stored for key")?;
+
+        Ok(SerializedNode {
+            persistent_id: K::PERSISTENT_ID.to_owned(),
+            key: bincode::serialize(key).with_context(|| format!("serializing key `{}`", key))?,
+            value: K::serialize_value(value)
+                .with_context(|| format!("serializing value of key `{}`", key))?,
+            deps: Vec::new(),
+        })
+    }
+
+    fn deserialize(
+        &self,
+        node: &SerializedNode,
+    ) -> anyhow::Result<(CowDiceKeyHashed<'static>, DiceValidValue, StorageType)> {
+        let key: K = bincode::deserialize(&node.key)
+            .with_context(|| format!("deserializing key of type `{}`", K::PERSISTENT_ID))?;
+        let value = K::deserialize_value(&node.value)
+            .with_context(|| format!("deserializing value of key `{}`", key))?;
+
+        Ok((
+            CowDiceKeyHashed::key(key),
+            DiceValidValue::key::<K>(value),
+            K::storage_type(),
+        ))
+    }
+}
+
+impl DiceModern {
+    pub(crate) async fn snapshot(
+        &self,
+        registry: &DiceSnapshotRegistry,
+        validation_token: String,
+    ) -> anyhow::Result<DiceSnapshot> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.state_handle
+            .request(StateRequest::Snapshot { resp: tx });
+        let verified = rx.await?;
+
+        let by_key: HashMap<DiceKey, &PersistedNode> =
+            verified.iter().map(|node| (node.key, node)).collect();
+
+        // index of each visited key in the snapshot, or `None` if it can't be persisted because it
+        // or one of its transitive deps is not a registered `PersistentKey`
+        let mut indexes: HashMap<DiceKey, Option<u32>> = HashMap::default();
+        let mut nodes = Vec::new();
+
+        for root in &verified {
+            let mut stack = vec![(root.key, false)];
+            while let Some((key, deps_visited)) = stack.pop() {
+                if indexes.contains_key(&key) {
+                    continue;
+                }
+                let node = match by_key.get(&key) {
+                    Some(node) => node,
+                    None => {
+                        indexes.insert(key, None);
+                        continue;
+                    }
+                };
+
+                if !deps_visited {
+                    stack.push((key, true));
+                    stack.extend(
+                        node.deps
+                            .iter()
+                            .filter(|dep| !indexes.contains_key(dep))
+                            .map(|dep| (*dep, false)),
+                    );
+                    continue;
+                }
+
+                let deps: Option<Vec<u32>> = node
+                    .deps
+                    .iter()
+                    .map(|dep| indexes.get(dep).copied().flatten())
+                    .collect();
+
+                let index = match (deps, self.key_index.get(key)) {
+                    (Some(deps), DiceKeyErased::Key(k)) => {
+                        match registry.by_type.get(&k.as_any().type_id()) {
+                            Some(persistence) => {
+                                let mut serialized =
+                                    persistence.serialize(k.as_any(), &node.value)?;
+                                serialized.deps = deps;
+                                nodes.push(serialized);
+                                Some((nodes.len() - 1) as u32)
+                            }
+                            None => None,
+                        }
+                    }
+                    _ => None,
+                };
+                indexes.insert(key, index);
+            }
+        }
+
+        debug!(
+            msg = "snapshotted dice graph",
+            verified = verified.len(),
+            persisted = nodes.len()
+        );
+
+        Ok(DiceSnapshot {
+            validation_token,
+            nodes,
+        })
+    }
+
+    /// Restores the nodes of the snapshot, returning the number of nodes restored. Nodes whose
+    /// key type is no longer registered are skipped, as are all the nodes that depend on them.
+    pub(crate) async fn restore(
+        &self,
+        registry: &DiceSnapshotRegistry,
+        snapshot: DiceSnapshot,
+    ) -> anyhow::Result<usize> {
+        let mut keys: Vec<Option<DiceKey>> = Vec::with_capacity(snapshot.nodes.len());
+        let mut restored = Vec::new();
+
+        for (i, node) in snapshot.nodes.iter().enumerate() {
+            let deps: Option<Vec<DiceKey>> = node
+                .deps
+                .iter()
+                .map(|dep| {
+                    keys.get(*dep as usize)
+                        .copied()
+                        .ok_or(DiceSnapshotError::InvalidDependency { node: i, dep: *dep })
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .collect();
+
+            let key = match (deps, registry.by_id.get(node.persistent_id.as_str())) {
+                (Some(deps), Some(persistence)) => {
+                    let (key, value, storage) = persistence.deserialize(node)?;
+                    let key = self.key_index.index(key);
+                    restored.push((
+                        PersistedNode {
+                            key,
+                            value,
+                            deps: Arc::new(deps),
+                        },
+                        storage,
+                    ));
+                    Some(key)
+                }
+                _ => None,
+            };
+            keys.push(key);
+        }
+
+        let count = restored.len();
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        self.state_handle.request(StateRequest::Restore {
+            nodes: restored,
+            resp: tx,
+        });
+        rx.await??;
+
+        Ok(count)
+    }
+}
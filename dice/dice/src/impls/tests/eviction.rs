@@ -0,0 +1,131 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use derive_more::Display;
+use dupe::Dupe;
+use more_futures::cancellation::CancellationContext;
+
+use crate::api::computations::DiceComputations;
+use crate::api::data::DiceData;
+use crate::api::eviction::DiceEvictionPolicy;
+use crate::api::injected::InjectedKey;
+use crate::api::key::Key;
+use crate::impls::dice::DiceModern;
+
+#[derive(Allocative, Clone, Dupe, Debug, Display, Eq, Hash, PartialEq)]
+#[display(fmt = "{:?}", self)]
+struct Input(u32);
+
+impl InjectedKey for Input {
+    type Value = u32;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+struct ComputeCount(AtomicUsize);
+
+#[derive(Allocative, Clone, Dupe, Debug, Display, Eq, Hash, PartialEq)]
+#[display(fmt = "{:?}", self)]
+struct Doubled(u32);
+
+#[async_trait]
+impl Key for Doubled {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.global_data()
+            .get::<Arc<ComputeCount>>()
+            .unwrap()
+            .0
+            .fetch_add(1, Ordering::SeqCst);
+        ctx.compute(&Input(self.0)).await.unwrap() * 2
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unused_keys_are_evicted_and_recomputed() -> anyhow::Result<()> {
+    let count = Arc::new(ComputeCount(AtomicUsize::new(0)));
+    let mut data = DiceData::new();
+    data.set(count.dupe());
+    let dice = DiceModern::new(data);
+    dice.set_eviction_policy(DiceEvictionPolicy {
+        max_unused_versions: Some(1),
+        memory_budget_bytes: None,
+    });
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(1), 1), (Input(2), 1)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Doubled(1)).await?, 2);
+    drop(ctx);
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(2), 2)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Doubled(2)).await?, 4);
+    drop(ctx);
+    // `Doubled(1)` was requested in the previous version, so is still kept
+    assert_eq!(dice.metrics().evicted_key_count, 0);
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(2), 3)])?;
+    let ctx = updater.commit().await;
+    // only `Doubled(1)` is evicted, injected keys are never evicted
+    assert_eq!(dice.metrics().evicted_key_count, 1);
+    assert_eq!(count.0.load(Ordering::SeqCst), 2);
+
+    assert_eq!(ctx.compute(&Doubled(1)).await?, 2);
+    assert_eq!(count.0.load(Ordering::SeqCst), 3);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn keys_requested_by_active_transactions_are_kept() -> anyhow::Result<()> {
+    let count = Arc::new(ComputeCount(AtomicUsize::new(0)));
+    let mut data = DiceData::new();
+    data.set(count.dupe());
+    let dice = DiceModern::new(data);
+    dice.set_eviction_policy(DiceEvictionPolicy {
+        max_unused_versions: Some(0),
+        memory_budget_bytes: None,
+    });
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(1), 1), (Input(2), 1)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Doubled(1)).await?, 2);
+
+    // `ctx` is still alive, so nothing it requested may be evicted
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(2), 2)])?;
+    let _ctx2 = updater.commit().await;
+    assert_eq!(dice.metrics().evicted_key_count, 0);
+
+    assert_eq!(ctx.compute(&Doubled(1)).await?, 2);
+    assert_eq!(count.0.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use derive_more::Display;
+use dupe::Dupe;
+use more_futures::cancellation::CancellationContext;
+
+use crate::api::computations::DiceComputations;
+use crate::api::data::DiceData;
+use crate::api::injected::InjectedKey;
+use crate::api::key::Key;
+use crate::impls::dice::DiceModern;
+
+#[derive(Allocative, Clone, Dupe, Debug, Display, Eq, Hash, PartialEq)]
+#[display(fmt = "{:?}", self)]
+struct Input(u32);
+
+impl InjectedKey for Input {
+    type Value = u32;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Allocative, Clone, Dupe, Debug, Display, Eq, Hash, PartialEq)]
+#[display(fmt = "{:?}", self)]
+struct Middle(u32);
+
+#[async_trait]
+impl Key for Middle {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.compute(&Input(self.0)).await.unwrap() + 1
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[derive(Allocative, Clone, Dupe, Debug, Display, Eq, Hash, PartialEq)]
+#[display(fmt = "{:?}", self)]
+struct Top(u32);
+
+#[async_trait]
+impl Key for Top {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.compute(&Middle(self.0)).await.unwrap() + 1
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+#[tokio::test]
+async fn explains_transitive_invalidation() -> anyhow::Result<()> {
+    let dice = DiceModern::new(DiceData::new());
+    dice.record_invalidations(true);
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(1), 1), (Input(2), 1)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Top(1)).await?, 3);
+    assert_eq!(ctx.compute(&Top(2)).await?, 3);
+    drop(ctx);
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(1), 5)])?;
+    drop(updater.commit().await);
+
+    let paths = dice.explain_invalidations(|_: &Top| true).await?;
+    assert_eq!(paths.len(), 1);
+    let path = &paths[0];
+    assert!(!path.truncated);
+    assert_eq!(
+        path.keys.iter().map(|k| k.key.as_str()).collect::<Vec<_>>(),
+        vec!["Input(1)", "Middle(1)", "Top(1)"]
+    );
+
+    assert_eq!(dice.explain_invalidations(|k: &Top| k.0 == 1).await?, paths);
+    assert!(
+        dice.explain_invalidations(|k: &Top| k.0 == 2)
+            .await?
+            .is_empty()
+    );
+    // only keys of the requested type are explained
+    assert_eq!(
+        dice.explain_invalidations(|_: &Middle| true).await?[0]
+            .keys
+            .last()
+            .map(|k| k.key.as_str()),
+        Some("Middle(1)")
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn explain_requires_recording() -> anyhow::Result<()> {
+    let dice = DiceModern::new(DiceData::new());
+    assert!(dice.explain_invalidations(|_: &Top| true).await.is_err());
+
+    dice.record_invalidations(true);
+    assert!(dice.explain_invalidations(|_: &Top| true).await?.is_empty());
+
+    Ok(())
+}
@@ -10,8 +10,11 @@
 mod activation_tracker;
 mod demo;
 mod events;
+mod eviction;
 mod general;
+mod invalidations;
 mod keys;
+mod persistence;
 mod spawner;
 mod transients;
 mod user_data;
@@ -0,0 +1,203 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use derive_more::Display;
+use dupe::Dupe;
+use more_futures::cancellation::CancellationContext;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::api::computations::DiceComputations;
+use crate::api::data::DiceData;
+use crate::api::injected::InjectedKey;
+use crate::api::key::Key;
+use crate::api::persistence::DiceSnapshot;
+use crate::api::persistence::DiceSnapshotRegistry;
+use crate::api::persistence::PersistentKey;
+use crate::impls::dice::DiceModern;
+
+#[derive(Allocative, Clone, Dupe, Debug, Display, Eq, Hash, PartialEq)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "{:?}", self)]
+struct Input(u32);
+
+impl InjectedKey for Input {
+    type Value = u32;
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+impl PersistentKey for Input {
+    const PERSISTENT_ID: &'static str = "Input";
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn deserialize_value(bytes: &[u8]) -> anyhow::Result<Self::Value> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+struct ComputeCount(AtomicUsize);
+
+#[derive(Allocative, Clone, Dupe, Debug, Display, Eq, Hash, PartialEq)]
+#[derive(Serialize, Deserialize)]
+#[display(fmt = "{:?}", self)]
+struct Doubled(u32);
+
+#[async_trait]
+impl Key for Doubled {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.global_data()
+            .get::<Arc<ComputeCount>>()
+            .unwrap()
+            .0
+            .fetch_add(1, Ordering::SeqCst);
+        ctx.compute(&Input(self.0)).await.unwrap() * 2
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+impl PersistentKey for Doubled {
+    const PERSISTENT_ID: &'static str = "Doubled";
+
+    fn serialize_value(value: &Self::Value) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn deserialize_value(bytes: &[u8]) -> anyhow::Result<Self::Value> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+/// Depends on persistent keys, but is not itself persistent
+#[derive(Allocative, Clone, Dupe, Debug, Display, Eq, Hash, PartialEq)]
+#[display(fmt = "{:?}", self)]
+struct Unregistered(u32);
+
+#[async_trait]
+impl Key for Unregistered {
+    type Value = u32;
+
+    async fn compute(
+        &self,
+        ctx: &DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        ctx.compute(&Doubled(self.0)).await.unwrap() + 1
+    }
+
+    fn equality(x: &Self::Value, y: &Self::Value) -> bool {
+        x == y
+    }
+}
+
+fn registry() -> DiceSnapshotRegistry {
+    let mut registry = DiceSnapshotRegistry::new();
+    registry.register::<Input>();
+    registry.register::<Doubled>();
+    registry
+}
+
+fn dice_with_counter() -> (Arc<DiceModern>, Arc<ComputeCount>) {
+    let count = Arc::new(ComputeCount(AtomicUsize::new(0)));
+    let mut data = DiceData::new();
+    data.set(count.dupe());
+    (DiceModern::new(data), count)
+}
+
+#[tokio::test]
+async fn snapshot_restore_roundtrip() -> anyhow::Result<()> {
+    let (dice, count) = dice_with_counter();
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(1), 5)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Unregistered(1)).await?, 11);
+    assert_eq!(count.0.load(Ordering::SeqCst), 1);
+    drop(ctx);
+
+    let snapshot = dice.snapshot(&registry(), "token".to_owned()).await?;
+    // `Unregistered` is not persisted, but everything it depends on is.
+    assert_eq!(snapshot.len(), 2);
+
+    let mut bytes = Vec::new();
+    snapshot.write(&mut bytes)?;
+    let snapshot = DiceSnapshot::read(bytes.as_slice())?;
+    assert_eq!(snapshot.validation_token(), "token");
+
+    let (restored, count) = dice_with_counter();
+    assert_eq!(restored.restore(&registry(), snapshot).await?, 2);
+
+    let ctx = restored.updater().commit().await;
+    assert_eq!(ctx.compute(&Doubled(1)).await?, 10);
+    assert_eq!(ctx.compute(&Unregistered(1)).await?, 11);
+    assert_eq!(count.0.load(Ordering::SeqCst), 0);
+    drop(ctx);
+
+    // invalidations propagate through the restored edges
+    let mut updater = restored.updater();
+    updater.changed_to(vec![(Input(1), 6)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Unregistered(1)).await?, 13);
+    assert_eq!(count.0.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn snapshot_skips_keys_with_unregistered_deps() -> anyhow::Result<()> {
+    let (dice, _count) = dice_with_counter();
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(1), 5)])?;
+    let ctx = updater.commit().await;
+    assert_eq!(ctx.compute(&Doubled(1)).await?, 10);
+    drop(ctx);
+
+    let mut registry = DiceSnapshotRegistry::new();
+    registry.register::<Doubled>();
+
+    let snapshot = dice.snapshot(&registry, "token".to_owned()).await?;
+    assert!(snapshot.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restore_into_updated_dice_fails() -> anyhow::Result<()> {
+    let (dice, _count) = dice_with_counter();
+
+    let mut updater = dice.updater();
+    updater.changed_to(vec![(Input(1), 5)])?;
+    drop(updater.commit().await);
+
+    let snapshot = dice.snapshot(&registry(), "token".to_owned()).await?;
+    assert!(dice.restore(&registry(), snapshot).await.is_err());
+
+    Ok(())
+}
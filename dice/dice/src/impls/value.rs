@@ -31,7 +31,10 @@ impl Debug for DiceValidValue {
 }
 
 impl DiceValidValue {
-    #[cfg(test)]
+    pub(crate) fn key<K: Key>(value: K::Value) -> Self {
+        Self(std::sync::Arc::new(DiceKeyValue::<K>::new(value)))
+    }
+
     pub(crate) fn downcast_ref<V: Any>(&self) -> Option<&V> {
         self.0.downcast_ref()
     }
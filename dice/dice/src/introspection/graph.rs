@@ -133,6 +133,7 @@ pub struct NodeID(pub usize);
     Copy,
     Ord,
     PartialOrd,
+    Debug,
     derive_more::Display
 )]
 pub struct VersionNumber(pub usize);
@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//!
+//! Explanations of why keys were invalidated, built from the invalidation causes DICE records
+//! when `Dice::record_invalidations` is enabled.
+
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use thiserror::Error;
+
+use crate::impls::core::graph::invalidations::InvalidationCause;
+use crate::impls::core::graph::invalidations::InvalidationCauses;
+use crate::impls::key::DiceKey;
+use crate::introspection::graph::AnyKey;
+use crate::introspection::graph::VersionNumber;
+use crate::HashSet;
+
+/// A key along an `InvalidationPath`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidatedKey {
+    pub type_name: String,
+    pub key: String,
+}
+
+impl Display for InvalidatedKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.key, self.type_name)
+    }
+}
+
+/// The chain of invalidations that led to a key being invalidated, starting at the key that was
+/// changed and ending at the key being explained.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidationPath {
+    /// The version at which the key was last invalidated
+    pub version: VersionNumber,
+    pub keys: Vec<InvalidatedKey>,
+    /// Set when the start of the chain is no longer known, because a key along it has since been
+    /// invalidated again by a later version. The first key is then the earliest one known.
+    pub truncated: bool,
+}
+
+impl Display for InvalidationPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut keys = self.keys.iter();
+        if let Some(first) = keys.next() {
+            if self.truncated {
+                writeln!(f, "{} (cause no longer recorded)", first)?;
+            } else {
+                writeln!(f, "{} changed at {}", first, self.version)?;
+            }
+        }
+        for key in keys {
+            writeln!(f, "  -> {}", key)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum InvalidationRecordingError {
+    #[error("Recording invalidations is only supported by the modern DICE implementation")]
+    Unsupported,
+    #[error(
+        "DICE is not recording invalidations. Enable it with `buck2.dice_record_invalidations = true`"
+    )]
+    NotRecording,
+}
+
+/// Builds the path explaining the latest invalidation of `key`, which must be recorded.
+pub(crate) fn invalidation_path(
+    key: DiceKey,
+    recorded: &InvalidationCauses,
+    introspect: impl Fn(DiceKey) -> AnyKey,
+) -> InvalidationPath {
+    let (version, mut cause) = recorded[&key];
+
+    let mut path = vec![key];
+    let mut seen = HashSet::default();
+    seen.insert(key);
+    let mut truncated = false;
+
+    while let InvalidationCause::Dep(dep) = cause {
+        path.push(dep);
+        match recorded.get(&dep) {
+            Some((dep_version, dep_cause)) if *dep_version == version && seen.insert(dep) => {
+                cause = *dep_cause;
+            }
+            _ => {
+                truncated = true;
+                break;
+            }
+        }
+    }
+    path.reverse();
+
+    InvalidationPath {
+        version: version.to_introspectable(),
+        keys: path
+            .into_iter()
+            .map(|k| {
+                let key = introspect(k);
+                InvalidatedKey {
+                    type_name: key.short_type_name().to_owned(),
+                    key: key.to_string(),
+                }
+            })
+            .collect(),
+        truncated,
+    }
+}
@@ -225,6 +225,7 @@ impl DiceLegacy {
             active_transaction_count: self
                 .active_transaction_count
                 .load(std::sync::atomic::Ordering::SeqCst),
+            evicted_key_count: 0,
         }
     }
 
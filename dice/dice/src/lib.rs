@@ -249,9 +249,14 @@ pub use crate::api::error::DiceError;
 pub use crate::api::error::DiceResult;
 pub use crate::api::events::DiceEvent;
 pub use crate::api::events::DiceEventListener;
+pub use crate::api::eviction::DiceEvictionPolicy;
 pub use crate::api::injected::InjectedKey;
 pub use crate::api::key::Key;
 pub use crate::api::opaque::OpaqueValue;
+pub use crate::api::persistence::DiceSnapshot;
+use crate::api::persistence::DiceSnapshotError;
+pub use crate::api::persistence::DiceSnapshotRegistry;
+pub use crate::api::persistence::PersistentKey;
 pub use crate::api::projection::DiceProjectionComputations;
 pub use crate::api::projection::ProjectionKey;
 pub use crate::api::transaction::DiceEquality;
@@ -262,9 +267,12 @@ pub use crate::api::user_data::UserCycleDetector;
 pub use crate::api::user_data::UserCycleDetectorGuard;
 pub use crate::api::which::WhichDice;
 pub use crate::api::which::WhichSpawner;
+use crate::impls::core::eviction::DiceEvictionError;
 use crate::impls::dice::DiceModern;
 use crate::impls::dice::DiceModernDataBuilder;
 use crate::introspection::graph::GraphIntrospectable;
+use crate::introspection::invalidations::InvalidationPath;
+use crate::introspection::invalidations::InvalidationRecordingError;
 use crate::introspection::serialize_dense_graph;
 use crate::introspection::serialize_graph;
 use crate::legacy::DiceLegacy;
@@ -325,6 +333,58 @@ impl DiceImplementation {
         }
     }
 
+    pub async fn snapshot(
+        &self,
+        registry: &DiceSnapshotRegistry,
+        validation_token: String,
+    ) -> anyhow::Result<DiceSnapshot> {
+        match self {
+            DiceImplementation::Legacy(_) => Err(DiceSnapshotError::Unsupported.into()),
+            DiceImplementation::Modern(dice) => dice.snapshot(registry, validation_token).await,
+        }
+    }
+
+    pub async fn restore(
+        &self,
+        registry: &DiceSnapshotRegistry,
+        snapshot: DiceSnapshot,
+    ) -> anyhow::Result<usize> {
+        match self {
+            DiceImplementation::Legacy(_) => Err(DiceSnapshotError::Unsupported.into()),
+            DiceImplementation::Modern(dice) => dice.restore(registry, snapshot).await,
+        }
+    }
+
+    pub fn set_eviction_policy(&self, policy: DiceEvictionPolicy) -> anyhow::Result<()> {
+        match self {
+            DiceImplementation::Legacy(_) => Err(DiceEvictionError::Unsupported.into()),
+            DiceImplementation::Modern(dice) => {
+                dice.set_eviction_policy(policy);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn record_invalidations(&self, enabled: bool) -> anyhow::Result<()> {
+        match self {
+            DiceImplementation::Legacy(_) => Err(InvalidationRecordingError::Unsupported.into()),
+            DiceImplementation::Modern(dice) => {
+                dice.record_invalidations(enabled);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn explain_invalidations<K: Key>(
+        &self,
+        filter: impl Fn(&K) -> bool,
+    ) -> anyhow::Result<Vec<InvalidationPath>> {
+        match self {
+            DiceImplementation::Legacy(_) => Err(InvalidationRecordingError::Unsupported.into()),
+            DiceImplementation::Modern(dice) => dice.explain_invalidations(filter).await,
+        }
+    }
+
     pub fn detect_cycles(&self) -> &DetectCycles {
         match self {
             DiceImplementation::Legacy(dice) => dice.detect_cycles(),
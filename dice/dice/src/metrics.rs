@@ -14,4 +14,6 @@ pub struct Metrics {
     /// The number of keys currently active in the per transaction cache
     pub currently_active_key_count: usize,
     pub active_transaction_count: u32,
+    /// The total number of keys evicted for being unused
+    pub evicted_key_count: usize,
 }
@@ -9,14 +9,19 @@
 
 use std::collections::HashMap;
 use std::env::VarError;
+use std::io::Write;
 use std::pin::Pin;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
 
-use anyhow::Context;
+use anyhow::Context as _;
+use buck2_credential_helper::CredentialHelper;
 use buck2_re_configuration::Buck2OssReConfiguration;
 use buck2_re_configuration::HttpHeader;
-use dupe::Dupe;
 use futures::future::BoxFuture;
 use futures::future::Future;
 use futures::stream::BoxStream;
@@ -26,6 +31,9 @@ use futures::Stream;
 use gazebo::prelude::*;
 use once_cell::sync::Lazy;
 use prost::Message;
+use re_grpc_proto::build::bazel::remote::asset::v1::fetch_client::FetchClient;
+use re_grpc_proto::build::bazel::remote::asset::v1::FetchBlobRequest as GFetchBlobRequest;
+use re_grpc_proto::build::bazel::remote::asset::v1::Qualifier as GQualifier;
 use re_grpc_proto::build::bazel::remote::execution::v2::action_cache_client::ActionCacheClient;
 use re_grpc_proto::build::bazel::remote::execution::v2::batch_update_blobs_request::Request;
 use re_grpc_proto::build::bazel::remote::execution::v2::capabilities_client::CapabilitiesClient;
@@ -38,21 +46,28 @@ use re_grpc_proto::build::bazel::remote::execution::v2::BatchReadBlobsRequest;
 use re_grpc_proto::build::bazel::remote::execution::v2::BatchReadBlobsResponse;
 use re_grpc_proto::build::bazel::remote::execution::v2::BatchUpdateBlobsRequest;
 use re_grpc_proto::build::bazel::remote::execution::v2::BatchUpdateBlobsResponse;
+use re_grpc_proto::build::bazel::remote::execution::v2::CacheCapabilities;
 use re_grpc_proto::build::bazel::remote::execution::v2::Digest;
 use re_grpc_proto::build::bazel::remote::execution::v2::ExecuteOperationMetadata;
 use re_grpc_proto::build::bazel::remote::execution::v2::ExecuteRequest as GExecuteRequest;
 use re_grpc_proto::build::bazel::remote::execution::v2::ExecuteResponse as GExecuteResponse;
+use re_grpc_proto::build::bazel::remote::execution::v2::ExecutedActionMetadata;
 use re_grpc_proto::build::bazel::remote::execution::v2::FindMissingBlobsRequest;
 use re_grpc_proto::build::bazel::remote::execution::v2::FindMissingBlobsResponse;
 use re_grpc_proto::build::bazel::remote::execution::v2::GetActionResultRequest;
 use re_grpc_proto::build::bazel::remote::execution::v2::GetCapabilitiesRequest;
+use re_grpc_proto::build::bazel::remote::execution::v2::OutputDirectory;
+use re_grpc_proto::build::bazel::remote::execution::v2::OutputFile;
 use re_grpc_proto::build::bazel::remote::execution::v2::ResultsCachePolicy;
+use re_grpc_proto::build::bazel::remote::execution::v2::UpdateActionResultRequest;
+use re_grpc_proto::build::bazel::remote::execution::v2::WaitExecutionRequest;
 use re_grpc_proto::google::bytestream::byte_stream_client::ByteStreamClient;
 use re_grpc_proto::google::bytestream::ReadRequest;
 use re_grpc_proto::google::bytestream::ReadResponse;
 use re_grpc_proto::google::bytestream::WriteRequest;
 use re_grpc_proto::google::bytestream::WriteResponse;
 use re_grpc_proto::google::longrunning::operation::Result as OpResult;
+use re_grpc_proto::google::longrunning::Operation;
 use re_grpc_proto::google::rpc::Code;
 use re_grpc_proto::google::rpc::Status;
 use regex::Regex;
@@ -69,11 +84,14 @@ use tonic::transport::Certificate;
 use tonic::transport::Channel;
 use tonic::transport::Identity;
 use tonic::transport::Uri;
+use tonic::Streaming;
 
 use crate::error::*;
 use crate::metadata::*;
 use crate::request::*;
 use crate::response::*;
+use crate::retry::RetryObserver;
+use crate::retry::RetryPolicy;
 
 // RBE Services (e.g. Buildbarn) may not be robust against having too many files open at
 // once. Limit to an arbitrary reasonable number since this information is not expressed
@@ -82,6 +100,9 @@ const CONCURRENT_UPLOAD_LIMIT: usize = 64;
 
 const DEFAULT_MAX_MSG_SIZE: usize = 4 * 1000 * 1000;
 
+// Favor speed over ratio: blobs are compressed on the critical path of uploads.
+const ZSTD_LEVEL: i32 = 1;
+
 fn tdigest_to(tdigest: TDigest) -> Digest {
     Digest {
         hash: tdigest.hash,
@@ -127,6 +148,13 @@ fn ttimestamp_from(ts: Option<::prost_types::Timestamp>) -> TTimestamp {
     }
 }
 
+fn ttimestamp_to(ts: TTimestamp) -> Option<::prost_types::Timestamp> {
+    Some(::prost_types::Timestamp {
+        seconds: ts.seconds,
+        nanos: ts.nanos,
+    })
+}
+
 async fn create_tls_config(opts: &Buck2OssReConfiguration) -> anyhow::Result<ClientTlsConfig> {
     let config = ClientTlsConfig::new();
 
@@ -198,6 +226,36 @@ fn prepare_uri(uri: Uri, tls: bool) -> anyhow::Result<Uri> {
     Ok(Uri::from_parts(parts)?)
 }
 
+/// The path of the socket if `address` is a unix domain socket, i.e. `unix:///path/to/socket` or
+/// `unix:path/to/socket`.
+fn unix_socket_path(address: &str) -> Option<&str> {
+    address
+        .strip_prefix("unix://")
+        .or_else(|| address.strip_prefix("unix:"))
+}
+
+/// Connect to a server listening on a unix domain socket, such as a local caching proxy. TLS is
+/// never used for those.
+#[cfg(unix)]
+async fn connect_unix(path: String) -> anyhow::Result<Channel> {
+    use tower::service_fn;
+
+    // The connector ignores this URI, but one is required to build the endpoint.
+    let channel = Channel::builder(Uri::from_static("http://localhost"))
+        .connect_with_connector(service_fn(move |_: Uri| {
+            tokio::net::UnixStream::connect(path.clone())
+        }))
+        .await?;
+    Ok(channel)
+}
+
+#[cfg(not(unix))]
+async fn connect_unix(_path: String) -> anyhow::Result<Channel> {
+    Err(anyhow::anyhow!(
+        "Unix domain sockets are not supported on this platform"
+    ))
+}
+
 /// Contains information queried from the the Remote Execution Capabilities service.
 pub struct RECapabilities {
     /// Largest size of a message before being uploaded using bytestream service.
@@ -205,6 +263,330 @@ pub struct RECapabilities {
     max_msg_size: usize,
     /// Does the remote server support execution.
     exec_enabled: bool,
+    /// Compressors to use when transferring blobs with the CAS.
+    compression: Compression,
+}
+
+/// Compressors to use for CAS transfers, negotiated from the capabilities of the remote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Compression {
+    /// Used for ByteStream reads and writes, and accepted in batch reads.
+    bytestream: compressor::Value,
+    /// Used for blobs inlined in batch updates.
+    batch_update: compressor::Value,
+}
+
+impl Compression {
+    const IDENTITY: Self = Self {
+        bytestream: compressor::Value::Identity,
+        batch_update: compressor::Value::Identity,
+    };
+
+    /// Use zstd wherever the remote advertises support for it, if compression is enabled.
+    fn negotiate(enabled: bool, cache_cap: &CacheCapabilities) -> Self {
+        let pick = |supported: &[i32]| {
+            if enabled && supported.contains(&(compressor::Value::Zstd as i32)) {
+                compressor::Value::Zstd
+            } else {
+                compressor::Value::Identity
+            }
+        };
+
+        Self {
+            bytestream: pick(&cache_cap.supported_compressors),
+            batch_update: pick(&cache_cap.supported_batch_update_compressors),
+        }
+    }
+
+    fn acceptable_compressors(&self) -> Vec<i32> {
+        // Identity is always acceptable, even when not listed.
+        let mut compressors = vec![compressor::Value::Identity as i32];
+        if self.bytestream != compressor::Value::Identity {
+            compressors.push(self.bytestream as i32);
+        }
+        compressors
+    }
+}
+
+/// The part of a ByteStream resource name that identifies a blob: `blobs/{hash}/{size}`, or
+/// `compressed-blobs/{compressor}/{hash}/{size}` for compressed transfers.
+fn blob_resource_path(compressor: compressor::Value, hash: &str, size: i64) -> String {
+    match compressor {
+        compressor::Value::Identity => format!("blobs/{}/{}", hash, size),
+        compressor::Value::Zstd => format!("compressed-blobs/zstd/{}/{}", hash, size),
+        compressor::Value::Deflate => format!("compressed-blobs/deflate/{}/{}", hash, size),
+    }
+}
+
+fn compress(compressor: compressor::Value, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    match compressor {
+        compressor::Value::Identity => Ok(data),
+        compressor::Value::Zstd => {
+            zstd::bulk::compress(&data, ZSTD_LEVEL).context("Error compressing with zstd")
+        }
+        compressor::Value::Deflate => Err(anyhow::anyhow!("Deflate compression is not supported")),
+    }
+}
+
+fn decompress(compressor: i32, data: Vec<u8>, digest: &TDigest) -> anyhow::Result<Vec<u8>> {
+    let data = match compressor::Value::from_i32(compressor) {
+        Some(compressor::Value::Identity) => return Ok(data),
+        Some(compressor::Value::Zstd) => {
+            zstd::bulk::decompress(&data, digest.size_in_bytes as usize)
+                .with_context(|| format!("Error decompressing `{}` with zstd", digest))?
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Received `{}` with unsupported compressor: {}",
+                digest,
+                compressor
+            ));
+        }
+    };
+    if data.len() as i64 != digest.size_in_bytes {
+        return Err(anyhow::anyhow!(
+            "Decompressed `{}` to {} bytes",
+            digest,
+            data.len()
+        ));
+    }
+    Ok(data)
+}
+
+/// Decodes a blob read from the ByteStream service one chunk at a time.
+enum ChunkDecoder {
+    Identity,
+    Zstd {
+        decoder: zstd::stream::write::Decoder<'static, Vec<u8>>,
+        decoded: i64,
+    },
+}
+
+impl ChunkDecoder {
+    fn new(compressor: compressor::Value) -> anyhow::Result<Self> {
+        match compressor {
+            compressor::Value::Identity => Ok(Self::Identity),
+            compressor::Value::Zstd => Ok(Self::Zstd {
+                decoder: zstd::stream::write::Decoder::new(Vec::new())
+                    .context("Error creating zstd decoder")?,
+                decoded: 0,
+            }),
+            compressor::Value::Deflate => {
+                Err(anyhow::anyhow!("Deflate compression is not supported"))
+            }
+        }
+    }
+
+    /// Returns the uncompressed data that is available after receiving this chunk.
+    fn decode(&mut self, chunk: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Identity => Ok(chunk),
+            Self::Zstd { decoder, decoded } => {
+                decoder
+                    .write_all(&chunk)
+                    .and_then(|()| decoder.flush())
+                    .context("Error decompressing with zstd")?;
+                let data = std::mem::take(decoder.get_mut());
+                *decoded += data.len() as i64;
+                Ok(data)
+            }
+        }
+    }
+
+    /// Check that the whole blob was received.
+    fn finish(self, digest: &TDigest) -> anyhow::Result<()> {
+        match self {
+            Self::Identity => Ok(()),
+            Self::Zstd { decoded, .. } => {
+                if decoded != digest.size_in_bytes {
+                    return Err(anyhow::anyhow!(
+                        "Decompressed `{}` to {} bytes",
+                        digest,
+                        decoded
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Split data to upload to the ByteStream service into messages of at most `max_msg_size`.
+fn write_requests(resource_name: &str, data: &[u8], max_msg_size: usize) -> Vec<WriteRequest> {
+    let mut upload_segments = vec![];
+    // For compressed blobs, the offset of the first message is in the uncompressed blob and
+    // the following ones are offset by the compressed data, which works out the same for us.
+    for (i, chunk) in data.chunks(max_msg_size).enumerate() {
+        upload_segments.push(WriteRequest {
+            resource_name: resource_name.to_owned(),
+            write_offset: (i * max_msg_size) as i64,
+            finish_write: false,
+            data: chunk.to_owned(),
+        });
+    }
+    if let Some(last) = upload_segments.last_mut() {
+        last.finish_write = true;
+    }
+    upload_segments
+}
+
+/// The resource names of ByteStream uploads of a blob, `{instance}/uploads/{uuid}/{blob}`.
+struct UploadResourceName {
+    prefix: String,
+    blob: String,
+}
+
+impl UploadResourceName {
+    fn new(instance_name: &InstanceName, compression: Compression, hash: &str, size: i64) -> Self {
+        Self {
+            prefix: format!("{}uploads", instance_name.as_resource_prefix()),
+            blob: blob_resource_path(compression.bytestream, hash, size),
+        }
+    }
+
+    /// A resource name for a new upload. Each attempt to write a blob is a new upload: resuming
+    /// one would have to start at the size the server committed so far.
+    fn next(&self) -> String {
+        format!("{}/{}/{}", self.prefix, uuid::Uuid::new_v4(), self.blob)
+    }
+}
+
+/// Creates the requests of a ByteStream write. Writes are streamed, so a write is retried from
+/// the start by calling this again. Errors producing the requests, e.g. reading a file, end the
+/// stream.
+type WriteRequestsFn =
+    Arc<dyn Fn() -> BoxStream<'static, anyhow::Result<WriteRequest>> + Send + Sync>;
+
+/// The requests of a ByteStream write, ending at the first error producing them, which is kept
+/// in `error` for the caller to report. This is a named type rather than a combinator on a
+/// `BoxStream` so that the compiler can prove the write future is `Send`.
+struct WriteRequestStream {
+    requests: BoxStream<'static, anyhow::Result<WriteRequest>>,
+    error: Arc<Mutex<Option<anyhow::Error>>>,
+}
+
+impl Stream for WriteRequestStream {
+    type Item = WriteRequest;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<WriteRequest>> {
+        match futures::ready!(self.requests.poll_next_unpin(cx)) {
+            Some(Ok(request)) => Poll::Ready(Some(request)),
+            Some(Err(e)) => {
+                *self.error.lock().unwrap() = Some(e);
+                self.requests = futures::stream::empty().boxed();
+                Poll::Ready(None)
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// Read a file to upload with the ByteStream service in messages of at most `max_msg_size`,
+/// compressing it on the way, so that large files are never fully loaded in memory.
+fn file_write_requests(
+    name: String,
+    resource_name: String,
+    compressor: compressor::Value,
+    max_msg_size: usize,
+) -> BoxStream<'static, anyhow::Result<WriteRequest>> {
+    struct State {
+        file: Option<tokio::fs::File>,
+        encoder: Option<zstd::stream::write::Encoder<'static, Vec<u8>>>,
+        /// Data read (and compressed) but not sent yet.
+        pending: Vec<u8>,
+        write_offset: i64,
+        eof: bool,
+        finished: bool,
+    }
+
+    let state = State {
+        file: None,
+        encoder: None,
+        pending: Vec::new(),
+        write_offset: 0,
+        eof: false,
+        finished: false,
+    };
+
+    futures::stream::try_unfold(state, move |mut state| {
+        let name = name.clone();
+        let resource_name = resource_name.clone();
+        async move {
+            if state.finished {
+                return Ok(None);
+            }
+            if state.file.is_none() {
+                state.file = Some(
+                    tokio::fs::File::open(&name)
+                        .await
+                        .with_context(|| format!("Opening `{name}` for reading failed"))?,
+                );
+                state.encoder = match compressor {
+                    compressor::Value::Identity => None,
+                    compressor::Value::Zstd => Some(
+                        zstd::stream::write::Encoder::new(Vec::new(), ZSTD_LEVEL)
+                            .context("Error creating zstd encoder")?,
+                    ),
+                    compressor::Value::Deflate => {
+                        return Err(anyhow::anyhow!("Deflate compression is not supported"));
+                    }
+                };
+            }
+
+            // Read past a full message so that we know whether it's the last one.
+            let mut data = vec![0; max_msg_size];
+            while !state.eof && state.pending.len() <= max_msg_size {
+                let length = state
+                    .file
+                    .as_mut()
+                    .expect("opened above")
+                    .read(&mut data)
+                    .await
+                    .with_context(|| format!("Error reading from {name}"))?;
+                match (length, &mut state.encoder) {
+                    (0, encoder) => {
+                        state.eof = true;
+                        if let Some(encoder) = encoder.take() {
+                            state
+                                .pending
+                                .extend(encoder.finish().context("Error compressing with zstd")?);
+                        }
+                    }
+                    (length, Some(encoder)) => {
+                        encoder
+                            .write_all(&data[..length])
+                            .context("Error compressing with zstd")?;
+                        state.pending.append(encoder.get_mut());
+                    }
+                    (length, None) => state.pending.extend_from_slice(&data[..length]),
+                }
+            }
+
+            let length = std::cmp::min(max_msg_size, state.pending.len());
+            let data: Vec<u8> = state.pending.drain(..length).collect();
+            // For compressed blobs, the offset of the first message is in the uncompressed blob
+            // and the following ones are offset by the compressed data, which works out the same
+            // for us.
+            let request = WriteRequest {
+                resource_name,
+                write_offset: state.write_offset,
+                finish_write: state.eof && state.pending.is_empty(),
+                data,
+            };
+            state.write_offset += length as i64;
+            state.finished = request.finish_write;
+            Ok(Some((request, state)))
+        }
+    })
+    .boxed()
+}
+
+/// Whether a write of a blob of `size` bytes, which took `sent` bytes once compressed, completed.
+/// Compressed writes may report the compressed size, or `-1` when the blob was already present.
+fn is_committed(compressor: compressor::Value, committed_size: i64, size: i64, sent: i64) -> bool {
+    committed_size == size
+        || (compressor != compressor::Value::Identity
+            && (committed_size == -1 || committed_size == sent))
 }
 
 struct InstanceName(Option<String>);
@@ -228,7 +610,12 @@ impl InstanceName {
 pub struct REClientBuilder;
 
 impl REClientBuilder {
-    pub async fn build_and_connect(opts: &Buck2OssReConfiguration) -> anyhow::Result<REClient> {
+    /// The credential helper, if any, is shared with the other clients of the daemon. The
+    /// credentials for each service are fetched here and then refreshed in the background.
+    pub async fn build_and_connect(
+        opts: &Buck2OssReConfiguration,
+        credential_helper: Option<Arc<CredentialHelper>>,
+    ) -> anyhow::Result<REClient> {
         // We just always create this just in case, so that we implicitly validate it if set.
         let tls_config = create_tls_config(opts)
             .await
@@ -239,77 +626,152 @@ impl REClientBuilder {
         let create_channel = |address: Option<String>| async move {
             let address = address.as_ref().context("No address")?;
             let address = substitute_env_vars(address).context("Invalid address")?;
+
+            // Services behind a Unix socket are local, and don't get credentials.
+            if let Some(path) = unix_socket_path(&address) {
+                let channel = connect_unix(path.to_owned())
+                    .await
+                    .with_context(|| format!("Error connecting to `{}`", address))?;
+                return anyhow::Ok((channel, None));
+            }
+
             let uri = address.parse().context("Invalid address")?;
             let uri = prepare_uri(uri, opts.tls).context("Invalid URI")?;
 
-            let mut channel = Channel::builder(uri);
+            let mut channel = Channel::builder(uri.clone());
             if opts.tls {
                 channel = channel.tls_config(tls_config.clone())?;
             }
 
-            anyhow::Ok(
-                channel
-                    .connect()
-                    .await
-                    .with_context(|| format!("Error connecting to `{}`", address))?,
-            )
+            let channel = channel
+                .connect()
+                .await
+                .with_context(|| format!("Error connecting to `{}`", address))?;
+            anyhow::Ok((channel, Some(uri)))
         };
 
-        let (cas, execution, action_cache, bytestream, capabilities) = futures::future::join5(
+        // Without an engine we only use the remote as a cache, so ask the CAS for capabilities.
+        let capabilities_address = opts
+            .engine_address
+            .clone()
+            .or_else(|| opts.cas_address.clone());
+
+        let (cas, action_cache, bytestream, capabilities) = futures::future::join4(
             create_channel(opts.cas_address.clone()),
-            create_channel(opts.engine_address.clone()),
             create_channel(opts.action_cache_address.clone()),
             create_channel(opts.cas_address.clone()),
-            create_channel(opts.engine_address.clone()),
+            create_channel(capabilities_address),
         )
         .await;
 
-        let interceptor = InjectHeadersInterceptor::new(&opts.http_headers)?;
+        let (cas, cas_uri) = cas.context("Error creating CAS client")?;
+        let (action_cache, action_cache_uri) =
+            action_cache.context("Error creating ActionCache client")?;
+        let (bytestream, bytestream_uri) =
+            bytestream.context("Error creating Bytestream client")?;
+        let (capabilities, capabilities_uri) =
+            capabilities.context("Error creating Capabilities client")?;
+
+        let execution = match opts.engine_address.clone() {
+            Some(address) => Some(
+                create_channel(Some(address))
+                    .await
+                    .context("Error creating Execution client")?,
+            ),
+            None => None,
+        };
+
+        let fetch = match opts.remote_asset_address.clone() {
+            Some(address) => Some(
+                create_channel(Some(address))
+                    .await
+                    .context("Error creating Fetch client")?,
+            ),
+            None => None,
+        };
+
+        // The interceptors can't wait for the helper, so they only ever read credentials fetched
+        // ahead of time.
+        if let Some(helper) = &credential_helper {
+            let mut uris = vec![
+                &cas_uri,
+                &action_cache_uri,
+                &bytestream_uri,
+                &capabilities_uri,
+            ];
+            uris.extend(execution.as_ref().map(|(_, uri)| uri));
+            uris.extend(fetch.as_ref().map(|(_, uri)| uri));
+            let mut hosts: Vec<Uri> = Vec::new();
+            for uri in uris.into_iter().flatten() {
+                if !hosts.iter().any(|h| h.host() == uri.host()) {
+                    hosts.push(uri.clone());
+                }
+            }
+            futures::future::try_join_all(hosts.into_iter().map(|uri| helper.prefetch(uri)))
+                .await
+                .context("Error getting credentials for RE")?;
+        }
+
+        let interceptor = InjectHeadersInterceptor::new(&opts.http_headers, credential_helper)?;
+
+        let execution_client = execution.map(|(execution, execution_uri)| {
+            ExecutionClient::with_interceptor(execution, interceptor.for_uri(execution_uri))
+        });
+
+        let fetch_client = fetch.map(|(fetch, fetch_uri)| {
+            FetchClient::with_interceptor(fetch, interceptor.for_uri(fetch_uri))
+        });
 
         let mut grpc_clients = GRPCClients {
             cas_client: ContentAddressableStorageClient::with_interceptor(
-                cas.context("Error creating CAS client")?,
-                interceptor.dupe(),
-            ),
-            execution_client: ExecutionClient::with_interceptor(
-                execution.context("Error creating Execution client")?,
-                interceptor.dupe(),
+                cas,
+                interceptor.for_uri(cas_uri),
             ),
+            execution_client,
             action_cache_client: ActionCacheClient::with_interceptor(
-                action_cache.context("Error creating ActionCache client")?,
-                interceptor.dupe(),
+                action_cache,
+                interceptor.for_uri(action_cache_uri),
             ),
             bytestream_client: ByteStreamClient::with_interceptor(
-                bytestream.context("Error creating Bytestream client")?,
-                interceptor.dupe(),
+                bytestream,
+                interceptor.for_uri(bytestream_uri),
             ),
             capabilities_client: CapabilitiesClient::with_interceptor(
-                capabilities.context("Error creating Capabilities client")?,
-                interceptor.dupe(),
+                capabilities,
+                interceptor.for_uri(capabilities_uri),
             ),
+            fetch_client,
         };
 
         let instance_name = InstanceName(opts.instance_name.clone());
 
         let capabilities = if opts.capabilities.unwrap_or(true) {
-            Self::fetch_rbe_capabilities(&mut grpc_clients, &instance_name).await?
+            Self::fetch_rbe_capabilities(&mut grpc_clients, &instance_name, opts.compression)
+                .await?
         } else {
             RECapabilities {
                 exec_enabled: true,
                 max_msg_size: DEFAULT_MAX_MSG_SIZE,
+                compression: Compression::IDENTITY,
             }
         };
 
-        if !capabilities.exec_enabled {
+        if grpc_clients.execution_client.is_some() && !capabilities.exec_enabled {
             return Err(anyhow::anyhow!("Server has remote execution disabled."));
         }
 
-        Ok(REClient::new(grpc_clients, capabilities, instance_name))
+        Ok(REClient::new(
+            grpc_clients,
+            capabilities,
+            instance_name,
+            RetryPolicy::new(opts),
+        ))
     }
 
     async fn fetch_rbe_capabilities(
         clients: &mut GRPCClients,
         instance_name: &InstanceName,
+        compression_enabled: bool,
     ) -> anyhow::Result<RECapabilities> {
         // TODO use more of the capabilities of the remote build executor
 
@@ -325,6 +787,7 @@ impl REClientBuilder {
         // with enough room for headers.
         let mut max_msg_size = DEFAULT_MAX_MSG_SIZE;
         let mut exec_enabled = true;
+        let mut compression = Compression::IDENTITY;
 
         if let Some(cache_cap) = resp.cache_capabilities {
             let size = cache_cap.max_batch_total_size_bytes as usize;
@@ -332,6 +795,7 @@ impl REClientBuilder {
             if size != 0 {
                 max_msg_size = size;
             }
+            compression = Compression::negotiate(compression_enabled, &cache_cap);
         }
 
         if let Some(exec_cap) = resp.execution_capabilities {
@@ -341,17 +805,25 @@ impl REClientBuilder {
         Ok(RECapabilities {
             max_msg_size,
             exec_enabled,
+            compression,
         })
     }
 }
 
-#[derive(Clone, Dupe)]
+#[derive(Clone)]
 struct InjectHeadersInterceptor {
     headers: Arc<Vec<(MetadataKey<metadata::Ascii>, MetadataValue<metadata::Ascii>)>>,
+    credential_helper: Option<Arc<CredentialHelper>>,
+    /// The URI of the service this interceptor is used for, which the credential helper is
+    /// queried for. Not set for services behind a Unix socket.
+    uri: Option<Uri>,
 }
 
 impl InjectHeadersInterceptor {
-    pub fn new(headers: &[HttpHeader]) -> anyhow::Result<Self> {
+    pub fn new(
+        headers: &[HttpHeader],
+        credential_helper: Option<Arc<CredentialHelper>>,
+    ) -> anyhow::Result<Self> {
         let headers = headers
             .iter()
             .map(|h| {
@@ -374,8 +846,40 @@ impl InjectHeadersInterceptor {
 
         Ok(Self {
             headers: Arc::new(headers),
+            credential_helper,
+            uri: None,
         })
     }
+
+    fn for_uri(&self, uri: Option<Uri>) -> Self {
+        Self {
+            uri,
+            ..self.clone()
+        }
+    }
+
+    fn add_credentials(&self, request: &mut tonic::Request<()>) -> anyhow::Result<()> {
+        let (helper, uri) = match (&self.credential_helper, &self.uri) {
+            (Some(helper), Some(uri)) => (helper, uri),
+            _ => return Ok(()),
+        };
+
+        // Interceptors can't be async, so rely on the credentials prefetched in
+        // `build_and_connect`, which are refreshed in the background.
+        let credentials = helper
+            .cached(uri)
+            .with_context(|| format!("No credentials were fetched for `{}`", uri))?;
+        for (key, value) in &credentials.headers {
+            let key =
+                MetadataKey::<metadata::Ascii>::from_bytes(key.as_bytes()).with_context(|| {
+                    format!("Invalid header name from credential helper: `{}`", key)
+                })?;
+            let value = MetadataValue::try_from(value.as_str())
+                .with_context(|| format!("Invalid value for header `{}`", key))?;
+            request.metadata_mut().append(key, value);
+        }
+        Ok(())
+    }
 }
 
 impl Interceptor for InjectHeadersInterceptor {
@@ -386,6 +890,8 @@ impl Interceptor for InjectHeadersInterceptor {
         for (k, v) in self.headers.iter() {
             request.metadata_mut().insert(k.clone(), v.clone());
         }
+        self.add_credentials(&mut request)
+            .map_err(|e| tonic::Status::unauthenticated(format!("{:#}", e)))?;
         Ok(request)
     }
 }
@@ -393,24 +899,47 @@ impl Interceptor for InjectHeadersInterceptor {
 pub struct GRPCClients {
     cas_client:
         ContentAddressableStorageClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
-    execution_client: ExecutionClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
+    /// Not present in cache-only mode, when no engine is configured.
+    execution_client:
+        Option<ExecutionClient<InterceptedService<Channel, InjectHeadersInterceptor>>>,
     action_cache_client: ActionCacheClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
     bytestream_client: ByteStreamClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
     capabilities_client: CapabilitiesClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
+    /// Only present if a remote asset server is configured.
+    fetch_client: Option<FetchClient<InterceptedService<Channel, InjectHeadersInterceptor>>>,
 }
 
+/// Bytes transferred with the CAS, as sent over the network and once uncompressed.
 #[derive(Default)]
 pub struct REState {
-    // TODO(aloiscochard): Update those values
-    network_uploaded: i64,   // in bytes
-    network_downloaded: i64, // in bytes
+    network_uploaded: AtomicI64,
+    network_downloaded: AtomicI64,
+    network_uploaded_uncompressed: AtomicI64,
+    network_downloaded_uncompressed: AtomicI64,
+}
+
+impl REState {
+    fn record_upload(&self, sent: usize, uncompressed: usize) {
+        self.network_uploaded
+            .fetch_add(sent as i64, Ordering::Relaxed);
+        self.network_uploaded_uncompressed
+            .fetch_add(uncompressed as i64, Ordering::Relaxed);
+    }
+
+    fn record_download(&self, received: usize, uncompressed: usize) {
+        self.network_downloaded
+            .fetch_add(received as i64, Ordering::Relaxed);
+        self.network_downloaded_uncompressed
+            .fetch_add(uncompressed as i64, Ordering::Relaxed);
+    }
 }
 
 pub struct REClient {
     grpc_clients: GRPCClients,
     capabilities: RECapabilities,
     instance_name: InstanceName,
-    state: Mutex<REState>,
+    state: REState,
+    retry: RetryPolicy,
 }
 
 impl Drop for REClient {
@@ -420,6 +949,109 @@ impl Drop for REClient {
     }
 }
 
+/// An execution in progress, and what we need to resume it with `WaitExecution` if its stream is
+/// interrupted before the operation is done.
+struct ExecuteStream {
+    stream: Streaming<Operation>,
+    client: ExecutionClient<InterceptedService<Channel, InjectHeadersInterceptor>>,
+    /// Sent again if the stream fails before we learn the name of the operation.
+    request: GExecuteRequest,
+    metadata: RemoteExecutionMetadata,
+    retry: RetryPolicy,
+    action_digest: TDigest,
+    /// The name of the operation, once the remote told us.
+    operation_name: Option<String>,
+    done: bool,
+    /// How many times the execution was resumed since the last message from the remote.
+    retries: usize,
+}
+
+/// Whether the remote does not know the operation we asked about, e.g. because it restarted.
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error.chain().any(|e| {
+        matches!(
+            e.downcast_ref::<tonic::Status>(),
+            Some(status) if status.code() == tonic::Code::NotFound
+        )
+    })
+}
+
+impl ExecuteStream {
+    async fn next(&mut self) -> anyhow::Result<Option<Operation>> {
+        loop {
+            let error = match self.stream.try_next().await {
+                Ok(Some(msg)) => {
+                    if !msg.name.is_empty() {
+                        self.operation_name = Some(msg.name.clone());
+                    }
+                    self.done = msg.done;
+                    // The execution is making progress, so only count failures since the last
+                    // message against the retry limit.
+                    self.retries = 0;
+                    return Ok(Some(msg));
+                }
+                Ok(None) if self.done => return Ok(None),
+                Ok(None) => anyhow::Error::from(tonic::Status::unavailable(
+                    "Stream ended before the operation completed",
+                )),
+                Err(e) => anyhow::Error::from(e),
+            };
+            self.resume(error).await?;
+        }
+    }
+
+    async fn resume(&mut self, mut error: anyhow::Error) -> anyhow::Result<()> {
+        loop {
+            // If the remote lost the operation, there is nothing to wait for, so execute the
+            // action again.
+            let lost_operation = self.operation_name.is_some() && is_not_found(&error);
+            let should_retry = if lost_operation {
+                self.retry.can_retry(self.retries)
+            } else {
+                self.retry.should_retry(self.retries, &error)
+            };
+            if !should_retry {
+                return Err(error.context("RE channel error"));
+            }
+            if lost_operation {
+                self.operation_name = None;
+            }
+
+            let rpc = match &self.operation_name {
+                Some(_) => "WaitExecution",
+                None => "Execute",
+            };
+            self.retries += 1;
+            self.retry
+                .wait(rpc, Some(&self.action_digest), self.retries, &error)
+                .await;
+
+            let stream = match &self.operation_name {
+                Some(name) => {
+                    let request = with_internal_metadata(
+                        WaitExecutionRequest { name: name.clone() },
+                        self.metadata.clone(),
+                    );
+                    self.client.wait_execution(request).await
+                }
+                // There is no operation to wait for, so start over. Execute is idempotent.
+                None => {
+                    let request =
+                        with_internal_metadata(self.request.clone(), self.metadata.clone());
+                    self.client.execute(request).await
+                }
+            };
+            match stream {
+                Ok(stream) => {
+                    self.stream = stream.into_inner();
+                    return Ok(());
+                }
+                Err(e) => error = e.into(),
+            }
+        }
+    }
+}
+
 /// Information on components of a batch upload.
 /// Used to defer reading of NamedDigest contents till
 /// actual execution of upload and prevent opening too many
@@ -473,31 +1105,41 @@ impl REClient {
         grpc_clients: GRPCClients,
         capabilities: RECapabilities,
         instance_name: InstanceName,
+        retry: RetryPolicy,
     ) -> Self {
         REClient {
             grpc_clients,
             capabilities,
             instance_name,
-            state: Mutex::new(REState::default()),
+            state: REState::default(),
+            retry,
         }
     }
 
+    /// Be notified of every retry, e.g. to surface them to the user.
+    pub fn with_retry_observer(mut self, observer: RetryObserver) -> Self {
+        self.retry.observer = Some(observer);
+        self
+    }
+
     pub async fn get_action_result(
         &self,
         metadata: RemoteExecutionMetadata,
         request: ActionResultRequest,
     ) -> anyhow::Result<ActionResultResponse> {
-        let mut client = self.grpc_clients.action_cache_client.clone();
+        let request = GetActionResultRequest {
+            instance_name: self.instance_name.as_str().to_owned(),
+            action_digest: Some(tdigest_to(request.digest)),
+            ..Default::default()
+        };
 
-        let res = client
-            .get_action_result(with_internal_metadata(
-                GetActionResultRequest {
-                    instance_name: self.instance_name.as_str().to_owned(),
-                    action_digest: Some(tdigest_to(request.digest)),
-                    ..Default::default()
-                },
-                metadata,
-            ))
+        let res = self
+            .retry
+            .retry("GetActionResult", None, || {
+                let mut client = self.grpc_clients.action_cache_client.clone();
+                let request = with_internal_metadata(request.clone(), metadata.clone());
+                async move { anyhow::Ok(client.get_action_result(request).await?) }
+            })
             .await?;
 
         Ok(ActionResultResponse {
@@ -508,10 +1150,26 @@ impl REClient {
 
     pub async fn write_action_result(
         &self,
-        _metadata: RemoteExecutionMetadata,
-        _request: WriteActionResultRequest,
+        metadata: RemoteExecutionMetadata,
+        request: WriteActionResultRequest,
     ) -> anyhow::Result<WriteActionResultResponse> {
-        Err(anyhow::anyhow!("Not supported"))
+        let request = UpdateActionResultRequest {
+            instance_name: self.instance_name.as_str().to_owned(),
+            action_digest: Some(tdigest_to(request.action_digest)),
+            action_result: Some(convert_taction_result_to_rbe(request.action_result)),
+            ..Default::default()
+        };
+
+        // Writing the same result twice is harmless, so this is safe to retry.
+        self.retry
+            .retry("UpdateActionResult", None, || {
+                let mut client = self.grpc_clients.action_cache_client.clone();
+                let request = with_internal_metadata(request.clone(), metadata.clone());
+                async move { anyhow::Ok(client.update_action_result(request).await?) }
+            })
+            .await?;
+
+        Ok(WriteActionResultResponse {})
     }
 
     pub async fn execute_with_progress(
@@ -522,7 +1180,11 @@ impl REClient {
         // TODO(aloiscochard): Map those properly in the request
         // use crate::proto::build::bazel::remote::execution::v2::ExecutionPolicy;
 
-        let mut client = self.grpc_clients.execution_client.clone();
+        let client = self
+            .grpc_clients
+            .execution_client
+            .clone()
+            .context("Remote execution is disabled: no `engine_address` is configured")?;
 
         let action_digest = tdigest_to(execute_request.action_digest.clone());
 
@@ -534,13 +1196,30 @@ impl REClient {
             action_digest: Some(action_digest.clone()),
         };
 
-        let stream = client
-            .execute(with_internal_metadata(request, metadata))
-            .await?
-            .into_inner();
+        // Execute is idempotent: the remote deduplicates executions of the same action.
+        let stream = self
+            .retry
+            .retry("Execute", Some(&execute_request.action_digest), || {
+                let mut client = client.clone();
+                let request = with_internal_metadata(request.clone(), metadata.clone());
+                async move { anyhow::Ok(client.execute(request).await?.into_inner()) }
+            })
+            .await?;
 
-        let stream = futures::stream::try_unfold(stream, move |mut stream| async {
-            let msg = match stream.try_next().await.context("RE channel error")? {
+        let stream = ExecuteStream {
+            stream,
+            client,
+            request,
+            metadata,
+            retry: self.retry.clone(),
+            action_digest: execute_request.action_digest.clone(),
+            operation_name: None,
+            done: false,
+            retries: 0,
+        };
+
+        let stream = futures::stream::try_unfold(stream, move |mut stream| async move {
+            let msg = match stream.next().await? {
                 Some(msg) => msg,
                 None => return Ok(None),
             };
@@ -636,27 +1315,47 @@ impl REClient {
         metadata: RemoteExecutionMetadata,
         request: UploadRequest,
     ) -> anyhow::Result<UploadResponse> {
+        let metadata = &metadata;
         upload_impl(
             &self.instance_name,
             request,
             self.capabilities.max_msg_size,
-            |re_request| async {
-                let metadata = metadata.clone();
-                let mut cas_client = self.grpc_clients.cas_client.clone();
-                let resp = cas_client
-                    .batch_update_blobs(with_internal_metadata(re_request, metadata))
-                    .await?;
-                Ok(resp.into_inner())
+            self.capabilities.compression,
+            &self.state,
+            |re_request| async move {
+                self.retry
+                    .retry("BatchUpdateBlobs", None, || {
+                        let mut cas_client = self.grpc_clients.cas_client.clone();
+                        let request = with_internal_metadata(re_request.clone(), metadata.clone());
+                        async move { anyhow::Ok(cas_client.batch_update_blobs(request).await?) }
+                    })
+                    .await
+                    .map(|resp| resp.into_inner())
             },
-            |segments| async {
-                let metadata = metadata.clone();
-                let mut bytestream_client = self.grpc_clients.bytestream_client.clone();
-                let requests = futures::stream::iter(segments);
-                let resp = bytestream_client
-                    .write(with_internal_metadata(requests, metadata))
-                    .await?;
-
-                Ok(resp.into_inner())
+            |requests| async move {
+                // Writes are idempotent since blobs are content addressed, so a failed write is
+                // retried from the start, as a new upload.
+                self.retry
+                    .retry("Write", None, || {
+                        let mut bytestream_client = self.grpc_clients.bytestream_client.clone();
+                        // The server only sees the stream end early if producing the requests
+                        // fails, so report that error instead of the one it returns.
+                        let error = Arc::new(Mutex::new(None));
+                        let requests = WriteRequestStream {
+                            requests: requests(),
+                            error: error.clone(),
+                        };
+                        let request = with_internal_metadata(requests, metadata.clone());
+                        async move {
+                            let response = bytestream_client.write(request).await;
+                            if let Some(e) = error.lock().unwrap().take() {
+                                return Err(e);
+                            }
+                            anyhow::Ok(response?)
+                        }
+                    })
+                    .await
+                    .map(|resp| resp.into_inner())
             },
         )
         .await
@@ -676,28 +1375,36 @@ impl REClient {
         metadata: RemoteExecutionMetadata,
         request: DownloadRequest,
     ) -> anyhow::Result<DownloadResponse> {
+        let metadata = &metadata;
         download_impl(
             &self.instance_name,
             request,
             self.capabilities.max_msg_size,
-            |re_request| async {
-                let metadata = metadata.clone();
-                let mut client = self.grpc_clients.cas_client.clone();
-                Ok(client
-                    .batch_read_blobs(with_internal_metadata(re_request, metadata))
-                    .await?
-                    .into_inner())
+            self.capabilities.compression,
+            &self.state,
+            |re_request| async move {
+                self.retry
+                    .retry("BatchReadBlobs", None, || {
+                        let mut client = self.grpc_clients.cas_client.clone();
+                        let request = with_internal_metadata(re_request.clone(), metadata.clone());
+                        async move { anyhow::Ok(client.batch_read_blobs(request).await?) }
+                    })
+                    .await
+                    .map(|resp| resp.into_inner())
             },
-            |read_request| {
-                let metadata = metadata.clone();
-                async move {
-                    let mut client = self.grpc_clients.bytestream_client.clone();
-                    let response = client
-                        .read(with_internal_metadata(read_request, metadata))
-                        .await?
-                        .into_inner();
-                    Ok(Box::pin(response.into_stream()))
-                }
+            |read_request| async move {
+                // Only starting the read is retried: once data has been received, retrying would
+                // require resuming at the right offset.
+                let response = self
+                    .retry
+                    .retry("Read", None, || {
+                        let mut client = self.grpc_clients.bytestream_client.clone();
+                        let request =
+                            with_internal_metadata(read_request.clone(), metadata.clone());
+                        async move { anyhow::Ok(client.read(request).await?.into_inner()) }
+                    })
+                    .await?;
+                Ok(Box::pin(response.into_stream()))
             },
         )
         .await
@@ -708,7 +1415,6 @@ impl REClient {
         metadata: RemoteExecutionMetadata,
         request: GetDigestsTtlRequest,
     ) -> anyhow::Result<GetDigestsTtlResponse> {
-        let mut cas_client = self.grpc_clients.cas_client.clone();
         let mut remote_ttl: HashMap<TDigest, DigestWithTtl> = HashMap::new();
 
         for digest_chunk in request.digests.chunks(100) {
@@ -725,14 +1431,17 @@ impl REClient {
                     },
                 );
             }
-            let missing_blobs = cas_client
-                .find_missing_blobs(with_internal_metadata(
-                    FindMissingBlobsRequest {
-                        instance_name: self.instance_name.as_str().to_owned(),
-                        blob_digests: digest_chunk.map(|b| tdigest_to(b.clone())),
-                    },
-                    metadata.clone(),
-                ))
+            let request = FindMissingBlobsRequest {
+                instance_name: self.instance_name.as_str().to_owned(),
+                blob_digests: digest_chunk.map(|b| tdigest_to(b.clone())),
+            };
+            let missing_blobs = self
+                .retry
+                .retry("FindMissingBlobs", None, || {
+                    let mut cas_client = self.grpc_clients.cas_client.clone();
+                    let request = with_internal_metadata(request.clone(), metadata.clone());
+                    async move { anyhow::Ok(cas_client.find_missing_blobs(request).await?) }
+                })
                 .await
                 .context("Failed to request what blobs are not present on remote")?;
             let resp: FindMissingBlobsResponse = missing_blobs.into_inner();
@@ -754,6 +1463,54 @@ impl REClient {
         })
     }
 
+    /// Resolve a blob through the Remote Asset API. The server downloads it if necessary, and
+    /// makes it available in the CAS.
+    pub async fn fetch_blob(
+        &self,
+        metadata: RemoteExecutionMetadata,
+        request: FetchBlobRequest,
+    ) -> anyhow::Result<FetchBlobResponse> {
+        let client = self.grpc_clients.fetch_client.as_ref().context(
+            "No remote asset server is configured (`buck2_re_client.remote_asset_address`)",
+        )?;
+
+        let request = GFetchBlobRequest {
+            instance_name: self.instance_name.as_str().to_owned(),
+            uris: request.uris,
+            qualifiers: request.qualifiers.into_map(|q| GQualifier {
+                name: q.name,
+                value: q.value,
+            }),
+            ..Default::default()
+        };
+
+        let response = self
+            .retry
+            .retry("FetchBlob", None, || {
+                let mut client = client.clone();
+                let request = with_internal_metadata(request.clone(), metadata.clone());
+                async move { anyhow::Ok(client.fetch_blob(request).await?) }
+            })
+            .await
+            .with_context(|| format!("Failed to fetch `{}`", request.uris.join("`, `")))?
+            .into_inner();
+
+        if let Some(status) = response.status {
+            check_status(status)
+                .with_context(|| format!("Failed to fetch `{}`", request.uris.join("`, `")))?;
+        }
+
+        let digest = response
+            .blob_digest
+            .context("Remote asset server did not return a digest")?;
+
+        Ok(FetchBlobResponse {
+            uri: response.uri,
+            digest: tdigest_from(digest),
+            expires_at: response.expires_at.map(|ts| ttimestamp_from(Some(ts))),
+        })
+    }
+
     pub fn get_execution_client(&self) -> &Self {
         self
     }
@@ -776,10 +1533,14 @@ impl REClient {
     }
 
     pub fn get_network_stats(&self) -> anyhow::Result<NetworkStatisticsResponse> {
-        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let state = &self.state;
         Ok(NetworkStatisticsResponse {
-            downloaded: state.network_downloaded,
-            uploaded: state.network_uploaded,
+            downloaded: state.network_downloaded.load(Ordering::Relaxed),
+            uploaded: state.network_uploaded.load(Ordering::Relaxed),
+            downloaded_uncompressed: state
+                .network_downloaded_uncompressed
+                .load(Ordering::Relaxed),
+            uploaded_uncompressed: state.network_uploaded_uncompressed.load(Ordering::Relaxed),
             _dot_dot_default: (),
         })
     }
@@ -789,6 +1550,59 @@ impl REClient {
     }
 }
 
+fn convert_taction_result_to_rbe(action_result: TActionResult2) -> ActionResult {
+    let execution_metadata = action_result.execution_metadata;
+
+    ActionResult {
+        output_files: action_result
+            .output_files
+            .into_map(|output_file| OutputFile {
+                path: output_file.name,
+                digest: Some(tdigest_to(output_file.digest.digest)),
+                is_executable: output_file.executable,
+                ..Default::default()
+            }),
+        output_directories: action_result
+            .output_directories
+            .into_map(|output_directory| OutputDirectory {
+                path: output_directory.path,
+                tree_digest: Some(tdigest_to(output_directory.tree_digest)),
+                ..Default::default()
+            }),
+        exit_code: action_result.exit_code,
+        stdout_raw: action_result.stdout_raw.unwrap_or_default(),
+        stdout_digest: action_result.stdout_digest.map(tdigest_to),
+        stderr_raw: action_result.stderr_raw.unwrap_or_default(),
+        stderr_digest: action_result.stderr_digest.map(tdigest_to),
+        execution_metadata: Some(ExecutedActionMetadata {
+            worker: execution_metadata.worker,
+            queued_timestamp: ttimestamp_to(execution_metadata.queued_timestamp),
+            worker_start_timestamp: ttimestamp_to(execution_metadata.worker_start_timestamp),
+            worker_completed_timestamp: ttimestamp_to(
+                execution_metadata.worker_completed_timestamp,
+            ),
+            input_fetch_start_timestamp: ttimestamp_to(
+                execution_metadata.input_fetch_start_timestamp,
+            ),
+            input_fetch_completed_timestamp: ttimestamp_to(
+                execution_metadata.input_fetch_completed_timestamp,
+            ),
+            execution_start_timestamp: ttimestamp_to(execution_metadata.execution_start_timestamp),
+            execution_completed_timestamp: ttimestamp_to(
+                execution_metadata.execution_completed_timestamp,
+            ),
+            output_upload_start_timestamp: ttimestamp_to(
+                execution_metadata.output_upload_start_timestamp,
+            ),
+            output_upload_completed_timestamp: ttimestamp_to(
+                execution_metadata.output_upload_completed_timestamp,
+            ),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
 fn convert_action_result(action_result: ActionResult) -> anyhow::Result<TActionResult2> {
     let execution_metadata = action_result
         .execution_metadata
@@ -878,6 +1692,8 @@ async fn download_impl<Byt, BytRet, Cas>(
     instance_name: &InstanceName,
     request: DownloadRequest,
     max_msg_size: usize,
+    compression: Compression,
+    stats: &REState,
     cas_f: impl Fn(BatchReadBlobsRequest) -> Cas,
     bystream_fut: impl Fn(ReadRequest) -> Byt + Sync + Send + Copy,
 ) -> anyhow::Result<DownloadResponse>
@@ -891,10 +1707,9 @@ where
         let size_in_bytes = digest.size_in_bytes;
 
         let resource_name = format!(
-            "{}blobs/{}/{}",
+            "{}{}",
             instance_name.as_resource_prefix(),
-            hash,
-            size_in_bytes
+            blob_resource_path(compression.bytestream, &hash, size_in_bytes)
         );
 
         bystream_fut(ReadRequest {
@@ -929,7 +1744,7 @@ where
             let read_blob_req = BatchReadBlobsRequest {
                 instance_name: instance_name.as_str().to_owned(),
                 digests: std::mem::take(&mut curr_digests),
-                acceptable_compressors: vec![compressor::Value::Identity as i32],
+                acceptable_compressors: compression.acceptable_compressors(),
             };
             requests.push(read_blob_req);
         }
@@ -940,7 +1755,7 @@ where
         let read_blob_req = BatchReadBlobsRequest {
             instance_name: instance_name.as_str().to_owned(),
             digests: std::mem::take(&mut curr_digests),
-            acceptable_compressors: vec![compressor::Value::Identity as i32],
+            acceptable_compressors: compression.acceptable_compressors(),
         };
         requests.push(read_blob_req);
     }
@@ -953,7 +1768,10 @@ where
         for r in resp.responses.into_iter() {
             let digest = tdigest_from(r.digest.context("Response digest not found.")?);
             check_status(r.status.unwrap_or_default())?;
-            batched_blobs_response.insert(digest, r.data);
+            let received = r.data.len();
+            let data = decompress(r.compressor, r.data, &digest)?;
+            stats.record_download(received, data.len());
+            batched_blobs_response.insert(digest, data);
         }
     }
 
@@ -972,13 +1790,18 @@ where
     for digest in inlined_digests {
         let data = if digest.size_in_bytes as usize >= max_msg_size {
             let mut accum = vec![];
+            let mut decoder = ChunkDecoder::new(compression.bytestream)?;
             let mut responses = bystream_fut(digest.clone()).await?;
             while let Some(resp) = responses.next().await {
-                let data = resp
+                let chunk = resp
                     .with_context(|| format!("Failed to fetch inline digest: {digest}"))?
                     .data;
+                let received = chunk.len();
+                let data = decoder.decode(chunk)?;
+                stats.record_download(received, data.len());
                 accum.extend_from_slice(&data);
             }
+            decoder.finish(&digest)?;
             accum
         } else {
             get(&digest)?
@@ -1017,15 +1840,20 @@ where
                     .await
                     .with_context(|| format!("Error writing: {}", req.named_digest.digest))?;
             } else {
+                let mut decoder = ChunkDecoder::new(compression.bytestream)?;
                 let mut responses = bystream_fut(req.named_digest.digest.clone()).await?;
                 while let Some(resp) = responses.next().await {
-                    let data = resp
+                    let chunk = resp
                         .with_context(|| format!("Failed to fetch file: {:?}", file))?
                         .data;
+                    let received = chunk.len();
+                    let data = decoder.decode(chunk)?;
+                    stats.record_download(received, data.len());
                     file.write_all(&data).await.with_context(|| {
                         format!("Error writing chunk of: {}", req.named_digest.digest)
                     })?;
                 }
+                decoder.finish(&req.named_digest.digest)?;
             }
             file.flush().await.context("Error flushing")?;
             anyhow::Ok(())
@@ -1050,8 +1878,10 @@ async fn upload_impl<Byt, Cas>(
     instance_name: &InstanceName,
     request: UploadRequest,
     max_msg_size: usize,
+    compression: Compression,
+    stats: &REState,
     cas_f: impl Fn(BatchUpdateBlobsRequest) -> Cas + Sync + Send + Copy,
-    bystream_fut: impl Fn(Vec<WriteRequest>) -> Byt + Sync + Send + Copy,
+    bystream_fut: impl Fn(WriteRequestsFn) -> Byt + Sync + Send + Copy,
 ) -> anyhow::Result<UploadResponse>
 where
     Cas: Future<Output = anyhow::Result<BatchUpdateBlobsResponse>> + Send,
@@ -1075,29 +1905,27 @@ where
         }
 
         let data = blob.blob;
-        let client_uuid = uuid::Uuid::new_v4().to_string();
-        let resource_name = format!(
-            "{}uploads/{}/blobs/{}/{}",
-            instance_name.as_resource_prefix(),
-            client_uuid,
-            hash,
-            size
-        );
+        let resource_name = UploadResourceName::new(instance_name, compression, &hash, size);
         let fut = async move {
-            // Number of complete (non-partial) messages
-            let mut upload_segments = vec![];
-            for (i, chunk) in data.chunks(max_msg_size).enumerate() {
-                upload_segments.push(WriteRequest {
-                    resource_name: resource_name.to_owned(),
-                    write_offset: (i * max_msg_size) as i64,
-                    finish_write: false,
-                    data: chunk.to_owned(),
-                });
-            }
-            upload_segments.last_mut().unwrap().finish_write = true;
-
-            let resp = bystream_fut(upload_segments).await?;
-            if resp.committed_size != size {
+            let data = Arc::new(compress(compression.bytestream, data)?);
+            let sent = data.len();
+            let requests: WriteRequestsFn = Arc::new(move || {
+                futures::stream::iter(
+                    write_requests(&resource_name.next(), &data, max_msg_size)
+                        .into_iter()
+                        .map(Ok),
+                )
+                .boxed()
+            });
+
+            let resp = bystream_fut(requests).await?;
+            stats.record_upload(sent, size as usize);
+            if !is_committed(
+                compression.bytestream,
+                resp.committed_size,
+                size,
+                sent as i64,
+            ) {
                 return Err(anyhow::anyhow!(
                     "Failed to upload inline blob: invalid committed_size from WriteResponse"
                 ));
@@ -1117,45 +1945,38 @@ where
             batched_blob_updates.push(BatchUploadRequest::File(file));
             continue;
         }
-        let client_uuid = uuid::Uuid::new_v4().to_string();
-        let resource_name = format!(
-            "{}uploads/{}/blobs/{}/{}",
-            instance_name.as_resource_prefix(),
-            client_uuid,
-            hash.clone(),
-            size
-        );
+        let resource_name = UploadResourceName::new(instance_name, compression, &hash, size);
         let fut = async move {
-            let mut file = tokio::fs::File::open(&name)
-                .await
-                .with_context(|| format!("Opening `{name}` for reading failed"))?;
-            let mut data = vec![0; max_msg_size];
-
-            let mut write_offset = 0;
-            let mut upload_segments = Vec::new();
-            loop {
-                let length = file
-                    .read(&mut data)
-                    .await
-                    .with_context(|| format!("Error reading from {name}"))?;
-                if length == 0 {
-                    break;
-                }
-                upload_segments.push(WriteRequest {
-                    resource_name: resource_name.to_owned(),
-                    write_offset,
-                    finish_write: false,
-                    data: data[..length].to_owned(),
-                });
-                write_offset += length as i64;
+            if max_msg_size == 0 {
+                return Err(anyhow::anyhow!(
+                    "Failed to upload `{name}`: the maximum message size is 0"
+                ));
             }
-            upload_segments
-                .last_mut()
-                .with_context(|| format!("Read no segments from `{name} "))?
-                .finish_write = true;
+            // The bytes sent by the last attempt, which is the compressed size of the file.
+            let sent = Arc::new(AtomicI64::new(0));
+            let requests: WriteRequestsFn = Arc::new({
+                let name = name.clone();
+                let sent = sent.clone();
+                move || {
+                    sent.store(0, Ordering::Relaxed);
+                    let sent = sent.clone();
+                    file_write_requests(
+                        name.clone(),
+                        resource_name.next(),
+                        compression.bytestream,
+                        max_msg_size,
+                    )
+                    .inspect_ok(move |request| {
+                        sent.fetch_add(request.data.len() as i64, Ordering::Relaxed);
+                    })
+                    .boxed()
+                }
+            });
 
-            let resp = bystream_fut(upload_segments).await?;
-            if resp.committed_size != size {
+            let resp = bystream_fut(requests).await?;
+            let sent = sent.load(Ordering::Relaxed);
+            stats.record_upload(sent as usize, size as usize);
+            if !is_committed(compression.bytestream, resp.committed_size, size, sent) {
                 return Err(anyhow::anyhow!(
                     "Failed to upload `{name}`: invalid committed_size from WriteResponse"
                 ));
@@ -1179,8 +2000,8 @@ where
                     BatchUploadRequest::Blob(blob) => {
                         re_request.requests.push(Request {
                             digest: Some(tdigest_to(blob.digest.clone())),
-                            data: blob.blob.clone(),
-                            compressor: compressor::Value::Identity as i32,
+                            data: compress(compression.batch_update, blob.blob.clone())?,
+                            compressor: compression.batch_update as i32,
                         });
                     }
                     BatchUploadRequest::File(file) => {
@@ -1193,8 +2014,8 @@ where
 
                         re_request.requests.push(Request {
                             digest: Some(tdigest_to(file.digest.clone())),
-                            data,
-                            compressor: compressor::Value::Identity as i32,
+                            data: compress(compression.batch_update, data)?,
+                            compressor: compression.batch_update as i32,
                         });
                     }
                 }
@@ -1204,8 +2025,15 @@ where
                 .iter()
                 .map(|x| x.digest.as_ref().unwrap().hash.clone())
                 .collect::<Vec<String>>();
+            let (sent, uncompressed) = re_request.requests.iter().fold((0, 0), |(s, u), x| {
+                (
+                    s + x.data.len(),
+                    u + x.digest.as_ref().unwrap().size_bytes as usize,
+                )
+            });
 
             let response = cas_f(re_request).await?;
+            stats.record_upload(sent, uncompressed);
             let failures: Vec<String> = response
                 .responses
                 .iter()
@@ -1303,13 +2131,21 @@ fn substitute_env_vars_impl(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::VecDeque;
+
     use re_grpc_proto::build::bazel::remote::execution::v2::batch_read_blobs_response;
     use re_grpc_proto::build::bazel::remote::execution::v2::batch_update_blobs_response;
+    use re_grpc_proto::build::bazel::remote::execution::v2::execution_server::Execution;
+    use re_grpc_proto::build::bazel::remote::execution::v2::execution_server::ExecutionServer;
 
     use super::*;
     use crate::NamedDigest;
     use crate::NamedDigestWithPermissions;
 
+    async fn collect_write_requests(requests: WriteRequestsFn) -> Vec<WriteRequest> {
+        requests().try_collect().await.unwrap()
+    }
+
     #[tokio::test]
     async fn test_download_named() -> anyhow::Result<()> {
         let work = tempfile::tempdir()?;
@@ -1376,6 +2212,8 @@ mod tests {
             &InstanceName(None),
             req,
             10000,
+            Compression::IDENTITY,
+            &REState::default(),
             |req| {
                 let res = res.clone();
                 let digest1 = digest1.clone();
@@ -1482,6 +2320,8 @@ mod tests {
             &InstanceName(None),
             req,
             10, // kept small to simulate a large file download
+            Compression::IDENTITY,
+            &REState::default(),
             |req| {
                 let res = res.clone();
                 let digest1 = digest1.clone();
@@ -1563,6 +2403,8 @@ mod tests {
             &InstanceName(None),
             req,
             100000,
+            Compression::IDENTITY,
+            &REState::default(),
             |req| {
                 let res = res.clone();
                 let digest1 = digest1.clone();
@@ -1636,6 +2478,8 @@ mod tests {
             &InstanceName(None),
             req,
             10, // intentionally small value to keep data in the test blobs small
+            Compression::IDENTITY,
+            &REState::default(),
             |req| {
                 let res = res.clone();
                 let digest1 = digest1.clone();
@@ -1691,6 +2535,8 @@ mod tests {
             &InstanceName(None),
             req,
             100000,
+            Compression::IDENTITY,
+            &REState::default(),
             |req| {
                 let res = res.clone();
                 async move {
@@ -1729,6 +2575,8 @@ mod tests {
             &InstanceName(Some("instance".to_owned())),
             req,
             0,
+            Compression::IDENTITY,
+            &REState::default(),
             |_req| async { panic!("not called") },
             |req| async move {
                 assert_eq!(req.resource_name, "instance/blobs/aa/0");
@@ -1798,6 +2646,8 @@ mod tests {
             &InstanceName(None),
             req,
             10000,
+            Compression::IDENTITY,
+            &REState::default(),
             |req| {
                 let res = res.clone();
                 let digest1 = digest1.clone();
@@ -1880,6 +2730,8 @@ mod tests {
             &InstanceName(None),
             req,
             10, // kept small to simulate a large file upload
+            Compression::IDENTITY,
+            &REState::default(),
             |req| {
                 let res = res.clone();
                 let digest1 = digest1.clone();
@@ -1893,6 +2745,7 @@ mod tests {
             |write_reqs| {
                 let blob_data = blob_data.clone();
                 async move {
+                    let write_reqs = collect_write_requests(write_reqs).await;
                     assert_eq!(write_reqs.len(), 2);
                     assert_eq!(write_reqs[0].write_offset, 0);
                     assert!(!write_reqs[0].finish_write);
@@ -1953,6 +2806,8 @@ mod tests {
             &InstanceName(None),
             req,
             10, // kept small to simulate a large inlined upload
+            Compression::IDENTITY,
+            &REState::default(),
             |req| {
                 let res = res.clone();
                 let digest1 = digest1.clone();
@@ -1967,6 +2822,7 @@ mod tests {
             |write_reqs| {
                 let blob_data2 = blob_data2.clone();
                 async move {
+                    let write_reqs = collect_write_requests(write_reqs).await;
                     assert_eq!(write_reqs.len(), 2);
                     assert_eq!(write_reqs[0].write_offset, 0);
                     assert!(!write_reqs[0].finish_write);
@@ -2013,6 +2869,8 @@ mod tests {
             &InstanceName(None), // TODO
             req,
             10,
+            Compression::IDENTITY,
+            &REState::default(),
             |_req| async move {
                 panic!("This should not be called as there are no blobs to upload in batch");
             },
@@ -2073,10 +2931,13 @@ mod tests {
             &InstanceName(None),
             req,
             3,
+            Compression::IDENTITY,
+            &REState::default(),
             |_req| async move {
                 panic!("Not called");
             },
             |write_reqs| async move {
+                let write_reqs = collect_write_requests(write_reqs).await;
                 assert_eq!(write_reqs.len(), 2);
                 assert!(write_reqs[1].finish_write);
                 anyhow::Ok(WriteResponse { committed_size: 6 })
@@ -2113,6 +2974,8 @@ mod tests {
             &InstanceName(None),
             req,
             0,
+            Compression::IDENTITY,
+            &REState::default(),
             |_req| async move {
                 panic!("Not called");
             },
@@ -2158,12 +3021,18 @@ mod tests {
             &InstanceName(Some("instance".to_owned())),
             req,
             1,
+            Compression::IDENTITY,
+            &REState::default(),
             |_req| async move {
                 panic!("Not called");
             },
             |write_reqs| async move {
+                let retried = collect_write_requests(write_reqs.clone()).await;
+                let write_reqs = collect_write_requests(write_reqs).await;
                 assert!(write_reqs[0].resource_name.starts_with("instance/uploads/"));
                 assert!(write_reqs[0].resource_name.ends_with("/blobs/aa/3"));
+                // Every attempt is a new upload.
+                assert_ne!(retried[0].resource_name, write_reqs[0].resource_name);
                 anyhow::Ok(WriteResponse { committed_size: 3 })
             },
         )
@@ -2172,6 +3041,286 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_negotiate_compression() {
+        let cache_cap = CacheCapabilities {
+            supported_compressors: vec![compressor::Value::Zstd as i32],
+            ..Default::default()
+        };
+        assert_eq!(
+            Compression::negotiate(true, &cache_cap),
+            Compression {
+                bytestream: compressor::Value::Zstd,
+                batch_update: compressor::Value::Identity,
+            }
+        );
+        assert_eq!(
+            Compression::negotiate(false, &cache_cap),
+            Compression::IDENTITY
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_compressed() -> anyhow::Result<()> {
+        let digest1 = TDigest {
+            hash: "aa".to_owned(),
+            size_in_bytes: 3,
+            ..Default::default()
+        };
+
+        let blob_data = vec![7; 1000];
+        let digest2 = TDigest {
+            hash: "xl".to_owned(),
+            size_in_bytes: 1000,
+            ..Default::default()
+        };
+        let compressed = zstd::bulk::compress(&blob_data, ZSTD_LEVEL)?;
+
+        let req = DownloadRequest {
+            inlined_digests: Some(vec![digest1.clone(), digest2.clone()]),
+            ..Default::default()
+        };
+
+        let res = BatchReadBlobsResponse {
+            responses: vec![batch_read_blobs_response::Response {
+                digest: Some(tdigest_to(digest1.clone())),
+                data: zstd::bulk::compress(&[1, 2, 3], ZSTD_LEVEL)?,
+                compressor: compressor::Value::Zstd as i32,
+                ..Default::default()
+            }],
+        };
+
+        let stats = REState::default();
+        let compression = Compression {
+            bytestream: compressor::Value::Zstd,
+            batch_update: compressor::Value::Identity,
+        };
+        let received = res.responses[0].data.len() + compressed.len();
+
+        let res = download_impl(
+            &InstanceName(None),
+            req,
+            100,
+            compression,
+            &stats,
+            |req| {
+                let res = res.clone();
+                async move {
+                    assert_eq!(
+                        req.acceptable_compressors,
+                        vec![
+                            compressor::Value::Identity as i32,
+                            compressor::Value::Zstd as i32
+                        ]
+                    );
+                    Ok(res)
+                }
+            },
+            |req| {
+                // Split the compressed data to exercise decoding across chunks.
+                let chunks = compressed
+                    .chunks(5)
+                    .map(|c| Ok(ReadResponse { data: c.to_vec() }))
+                    .collect::<Vec<_>>();
+                async move {
+                    assert_eq!(req.resource_name, "compressed-blobs/zstd/xl/1000");
+                    anyhow::Ok(Box::pin(futures::stream::iter(chunks)))
+                }
+            },
+        )
+        .await?;
+
+        let blobs = res.inlined_blobs.unwrap();
+        assert_eq!(blobs[0].blob, vec![1, 2, 3]);
+        assert_eq!(blobs[1].blob, blob_data);
+
+        assert_eq!(
+            stats.network_downloaded.load(Ordering::Relaxed),
+            received as i64
+        );
+        assert_eq!(
+            stats
+                .network_downloaded_uncompressed
+                .load(Ordering::Relaxed),
+            1003
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_compressed() -> anyhow::Result<()> {
+        let digest1 = TDigest {
+            hash: "aa".to_owned(),
+            size_in_bytes: 3,
+            ..Default::default()
+        };
+
+        let blob_data = vec![7; 1000];
+        let digest2 = TDigest {
+            hash: "xl".to_owned(),
+            size_in_bytes: 1000,
+            ..Default::default()
+        };
+
+        let req = UploadRequest {
+            inlined_blobs_with_digest: Some(vec![
+                InlinedBlobWithDigest {
+                    blob: blob_data.clone(),
+                    digest: digest2.clone(),
+                    ..Default::default()
+                },
+                InlinedBlobWithDigest {
+                    blob: b"aaa".to_vec(),
+                    digest: digest1.clone(),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let stats = REState::default();
+        let compression = Compression {
+            bytestream: compressor::Value::Zstd,
+            batch_update: compressor::Value::Zstd,
+        };
+
+        upload_impl(
+            &InstanceName(None),
+            req,
+            100,
+            compression,
+            &stats,
+            |req| async move {
+                assert_eq!(req.requests.len(), 1);
+                assert_eq!(req.requests[0].compressor, compressor::Value::Zstd as i32);
+                assert_eq!(zstd::bulk::decompress(&req.requests[0].data, 3)?, b"aaa");
+                Ok(BatchUpdateBlobsResponse {
+                    responses: vec![batch_update_blobs_response::Response {
+                        digest: req.requests[0].digest.clone(),
+                        status: Some(Status::default()),
+                    }],
+                })
+            },
+            |write_reqs| {
+                let blob_data = blob_data.clone();
+                async move {
+                    let write_reqs = collect_write_requests(write_reqs).await;
+                    assert!(write_reqs[0].resource_name.starts_with("uploads/"));
+                    assert!(
+                        write_reqs[0]
+                            .resource_name
+                            .ends_with("/compressed-blobs/zstd/xl/1000")
+                    );
+                    assert!(write_reqs.last().unwrap().finish_write);
+                    let data = write_reqs
+                        .iter()
+                        .flat_map(|r| r.data.iter().copied())
+                        .collect::<Vec<_>>();
+                    assert_eq!(zstd::bulk::decompress(&data, 1000)?, blob_data);
+                    // The blob was already present.
+                    anyhow::Ok(WriteResponse { committed_size: -1 })
+                }
+            },
+        )
+        .await?;
+
+        assert_eq!(
+            stats.network_uploaded_uncompressed.load(Ordering::Relaxed),
+            1003
+        );
+        assert!(stats.network_uploaded.load(Ordering::Relaxed) < 1003);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_compressed_file() -> anyhow::Result<()> {
+        let blob_data = (0..1000u32)
+            .map(|i| (i * 7919 % 251) as u8)
+            .collect::<Vec<_>>();
+
+        let work = tempfile::tempdir()?;
+        let path = work.path().join("path");
+        let path = path.to_str().context("tempdir is not utf8")?;
+        tokio::fs::write(path, &blob_data).await?;
+
+        let req = UploadRequest {
+            files_with_digest: Some(vec![NamedDigest {
+                name: path.to_owned(),
+                digest: TDigest {
+                    hash: "xl".to_owned(),
+                    size_in_bytes: 1000,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        let stats = REState::default();
+        let compression = Compression {
+            bytestream: compressor::Value::Zstd,
+            batch_update: compressor::Value::Zstd,
+        };
+
+        upload_impl(
+            &InstanceName(None),
+            req,
+            16,
+            compression,
+            &stats,
+            |_req| async move {
+                panic!("Not called");
+            },
+            |write_reqs| {
+                let blob_data = blob_data.clone();
+                async move {
+                    let write_reqs = collect_write_requests(write_reqs).await;
+                    assert!(write_reqs.len() > 1);
+                    let mut data = Vec::new();
+                    for (i, req) in write_reqs.iter().enumerate() {
+                        assert!(
+                            req.resource_name
+                                .ends_with("/compressed-blobs/zstd/xl/1000")
+                        );
+                        assert!(req.data.len() <= 16);
+                        assert_eq!(req.write_offset, data.len() as i64);
+                        assert_eq!(req.finish_write, i == write_reqs.len() - 1);
+                        data.extend_from_slice(&req.data);
+                    }
+                    assert_eq!(zstd::bulk::decompress(&data, 1000)?, blob_data);
+                    // Servers may report the compressed size.
+                    anyhow::Ok(WriteResponse {
+                        committed_size: data.len() as i64,
+                    })
+                }
+            },
+        )
+        .await?;
+
+        assert_eq!(
+            stats.network_uploaded_uncompressed.load(Ordering::Relaxed),
+            1000
+        );
+        assert!(stats.network_uploaded.load(Ordering::Relaxed) < 1000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_committed() {
+        use compressor::Value;
+
+        assert!(is_committed(Value::Identity, 1000, 1000, 1000));
+        assert!(!is_committed(Value::Identity, -1, 1000, 1000));
+        assert!(!is_committed(Value::Identity, 10, 1000, 10));
+        assert!(is_committed(Value::Zstd, 1000, 1000, 10));
+        assert!(is_committed(Value::Zstd, 10, 1000, 10));
+        assert!(is_committed(Value::Zstd, -1, 1000, 10));
+        assert!(!is_committed(Value::Zstd, 5, 1000, 10));
+    }
+
     #[test]
     fn test_substitute_env_vars() {
         let getter = |s: &str| match s {
@@ -2197,4 +3346,270 @@ mod tests {
         assert_eq!(substitute_env_vars_impl("FOO", getter).unwrap(), "FOO");
         assert!(substitute_env_vars_impl("$FOO$BAZ", getter).is_err());
     }
+
+    #[test]
+    fn test_convert_action_result_roundtrip() -> anyhow::Result<()> {
+        let digest = |hash: &str| TDigest {
+            hash: hash.to_owned(),
+            size_in_bytes: 3,
+            ..Default::default()
+        };
+
+        let result = TActionResult2 {
+            output_files: vec![TFile {
+                digest: DigestWithStatus {
+                    digest: digest("aa"),
+                    status: tstatus_ok(),
+                    ..Default::default()
+                },
+                name: "out/file".to_owned(),
+                executable: true,
+                ..Default::default()
+            }],
+            output_directories: vec![TDirectory2 {
+                path: "out/dir".to_owned(),
+                tree_digest: digest("bb"),
+                root_directory_digest: digest("cc"),
+                ..Default::default()
+            }],
+            exit_code: 0,
+            stdout_raw: Some(b"out".to_vec()),
+            stderr_digest: Some(digest("dd")),
+            execution_metadata: TExecutedActionMetadata {
+                worker: "host".to_owned(),
+                execution_start_timestamp: TTimestamp {
+                    seconds: 10,
+                    nanos: 20,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = convert_action_result(convert_taction_result_to_rbe(result))?;
+
+        assert_eq!(result.output_files.len(), 1);
+        assert_eq!(result.output_files[0].name, "out/file");
+        assert_eq!(result.output_files[0].digest.digest.hash, "aa");
+        assert!(result.output_files[0].executable);
+        assert_eq!(result.output_directories.len(), 1);
+        assert_eq!(result.output_directories[0].path, "out/dir");
+        assert_eq!(result.output_directories[0].tree_digest.hash, "bb");
+        assert_eq!(result.stdout_raw.as_deref(), Some(b"out".as_ref()));
+        assert_eq!(result.stderr_digest.map(|d| d.hash).as_deref(), Some("dd"));
+        assert_eq!(result.execution_metadata.worker, "host");
+        assert_eq!(
+            result.execution_metadata.execution_start_timestamp.seconds,
+            10
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unix_socket_path() {
+        assert_eq!(
+            unix_socket_path("unix:///tmp/cache.sock"),
+            Some("/tmp/cache.sock")
+        );
+        assert_eq!(unix_socket_path("unix:cache.sock"), Some("cache.sock"));
+        assert_eq!(unix_socket_path("grpc://localhost:8980"), None);
+        assert_eq!(unix_socket_path("localhost:8980"), None);
+    }
+
+    type Script = Result<Vec<Result<Operation, tonic::Status>>, tonic::Status>;
+
+    /// An execution service that answers each `Execute` and `WaitExecution` call with the next
+    /// script for that RPC: either an error for the call, or the messages (and final error) of
+    /// the stream it returns.
+    #[derive(Default)]
+    struct ScriptedExecution {
+        execute: Mutex<VecDeque<Script>>,
+        wait_execution: Mutex<VecDeque<Script>>,
+        calls: Mutex<Vec<&'static str>>,
+    }
+
+    impl ScriptedExecution {
+        fn play(&self, rpc: &'static str, scripts: &Mutex<VecDeque<Script>>) -> Script {
+            self.calls.lock().unwrap().push(rpc);
+            scripts
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_else(|| Err(tonic::Status::internal(format!("Unexpected {}", rpc))))
+        }
+    }
+
+    type OperationStream = BoxStream<'static, Result<Operation, tonic::Status>>;
+
+    #[tonic::async_trait]
+    impl Execution for ScriptedExecution {
+        type ExecuteStream = OperationStream;
+        type WaitExecutionStream = OperationStream;
+
+        async fn execute(
+            &self,
+            _request: tonic::Request<GExecuteRequest>,
+        ) -> Result<tonic::Response<OperationStream>, tonic::Status> {
+            let messages = self.play("Execute", &self.execute)?;
+            Ok(tonic::Response::new(
+                futures::stream::iter(messages).boxed(),
+            ))
+        }
+
+        async fn wait_execution(
+            &self,
+            _request: tonic::Request<WaitExecutionRequest>,
+        ) -> Result<tonic::Response<OperationStream>, tonic::Status> {
+            let messages = self.play("WaitExecution", &self.wait_execution)?;
+            Ok(tonic::Response::new(
+                futures::stream::iter(messages).boxed(),
+            ))
+        }
+    }
+
+    fn operation(done: bool) -> Result<Operation, tonic::Status> {
+        Ok(Operation {
+            name: "operations/1".to_owned(),
+            done,
+            ..Default::default()
+        })
+    }
+
+    fn unavailable() -> Result<Operation, tonic::Status> {
+        Err(tonic::Status::unavailable("connection reset"))
+    }
+
+    /// Starts `service` on a local port and returns the stream of an execution started on it,
+    /// resumed up to `max_retries` times in a row.
+    async fn execute_stream(
+        service: Arc<ScriptedExecution>,
+        max_retries: usize,
+    ) -> anyhow::Result<ExecuteStream> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let incoming = futures::stream::unfold(listener, |listener| async move {
+            Some((listener.accept().await.map(|(stream, _)| stream), listener))
+        });
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(ExecutionServer::from_arc(service))
+                .serve_with_incoming(incoming),
+        );
+
+        let channel = Channel::from_shared(format!("http://{}", addr))?
+            .connect()
+            .await?;
+        let mut client =
+            ExecutionClient::with_interceptor(channel, InjectHeadersInterceptor::new(&[], None)?);
+        let request = GExecuteRequest::default();
+        let stream = client.execute(request.clone()).await?.into_inner();
+
+        Ok(ExecuteStream {
+            stream,
+            client,
+            request,
+            metadata: RemoteExecutionMetadata::default(),
+            retry: RetryPolicy::new(&Buck2OssReConfiguration {
+                max_retries,
+                ..Default::default()
+            }),
+            action_digest: TDigest::default(),
+            operation_name: None,
+            done: false,
+            retries: 0,
+        })
+    }
+
+    async fn collect_done(stream: &mut ExecuteStream) -> anyhow::Result<Vec<bool>> {
+        let mut done = Vec::new();
+        while let Some(msg) = stream.next().await? {
+            done.push(msg.done);
+        }
+        Ok(done)
+    }
+
+    #[tokio::test]
+    async fn test_execute_stream_resets_retries_on_progress() -> anyhow::Result<()> {
+        let service = Arc::new(ScriptedExecution::default());
+        service
+            .execute
+            .lock()
+            .unwrap()
+            .push_back(Ok(vec![operation(false), unavailable()]));
+        service.wait_execution.lock().unwrap().extend([
+            Ok(vec![operation(false), unavailable()]),
+            Ok(vec![operation(false), unavailable()]),
+            Ok(vec![operation(true)]),
+        ]);
+
+        // Every stream makes progress before it fails, so a single retry is always enough.
+        let mut stream = execute_stream(service.clone(), 1).await?;
+        assert_eq!(
+            vec![false, false, false, true],
+            collect_done(&mut stream).await?
+        );
+        assert_eq!(
+            vec!["Execute", "WaitExecution", "WaitExecution", "WaitExecution"],
+            *service.calls.lock().unwrap()
+        );
+
+        // Without progress, retries run out.
+        let service = Arc::new(ScriptedExecution::default());
+        service
+            .execute
+            .lock()
+            .unwrap()
+            .push_back(Ok(vec![operation(false), unavailable()]));
+        service
+            .wait_execution
+            .lock()
+            .unwrap()
+            .extend([Ok(vec![unavailable()]), Ok(vec![operation(true)])]);
+
+        let mut stream = execute_stream(service.clone(), 1).await?;
+        assert!(collect_done(&mut stream).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_execute_stream_executes_again_when_operation_is_lost() -> anyhow::Result<()> {
+        let service = Arc::new(ScriptedExecution::default());
+        service.execute.lock().unwrap().extend([
+            Ok(vec![operation(false), unavailable()]),
+            Ok(vec![operation(false), operation(true)]),
+        ]);
+        service
+            .wait_execution
+            .lock()
+            .unwrap()
+            .push_back(Err(tonic::Status::not_found("operations/1")));
+
+        let mut stream = execute_stream(service.clone(), 2).await?;
+        assert_eq!(vec![false, false, true], collect_done(&mut stream).await?);
+        assert_eq!(
+            vec!["Execute", "WaitExecution", "Execute"],
+            *service.calls.lock().unwrap()
+        );
+
+        // Losing the operation counts against the retries.
+        let service = Arc::new(ScriptedExecution::default());
+        service
+            .execute
+            .lock()
+            .unwrap()
+            .push_back(Ok(vec![operation(false), unavailable()]));
+        service
+            .wait_execution
+            .lock()
+            .unwrap()
+            .push_back(Err(tonic::Status::not_found("operations/1")));
+
+        let mut stream = execute_stream(service.clone(), 1).await?;
+        let err = collect_done(&mut stream).await.unwrap_err();
+        assert!(is_not_found(&err), "{:#}", err);
+
+        Ok(())
+    }
 }
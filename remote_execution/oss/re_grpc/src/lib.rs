@@ -19,6 +19,7 @@ mod grpc;
 mod metadata;
 mod request;
 mod response;
+mod retry;
 pub use client::*;
 pub use digest::*;
 pub use error::*;
@@ -26,3 +27,4 @@ pub use grpc::*;
 pub use metadata::*;
 pub use request::*;
 pub use response::*;
+pub use retry::*;
@@ -94,6 +94,21 @@ pub struct TResultsCachePolicy {
     pub _dot_dot: (),
 }
 
+#[derive(Default)]
+pub struct FetchBlobRequest {
+    /// Locations of the blob, which should all have the same contents.
+    pub uris: Vec<String>,
+    pub qualifiers: Vec<Qualifier>,
+    pub _dot_dot: (),
+}
+
+#[derive(Clone, Default)]
+pub struct Qualifier {
+    pub name: String,
+    pub value: String,
+    pub _dot_dot: (),
+}
+
 #[derive(Clone, Default)]
 pub struct WriteActionResultRequest {
     pub action_digest: TDigest,
@@ -154,6 +154,15 @@ pub struct GetDigestsTtlResponse {
     pub digests_with_ttl: Vec<DigestWithTtl>,
 }
 
+#[derive(Clone, Default)]
+pub struct FetchBlobResponse {
+    /// The URI the blob was fetched from.
+    pub uri: String,
+    pub digest: TDigest,
+    /// How long the blob is guaranteed to stay in the CAS, if the server said.
+    pub expires_at: Option<TTimestamp>,
+}
+
 #[derive(Clone, Default)]
 pub struct ExecuteResponse {
     pub action_result: TActionResult2,
@@ -223,8 +232,14 @@ pub struct TFile {
 
 #[derive(Clone, Default)]
 pub struct NetworkStatisticsResponse {
+    /// Bytes sent to the CAS, compressed if compression is in use.
     pub uploaded: i64,
+    /// Bytes received from the CAS, compressed if compression is in use.
     pub downloaded: i64,
+    /// Size of the blobs uploaded to the CAS.
+    pub uploaded_uncompressed: i64,
+    /// Size of the blobs downloaded from the CAS.
+    pub downloaded_uncompressed: i64,
     // Compatibility with the Thrift structs
     pub _dot_dot_default: (),
 }
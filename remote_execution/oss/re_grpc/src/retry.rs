@@ -0,0 +1,229 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use buck2_re_configuration::Buck2OssReConfiguration;
+use futures::future::Future;
+use rand::Rng;
+
+use crate::TDigest;
+
+/// Details of an RPC that failed with a transient error and is about to be retried.
+pub struct RetryInfo<'a> {
+    /// The RPC being retried, e.g. `Execute` or `BatchUpdateBlobs`.
+    pub rpc: &'static str,
+    /// The action being executed, for `Execute` and `WaitExecution`.
+    pub action_digest: Option<&'a TDigest>,
+    /// The number of the upcoming attempt, starting at 2.
+    pub attempt: usize,
+    pub backoff: Duration,
+    pub error: &'a anyhow::Error,
+}
+
+/// Called before every retry, e.g. to log it.
+pub type RetryObserver = Arc<dyn Fn(&RetryInfo<'_>) + Send + Sync>;
+
+#[derive(Clone)]
+pub(crate) struct RetryPolicy {
+    max_retries: usize,
+    initial_backoff: Duration,
+    pub(crate) observer: Option<RetryObserver>,
+}
+
+impl RetryPolicy {
+    pub(crate) fn new(opts: &Buck2OssReConfiguration) -> Self {
+        Self {
+            max_retries: opts.max_retries,
+            initial_backoff: Duration::from_millis(opts.retry_backoff_ms),
+            observer: None,
+        }
+    }
+
+    /// Run `f` until it succeeds, fails with an error that isn't transient, or we run out of
+    /// retries. Only use this for idempotent RPCs.
+    pub(crate) async fn retry<F, Fut, T>(
+        &self,
+        rpc: &'static str,
+        action_digest: Option<&TDigest>,
+        f: F,
+    ) -> anyhow::Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut retries = 0;
+        loop {
+            match f().await {
+                Err(e) if self.should_retry(retries, &e) => {
+                    retries += 1;
+                    self.wait(rpc, action_digest, retries, &e).await;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Whether to retry after `retries` retries already happened.
+    pub(crate) fn should_retry(&self, retries: usize, error: &anyhow::Error) -> bool {
+        self.can_retry(retries) && is_transient(error)
+    }
+
+    /// Whether there are retries left after `retries` retries already happened, whatever the
+    /// error was.
+    pub(crate) fn can_retry(&self, retries: usize) -> bool {
+        retries < self.max_retries
+    }
+
+    /// Report the upcoming retry and sleep until it's time to make it.
+    pub(crate) async fn wait(
+        &self,
+        rpc: &'static str,
+        action_digest: Option<&TDigest>,
+        retry: usize,
+        error: &anyhow::Error,
+    ) {
+        let backoff = self.backoff(retry);
+        tracing::warn!("Retrying {} after {:?}: {:#}", rpc, backoff, error);
+        if let Some(observer) = &self.observer {
+            observer(&RetryInfo {
+                rpc,
+                action_digest,
+                attempt: retry + 1,
+                backoff,
+                error,
+            });
+        }
+        tokio::time::sleep(backoff).await;
+    }
+
+    /// Exponential backoff, randomized within its upper half so that clients which failed together
+    /// don't all retry together.
+    fn backoff(&self, retry: usize) -> Duration {
+        let exponent = retry.saturating_sub(1).min(16) as u32;
+        let max = self.initial_backoff.saturating_mul(1 << exponent);
+        if max.is_zero() {
+            return max;
+        }
+        rand::thread_rng().gen_range(max / 2..=max)
+    }
+}
+
+/// Whether an error is worth retrying: the remote is overloaded or went away, but the same request
+/// may succeed later.
+fn is_transient(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|e| match e.downcast_ref::<tonic::Status>() {
+            Some(status) => matches!(
+                status.code(),
+                tonic::Code::Unavailable | tonic::Code::ResourceExhausted | tonic::Code::Aborted
+            ),
+            None => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use anyhow::Context;
+
+    use super::*;
+
+    fn policy(max_retries: usize) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::ZERO,
+            observer: None,
+        }
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(
+            &anyhow::Error::from(tonic::Status::unavailable("down")).context("Error")
+        ));
+        assert!(!is_transient(&anyhow::Error::from(
+            tonic::Status::not_found("missing")
+        )));
+        assert!(!is_transient(&anyhow::anyhow!("unavailable")));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            ..policy(3)
+        };
+        for _ in 0..10 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = policy.backoff(3);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry() -> anyhow::Result<()> {
+        let calls = &AtomicUsize::new(0);
+        let retried = Arc::new(AtomicUsize::new(0));
+        let observer: RetryObserver = Arc::new({
+            let retried = retried.clone();
+            move |info: &RetryInfo<'_>| {
+                assert_eq!(info.rpc, "Test");
+                assert_eq!(info.attempt, retried.fetch_add(1, Ordering::Relaxed) + 2);
+            }
+        });
+        let policy = RetryPolicy {
+            observer: Some(observer),
+            ..policy(3)
+        };
+
+        let res = policy
+            .retry("Test", None, || async move {
+                if calls.fetch_add(1, Ordering::Relaxed) < 2 {
+                    Err(tonic::Status::unavailable("down")).context("Error")
+                } else {
+                    Ok(42)
+                }
+            })
+            .await?;
+
+        assert_eq!(res, 42);
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+        assert_eq!(retried.load(Ordering::Relaxed), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up() {
+        let calls = &AtomicUsize::new(0);
+
+        let res: anyhow::Result<()> = policy(2)
+            .retry("Test", None, || async move {
+                calls.fetch_add(1, Ordering::Relaxed);
+                Err(tonic::Status::unavailable("down").into())
+            })
+            .await;
+        assert!(res.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+        let res: anyhow::Result<()> = policy(2)
+            .retry("Test", None, || async move {
+                calls.fetch_add(1, Ordering::Relaxed);
+                Err(tonic::Status::invalid_argument("bad").into())
+            })
+            .await;
+        assert!(res.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+    }
+}
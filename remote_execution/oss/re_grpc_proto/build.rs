@@ -11,6 +11,7 @@ use std::io;
 
 fn main() -> io::Result<()> {
     let proto_files = &[
+        "proto/build/bazel/remote/asset/v1/remote_asset.proto",
         "proto/build/bazel/remote/execution/v2/remote_execution.proto",
         "proto/build/bazel/semver/semver.proto",
         "proto/google/api/annotations.proto",
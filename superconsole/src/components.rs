@@ -17,8 +17,12 @@ pub use blank::Blank;
 pub use bordering::Bordered;
 pub use bounding::Bounded;
 pub(crate) use canvas::Canvas;
+pub use focusing::Focused;
 pub use padding::Padded;
+pub use progress_bar::ProgressBar;
+pub use scrolling::Scrolled;
 pub use splitting::Split;
+pub use table::Table;
 
 pub use crate::components::draw_horizontal::DrawHorizontal;
 pub use crate::components::draw_vertical::DrawVertical;
@@ -33,8 +37,12 @@ mod canvas;
 mod draw_horizontal;
 mod draw_vertical;
 pub(crate) mod echo;
+pub mod focusing;
 pub mod padding;
+pub mod progress_bar;
+pub mod scrolling;
 pub mod splitting;
+pub mod table;
 
 /// Used to mark whether a draw is final.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
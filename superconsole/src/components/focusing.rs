@@ -0,0 +1,113 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::ops::Range;
+
+use crossterm::style::Attribute;
+
+use crate::components::Scrolled;
+use crate::Component;
+use crate::Dimensions;
+use crate::DrawMode;
+use crate::Lines;
+
+/// The `Focused` [`Component`](Component) highlights the lines of its child that have focus, e.g.
+/// the item under a cursor in a list, and scrolls so that they are visible.
+///
+/// Which lines have focus is up to the caller, which usually moves the focus in response to
+/// console input. Without focus, the child is drawn as is.
+#[derive(Debug)]
+pub struct Focused<C: Component = Box<dyn Component>> {
+    child: C,
+    focus: Option<Range<usize>>,
+}
+
+impl<C: Component> Focused<C> {
+    pub fn new(child: C, focus: Option<Range<usize>>) -> Self {
+        Self { child, focus }
+    }
+}
+
+/// Draws the child with some lines in reverse video.
+struct Highlighted<'a, C: Component> {
+    child: &'a C,
+    lines: Range<usize>,
+}
+
+impl<'a, C: Component> Component for Highlighted<'a, C> {
+    fn draw_unchecked(&self, dimensions: Dimensions, mode: DrawMode) -> anyhow::Result<Lines> {
+        let mut output = self.child.draw(dimensions, mode)?;
+        for line in output
+            .iter_mut()
+            .take(self.lines.end)
+            .skip(self.lines.start)
+        {
+            line.set_attribute(Attribute::Reverse);
+        }
+        Ok(output)
+    }
+}
+
+impl<C: Component> Component for Focused<C> {
+    fn draw_unchecked(&self, dimensions: Dimensions, mode: DrawMode) -> anyhow::Result<Lines> {
+        match &self.focus {
+            Some(focus) => Scrolled::new(
+                Highlighted {
+                    child: &self.child,
+                    lines: focus.clone(),
+                },
+                0,
+            )
+            .keep_visible(focus.clone())
+            .draw(dimensions, mode),
+            None => self.child.draw(dimensions, mode),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::echo::Echo;
+    use crate::Line;
+
+    fn numbered(n: usize) -> Echo {
+        Echo(Lines(
+            (0..n)
+                .map(|i| Line::unstyled(&i.to_string()).unwrap())
+                .collect(),
+        ))
+    }
+
+    fn reversed(line: &Line) -> bool {
+        line.iter()
+            .all(|span| span.style.attributes.has(Attribute::Reverse))
+    }
+
+    #[test]
+    fn test_no_focus() -> anyhow::Result<()> {
+        let output =
+            Focused::new(numbered(10), None).draw(Dimensions::new(10, 3), DrawMode::Normal)?;
+        assert_eq!(output.len(), 3);
+        assert_eq!(output.0[0].to_unstyled(), "0");
+        assert!(!output.iter().any(reversed));
+        Ok(())
+    }
+
+    #[test]
+    fn test_focus() -> anyhow::Result<()> {
+        let output = Focused::new(numbered(10), Some(5..6))
+            .draw(Dimensions::new(10, 3), DrawMode::Normal)?;
+        let lines: Vec<_> = output.iter().map(|line| line.to_unstyled()).collect();
+        assert_eq!(lines, ["3", "4", "5"]);
+        let highlighted: Vec<_> = output.iter().map(reversed).collect();
+        assert_eq!(highlighted, [false, false, true]);
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Progress bars, both for work whose size is known (determinate), and for work whose size isn't
+//! (indeterminate), which is shown as a block bouncing between the ends of the bar.
+
+use crate::Component;
+use crate::Dimensions;
+use crate::DrawMode;
+use crate::Line;
+use crate::Lines;
+use crate::Span;
+
+/// Blocks that fill one to seven eighths of a cell, from the left.
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Which characters to draw a [`ProgressBar`](ProgressBar) with.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ProgressBarCharset {
+    /// Block elements, which are precise to an eighth of a cell: `▕███▍    ▏`.
+    Unicode,
+    /// For terminals or fonts without block elements: `[===>    ]`.
+    Ascii,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Progress {
+    Determinate(f64),
+    Indeterminate(usize),
+}
+
+/// The `ProgressBar` [`Component`](Component) draws a single line bar, as wide as it's allowed
+/// unless given a [`width`](ProgressBar::width).
+#[derive(Debug)]
+pub struct ProgressBar {
+    progress: Progress,
+    charset: ProgressBarCharset,
+    width: Option<usize>,
+}
+
+impl ProgressBar {
+    /// A bar that is `fraction` full. The fraction is clamped to `0.0..=1.0`.
+    pub fn new(fraction: f64) -> Self {
+        Self {
+            progress: Progress::Determinate(fraction),
+            charset: ProgressBarCharset::Unicode,
+            width: None,
+        }
+    }
+
+    /// A bar for work of unknown size. Components don't keep state between draws, so to animate
+    /// it, the caller passes a `tick` that increases with every draw.
+    pub fn indeterminate(tick: usize) -> Self {
+        Self {
+            progress: Progress::Indeterminate(tick),
+            charset: ProgressBarCharset::Unicode,
+            width: None,
+        }
+    }
+
+    pub fn charset(mut self, charset: ProgressBarCharset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Draw the bar this wide, including its ends, if there is room.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// The inside of the bar, `width` cells wide.
+    fn fill(&self, width: usize) -> String {
+        match (self.progress, self.charset) {
+            (Progress::Determinate(fraction), ProgressBarCharset::Unicode) => {
+                let eighths = (fraction.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+                let mut fill = "█".repeat(eighths / 8);
+                if eighths % 8 > 0 {
+                    fill.push(PARTIAL_BLOCKS[eighths % 8 - 1]);
+                }
+                format!("{:<width$}", fill, width = width)
+            }
+            (Progress::Determinate(fraction), ProgressBarCharset::Ascii) => {
+                let filled = (fraction.clamp(0.0, 1.0) * width as f64).floor() as usize;
+                let mut fill = "=".repeat(filled);
+                if filled < width {
+                    fill.push('>');
+                }
+                format!("{:<width$}", fill, width = width)
+            }
+            (Progress::Indeterminate(tick), charset) => {
+                let block = match charset {
+                    ProgressBarCharset::Unicode => "█".repeat((width / 4).max(1).min(width)),
+                    ProgressBarCharset::Ascii => "<=>"[..3.min(width)].to_owned(),
+                };
+                let block_len = block.chars().count();
+                let travel = width - block_len;
+                let position = if travel == 0 {
+                    0
+                } else {
+                    // Go right, then back left.
+                    let step = tick % (2 * travel);
+                    if step <= travel {
+                        step
+                    } else {
+                        2 * travel - step
+                    }
+                };
+                format!(
+                    "{}{}{}",
+                    " ".repeat(position),
+                    block,
+                    " ".repeat(travel - position)
+                )
+            }
+        }
+    }
+}
+
+impl Component for ProgressBar {
+    fn draw_unchecked(&self, dimensions: Dimensions, _mode: DrawMode) -> anyhow::Result<Lines> {
+        let width = self
+            .width
+            .map_or(dimensions.width, |width| width.min(dimensions.width));
+        if width < 2 {
+            return Ok(Lines::new());
+        }
+
+        let (left, right) = match self.charset {
+            ProgressBarCharset::Unicode => ('▕', '▏'),
+            ProgressBarCharset::Ascii => ('[', ']'),
+        };
+        let bar = format!("{}{}{}", left, self.fill(width - 2), right);
+        Ok(Lines(vec![Line::from_iter([Span::new_unstyled(bar)?])]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::frame_contains;
+    use crate::testing::test_console;
+    use crate::testing::SuperConsoleTestingExt;
+
+    fn draw(bar: ProgressBar, width: usize) -> String {
+        let lines = bar
+            .draw(Dimensions::new(width, 1), DrawMode::Normal)
+            .unwrap();
+        lines.iter().map(|line| line.to_unstyled()).collect()
+    }
+
+    #[test]
+    fn test_determinate() {
+        assert_eq!(draw(ProgressBar::new(0.5), 10), "▕████    ▏");
+        assert_eq!(draw(ProgressBar::new(0.3), 10), "▕██▍     ▏");
+        assert_eq!(draw(ProgressBar::new(2.0), 10), "▕████████▏");
+        assert_eq!(draw(ProgressBar::new(0.0).width(6), 10), "▕    ▏");
+    }
+
+    #[test]
+    fn test_determinate_ascii() {
+        let ascii = |fraction| ProgressBar::new(fraction).charset(ProgressBarCharset::Ascii);
+        assert_eq!(draw(ascii(0.0), 10), "[>       ]");
+        assert_eq!(draw(ascii(0.5), 10), "[====>   ]");
+        assert_eq!(draw(ascii(1.0), 10), "[========]");
+    }
+
+    #[test]
+    fn test_indeterminate() {
+        let ascii = |tick| ProgressBar::indeterminate(tick).charset(ProgressBarCharset::Ascii);
+        assert_eq!(draw(ascii(0), 10), "[<=>     ]");
+        assert_eq!(draw(ascii(5), 10), "[     <=>]");
+        assert_eq!(draw(ascii(7), 10), "[   <=>  ]");
+        assert_eq!(draw(ascii(10), 10), "[<=>     ]");
+
+        assert_eq!(draw(ProgressBar::indeterminate(1), 10), "▕ ██     ▏");
+        // Too narrow to move.
+        assert_eq!(draw(ascii(4), 4), "[<=]");
+        assert_eq!(draw(ascii(4), 1), "");
+    }
+
+    #[test]
+    fn test_render() -> anyhow::Result<()> {
+        let mut console = test_console();
+        console.render(
+            &ProgressBar::new(0.25)
+                .charset(ProgressBarCharset::Ascii)
+                .width(10),
+        )?;
+        assert!(frame_contains(
+            &console.test_output()?.frames[0],
+            "[==>     ]"
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::ops::Range;
+
+use crate::Component;
+use crate::Dimensions;
+use crate::DrawMode;
+use crate::Lines;
+
+/// The `Scrolled` [`Component`](Component) draws its child tall enough to scroll to the requested
+/// lines and shows a window of its lines that fits in the given dimensions, starting `offset`
+/// lines from the top.
+///
+/// Components are redrawn from scratch on every render, so the caller owns the scroll state.
+/// Rather than tracking an offset, a caller can also ask for some lines to be kept visible, e.g.
+/// the line under a cursor, in which case the window moves as little as needed to show them.
+#[derive(Debug)]
+pub struct Scrolled<C: Component = Box<dyn Component>> {
+    child: C,
+    offset: usize,
+    keep_visible: Option<Range<usize>>,
+}
+
+impl<C: Component> Scrolled<C> {
+    pub fn new(child: C, offset: usize) -> Self {
+        Self {
+            child,
+            offset,
+            keep_visible: None,
+        }
+    }
+
+    /// Move the window so that these lines are visible. If they don't all fit, the first ones are
+    /// shown.
+    pub fn keep_visible(mut self, lines: Range<usize>) -> Self {
+        self.keep_visible = Some(lines);
+        self
+    }
+
+    /// The first line of the child to show, given that it drew `len` lines.
+    fn start(&self, len: usize, height: usize) -> usize {
+        let mut start = self.offset;
+        if let Some(lines) = &self.keep_visible {
+            if lines.end > start + height {
+                start = lines.end - height;
+            }
+            start = start.min(lines.start);
+        }
+        start.min(len.saturating_sub(height))
+    }
+}
+
+impl<C: Component> Component for Scrolled<C> {
+    fn draw_unchecked(&self, dimensions: Dimensions, mode: DrawMode) -> anyhow::Result<Lines> {
+        // Nothing below the window can be shown. The height must be bounded anyway, since some
+        // children (e.g. a vertical `Split`) pad their output to the height they are given.
+        let window_end = self
+            .keep_visible
+            .as_ref()
+            .map_or(self.offset, |lines| self.offset.max(lines.end));
+        let mut output = self.child.draw(
+            Dimensions {
+                width: dimensions.width,
+                height: window_end.saturating_add(dimensions.height),
+            },
+            mode,
+        )?;
+
+        let start = self.start(output.len(), dimensions.height);
+        output.0.drain(..start);
+        output.truncate_lines_bottom(dimensions.height);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::echo::Echo;
+    use crate::components::splitting::SplitKind;
+    use crate::components::Split;
+    use crate::Direction;
+    use crate::Line;
+
+    fn numbered(n: usize) -> Echo {
+        Echo(Lines(
+            (0..n)
+                .map(|i| Line::unstyled(&i.to_string()).unwrap())
+                .collect(),
+        ))
+    }
+
+    fn draw(scrolled: Scrolled<Echo>, height: usize) -> Vec<String> {
+        scrolled
+            .draw(Dimensions::new(10, height), DrawMode::Normal)
+            .unwrap()
+            .iter()
+            .map(|line| line.to_unstyled())
+            .collect()
+    }
+
+    #[test]
+    fn test_offset() {
+        assert_eq!(draw(Scrolled::new(numbered(10), 0), 3), ["0", "1", "2"]);
+        assert_eq!(draw(Scrolled::new(numbered(10), 4), 3), ["4", "5", "6"]);
+        // Scrolling past the end shows the last lines.
+        assert_eq!(draw(Scrolled::new(numbered(10), 9), 3), ["7", "8", "9"]);
+        // Nothing to scroll if everything fits.
+        assert_eq!(draw(Scrolled::new(numbered(2), 1), 3), ["0", "1"]);
+    }
+
+    #[test]
+    fn test_padded_child() -> anyhow::Result<()> {
+        // A vertical split pads its children to the height it is drawn with.
+        let split = Split::new(
+            vec![numbered(2), numbered(2)],
+            Direction::Vertical,
+            SplitKind::Equal,
+        );
+        let output = Scrolled::new(split, 1).draw(Dimensions::new(10, 3), DrawMode::Normal)?;
+        assert_eq!(output.len(), 3);
+        assert_eq!(output.0[0].to_unstyled(), "1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_keep_visible() {
+        // Already visible.
+        assert_eq!(
+            draw(Scrolled::new(numbered(10), 0).keep_visible(1..2), 3),
+            ["0", "1", "2"]
+        );
+        // Below the window: it moves down just enough.
+        assert_eq!(
+            draw(Scrolled::new(numbered(10), 0).keep_visible(5..7), 3),
+            ["4", "5", "6"]
+        );
+        // Above the window: it moves up.
+        assert_eq!(
+            draw(Scrolled::new(numbered(10), 6).keep_visible(2..3), 3),
+            ["2", "3", "4"]
+        );
+        // Too many lines to fit: the first ones win.
+        assert_eq!(
+            draw(Scrolled::new(numbered(10), 0).keep_visible(5..9), 3),
+            ["5", "6", "7"]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lays out cells of text in columns, so that callers don't have to pad them with spaces.
+//! Each column has its own width and alignment, and cells that don't fit are truncated.
+
+use crate::components::alignment::HorizontalAlignmentKind;
+use crate::Component;
+use crate::Dimensions;
+use crate::DrawMode;
+use crate::Line;
+use crate::Lines;
+
+/// How wide a [`Column`](Column) is.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ColumnWidth {
+    /// Exactly this many cells wide.
+    Fixed(usize),
+    /// As wide as the widest cell in the column. When the table is too wide, the widest of these
+    /// columns shrink first.
+    Content,
+    /// Whatever width the other columns leave, split evenly between all `Fill` columns.
+    Fill,
+}
+
+/// The layout of a column in a [`Table`](Table).
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct Column {
+    pub width: ColumnWidth,
+    /// Where cells go within the column. Justification is ignored.
+    pub alignment: HorizontalAlignmentKind,
+}
+
+impl Column {
+    /// A left aligned column.
+    pub fn new(width: ColumnWidth) -> Self {
+        Self {
+            width,
+            alignment: HorizontalAlignmentKind::Left(false),
+        }
+    }
+
+    pub fn align(mut self, alignment: HorizontalAlignmentKind) -> Self {
+        self.alignment = alignment;
+        self
+    }
+}
+
+/// The `Table` [`Component`](Component) draws rows of cells, one per [`Column`](Column).
+#[derive(Debug)]
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<Line>>,
+    separator: usize,
+}
+
+impl Table {
+    /// A table with the given columns, separated by a space.
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self {
+            columns,
+            rows: Vec::new(),
+            separator: 1,
+        }
+    }
+
+    /// Set the number of spaces between columns.
+    pub fn separator(mut self, separator: usize) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Add a row. Missing cells are left blank, and cells beyond the last column are ignored.
+    pub fn push_row(&mut self, cells: impl IntoIterator<Item = Line>) {
+        self.rows.push(cells.into_iter().collect());
+    }
+
+    /// The number of rows.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The width of each column when the table is drawn `width` cells wide.
+    fn column_widths(&self, width: usize) -> Vec<usize> {
+        let available = width.saturating_sub(self.separator * self.columns.len().saturating_sub(1));
+
+        let mut widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| match column.width {
+                ColumnWidth::Fixed(width) => width,
+                ColumnWidth::Content => self
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(Line::len)
+                    .max()
+                    .unwrap_or(0),
+                ColumnWidth::Fill => 0,
+            })
+            .collect();
+
+        // Shrink the widest `Content` column until everything fits, or there's nothing left to
+        // shrink, in which case the lines are truncated when drawn.
+        loop {
+            let total: usize = widths.iter().sum();
+            if total <= available {
+                break;
+            }
+            let mut shrinkable: Vec<usize> = (0..widths.len())
+                .filter(|i| self.columns[*i].width == ColumnWidth::Content && widths[*i] > 0)
+                .collect();
+            shrinkable.sort_by_key(|i| std::cmp::Reverse(widths[*i]));
+            let widest = match shrinkable.first() {
+                Some(widest) => *widest,
+                None => break,
+            };
+            // Shrink it down to the next widest in one go, rather than one cell at a time.
+            let next = shrinkable.get(1).map_or(0, |i| widths[*i]);
+            widths[widest] -= (total - available).min(widths[widest] - next).max(1);
+        }
+
+        let fill: Vec<usize> = (0..widths.len())
+            .filter(|i| self.columns[*i].width == ColumnWidth::Fill)
+            .collect();
+        if !fill.is_empty() {
+            let remaining = available.saturating_sub(widths.iter().sum());
+            for (n, i) in fill.iter().enumerate() {
+                widths[*i] = remaining / fill.len() + usize::from(n < remaining % fill.len());
+            }
+        }
+
+        widths
+    }
+}
+
+impl Component for Table {
+    fn draw_unchecked(&self, dimensions: Dimensions, _mode: DrawMode) -> anyhow::Result<Lines> {
+        let widths = self.column_widths(dimensions.width);
+
+        let lines = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut line = Line::default();
+                for (i, (column, width)) in self.columns.iter().zip(&widths).enumerate() {
+                    if i > 0 {
+                        line.pad_right(self.separator);
+                    }
+
+                    let mut cell = row.get(i).cloned().unwrap_or_default();
+                    cell.truncate_line(*width);
+                    let padding = width.saturating_sub(cell.len());
+                    match column.alignment {
+                        HorizontalAlignmentKind::Left(_) => cell.pad_right(padding),
+                        HorizontalAlignmentKind::Center => {
+                            cell.pad_left(padding / 2);
+                            cell.pad_right(padding - padding / 2);
+                        }
+                        HorizontalAlignmentKind::Right => cell.pad_left(padding),
+                    }
+                    line.extend(cell);
+                }
+                line
+            })
+            .collect();
+
+        Ok(Lines(lines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::frame_contains;
+    use crate::testing::test_console;
+    use crate::testing::SuperConsoleTestingExt;
+
+    fn row(cells: &[&str]) -> Vec<Line> {
+        cells.iter().map(|c| Line::unstyled(c).unwrap()).collect()
+    }
+
+    fn draw(table: &Table, width: usize) -> Vec<String> {
+        table
+            .draw(Dimensions::new(width, 10), DrawMode::Normal)
+            .unwrap()
+            .iter()
+            .map(|line| line.to_unstyled())
+            .collect()
+    }
+
+    #[test]
+    fn test_alignment() {
+        let mut table = Table::new(vec![
+            Column::new(ColumnWidth::Content),
+            Column::new(ColumnWidth::Content).align(HorizontalAlignmentKind::Right),
+            Column::new(ColumnWidth::Fixed(6)).align(HorizontalAlignmentKind::Center),
+        ]);
+        table.push_row(row(&["uploads", "3", "ok"]));
+        table.push_row(row(&["executes", "120", "error"]));
+        table.push_row(row(&["x"]));
+
+        assert_eq!(
+            draw(&table, 40),
+            [
+                "uploads    3   ok  ",
+                "executes 120 error ",
+                "x                  ",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut table = Table::new(vec![
+            Column::new(ColumnWidth::Fill),
+            Column::new(ColumnWidth::Content),
+            Column::new(ColumnWidth::Fill).align(HorizontalAlignmentKind::Right),
+        ])
+        .separator(0);
+        table.push_row(row(&["a", "|", "b"]));
+
+        assert_eq!(draw(&table, 8), ["a   |  b"]);
+        assert_eq!(draw(&table, 7), ["a  |  b"]);
+    }
+
+    #[test]
+    fn test_truncation() {
+        let mut table = Table::new(vec![
+            Column::new(ColumnWidth::Content),
+            Column::new(ColumnWidth::Content),
+            Column::new(ColumnWidth::Fixed(2)),
+        ]);
+        table.push_row(row(&["abcdefgh", "abcd", "ab"]));
+
+        // The widest column shrinks first.
+        assert_eq!(draw(&table, 14), ["abcdef abcd ab"]);
+        // Then they shrink together.
+        assert_eq!(draw(&table, 10), ["abc abc ab"]);
+    }
+
+    #[test]
+    fn test_render() -> anyhow::Result<()> {
+        let mut table = Table::new(vec![
+            Column::new(ColumnWidth::Content),
+            Column::new(ColumnWidth::Content).align(HorizontalAlignmentKind::Right),
+        ]);
+        table.push_row(row(&["Pass", "10"]));
+        table.push_row(row(&["Fail", "2"]));
+
+        let mut console = test_console();
+        console.render(&table)?;
+        let frame = &console.test_output()?.frames[0];
+        assert!(frame_contains(frame, "Pass 10"));
+        assert!(frame_contains(frame, "Fail  2"));
+        Ok(())
+    }
+}
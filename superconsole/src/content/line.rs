@@ -17,6 +17,7 @@ use std::slice;
 use std::vec;
 
 use crossterm::cursor::MoveToColumn;
+use crossterm::style::Attribute;
 use crossterm::terminal::Clear;
 use crossterm::terminal::ClearType;
 use crossterm::Command;
@@ -200,6 +201,15 @@ impl Line {
         self.0.push(span);
     }
 
+    /// Add an attribute to the style of every span in the line, e.g. to highlight it.
+    pub fn set_attribute(&mut self, attribute: Attribute) {
+        let this = mem::take(self);
+        self.extend(this.into_iter().map(|mut span| {
+            span.style.attributes.set(attribute);
+            span
+        }));
+    }
+
     /// Prepend a span to the line.
     pub fn push_front(&mut self, span: Span) {
         let this = mem::take(self);
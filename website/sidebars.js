@@ -147,6 +147,7 @@ const manualSidebar = [
     label: 'Build Observability',
     items: [
       'build_observability/interactive_console',
+      'build_observability/jsonl_console',
       isInternal() ? 'developers/observability' : [],
       isInternal() ? 'build_observability/datasets' : [],
     ],